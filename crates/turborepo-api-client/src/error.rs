@@ -11,6 +11,8 @@ pub enum Error {
     ReqwestError(#[from] reqwest::Error),
     #[error("skipping HTTP Request, too many failures have occurred.\nLast error: {0}")]
     TooManyFailures(#[from] Box<reqwest::Error>),
+    #[error("unable to read artifact: {0}")]
+    ArtifactBody(#[source] std::io::Error),
     #[error("Unable to set up TLS.")]
     TlsError(#[source] reqwest::Error),
     #[error("Error parsing header: {0}")]
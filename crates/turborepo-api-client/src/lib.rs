@@ -7,7 +7,7 @@ use std::{backtrace::Backtrace, env};
 use async_trait::async_trait;
 use lazy_static::lazy_static;
 use regex::Regex;
-pub use reqwest::Response;
+pub use reqwest::{Body, Response};
 use reqwest::{Method, RequestBuilder, StatusCode};
 use serde::Deserialize;
 use turborepo_ci::{is_ci, Vendor};
@@ -64,7 +64,8 @@ pub trait CacheClient {
     async fn put_artifact(
         &self,
         hash: &str,
-        artifact_body: &[u8],
+        artifact_body: &(dyn Fn() -> std::io::Result<Body> + Send + Sync),
+        artifact_size: u64,
         duration: u64,
         tag: Option<&str>,
         token: &str,
@@ -336,7 +337,8 @@ impl CacheClient for APIClient {
     async fn put_artifact(
         &self,
         hash: &str,
-        artifact_body: &[u8],
+        artifact_body: &(dyn Fn() -> std::io::Result<Body> + Send + Sync),
+        artifact_size: u64,
         duration: u64,
         tag: Option<&str>,
         token: &str,
@@ -360,27 +362,33 @@ impl CacheClient for APIClient {
             request_url = preflight_response.location.clone();
         }
 
-        let mut request_builder = self
-            .client
-            .put(request_url)
-            .header("Content-Type", "application/octet-stream")
-            .header("x-artifact-duration", duration.to_string())
-            .header("User-Agent", self.user_agent.clone())
-            .body(artifact_body.to_vec());
-
-        if allow_auth {
-            request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
-        }
+        // Streamed bodies can't be cloned, so each attempt gets a new one
+        let response = retry::make_retryable_request_with(|| {
+            let mut request_builder = self
+                .client
+                .put(request_url.clone())
+                .header("Content-Type", "application/octet-stream")
+                .header("Content-Length", artifact_size)
+                .header("x-artifact-duration", duration.to_string())
+                .header("User-Agent", self.user_agent.clone())
+                .body(artifact_body().map_err(Error::ArtifactBody)?);
+
+            if allow_auth {
+                request_builder =
+                    request_builder.header("Authorization", format!("Bearer {}", token));
+            }
 
-        request_builder = Self::add_team_params(request_builder, team_id, team_slug);
+            request_builder = Self::add_team_params(request_builder, team_id, team_slug);
 
-        request_builder = Self::add_ci_header(request_builder);
+            request_builder = Self::add_ci_header(request_builder);
 
-        if let Some(tag) = tag {
-            request_builder = request_builder.header("x-artifact-tag", tag);
-        }
+            if let Some(tag) = tag {
+                request_builder = request_builder.header("x-artifact-tag", tag);
+            }
 
-        let response = retry::make_retryable_request(request_builder).await?;
+            Ok(request_builder)
+        })
+        .await?;
 
         if response.status() == StatusCode::FORBIDDEN {
             return Err(Self::handle_403(response).await);
@@ -639,11 +647,17 @@ fn build_user_agent(version: &str) -> String {
 
 #[cfg(test)]
 mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
     use anyhow::Result;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
     use turborepo_vercel_api_mock::start_test_server;
     use url::Url;
 
-    use crate::{APIClient, Client};
+    use crate::{APIClient, Body, CacheClient, Client};
 
     #[tokio::test]
     async fn test_do_preflight() -> Result<()> {
@@ -727,4 +741,67 @@ mod test {
         let err = APIClient::handle_403(response).await;
         assert_eq!(err.to_string(), "unknown status forbidden: Not authorized");
     }
+
+    #[tokio::test]
+    async fn test_put_artifact_retries_dropped_connection() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        // Large enough that the body is still being sent when the connection
+        // drops
+        let artifact = (0..8 * 1024 * 1024)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let server = tokio::spawn(async move {
+            // Drop the first connection after receiving part of the body
+            let (mut socket, _) = listener.accept().await?;
+            let mut partial = vec![0; 64 * 1024];
+            socket.read_exact(&mut partial).await?;
+            drop(socket);
+
+            let (mut socket, _) = listener.accept().await?;
+            let (read_half, mut write_half) = socket.split();
+            let mut reader = BufReader::new(read_half);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).await?;
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse()?;
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).await?;
+            write_half
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await?;
+            anyhow::Ok(body)
+        });
+
+        let client = APIClient::new(&base_url, 200, "2.0.0", false)?;
+        let attempts = AtomicUsize::new(0);
+        client
+            .put_artifact(
+                "hash",
+                &|| {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    Ok(Body::from(artifact.clone()))
+                },
+                artifact.len() as u64,
+                100,
+                None,
+                "token",
+                None,
+                None,
+            )
+            .await?;
+
+        // The retry sent a new body with the whole artifact
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert!(server.await?? == artifact);
+        Ok(())
+    }
 }
@@ -15,6 +15,7 @@ const RETRY_MAX: u32 = 2;
 ///
 /// * `request_builder`: The request builder with everything, i.e. headers and
 ///   body already set. NOTE: This must be cloneable, so no streams are allowed.
+///   Use `make_retryable_request_with` for requests with streamed bodies.
 ///
 /// returns: Result<Response, Error>
 pub(crate) async fn make_retryable_request(
@@ -49,16 +50,53 @@ pub(crate) async fn make_retryable_request(
     Err(Error::TooManyFailures(Box::new(last_error.unwrap())))
 }
 
-fn should_retry_request(error: &reqwest::Error) -> bool {
-    if let Some(status) = error.status() {
-        if status == StatusCode::TOO_MANY_REQUESTS {
-            return true;
+/// Like `make_retryable_request`, but builds a new request for every attempt,
+/// so that requests with streamed bodies can be retried by recreating their
+/// body. Responses with a retryable status and requests that fail to be sent,
+/// such as when the connection drops while the body is uploaded, are retried
+/// too.
+///
+/// # Arguments
+///
+/// * `build_request`: Builds the request for an attempt, with headers and body
+///   set.
+///
+/// returns: Result<Response, Error>
+pub(crate) async fn make_retryable_request_with(
+    build_request: impl Fn() -> Result<RequestBuilder, Error>,
+) -> Result<Response, Error> {
+    let mut last_error = None;
+    for retry_count in 0..RETRY_MAX {
+        match build_request()?.send().await {
+            Ok(response) => {
+                if !should_retry_status(response.status()) {
+                    return Ok(response);
+                }
+                last_error = response.error_for_status().err();
+            }
+            Err(err) => {
+                if !(should_retry_request(&err) || err.is_request()) {
+                    return Err(err.into());
+                }
+                last_error = Some(err);
+            }
         }
 
-        if status.as_u16() >= 500 && status.as_u16() != 501 {
-            return true;
-        }
+        let sleep_period = (2_u64)
+            .pow(retry_count)
+            .clamp(MIN_SLEEP_TIME_SECS, MAX_SLEEP_TIME_SECS);
+        sleep(std::time::Duration::from_secs(sleep_period)).await;
     }
 
-    false
+    Err(Error::TooManyFailures(Box::new(
+        last_error.expect("retryable responses are errors"),
+    )))
+}
+
+fn should_retry_request(error: &reqwest::Error) -> bool {
+    error.status().map_or(false, should_retry_status)
+}
+
+fn should_retry_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || (status.as_u16() >= 500 && status.as_u16() != 501)
 }
@@ -0,0 +1,142 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("missing or invalid access token")]
+    Unauthorized,
+    #[error("access token is not allowed to access this team")]
+    Forbidden,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid team token \"{0}\", expected a value like \"my-team=token\"")]
+pub struct ParseTeamTokenError(String);
+
+/// A token that only grants access to a single team's artifacts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamToken {
+    pub team: String,
+    pub token: String,
+}
+
+impl FromStr for TeamToken {
+    type Err = ParseTeamTokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((team, token)) if !team.is_empty() && !token.is_empty() => Ok(Self {
+                team: team.to_string(),
+                token: token.to_string(),
+            }),
+            _ => Err(ParseTeamTokenError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Access {
+    AllTeams,
+    Teams(HashSet<String>),
+}
+
+/// Maps bearer tokens to the teams they can read and write artifacts for
+#[derive(Debug, Default)]
+pub struct Auth {
+    tokens: HashMap<String, Access>,
+}
+
+impl Auth {
+    /// `tokens` can access the personal namespace and every team, while
+    /// `team_tokens` are limited to their team.
+    pub fn new(tokens: &[String], team_tokens: &[TeamToken]) -> Self {
+        let mut access = HashMap::new();
+        for TeamToken { team, token } in team_tokens {
+            let entry = access
+                .entry(token.clone())
+                .or_insert_with(|| Access::Teams(HashSet::new()));
+            if let Access::Teams(teams) = entry {
+                teams.insert(team.clone());
+            }
+        }
+        for token in tokens {
+            access.insert(token.clone(), Access::AllTeams);
+        }
+        Self { tokens: access }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Checks the request's bearer token against the requested team. Team
+    /// scoped tokens must always name their team.
+    pub fn authorize(&self, headers: &HeaderMap, team: Option<&str>) -> Result<(), AuthError> {
+        let access = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| self.tokens.get(token.trim()))
+            .ok_or(AuthError::Unauthorized)?;
+
+        match (access, team) {
+            (Access::AllTeams, _) => Ok(()),
+            (Access::Teams(teams), Some(team)) if teams.contains(team) => Ok(()),
+            (Access::Teams(_), _) => Err(AuthError::Forbidden),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn auth() -> Auth {
+        Auth::new(
+            &["admin".to_string()],
+            &[
+                "team_a=scoped".parse().unwrap(),
+                "team_b=scoped".parse().unwrap(),
+            ],
+        )
+    }
+
+    fn headers(token: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = token {
+            headers.insert(AUTHORIZATION, format!("Bearer {token}").parse().unwrap());
+        }
+        headers
+    }
+
+    #[test_case(Some("admin"), None, Ok(()) ; "admin personal")]
+    #[test_case(Some("admin"), Some("team_c"), Ok(()) ; "admin any team")]
+    #[test_case(Some("scoped"), Some("team_a"), Ok(()) ; "scoped own team")]
+    #[test_case(Some("scoped"), Some("team_b"), Ok(()) ; "scoped second team")]
+    #[test_case(Some("scoped"), Some("team_c"), Err(AuthError::Forbidden) ; "scoped other team")]
+    #[test_case(Some("scoped"), None, Err(AuthError::Forbidden) ; "scoped personal")]
+    #[test_case(Some("wrong"), None, Err(AuthError::Unauthorized) ; "unknown token")]
+    #[test_case(None, None, Err(AuthError::Unauthorized) ; "missing token")]
+    fn test_authorize(token: Option<&str>, team: Option<&str>, expected: Result<(), AuthError>) {
+        assert_eq!(auth().authorize(&headers(token), team), expected);
+    }
+
+    #[test_case("team=token", Some(("team", "token")) ; "valid")]
+    #[test_case("team=to=ken", Some(("team", "to=ken")) ; "token with equals")]
+    #[test_case("token", None ; "missing team")]
+    #[test_case("=token", None ; "empty team")]
+    fn test_parse_team_token(input: &str, expected: Option<(&str, &str)>) {
+        let actual = input.parse::<TeamToken>().ok();
+        assert_eq!(
+            actual.as_ref().map(|t| (t.team.as_str(), t.token.as_str())),
+            expected
+        );
+    }
+}
@@ -0,0 +1,515 @@
+//! A self-hostable Turborepo remote cache.
+//!
+//! Implements the artifact routes of the Vercel Remote Cache API on top of
+//! persistent disk storage so that `turbo` can point `--api` at it.
+#![deny(clippy::all)]
+
+mod auth;
+mod metrics;
+mod storage;
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
+
+pub use auth::{Auth, AuthError, TeamToken};
+use axum::{
+    body::StreamBody,
+    extract::{BodyStream, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+use tokio_util::io::ReaderStream;
+use tracing::{error, info, warn};
+use turborepo_cache::eviction::{parse_age, parse_size, EvictionOpts};
+use turborepo_vercel_api::{AnalyticsEvent, CachingStatus, CachingStatusResponse};
+
+pub use crate::{
+    metrics::Metrics,
+    storage::{ArtifactMetadata, Storage, StorageStats},
+};
+
+const ARTIFACT_DURATION_HEADER: &str = "x-artifact-duration";
+const ARTIFACT_TAG_HEADER: &str = "x-artifact-tag";
+// How often age based eviction runs in the background. Size based eviction
+// also runs after every upload.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "turbo-cache-server",
+    version,
+    about = "A self-hostable Turborepo remote cache"
+)]
+pub struct Args {
+    /// The address to listen on
+    #[arg(long, env = "TURBO_CACHE_SERVER_HOST", default_value = "0.0.0.0")]
+    pub host: IpAddr,
+    /// The port to listen on
+    #[arg(long, env = "TURBO_CACHE_SERVER_PORT", default_value_t = 3000)]
+    pub port: u16,
+    /// The directory artifacts are stored in
+    #[arg(long, env = "TURBO_CACHE_SERVER_STORAGE_DIR")]
+    pub storage_dir: PathBuf,
+    /// A token that can access every team's artifacts. Can be passed
+    /// multiple times.
+    #[arg(
+        long = "token",
+        env = "TURBO_CACHE_SERVER_TOKENS",
+        value_delimiter = ','
+    )]
+    pub tokens: Vec<String>,
+    /// A token that can only access a single team's artifacts, in the form
+    /// `<team>=<token>`. The team is the `--team` passed to turbo. Can be
+    /// passed multiple times.
+    #[arg(
+        long = "team-token",
+        env = "TURBO_CACHE_SERVER_TEAM_TOKENS",
+        value_delimiter = ','
+    )]
+    pub team_tokens: Vec<TeamToken>,
+    /// Evict the least recently used artifacts once the stored artifacts
+    /// exceed this size, e.g. `50GB`
+    #[arg(long, env = "TURBO_CACHE_SERVER_MAX_SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+    /// Evict artifacts that haven't been read or written for this long,
+    /// e.g. `30d`
+    #[arg(long, env = "TURBO_CACHE_SERVER_MAX_AGE", value_parser = parse_age)]
+    pub max_age: Option<Duration>,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("no access tokens configured, pass --token or --team-token")]
+    NoTokens,
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error("invalid {ARTIFACT_DURATION_HEADER} header")]
+    InvalidDuration,
+    #[error("invalid {ARTIFACT_TAG_HEADER} header")]
+    InvalidTag,
+    #[error(transparent)]
+    Storage(#[from] storage::Error),
+    #[error("failed to run server: {0}")]
+    Serve(String),
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            Error::Auth(AuthError::Unauthorized) => (StatusCode::UNAUTHORIZED, "unauthorized"),
+            Error::Auth(AuthError::Forbidden) => (StatusCode::FORBIDDEN, "forbidden"),
+            Error::InvalidDuration
+            | Error::InvalidTag
+            | Error::Storage(storage::Error::InvalidHash(_) | storage::Error::InvalidTeam(_)) => {
+                (StatusCode::BAD_REQUEST, "bad_request")
+            }
+            _ => {
+                error!("{self}");
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal_error")
+            }
+        };
+        // Matches the error format of the Vercel API so that turbo can
+        // surface the message
+        let body = json!({ "error": { "code": code, "message": self.to_string() } });
+        (status, Json(body)).into_response()
+    }
+}
+
+pub struct AppState {
+    pub auth: Auth,
+    pub storage: Storage,
+    pub metrics: Metrics,
+    started_at: Instant,
+}
+
+impl AppState {
+    pub fn new(auth: Auth, storage: Storage) -> Self {
+        Self {
+            auth,
+            storage,
+            metrics: Metrics::default(),
+            started_at: Instant::now(),
+        }
+    }
+
+    async fn evict(&self) {
+        match self.storage.evict().await {
+            Ok(evicted) => {
+                self.metrics
+                    .evictions
+                    .fetch_add(evicted as u64, Ordering::Relaxed);
+            }
+            Err(err) => warn!("failed to evict artifacts: {err}"),
+        }
+    }
+}
+
+/// Identifies the team an artifact belongs to. turbo sends `teamId` for
+/// team ids and `slug` for team slugs.
+#[derive(Debug, Default, Deserialize)]
+pub struct TeamParams {
+    #[serde(rename = "teamId")]
+    team_id: Option<String>,
+    slug: Option<String>,
+}
+
+impl TeamParams {
+    fn team(&self) -> Option<&str> {
+        self.team_id.as_deref().or(self.slug.as_deref())
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusResponse {
+    status: &'static str,
+    artifacts: u64,
+    bytes: u64,
+    max_bytes: Option<u64>,
+    uptime_seconds: u64,
+}
+
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/metrics", get(metrics))
+        .route("/v8/artifacts/status", get(caching_status))
+        .route("/v8/artifacts/events", post(record_events))
+        .route(
+            "/v8/artifacts/:hash",
+            get(get_artifact).head(head_artifact).put(put_artifact),
+        )
+        .with_state(state)
+}
+
+/// Opens the storage directory and serves the cache until the process is
+/// interrupted.
+pub async fn serve(args: Args) -> Result<(), Error> {
+    let auth = Auth::new(&args.tokens, &args.team_tokens);
+    if auth.is_empty() {
+        return Err(Error::NoTokens);
+    }
+    let eviction = EvictionOpts {
+        max_size: args.max_size,
+        max_age: args.max_age,
+    };
+    let storage = Storage::open(args.storage_dir, eviction).await?;
+    let state = Arc::new(AppState::new(auth, storage));
+    state.evict().await;
+
+    if eviction.max_age.is_some() {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(EVICTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                state.evict().await;
+            }
+        });
+    }
+
+    let addr = SocketAddr::new(args.host, args.port);
+    info!("listening on {addr}");
+    axum::Server::bind(&addr)
+        .serve(router(state).into_make_service())
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|err| Error::Serve(err.to_string()))
+}
+
+async fn status(State(state): State<Arc<AppState>>) -> Json<StatusResponse> {
+    let stats = state.storage.stats();
+    Json(StatusResponse {
+        status: "ok",
+        artifacts: stats.artifacts,
+        bytes: stats.bytes,
+        max_bytes: state.storage.eviction().max_size,
+        uptime_seconds: state.started_at.elapsed().as_secs(),
+    })
+}
+
+async fn metrics(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let body = state
+        .metrics
+        .render(state.storage.stats(), state.storage.eviction().max_size);
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+async fn caching_status(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+) -> Result<Json<CachingStatusResponse>, Error> {
+    state.auth.authorize(&headers, params.team())?;
+    Ok(Json(CachingStatusResponse {
+        status: CachingStatus::Enabled,
+    }))
+}
+
+async fn record_events(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+    Json(events): Json<Vec<AnalyticsEvent>>,
+) -> Result<StatusCode, Error> {
+    state.auth.authorize(&headers, params.team())?;
+    state
+        .metrics
+        .analytics_events
+        .fetch_add(events.len() as u64, Ordering::Relaxed);
+    Ok(StatusCode::OK)
+}
+
+async fn head_artifact(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    state.auth.authorize(&headers, params.team())?;
+    let Some(metadata) = state.storage.metadata(params.team(), &hash).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    Ok((artifact_headers(&metadata), StatusCode::OK).into_response())
+}
+
+async fn get_artifact(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    state.auth.authorize(&headers, params.team())?;
+    let Some((file, size, metadata)) = state.storage.get(params.team(), &hash).await? else {
+        state.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    state.metrics.hits.fetch_add(1, Ordering::Relaxed);
+    state
+        .metrics
+        .downloaded_bytes
+        .fetch_add(size, Ordering::Relaxed);
+
+    let mut headers = artifact_headers(&metadata);
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(size));
+    Ok((headers, StreamBody::new(ReaderStream::new(file))).into_response())
+}
+
+async fn put_artifact(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+    body: BodyStream,
+) -> Result<Response, Error> {
+    state.auth.authorize(&headers, params.team())?;
+    let duration = match headers.get(ARTIFACT_DURATION_HEADER) {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|duration| duration.parse().ok())
+            .ok_or(Error::InvalidDuration)?,
+        None => 0,
+    };
+    let tag = headers
+        .get(ARTIFACT_TAG_HEADER)
+        .map(|value| value.to_str().map(str::to_owned))
+        .transpose()
+        .map_err(|_| Error::InvalidTag)?;
+
+    let metadata = ArtifactMetadata { duration, tag };
+    let size = state
+        .storage
+        .put(params.team(), &hash, &metadata, body)
+        .await?;
+    state.metrics.uploads.fetch_add(1, Ordering::Relaxed);
+    state
+        .metrics
+        .uploaded_bytes
+        .fetch_add(size, Ordering::Relaxed);
+
+    if state.storage.eviction().max_size.is_some() {
+        state.evict().await;
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(json!({ "urls": [hash] }))).into_response())
+}
+
+fn artifact_headers(metadata: &ArtifactMetadata) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        ARTIFACT_DURATION_HEADER,
+        HeaderValue::from(metadata.duration),
+    );
+    if let Some(tag) = metadata
+        .tag
+        .as_deref()
+        .and_then(|tag| HeaderValue::from_str(tag).ok())
+    {
+        headers.insert(ARTIFACT_TAG_HEADER, tag);
+    }
+    headers
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{
+        body::{Body, HttpBody},
+        http::{Method, Request},
+    };
+    use test_case::test_case;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    const TOKEN: &str = "token";
+    const TEAM_TOKEN: &str = "team-token";
+
+    async fn app(dir: &std::path::Path, eviction: EvictionOpts) -> Router {
+        let auth = Auth::new(
+            &[TOKEN.to_string()],
+            &[TeamToken {
+                team: "team_a".to_string(),
+                token: TEAM_TOKEN.to_string(),
+            }],
+        );
+        let storage = Storage::open(dir.to_owned(), eviction).await.unwrap();
+        router(Arc::new(AppState::new(auth, storage)))
+    }
+
+    fn request(method: Method, uri: &str, token: Option<&str>, body: Body) -> Request<Body> {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        builder.body(body).unwrap()
+    }
+
+    async fn body_bytes(response: Response) -> Vec<u8> {
+        let mut body = response.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_artifact_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = app(dir.path(), EvictionOpts::default()).await;
+
+        let put = Request::builder()
+            .method(Method::PUT)
+            .uri("/v8/artifacts/abc?teamId=team_a")
+            .header(header::AUTHORIZATION, format!("Bearer {TEAM_TOKEN}"))
+            .header(ARTIFACT_DURATION_HEADER, "123")
+            .header(ARTIFACT_TAG_HEADER, "ed25519:key:signature")
+            .body(Body::from("artifact contents"))
+            .unwrap();
+        let response = app.clone().oneshot(put).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+        let get = request(
+            Method::GET,
+            "/v8/artifacts/abc?slug=team_a",
+            Some(TOKEN),
+            Body::empty(),
+        );
+        let response = app.clone().oneshot(get).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ARTIFACT_DURATION_HEADER], "123");
+        assert_eq!(
+            response.headers()[ARTIFACT_TAG_HEADER],
+            "ed25519:key:signature"
+        );
+        assert_eq!(body_bytes(response).await, b"artifact contents");
+
+        // Artifacts are scoped to their team
+        let head = request(
+            Method::HEAD,
+            "/v8/artifacts/abc",
+            Some(TOKEN),
+            Body::empty(),
+        );
+        let response = app.clone().oneshot(head).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let metrics = request(Method::GET, "/metrics", None, Body::empty());
+        let response = app.oneshot(metrics).await.unwrap();
+        let metrics = String::from_utf8(body_bytes(response).await).unwrap();
+        assert!(metrics.contains("turbo_cache_uploads_total 1\n"));
+        assert!(metrics.contains("turbo_cache_hits_total 1\n"));
+        assert!(metrics.contains("turbo_cache_artifacts 1\n"));
+    }
+
+    #[test_case(None, "/v8/artifacts/abc", StatusCode::UNAUTHORIZED ; "missing token")]
+    #[test_case(Some("wrong"), "/v8/artifacts/abc", StatusCode::UNAUTHORIZED ; "unknown token")]
+    #[test_case(Some(TEAM_TOKEN), "/v8/artifacts/abc?teamId=team_b", StatusCode::FORBIDDEN ; "other team")]
+    #[test_case(Some(TOKEN), "/v8/artifacts/..%2Fabc", StatusCode::BAD_REQUEST ; "invalid hash")]
+    #[test_case(Some(TOKEN), "/v8/artifacts/abc", StatusCode::NOT_FOUND ; "missing artifact")]
+    #[test_case(Some(TOKEN), "/v8/artifacts/status", StatusCode::OK ; "caching status")]
+    #[test_case(None, "/status", StatusCode::OK ; "server status")]
+    #[tokio::test]
+    async fn test_get_status_codes(token: Option<&str>, uri: &str, expected: StatusCode) {
+        let dir = tempfile::tempdir().unwrap();
+        let app = app(dir.path(), EvictionOpts::default()).await;
+        let response = app
+            .oneshot(request(Method::GET, uri, token, Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_upload_evicts_when_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = app(
+            dir.path(),
+            EvictionOpts {
+                max_size: Some(40),
+                max_age: None,
+            },
+        )
+        .await;
+
+        for hash in ["first", "second"] {
+            let put = request(
+                Method::PUT,
+                &format!("/v8/artifacts/{hash}"),
+                Some(TOKEN),
+                Body::from("twenty bytes of data"),
+            );
+            let response = app.clone().oneshot(put).await.unwrap();
+            assert_eq!(response.status(), StatusCode::ACCEPTED);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        for (hash, expected) in [("first", StatusCode::NOT_FOUND), ("second", StatusCode::OK)] {
+            let head = request(
+                Method::HEAD,
+                &format!("/v8/artifacts/{hash}"),
+                Some(TOKEN),
+                Body::empty(),
+            );
+            let response = app.clone().oneshot(head).await.unwrap();
+            assert_eq!(response.status(), expected, "{hash}");
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use anyhow::Result;
+use clap::Parser;
+use turborepo_cache_server::{serve, Args};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+    serve(Args::parse()).await?;
+    Ok(())
+}
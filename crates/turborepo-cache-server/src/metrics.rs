@@ -0,0 +1,87 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::storage::StorageStats;
+
+/// Counters exposed on the `/metrics` endpoint
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
+    pub uploads: AtomicU64,
+    pub uploaded_bytes: AtomicU64,
+    pub downloaded_bytes: AtomicU64,
+    pub evictions: AtomicU64,
+    pub analytics_events: AtomicU64,
+}
+
+impl Metrics {
+    /// Renders the counters and current storage usage in the Prometheus text
+    /// exposition format
+    pub fn render(&self, stats: StorageStats, max_bytes: Option<u64>) -> String {
+        let counters = [
+            (
+                "hits_total",
+                "Artifact downloads that found an artifact",
+                &self.hits,
+            ),
+            (
+                "misses_total",
+                "Artifact lookups that found nothing",
+                &self.misses,
+            ),
+            ("uploads_total", "Artifacts uploaded", &self.uploads),
+            (
+                "uploaded_bytes_total",
+                "Bytes of artifacts uploaded",
+                &self.uploaded_bytes,
+            ),
+            (
+                "downloaded_bytes_total",
+                "Bytes of artifacts downloaded",
+                &self.downloaded_bytes,
+            ),
+            (
+                "evictions_total",
+                "Artifacts removed by eviction",
+                &self.evictions,
+            ),
+            (
+                "analytics_events_total",
+                "Cache analytics events received",
+                &self.analytics_events,
+            ),
+        ];
+
+        let mut output = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+            // Writing to a String can't fail
+            let _ = write!(
+                output,
+                "# HELP turbo_cache_{name} {help}\n# TYPE turbo_cache_{name} \
+                 {kind}\nturbo_cache_{name} {value}\n"
+            );
+        };
+        metric(
+            "artifacts",
+            "gauge",
+            "Artifacts currently stored",
+            stats.artifacts,
+        );
+        metric("bytes", "gauge", "Bytes currently stored", stats.bytes);
+        if let Some(max_bytes) = max_bytes {
+            metric(
+                "max_bytes",
+                "gauge",
+                "Configured storage limit in bytes",
+                max_bytes,
+            );
+        }
+        for (name, help, counter) in counters {
+            metric(name, "counter", help, counter.load(Ordering::Relaxed));
+        }
+        output
+    }
+}
@@ -0,0 +1,437 @@
+//! Persistent artifact storage.
+//!
+//! Artifacts uploaded without a team are stored at `<root>/personal/<hash>`
+//! and team artifacts at `<root>/teams/<team>/<hash>`. Each artifact has a
+//! `<hash>.json` metadata file next to it holding the task duration and the
+//! signature tag sent by the client. Uploads are written to a temporary file
+//! first and renamed into place so readers never see a partial artifact.
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+use axum::body::Bytes;
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{debug, warn};
+use turborepo_cache::eviction::{select_evictions, CacheEntry, EvictionOpts};
+
+const PERSONAL_DIR: &str = "personal";
+const TEAMS_DIR: &str = "teams";
+const METADATA_EXTENSION: &str = "json";
+const TEMP_EXTENSION: &str = "tmp";
+// Hashes and team identifiers are used as path components, so they are
+// limited to a conservative character set
+const MAX_NAME_LENGTH: usize = 128;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid artifact hash \"{0}\"")]
+    InvalidHash(String),
+    #[error("invalid team \"{0}\"")]
+    InvalidTeam(String),
+    #[error("failed to access artifact storage: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid artifact metadata: {0}")]
+    Metadata(#[from] serde_json::Error),
+    #[error("failed to read artifact upload: {0}")]
+    Upload(#[source] axum::Error),
+}
+
+/// Information the client sends alongside an artifact that has to be
+/// returned when the artifact is fetched
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactMetadata {
+    /// Duration of the original task execution in milliseconds
+    pub duration: u64,
+    /// Signature of the artifact, verified by the client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+/// Totals across every stored artifact
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageStats {
+    pub artifacts: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    size: u64,
+    last_accessed: SystemTime,
+}
+
+pub struct Storage {
+    root: PathBuf,
+    eviction: EvictionOpts,
+    // Keyed by the artifact path relative to `root`
+    index: Mutex<HashMap<String, IndexEntry>>,
+    temp_counter: AtomicU64,
+}
+
+impl Storage {
+    /// Opens the storage directory, creating it if needed, and indexes the
+    /// artifacts left by previous runs.
+    pub async fn open(root: PathBuf, eviction: EvictionOpts) -> Result<Self, Error> {
+        fs::create_dir_all(root.join(PERSONAL_DIR)).await?;
+        fs::create_dir_all(root.join(TEAMS_DIR)).await?;
+
+        let mut index = HashMap::new();
+        index_dir(&root, PERSONAL_DIR, &mut index).await?;
+        let mut teams = fs::read_dir(root.join(TEAMS_DIR)).await?;
+        while let Some(team) = teams.next_entry().await? {
+            let Some(team_name) = team.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            if team.file_type().await?.is_dir() && is_valid_name(&team_name) {
+                index_dir(&root, &format!("{TEAMS_DIR}/{team_name}"), &mut index).await?;
+            }
+        }
+        debug!("indexed {} artifacts in {}", index.len(), root.display());
+
+        Ok(Self {
+            root,
+            eviction,
+            index: Mutex::new(index),
+            temp_counter: AtomicU64::new(0),
+        })
+    }
+
+    pub fn eviction(&self) -> &EvictionOpts {
+        &self.eviction
+    }
+
+    pub fn stats(&self) -> StorageStats {
+        let index = self.index.lock().expect("storage index lock poisoned");
+        StorageStats {
+            artifacts: index.len() as u64,
+            bytes: index.values().map(|entry| entry.size).sum(),
+        }
+    }
+
+    /// Returns the metadata of an artifact without touching its access time
+    pub async fn metadata(
+        &self,
+        team: Option<&str>,
+        hash: &str,
+    ) -> Result<Option<ArtifactMetadata>, Error> {
+        let key = artifact_key(team, hash)?;
+        if !self.contains(&key) {
+            return Ok(None);
+        }
+        self.read_metadata(&key).await
+    }
+
+    /// Opens an artifact for reading and marks it as recently used
+    pub async fn get(
+        &self,
+        team: Option<&str>,
+        hash: &str,
+    ) -> Result<Option<(fs::File, u64, ArtifactMetadata)>, Error> {
+        let key = artifact_key(team, hash)?;
+        if !self.contains(&key) {
+            return Ok(None);
+        }
+        let Some(metadata) = self.read_metadata(&key).await? else {
+            return Ok(None);
+        };
+        let file = match fs::File::open(self.root.join(&key)).await {
+            Ok(file) => file,
+            // Evicted between the index lookup and opening the file
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let size = file.metadata().await?.len();
+
+        let now = SystemTime::now();
+        if let Some(entry) = self
+            .index
+            .lock()
+            .expect("storage index lock poisoned")
+            .get_mut(&key)
+        {
+            entry.last_accessed = now;
+        }
+        // Persist the access time so eviction order survives restarts
+        if let Err(err) = touch(&self.root.join(&key), now) {
+            debug!("failed to update access time of {key}: {err}");
+        }
+
+        Ok(Some((file, size, metadata)))
+    }
+
+    /// Stores an artifact, replacing any existing artifact with the same
+    /// hash. Returns the number of bytes written.
+    pub async fn put<S>(
+        &self,
+        team: Option<&str>,
+        hash: &str,
+        metadata: &ArtifactMetadata,
+        mut body: S,
+    ) -> Result<u64, Error>
+    where
+        S: Stream<Item = Result<Bytes, axum::Error>> + Unpin,
+    {
+        let key = artifact_key(team, hash)?;
+        let artifact_path = self.root.join(&key);
+        let metadata_path = metadata_path(&artifact_path);
+        let parent = artifact_path
+            .parent()
+            .expect("artifact path has a parent directory");
+        fs::create_dir_all(parent).await?;
+
+        let temp_path = self.temp_path(parent, hash);
+        let result = async {
+            let mut file = fs::File::create(&temp_path).await?;
+            let mut size = 0;
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk.map_err(Error::Upload)?;
+                file.write_all(&chunk).await?;
+                size += chunk.len() as u64;
+            }
+            file.sync_all().await?;
+
+            let metadata_contents = serde_json::to_vec(metadata)?;
+            fs::write(&metadata_path, &metadata_contents).await?;
+            fs::rename(&temp_path, &artifact_path).await?;
+            Ok::<_, Error>(size + metadata_contents.len() as u64)
+        }
+        .await;
+
+        let size = match result {
+            Ok(size) => size,
+            Err(err) => {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(err);
+            }
+        };
+
+        self.index
+            .lock()
+            .expect("storage index lock poisoned")
+            .insert(
+                key,
+                IndexEntry {
+                    size,
+                    last_accessed: SystemTime::now(),
+                },
+            );
+
+        Ok(size)
+    }
+
+    /// Removes artifacts until the configured size and age limits are
+    /// satisfied. Returns the number of artifacts removed.
+    pub async fn evict(&self) -> Result<usize, Error> {
+        if !self.eviction.is_enabled() {
+            return Ok(0);
+        }
+
+        let evicted = {
+            let mut index = self.index.lock().expect("storage index lock poisoned");
+            let entries = index
+                .iter()
+                .map(|(key, entry)| CacheEntry {
+                    hash: key.clone(),
+                    size: entry.size,
+                    duration: 0,
+                    last_accessed: entry.last_accessed,
+                })
+                .collect::<Vec<_>>();
+            let evicted = select_evictions(&entries, &self.eviction, SystemTime::now())
+                .into_iter()
+                .map(|entry| entry.hash.clone())
+                .collect::<Vec<_>>();
+            // Remove from the index first so the artifacts are no longer
+            // served while their files are deleted
+            for key in &evicted {
+                index.remove(key);
+            }
+            evicted
+        };
+
+        for key in &evicted {
+            let artifact_path = self.root.join(key);
+            for path in [metadata_path(&artifact_path), artifact_path] {
+                if let Err(err) = fs::remove_file(&path).await {
+                    if err.kind() != io::ErrorKind::NotFound {
+                        warn!("failed to evict {}: {err}", path.display());
+                    }
+                }
+            }
+        }
+
+        Ok(evicted.len())
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.index
+            .lock()
+            .expect("storage index lock poisoned")
+            .contains_key(key)
+    }
+
+    async fn read_metadata(&self, key: &str) -> Result<Option<ArtifactMetadata>, Error> {
+        match fs::read(metadata_path(&self.root.join(key))).await {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn temp_path(&self, dir: &Path, hash: &str) -> PathBuf {
+        let id = self.temp_counter.fetch_add(1, Ordering::Relaxed);
+        dir.join(format!(
+            ".{hash}.{}.{id}.{TEMP_EXTENSION}",
+            std::process::id()
+        ))
+    }
+}
+
+// Adds every artifact in `dir` to the index and cleans up uploads that were
+// interrupted by a previous shutdown
+async fn index_dir(
+    root: &Path,
+    dir: &str,
+    index: &mut HashMap<String, IndexEntry>,
+) -> Result<(), Error> {
+    let mut entries = fs::read_dir(root.join(dir)).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == TEMP_EXTENSION) {
+            fs::remove_file(&path).await?;
+            continue;
+        }
+        let Some(hash) = entry.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+        if !is_valid_name(&hash) || !entry.file_type().await?.is_file() {
+            continue;
+        }
+        let Ok(metadata_file) = fs::metadata(metadata_path(&path)).await else {
+            warn!("ignoring artifact without metadata: {}", path.display());
+            continue;
+        };
+        let artifact_file = entry.metadata().await?;
+        index.insert(
+            format!("{dir}/{hash}"),
+            IndexEntry {
+                size: artifact_file.len() + metadata_file.len(),
+                last_accessed: artifact_file.modified()?,
+            },
+        );
+    }
+    Ok(())
+}
+
+fn artifact_key(team: Option<&str>, hash: &str) -> Result<String, Error> {
+    if !is_valid_name(hash) {
+        return Err(Error::InvalidHash(hash.to_string()));
+    }
+    match team {
+        Some(team) if !is_valid_name(team) => Err(Error::InvalidTeam(team.to_string())),
+        Some(team) => Ok(format!("{TEAMS_DIR}/{team}/{hash}")),
+        None => Ok(format!("{PERSONAL_DIR}/{hash}")),
+    }
+}
+
+fn touch(path: &Path, time: SystemTime) -> io::Result<()> {
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(path)?
+        .set_modified(time)
+}
+
+fn metadata_path(artifact_path: &Path) -> PathBuf {
+    artifact_path.with_extension(METADATA_EXTENSION)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use futures_util::stream;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn body(contents: &'static [u8]) -> impl Stream<Item = Result<Bytes, axum::Error>> + Unpin {
+        stream::iter([Ok(Bytes::from_static(contents))])
+    }
+
+    #[test_case(None, "abc123", Some("personal/abc123") ; "personal")]
+    #[test_case(Some("team_1"), "abc123", Some("teams/team_1/abc123") ; "team")]
+    #[test_case(None, "../abc", None ; "hash traversal")]
+    #[test_case(Some(".."), "abc", None ; "team traversal")]
+    #[test_case(None, "", None ; "empty hash")]
+    fn test_artifact_key(team: Option<&str>, hash: &str, expected: Option<&str>) {
+        assert_eq!(artifact_key(team, hash).ok().as_deref(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_and_reopen() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let metadata = ArtifactMetadata {
+            duration: 42,
+            tag: Some("tag".to_string()),
+        };
+
+        let storage = Storage::open(dir.path().to_owned(), EvictionOpts::default()).await?;
+        storage
+            .put(Some("team"), "abc", &metadata, body(b"artifact"))
+            .await?;
+        assert_eq!(storage.metadata(Some("team"), "abc").await?, Some(metadata));
+        assert_eq!(storage.metadata(None, "abc").await?, None);
+
+        let reopened = Storage::open(dir.path().to_owned(), EvictionOpts::default()).await?;
+        let (_, size, metadata) = reopened.get(Some("team"), "abc").await?.unwrap();
+        assert_eq!(size, 8);
+        assert_eq!(metadata.duration, 42);
+        assert_eq!(reopened.stats(), storage.stats());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let metadata = ArtifactMetadata::default();
+        let metadata_size = serde_json::to_vec(&metadata)?.len() as u64;
+        let eviction = EvictionOpts {
+            max_size: Some(2 * (4 + metadata_size)),
+            max_age: None,
+        };
+
+        let storage = Storage::open(dir.path().to_owned(), eviction).await?;
+        for hash in ["a", "b"] {
+            storage.put(None, hash, &metadata, body(b"1234")).await?;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // Reading "a" makes "b" the least recently used artifact
+        storage.get(None, "a").await?;
+        storage.put(None, "c", &metadata, body(b"1234")).await?;
+
+        assert_eq!(storage.evict().await?, 1);
+        assert!(storage.metadata(None, "a").await?.is_some());
+        assert!(storage.metadata(None, "b").await?.is_none());
+        assert!(storage.metadata(None, "c").await?.is_some());
+        assert!(!dir.path().join("personal/b").exists());
+        Ok(())
+    }
+}
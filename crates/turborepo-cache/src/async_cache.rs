@@ -197,7 +197,7 @@ mod tests {
 
     use crate::{
         test_cases::{get_test_cases, TestCase},
-        AsyncCache, CacheHitMetadata, CacheOpts, CacheSource, RemoteCacheOpts,
+        AsyncCache, CacheHitMetadata, CacheOpts, CacheSource, EvictionOpts, RemoteCacheOpts,
     };
 
     #[tokio::test]
@@ -232,7 +232,9 @@ mod tests {
             remote_cache_opts: Some(RemoteCacheOpts {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
+                shared_dir: None,
             }),
+            eviction_opts: EvictionOpts::default(),
         };
 
         let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
@@ -312,7 +314,9 @@ mod tests {
             remote_cache_opts: Some(RemoteCacheOpts {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
+                shared_dir: None,
             }),
+            eviction_opts: EvictionOpts::default(),
         };
 
         // Initialize client with invalid API url to ensure that we don't hit the
@@ -402,7 +406,9 @@ mod tests {
             remote_cache_opts: Some(RemoteCacheOpts {
                 unused_team_id: Some("my-team".to_string()),
                 signature: false,
+                shared_dir: None,
             }),
+            eviction_opts: EvictionOpts::default(),
         };
 
         let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
@@ -0,0 +1,203 @@
+use std::time::{Duration, SystemTime};
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("invalid cache size \"{0}\", expected a number of bytes or a value like \"10GB\"")]
+    InvalidSize(String),
+    #[error("invalid cache age \"{0}\", expected a number of seconds or a value like \"7d\"")]
+    InvalidAge(String),
+}
+
+/// Limits applied to the local file system cache. Entries are evicted least
+/// recently used first until every configured limit is satisfied.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvictionOpts {
+    /// The maximum total size of the cache directory in bytes
+    pub max_size: Option<u64>,
+    /// The maximum time since an entry was last written or restored
+    pub max_age: Option<Duration>,
+}
+
+impl EvictionOpts {
+    pub fn is_enabled(&self) -> bool {
+        self.max_size.is_some() || self.max_age.is_some()
+    }
+}
+
+/// A single artifact stored in the local file system cache
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub hash: String,
+    /// Combined size of the archive and its metadata file in bytes
+    pub size: u64,
+    /// Duration of the original task execution in milliseconds
+    pub duration: u64,
+    /// The last time this entry was written or restored
+    pub last_accessed: SystemTime,
+}
+
+/// Selects the entries that need to be removed in order to satisfy `opts`.
+///
+/// Entries older than `max_age` are always selected. If the remaining
+/// entries still exceed `max_size` the least recently used ones are selected
+/// until the cache fits.
+pub fn select_evictions<'a>(
+    entries: &'a [CacheEntry],
+    opts: &EvictionOpts,
+    now: SystemTime,
+) -> Vec<&'a CacheEntry> {
+    let mut by_access: Vec<_> = entries.iter().collect();
+    // Oldest first, hash is used as a tie breaker to keep results stable
+    by_access.sort_by(|a, b| {
+        a.last_accessed
+            .cmp(&b.last_accessed)
+            .then_with(|| a.hash.cmp(&b.hash))
+    });
+
+    let mut total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+    let mut evictions = Vec::new();
+    for entry in by_access {
+        let expired = opts.max_age.is_some_and(|max_age| {
+            now.duration_since(entry.last_accessed)
+                .is_ok_and(|age| age > max_age)
+        });
+        let over_size = opts.max_size.is_some_and(|max_size| total_size > max_size);
+        if !expired && !over_size {
+            // Since entries are sorted by age, none of the remaining ones can be expired
+            // and we're already within the size limit.
+            break;
+        }
+        total_size -= entry.size;
+        evictions.push(entry);
+    }
+
+    evictions
+}
+
+/// Parses a human readable size such as `500MB`, `10GB` or `1024` (bytes).
+/// Units are powers of 1024.
+pub fn parse_size(input: &str) -> Result<u64, ParseError> {
+    let err = || ParseError::InvalidSize(input.to_string());
+    let (number, unit) = split_unit(input.trim());
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        "t" | "tb" => 1 << 40,
+        _ => return Err(err()),
+    };
+    let number: f64 = number.parse().map_err(|_| err())?;
+    if !number.is_finite() || number < 0.0 {
+        return Err(err());
+    }
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Parses a human readable age such as `12h`, `7d`, `2w` or `3600` (seconds).
+pub fn parse_age(input: &str) -> Result<Duration, ParseError> {
+    let err = || ParseError::InvalidAge(input.to_string());
+    let (number, unit) = split_unit(input.trim());
+    let multiplier: u64 = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => return Err(err()),
+    };
+    let number: u64 = number.parse().map_err(|_| err())?;
+
+    number
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .ok_or_else(err)
+}
+
+fn split_unit(input: &str) -> (&str, &str) {
+    let unit_start = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(unit_start);
+    (number, unit.trim_start())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("1024", Ok(1024) ; "bare bytes")]
+    #[test_case("10KB", Ok(10 * 1024) ; "kilobytes")]
+    #[test_case("1.5 GB", Ok(3 * (1 << 29)) ; "fractional gigabytes")]
+    #[test_case("2t", Ok(2 * (1 << 40)) ; "short unit")]
+    #[test_case("10 parsecs", Err(ParseError::InvalidSize("10 parsecs".into())) ; "unknown unit")]
+    #[test_case("GB", Err(ParseError::InvalidSize("GB".into())) ; "missing number")]
+    fn test_parse_size(input: &str, expected: Result<u64, ParseError>) {
+        assert_eq!(parse_size(input), expected);
+    }
+
+    #[test_case("3600", Ok(Duration::from_secs(3600)) ; "bare seconds")]
+    #[test_case("12h", Ok(Duration::from_secs(12 * 60 * 60)) ; "hours")]
+    #[test_case("7d", Ok(Duration::from_secs(7 * 24 * 60 * 60)) ; "days")]
+    #[test_case("1.5d", Err(ParseError::InvalidAge("1.5d".into())) ; "fractional")]
+    #[test_case("7y", Err(ParseError::InvalidAge("7y".into())) ; "unknown unit")]
+    fn test_parse_age(input: &str, expected: Result<Duration, ParseError>) {
+        assert_eq!(parse_age(input), expected);
+    }
+
+    fn entry(hash: &str, size: u64, age_secs: u64, now: SystemTime) -> CacheEntry {
+        CacheEntry {
+            hash: hash.to_string(),
+            size,
+            duration: 0,
+            last_accessed: now - Duration::from_secs(age_secs),
+        }
+    }
+
+    #[test]
+    fn test_select_evictions() {
+        let now = SystemTime::now();
+        let entries = vec![
+            entry("newest", 10, 1, now),
+            entry("oldest", 10, 100, now),
+            entry("middle", 10, 50, now),
+        ];
+
+        let evicted_hashes = |opts: EvictionOpts| {
+            select_evictions(&entries, &opts, now)
+                .into_iter()
+                .map(|entry| entry.hash.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(evicted_hashes(EvictionOpts::default()).is_empty());
+        assert_eq!(
+            evicted_hashes(EvictionOpts {
+                max_size: Some(15),
+                max_age: None,
+            }),
+            vec!["oldest", "middle"]
+        );
+        assert_eq!(
+            evicted_hashes(EvictionOpts {
+                max_size: None,
+                max_age: Some(Duration::from_secs(60)),
+            }),
+            vec!["oldest"]
+        );
+        assert_eq!(
+            evicted_hashes(EvictionOpts {
+                max_size: Some(100),
+                max_age: Some(Duration::from_secs(10)),
+            }),
+            vec!["oldest", "middle"]
+        );
+    }
+}
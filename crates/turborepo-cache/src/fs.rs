@@ -1,16 +1,27 @@
-use std::{backtrace::Backtrace, fs::OpenOptions};
+use std::{
+    backtrace::Backtrace,
+    collections::HashMap,
+    fs::OpenOptions,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use camino::Utf8Path;
 use serde::{Deserialize, Serialize};
+use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{analytics, analytics::AnalyticsEvent};
 
 use crate::{
     cache_archive::{CacheReader, CacheWriter},
+    eviction::{select_evictions, CacheEntry, EvictionOpts},
     CacheError, CacheHitMetadata, CacheSource,
 };
 
+const UNCOMPRESSED_SUFFIX: &str = ".tar";
+const COMPRESSED_SUFFIX: &str = ".tar.zst";
+const METADATA_SUFFIX: &str = "-meta.json";
+
 pub struct FSCache {
     cache_directory: AbsoluteSystemPathBuf,
     analytics_recorder: Option<AnalyticsSender>,
@@ -97,6 +108,13 @@ impl FSCache {
 
         let restored_files = cache_reader.restore(anchor)?;
 
+        // The modification time of the archive doubles as the last access time
+        // that is used for eviction. Failing to update it isn't worth failing the
+        // cache hit over.
+        if let Err(e) = Self::touch(&cache_path) {
+            debug!("failed to record access time for {}: {}", hash, e);
+        }
+
         let meta = CacheMetadata::read(
             &self
                 .cache_directory
@@ -178,6 +196,122 @@ impl FSCache {
 
         Ok(())
     }
+
+    fn touch(path: &AbsoluteSystemPath) -> Result<(), std::io::Error> {
+        let mut options = OpenOptions::new();
+        options.write(true);
+        path.open_with_options(options)?
+            .set_modified(SystemTime::now())
+    }
+
+    /// Lists all of the artifacts currently stored in the cache directory
+    #[tracing::instrument(skip_all)]
+    pub fn entries(&self) -> Result<Vec<CacheEntry>, CacheError> {
+        struct PartialEntry {
+            size: u64,
+            last_accessed: Option<SystemTime>,
+        }
+
+        let mut partial_entries: HashMap<String, PartialEntry> = HashMap::new();
+        for dir_entry in std::fs::read_dir(self.cache_directory.as_std_path())? {
+            let dir_entry = dir_entry?;
+            let metadata = dir_entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let file_name = dir_entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+
+            let (hash, is_archive) = if let Some(hash) = file_name
+                .strip_suffix(COMPRESSED_SUFFIX)
+                .or_else(|| file_name.strip_suffix(UNCOMPRESSED_SUFFIX))
+            {
+                (hash, true)
+            } else if let Some(hash) = file_name.strip_suffix(METADATA_SUFFIX) {
+                (hash, false)
+            } else {
+                continue;
+            };
+
+            let partial_entry = partial_entries
+                .entry(hash.to_string())
+                .or_insert(PartialEntry {
+                    size: 0,
+                    last_accessed: None,
+                });
+            partial_entry.size += metadata.len();
+            if is_archive {
+                let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+                partial_entry.last_accessed = Some(
+                    partial_entry
+                        .last_accessed
+                        .map_or(modified, |last_accessed| last_accessed.max(modified)),
+                );
+            }
+        }
+
+        let mut entries: Vec<_> = partial_entries
+            .into_iter()
+            .map(|(hash, partial_entry)| {
+                let duration = CacheMetadata::read(
+                    &self
+                        .cache_directory
+                        .join_component(&format!("{}{}", hash, METADATA_SUFFIX)),
+                )
+                .map(|meta| meta.duration)
+                .unwrap_or(0);
+
+                CacheEntry {
+                    hash,
+                    size: partial_entry.size,
+                    duration,
+                    // A metadata file without an archive is left over from an
+                    // interrupted write and is treated as the oldest possible entry.
+                    last_accessed: partial_entry.last_accessed.unwrap_or(UNIX_EPOCH),
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| b.last_accessed.cmp(&a.last_accessed));
+
+        Ok(entries)
+    }
+
+    /// Removes every file belonging to the artifact with the given hash
+    #[tracing::instrument(skip_all)]
+    pub fn remove(&self, hash: &str) -> Result<(), CacheError> {
+        for suffix in [UNCOMPRESSED_SUFFIX, COMPRESSED_SUFFIX, METADATA_SUFFIX] {
+            let path = self
+                .cache_directory
+                .join_component(&format!("{}{}", hash, suffix));
+            match path.remove_file() {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evicts least recently used artifacts until the cache satisfies the
+    /// given limits. Returns the entries that were removed.
+    #[tracing::instrument(skip_all)]
+    pub fn evict(&self, opts: &EvictionOpts) -> Result<Vec<CacheEntry>, CacheError> {
+        if !opts.is_enabled() {
+            return Ok(Vec::new());
+        }
+
+        let entries = self.entries()?;
+        let evictions = select_evictions(&entries, opts, SystemTime::now());
+        for entry in &evictions {
+            debug!("evicting {} from local cache", entry.hash);
+            self.remove(&entry.hash)?;
+        }
+
+        Ok(evictions.into_iter().cloned().collect())
+    }
 }
 
 #[cfg(test)]
@@ -211,6 +345,54 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_fs_cache_eviction() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let test_file = AnchoredSystemPath::new("output.txt")?;
+        repo_root_path
+            .resolve(test_file)
+            .create_with_contents("some output")?;
+
+        let cache = FSCache::new(None, repo_root_path, None)?;
+        let now = SystemTime::now();
+        for (hash, age_secs) in [("new", 10), ("middle", 1000), ("old", 100000)] {
+            cache.put(repo_root_path, hash, &[test_file.to_owned()], 100)?;
+            let archive = cache
+                .cache_directory
+                .join_component(&format!("{}{}", hash, COMPRESSED_SUFFIX));
+            let mut options = OpenOptions::new();
+            options.write(true);
+            archive
+                .open_with_options(options)?
+                .set_modified(now - std::time::Duration::from_secs(age_secs))?;
+        }
+
+        let entries = cache.entries()?;
+        let hashes: Vec<_> = entries.iter().map(|entry| entry.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["new", "middle", "old"]);
+        assert!(entries.iter().all(|entry| entry.duration == 100));
+
+        // Restoring an artifact marks it as the most recently used
+        cache.fetch(repo_root_path, "old")?.unwrap();
+        let evicted = cache.evict(&EvictionOpts {
+            max_size: Some(entries[0].size * 2),
+            max_age: None,
+        })?;
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].hash, "middle");
+
+        let remaining: Vec<_> = cache
+            .entries()?
+            .into_iter()
+            .map(|entry| entry.hash)
+            .collect();
+        assert_eq!(remaining, vec!["old".to_string(), "new".to_string()]);
+        assert!(cache.exists("middle")?.is_none());
+
+        Ok(())
+    }
+
     async fn round_trip_test(test_case: &TestCase, port: u16) -> Result<()> {
         let repo_root = tempdir()?;
         let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
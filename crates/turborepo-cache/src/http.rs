@@ -1,19 +1,51 @@
-use std::{backtrace::Backtrace, io::Write};
+use std::{
+    backtrace::Backtrace,
+    io::{self, BufReader, BufWriter, Read, Write},
+};
 
+use futures::StreamExt;
+use tempfile::NamedTempFile;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{
     analytics::{self, AnalyticsEvent},
-    APIAuth, APIClient, CacheClient, Response,
+    APIAuth, APIClient, Body, CacheClient, Response,
 };
 
 use crate::{
     cache_archive::{CacheReader, CacheWriter},
-    signature_authentication::ArtifactSignatureAuthenticator,
+    signature_authentication::{ArtifactSignatureAuthenticator, TagGenerator},
     CacheError, CacheHitMetadata, CacheOpts, CacheSource,
 };
 
+// Buffer size used while spooling artifacts to and from disk
+const ARTIFACT_BUFFER_SIZE: usize = 1 << 20;
+
+// Counts the bytes of an artifact and feeds them to its tag generator as the
+// archive is written
+struct ArtifactWriter<'a, W> {
+    inner: W,
+    tag_generator: Option<&'a mut TagGenerator>,
+    size: u64,
+}
+
+impl<'a, W: Write> Write for ArtifactWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if let Some(tag_generator) = &mut self.tag_generator {
+            tag_generator.update(&buf[..written]);
+        }
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 pub struct HTTPCache {
     client: APIClient,
     signer_verifier: Option<ArtifactSignatureAuthenticator>,
@@ -30,32 +62,30 @@ impl HTTPCache {
         repo_root: AbsoluteSystemPathBuf,
         api_auth: APIAuth,
         analytics_recorder: Option<AnalyticsSender>,
-    ) -> HTTPCache {
-        let signer_verifier = if opts
+    ) -> Result<HTTPCache, CacheError> {
+        let signer_verifier = opts
             .remote_cache_opts
             .as_ref()
-            .map_or(false, |remote_cache_opts| remote_cache_opts.signature)
-        {
-            Some(ArtifactSignatureAuthenticator {
-                team_id: api_auth
-                    .team_id
-                    .as_deref()
-                    .unwrap_or_default()
-                    .as_bytes()
-                    .to_vec(),
-                secret_key_override: None,
+            .filter(|remote_cache_opts| remote_cache_opts.signature)
+            .map(|_| {
+                ArtifactSignatureAuthenticator::from_env(
+                    api_auth
+                        .team_id
+                        .as_deref()
+                        .unwrap_or_default()
+                        .as_bytes()
+                        .to_vec(),
+                )
             })
-        } else {
-            None
-        };
+            .transpose()?;
 
-        HTTPCache {
+        Ok(HTTPCache {
             client,
             signer_verifier,
             repo_root,
             api_auth,
             analytics_recorder,
-        }
+        })
     }
 
     #[tracing::instrument(skip_all)]
@@ -66,19 +96,48 @@ impl HTTPCache {
         files: &[AnchoredSystemPathBuf],
         duration: u64,
     ) -> Result<(), CacheError> {
-        let mut artifact_body = Vec::new();
-        self.write(&mut artifact_body, anchor, files).await?;
+        if let Some(signer) = &self.signer_verifier {
+            if !signer.can_sign() {
+                debug!("no signing key configured, skipping upload of {}", hash);
+                return Ok(());
+            }
+        }
 
-        let tag = self
+        let mut tag_generator = self
             .signer_verifier
             .as_ref()
-            .map(|signer| signer.generate_tag(hash.as_bytes(), &artifact_body))
+            .map(|signer| signer.tag_generator(hash.as_bytes()))
+            .transpose()?;
+
+        // The tag and size of the artifact have to be sent before its body, so
+        // the archive is spooled to a temporary file instead of memory and then
+        // streamed to the remote cache
+        let mut artifact = ArtifactWriter {
+            inner: BufWriter::with_capacity(ARTIFACT_BUFFER_SIZE, NamedTempFile::new()?),
+            tag_generator: tag_generator.as_mut(),
+            size: 0,
+        };
+        self.write(&mut artifact, anchor, files).await?;
+        let artifact_size = artifact.size;
+        let artifact_file = artifact
+            .inner
+            .into_inner()
+            .map_err(|err| err.into_error())?;
+
+        let tag = tag_generator
+            .map(|tag_generator| tag_generator.finalize())
             .transpose()?;
 
+        // Every attempt at the upload reads the artifact from the start
         self.client
             .put_artifact(
                 hash,
-                &artifact_body,
+                &|| {
+                    Ok(Body::from(tokio::fs::File::from_std(
+                        artifact_file.reopen()?,
+                    )))
+                },
+                artifact_size,
                 duration,
                 tag.as_deref(),
                 &self.api_auth.token,
@@ -101,6 +160,7 @@ impl HTTPCache {
         for file in files {
             cache_archive.add_file(anchor, file)?;
         }
+        cache_archive.finish()?;
 
         Ok(())
     }
@@ -178,40 +238,54 @@ impl HTTPCache {
 
         let duration = Self::get_duration_from_response(&response)?;
 
-        let body = if let Some(signer_verifier) = &self.signer_verifier {
-            let expected_tag = response
-                .headers()
-                .get("x-artifact-tag")
-                .ok_or(CacheError::ArtifactTagMissing(Backtrace::capture()))?;
-
-            let expected_tag = expected_tag
-                .to_str()
-                .map_err(|_| CacheError::InvalidTag(Backtrace::capture()))?
-                .to_string();
+        let mut verification = match &self.signer_verifier {
+            Some(signer_verifier) => {
+                let expected_tag = response
+                    .headers()
+                    .get("x-artifact-tag")
+                    .ok_or(CacheError::ArtifactTagMissing(Backtrace::capture()))?;
+
+                let expected_tag = expected_tag
+                    .to_str()
+                    .map_err(|_| CacheError::InvalidTag(Backtrace::capture()))?
+                    .to_string();
+
+                Some((
+                    signer_verifier.tag_generator(hash.as_bytes())?,
+                    expected_tag,
+                ))
+            }
+            None => None,
+        };
 
-            let body = response.bytes().await.map_err(|e| {
-                CacheError::ApiClientError(
-                    Box::new(turborepo_api_client::Error::ReqwestError(e)),
-                    Backtrace::capture(),
-                )
-            })?;
-            let is_valid = signer_verifier.validate(hash.as_bytes(), &body, &expected_tag)?;
+        // Nothing is restored until the whole artifact has been received and,
+        // for signed artifacts, verified, so it's spooled to a temporary file
+        // first. Restoring as it arrives would leave partially restored
+        // outputs behind if the download fails.
+        let mut artifact_file = tokio::fs::File::from_std(tempfile::tempfile()?);
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(Self::body_error)?;
+            if let Some((tag_generator, _)) = &mut verification {
+                tag_generator.update(&chunk);
+            }
+            artifact_file.write_all(&chunk).await?;
+        }
 
-            if !is_valid {
+        if let Some((tag_generator, expected_tag)) = verification {
+            if !tag_generator.verify(&expected_tag)? {
                 return Err(CacheError::InvalidTag(Backtrace::capture()));
             }
+        }
 
-            body
-        } else {
-            response.bytes().await.map_err(|e| {
-                CacheError::ApiClientError(
-                    Box::new(turborepo_api_client::Error::ReqwestError(e)),
-                    Backtrace::capture(),
-                )
-            })?
-        };
-
-        let files = Self::restore_tar(&self.repo_root, &body)?;
+        artifact_file.rewind().await?;
+        let artifact_file = artifact_file.into_std().await;
+        let files = self
+            .restore(BufReader::with_capacity(
+                ARTIFACT_BUFFER_SIZE,
+                artifact_file,
+            ))
+            .await?;
 
         self.log_fetch(analytics::CacheEvent::Hit, hash, duration);
         Ok(Some((
@@ -223,30 +297,57 @@ impl HTTPCache {
         )))
     }
 
+    // Restoring reads the archive synchronously, so it runs on a blocking
+    // thread
+    async fn restore(
+        &self,
+        reader: impl Read + Send + 'static,
+    ) -> Result<Vec<AnchoredSystemPathBuf>, CacheError> {
+        let repo_root = self.repo_root.clone();
+        tokio::task::spawn_blocking(move || Self::restore_tar(&repo_root, reader))
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+    }
+
     #[tracing::instrument(skip_all)]
     pub(crate) fn restore_tar(
         root: &AbsoluteSystemPath,
-        body: &[u8],
+        reader: impl Read,
     ) -> Result<Vec<AnchoredSystemPathBuf>, CacheError> {
-        let mut cache_reader = CacheReader::from_reader(body, true)?;
+        let mut cache_reader = CacheReader::from_reader(reader, true)?;
         cache_reader.restore(root)
     }
+
+    fn body_error(err: reqwest::Error) -> CacheError {
+        CacheError::ApiClientError(
+            Box::new(turborepo_api_client::Error::ReqwestError(err)),
+            Backtrace::capture(),
+        )
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use std::assert_matches::assert_matches;
+
     use anyhow::Result;
     use futures::future::try_join_all;
     use tempfile::tempdir;
-    use turbopath::AbsoluteSystemPathBuf;
+    use test_case::test_case;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
     use turborepo_analytics::start_analytics;
     use turborepo_api_client::{analytics, APIClient};
-    use turborepo_vercel_api_mock::start_test_server;
+    use turborepo_vercel_api_mock::{start_test_server, FLAKY_ARTIFACT_PREFIX};
 
     use crate::{
         http::{APIAuth, HTTPCache},
+        signature_authentication::{ArtifactSignatureAuthenticator, AsymmetricKeys},
         test_cases::{get_test_cases, validate_analytics, TestCase},
-        CacheOpts, CacheSource,
+        CacheError, CacheOpts, CacheSource,
     };
 
     #[tokio::test]
@@ -292,7 +393,7 @@ mod test {
             repo_root_path.to_owned(),
             api_auth,
             Some(analytics_recorder),
-        );
+        )?;
 
         // Should be a cache miss at first
         let miss = cache.fetch(hash).await?;
@@ -326,4 +427,225 @@ mod test {
 
         Ok(())
     }
+
+    // Large enough to span many chunks of the request and response bodies
+    const LARGE_ARTIFACT_SIZE: usize = 8 * 1024 * 1024;
+
+    #[test_case(false ; "unsigned")]
+    #[test_case(true ; "signed")]
+    #[tokio::test]
+    async fn test_large_artifact_round_trip(signed: bool) -> Result<()> {
+        let port = port_scanner::request_open_port().unwrap();
+        let handle = tokio::spawn(start_test_server(port));
+
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        // Pseudo-random contents so compression doesn't shrink the artifact
+        let mut state: u32 = 1;
+        let contents = (0..LARGE_ARTIFACT_SIZE)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 24) as u8
+            })
+            .collect::<Vec<_>>();
+        let file = AnchoredSystemPathBuf::from_raw("large.bin")?;
+        std::fs::write(repo_root_path.resolve(&file), &contents)?;
+
+        let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
+        let api_auth = APIAuth {
+            team_id: Some("my-team".to_string()),
+            token: "my-token".to_string(),
+            team_slug: None,
+        };
+        let mut cache = HTTPCache::new(
+            api_client,
+            &CacheOpts::default(),
+            repo_root_path.to_owned(),
+            api_auth,
+            None,
+        )?;
+        if signed {
+            cache.signer_verifier = Some(ArtifactSignatureAuthenticator::new(
+                b"my-team".to_vec(),
+                Some(b"secret".to_vec()),
+            ));
+        }
+
+        cache
+            .put(&repo_root_path, "large", &[file.clone()], 100)
+            .await?;
+        std::fs::remove_file(repo_root_path.resolve(&file))?;
+
+        let (cache_response, received_files) = cache.fetch("large").await?.unwrap();
+        assert_eq!(cache_response.time_saved, 100);
+        assert_eq!(received_files, vec![file.clone()]);
+        assert!(std::fs::read(repo_root_path.resolve(&file))? == contents);
+
+        if signed {
+            // Artifacts that fail verification are never restored
+            std::fs::remove_file(repo_root_path.resolve(&file))?;
+            cache.signer_verifier = Some(ArtifactSignatureAuthenticator::new(
+                b"my-team".to_vec(),
+                Some(b"other secret".to_vec()),
+            ));
+            assert_matches!(cache.fetch("large").await, Err(CacheError::InvalidTag(_)));
+            assert!(!repo_root_path.resolve(&file).exists());
+        }
+
+        handle.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_verify_only_put_skips_upload() -> Result<()> {
+        let port = port_scanner::request_open_port().unwrap();
+        let handle = tokio::spawn(start_test_server(port));
+
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        let file = AnchoredSystemPathBuf::from_raw("file.txt")?;
+        std::fs::write(repo_root_path.resolve(&file), "contents")?;
+
+        let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
+        let api_auth = APIAuth {
+            team_id: Some("my-team".to_string()),
+            token: "my-token".to_string(),
+            team_slug: None,
+        };
+        let mut cache = HTTPCache::new(
+            api_client,
+            &CacheOpts::default(),
+            repo_root_path.to_owned(),
+            api_auth,
+            None,
+        )?;
+        // Public keys only, as on a developer machine that verifies artifacts
+        // signed in CI
+        let mut signer = ArtifactSignatureAuthenticator::new(b"my-team".to_vec(), None);
+        signer.asymmetric_keys = Some(AsymmetricKeys::default());
+        cache.signer_verifier = Some(signer);
+
+        cache
+            .put(&repo_root_path, "verify-only", &[file], 100)
+            .await?;
+        assert!(cache.exists("verify-only").await?.is_none());
+
+        handle.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_put_retries_failed_upload() -> Result<()> {
+        let port = port_scanner::request_open_port().unwrap();
+        let handle = tokio::spawn(start_test_server(port));
+
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        let file = AnchoredSystemPathBuf::from_raw("file.txt")?;
+        std::fs::write(repo_root_path.resolve(&file), "contents")?;
+
+        let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
+        let api_auth = APIAuth {
+            team_id: Some("my-team".to_string()),
+            token: "my-token".to_string(),
+            team_slug: None,
+        };
+        let cache = HTTPCache::new(
+            api_client,
+            &CacheOpts::default(),
+            repo_root_path.to_owned(),
+            api_auth,
+            None,
+        )?;
+
+        // The mock server rejects the first upload of this hash
+        let hash = format!("{FLAKY_ARTIFACT_PREFIX}hash");
+        cache
+            .put(&repo_root_path, &hash, &[file.clone()], 100)
+            .await?;
+        std::fs::remove_file(repo_root_path.resolve(&file))?;
+
+        // The retry sent the whole artifact, not what was left of the first body
+        let (_, received_files) = cache.fetch(&hash).await?.unwrap();
+        assert_eq!(received_files, vec![file.clone()]);
+        assert_eq!(
+            std::fs::read_to_string(repo_root_path.resolve(&file))?,
+            "contents"
+        );
+
+        handle.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_fetch_restores_nothing() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        let small = AnchoredSystemPathBuf::from_raw("small.txt")?;
+        std::fs::write(repo_root_path.resolve(&small), "contents")?;
+        // Pseudo-random contents so the archive spans many chunks
+        let large = AnchoredSystemPathBuf::from_raw("large.bin")?;
+        let mut state: u32 = 1;
+        let contents = (0..LARGE_ARTIFACT_SIZE)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 24) as u8
+            })
+            .collect::<Vec<_>>();
+        std::fs::write(repo_root_path.resolve(&large), contents)?;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let api_client = APIClient::new(
+            format!("http://{}", listener.local_addr()?),
+            200,
+            "2.0.0",
+            false,
+        )?;
+        let api_auth = APIAuth {
+            team_id: Some("my-team".to_string()),
+            token: "my-token".to_string(),
+            team_slug: None,
+        };
+        let cache = HTTPCache::new(
+            api_client,
+            &CacheOpts::default(),
+            repo_root_path.to_owned(),
+            api_auth,
+            None,
+        )?;
+
+        let mut artifact = Vec::new();
+        cache
+            .write(
+                &mut artifact,
+                &repo_root_path,
+                &[small.clone(), large.clone()],
+            )
+            .await?;
+        std::fs::remove_file(repo_root_path.resolve(&small))?;
+        std::fs::remove_file(repo_root_path.resolve(&large))?;
+
+        // Sends the first half of the artifact, including the whole of
+        // small.txt, before dropping the connection
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await?;
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                request.push(socket.read_u8().await?);
+            }
+            let headers = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n",
+                artifact.len()
+            );
+            socket.write_all(headers.as_bytes()).await?;
+            socket.write_all(&artifact[..artifact.len() / 2]).await?;
+            anyhow::Ok(())
+        });
+
+        assert!(cache.fetch("hash").await.is_err());
+        server.await??;
+        assert!(!repo_root_path.resolve(&small).exists());
+        assert!(!repo_root_path.resolve(&large).exists());
+        Ok(())
+    }
 }
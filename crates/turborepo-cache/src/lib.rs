@@ -7,6 +7,8 @@
 mod async_cache;
 /// The core cache creation and restoration logic.
 pub mod cache_archive;
+/// Size- and age-based eviction for the file system cache
+pub mod eviction;
 /// File system cache
 pub mod fs;
 /// Remote cache
@@ -14,6 +16,8 @@ pub mod http;
 /// A wrapper that allows reads and writes from the file system and remote
 /// cache.
 mod multiplexer;
+/// Remote cache backed by a directory shared between machines
+pub mod shared;
 /// Cache signature authentication lets users provide a private key to sign
 /// their cache payloads.
 pub mod signature_authentication;
@@ -24,6 +28,7 @@ use std::{backtrace, backtrace::Backtrace};
 
 pub use async_cache::AsyncCache;
 use camino::Utf8PathBuf;
+pub use eviction::EvictionOpts;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -106,12 +111,14 @@ pub struct CacheOpts {
     pub skip_filesystem: bool,
     pub workers: u32,
     pub remote_cache_opts: Option<RemoteCacheOpts>,
+    pub eviction_opts: EvictionOpts,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RemoteCacheOpts {
     unused_team_id: Option<String>,
     signature: bool,
+    shared_dir: Option<Utf8PathBuf>,
 }
 
 impl RemoteCacheOpts {
@@ -119,6 +126,14 @@ impl RemoteCacheOpts {
         Self {
             unused_team_id,
             signature,
+            shared_dir: None,
         }
     }
+
+    /// Use a shared directory as a remote cache in addition to, or instead of,
+    /// the HTTP remote cache
+    pub fn with_shared_dir(mut self, shared_dir: Option<Utf8PathBuf>) -> Self {
+        self.shared_dir = shared_dir;
+        self
+    }
 }
@@ -1,11 +1,13 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use tracing::{debug, warn};
-use turbopath::{AbsoluteSystemPath, AnchoredSystemPathBuf};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{APIAuth, APIClient};
 
-use crate::{fs::FSCache, http::HTTPCache, CacheError, CacheHitMetadata, CacheOpts};
+use crate::{
+    fs::FSCache, http::HTTPCache, shared::SharedCache, CacheError, CacheHitMetadata, CacheOpts,
+};
 
 pub struct CacheMultiplexer {
     // We use an `AtomicBool` instead of removing the cache because that would require
@@ -13,11 +15,15 @@ pub struct CacheMultiplexer {
     // This does create a mild race condition where we might use the cache
     // even though another thread might be removing it, but that's fine.
     should_use_http_cache: AtomicBool,
+    // A shared directory that can't be written to can often still be read
+    // from, so a failed write only stops further writes
+    should_put_to_shared_cache: AtomicBool,
     // Just for keeping track of whether we've already printed a warning about the remote cache
     // being read-only
     should_print_skipping_remote_put: AtomicBool,
     remote_cache_read_only: bool,
     fs: Option<FSCache>,
+    shared: Option<SharedCache>,
     http: Option<HTTPCache>,
 }
 
@@ -32,11 +38,15 @@ impl CacheMultiplexer {
     ) -> Result<Self, CacheError> {
         let use_fs_cache = !opts.skip_filesystem;
         let use_http_cache = !opts.skip_remote;
+        let shared_dir = opts
+            .remote_cache_opts
+            .as_ref()
+            .and_then(|remote_cache_opts| remote_cache_opts.shared_dir.as_deref());
 
-        // Since the above two flags are not mutually exclusive it is possible to
+        // Since the above flags are not mutually exclusive it is possible to
         // configure yourself out of having a cache. We should tell you about it
         // but we shouldn't fail your build for that reason.
-        if !use_fs_cache && !use_http_cache {
+        if !use_fs_cache && !use_http_cache && shared_dir.is_none() {
             warn!("no caches are enabled");
         }
 
@@ -50,6 +60,23 @@ impl CacheMultiplexer {
             })
             .transpose()?;
 
+        if let Some(fs_cache) = &fs_cache {
+            // Eviction is best effort, an unreadable entry shouldn't prevent the run
+            if let Err(err) = fs_cache.evict(&opts.eviction_opts) {
+                warn!("failed to evict from local cache: {err}");
+            }
+        }
+
+        let shared_cache = shared_dir
+            .map(|shared_dir| {
+                SharedCache::new(
+                    AbsoluteSystemPathBuf::from_unknown(repo_root, shared_dir),
+                    opts,
+                    analytics_recorder.clone(),
+                )
+            })
+            .transpose()?;
+
         let http_cache = use_http_cache
             .then_some(api_auth)
             .flatten()
@@ -61,13 +88,16 @@ impl CacheMultiplexer {
                     api_auth,
                     analytics_recorder.clone(),
                 )
-            });
+            })
+            .transpose()?;
 
         Ok(CacheMultiplexer {
             should_print_skipping_remote_put: AtomicBool::new(true),
             should_use_http_cache: AtomicBool::new(http_cache.is_some()),
+            should_put_to_shared_cache: AtomicBool::new(shared_cache.is_some()),
             remote_cache_read_only: opts.remote_cache_read_only,
             fs: fs_cache,
+            shared: shared_cache,
             http: http_cache,
         })
     }
@@ -95,28 +125,39 @@ impl CacheMultiplexer {
             .map(|fs| fs.put(anchor, key, files, duration))
             .transpose()?;
 
-        let http_result = match self.get_http_cache() {
-            Some(http) => {
-                if self.remote_cache_read_only {
-                    if self
-                        .should_print_skipping_remote_put
-                        .load(Ordering::Relaxed)
-                    {
-                        // Warn once per build, not per task
-                        warn!("Remote cache is read-only, skipping upload");
-                        self.should_print_skipping_remote_put
-                            .store(false, Ordering::Relaxed);
-                    }
-                    // Cache is functional but running in read-only mode, so we don't want to try to
-                    // write to it
-                    None
-                } else {
-                    let http_result = http.put(anchor, key, files, duration).await;
+        let http_cache = self.get_http_cache();
+        if self.remote_cache_read_only && (self.shared.is_some() || http_cache.is_some()) {
+            if self
+                .should_print_skipping_remote_put
+                .load(Ordering::Relaxed)
+            {
+                // Warn once per build, not per task
+                warn!("Remote cache is read-only, skipping upload");
+                self.should_print_skipping_remote_put
+                    .store(false, Ordering::Relaxed);
+            }
+            // Cache is functional but running in read-only mode, so we don't want to try to
+            // write to it
+            return Ok(());
+        }
 
-                    Some(http_result)
-                }
+        if let Some(shared) = self
+            .shared
+            .as_ref()
+            .filter(|_| self.should_put_to_shared_cache.load(Ordering::Relaxed))
+        {
+            // A failed write to the shared cache shouldn't fail the task or
+            // skip the upload to the HTTP cache
+            if let Err(err) = shared.put(anchor, key, files, duration) {
+                warn!("failed to put to shared cache, skipping further writes to it: {err}");
+                self.should_put_to_shared_cache
+                    .store(false, Ordering::Relaxed);
             }
-            _ => None,
+        }
+
+        let http_result = match http_cache {
+            Some(http) => Some(http.put(anchor, key, files, duration).await),
+            None => None,
         };
 
         match http_result {
@@ -145,6 +186,22 @@ impl CacheMultiplexer {
             }
         }
 
+        if let Some(shared) = &self.shared {
+            match shared.fetch(anchor, key) {
+                Ok(Some((cache_hit, files))) => {
+                    // As with the HTTP cache, backfilling the fs cache is only an
+                    // optimization
+                    if let Some(fs) = &self.fs {
+                        let _ = fs.put(anchor, key, &files, cache_hit.time_saved);
+                    }
+
+                    return Ok(Some((cache_hit, files)));
+                }
+                Ok(None) => {}
+                Err(err) => warn!("failed to fetch from shared cache: {err}"),
+            }
+        }
+
         if let Some(http) = self.get_http_cache() {
             if let Ok(Some((CacheHitMetadata { source, time_saved }, files))) =
                 http.fetch(key).await
@@ -176,6 +233,16 @@ impl CacheMultiplexer {
             }
         }
 
+        if let Some(shared) = &self.shared {
+            match shared.exists(key) {
+                cache_hit @ Ok(Some(_)) => {
+                    return cache_hit;
+                }
+                Ok(None) => {}
+                Err(err) => debug!("failed to check shared cache: {:?}", err),
+            }
+        }
+
         if let Some(http) = self.get_http_cache() {
             match http.exists(key).await {
                 cache_hit @ Ok(Some(_)) => {
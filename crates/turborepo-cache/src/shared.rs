@@ -0,0 +1,435 @@
+use std::{
+    backtrace::Backtrace,
+    fs::OpenOptions,
+    io::{ErrorKind, Write},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_analytics::AnalyticsSender;
+use turborepo_api_client::analytics::{self, AnalyticsEvent};
+
+use crate::{
+    cache_archive::{CacheReader, CacheWriter},
+    signature_authentication::ArtifactSignatureAuthenticator,
+    CacheError, CacheHitMetadata, CacheOpts, CacheSource,
+};
+
+// A lock that hasn't been released after this long is assumed to belong to a
+// writer that crashed and can be taken over.
+const STALE_LOCK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A cache that lives in a directory shared between machines, e.g. an NFS or
+/// SMB mount. Artifacts are written to a temporary file and renamed into
+/// place so readers never observe a partially written artifact.
+pub struct SharedCache {
+    cache_directory: AbsoluteSystemPathBuf,
+    signer_verifier: Option<ArtifactSignatureAuthenticator>,
+    analytics_recorder: Option<AnalyticsSender>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct SharedCacheMetadata {
+    hash: String,
+    duration: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+}
+
+// Removes the lock file once the write has finished, whether or not it
+// succeeded.
+struct LockGuard(AbsoluteSystemPathBuf);
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.remove_file() {
+            debug!("failed to release shared cache lock {}: {}", self.0, e);
+        }
+    }
+}
+
+impl SharedCache {
+    #[tracing::instrument(skip_all)]
+    pub fn new(
+        cache_directory: AbsoluteSystemPathBuf,
+        opts: &CacheOpts,
+        analytics_recorder: Option<AnalyticsSender>,
+    ) -> Result<Self, CacheError> {
+        cache_directory.create_dir_all()?;
+
+        let signer_verifier = opts
+            .remote_cache_opts
+            .as_ref()
+            .filter(|remote_cache_opts| remote_cache_opts.signature)
+            .map(|remote_cache_opts| {
+                ArtifactSignatureAuthenticator::from_env(
+                    remote_cache_opts
+                        .unused_team_id
+                        .as_deref()
+                        .unwrap_or_default()
+                        .as_bytes()
+                        .to_vec(),
+                )
+            })
+            .transpose()?;
+
+        Ok(SharedCache {
+            cache_directory,
+            signer_verifier,
+            analytics_recorder,
+        })
+    }
+
+    fn archive_path(&self, hash: &str) -> AbsoluteSystemPathBuf {
+        self.cache_directory
+            .join_component(&format!("{}.tar.zst", hash))
+    }
+
+    fn metadata_path(&self, hash: &str) -> AbsoluteSystemPathBuf {
+        self.cache_directory
+            .join_component(&format!("{}-meta.json", hash))
+    }
+
+    fn log_fetch(&self, event: analytics::CacheEvent, hash: &str, duration: u64) {
+        // If analytics fails to record, it's not worth failing the cache
+        if let Some(analytics_recorder) = &self.analytics_recorder {
+            let analytics_event = AnalyticsEvent {
+                session_id: None,
+                source: analytics::CacheSource::Remote,
+                event,
+                hash: hash.to_string(),
+                duration,
+            };
+            debug!("logging fetch: {analytics_event:?}");
+            let _ = analytics_recorder.send(analytics_event);
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn put(
+        &self,
+        anchor: &AbsoluteSystemPath,
+        hash: &str,
+        files: &[AnchoredSystemPathBuf],
+        duration: u64,
+    ) -> Result<(), CacheError> {
+        if let Some(signer) = &self.signer_verifier {
+            if !signer.can_sign() {
+                debug!("no signing key configured, skipping write of {}", hash);
+                return Ok(());
+            }
+        }
+
+        let Some(_lock) = self.lock(hash)? else {
+            debug!("another process is writing {} to the shared cache", hash);
+            return Ok(());
+        };
+
+        let mut artifact_body = Vec::new();
+        {
+            let mut cache_archive = CacheWriter::from_writer(&mut artifact_body, true)?;
+            for file in files {
+                cache_archive.add_file(anchor, file)?;
+            }
+            cache_archive.finish()?;
+        }
+
+        let tag = self
+            .signer_verifier
+            .as_ref()
+            .map(|signer| signer.generate_tag(hash.as_bytes(), &artifact_body))
+            .transpose()?;
+
+        let meta = SharedCacheMetadata {
+            hash: hash.to_string(),
+            duration,
+            tag,
+        };
+        let meta_body = serde_json::to_vec(&meta)
+            .map_err(|e| CacheError::MetadataWriteFailure(e, Backtrace::capture()))?;
+
+        // The metadata is written last since its presence marks the artifact as
+        // complete.
+        self.write_atomic(&self.archive_path(hash), &artifact_body)?;
+        self.write_atomic(&self.metadata_path(hash), &meta_body)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn exists(&self, hash: &str) -> Result<Option<CacheHitMetadata>, CacheError> {
+        let metadata_path = self.metadata_path(hash);
+        if !metadata_path.exists() || !self.archive_path(hash).exists() {
+            return Ok(None);
+        }
+
+        let meta = Self::read_metadata(&metadata_path)?;
+        Ok(Some(CacheHitMetadata {
+            source: CacheSource::Remote,
+            time_saved: meta.duration,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn fetch(
+        &self,
+        anchor: &AbsoluteSystemPath,
+        hash: &str,
+    ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
+        let metadata_path = self.metadata_path(hash);
+        let archive_path = self.archive_path(hash);
+        if !metadata_path.exists() || !archive_path.exists() {
+            self.log_fetch(analytics::CacheEvent::Miss, hash, 0);
+            return Ok(None);
+        }
+
+        let meta = Self::read_metadata(&metadata_path)?;
+        let body = archive_path.read()?;
+
+        if let Some(signer_verifier) = &self.signer_verifier {
+            let expected_tag = meta
+                .tag
+                .as_deref()
+                .ok_or(CacheError::ArtifactTagMissing(Backtrace::capture()))?;
+            if !signer_verifier.validate(hash.as_bytes(), &body, expected_tag)? {
+                return Err(CacheError::InvalidTag(Backtrace::capture()));
+            }
+        }
+
+        let mut cache_reader = CacheReader::from_reader(body.as_slice(), true)?;
+        let files = cache_reader.restore(anchor)?;
+
+        self.log_fetch(analytics::CacheEvent::Hit, hash, meta.duration);
+        Ok(Some((
+            CacheHitMetadata {
+                source: CacheSource::Remote,
+                time_saved: meta.duration,
+            },
+            files,
+        )))
+    }
+
+    fn read_metadata(path: &AbsoluteSystemPath) -> Result<SharedCacheMetadata, CacheError> {
+        serde_json::from_str(&path.read_to_string()?)
+            .map_err(|e| CacheError::InvalidMetadata(e, Backtrace::capture()))
+    }
+
+    // Acquires an exclusive lock for writing `hash`. Returns `None` if another
+    // writer currently holds the lock, since it will produce the same artifact.
+    fn lock(&self, hash: &str) -> Result<Option<LockGuard>, CacheError> {
+        let lock_path = self
+            .cache_directory
+            .join_component(&format!("{}.lock", hash));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+
+        for _ in 0..2 {
+            match lock_path.open_with_options(options.clone()) {
+                Ok(mut file) => {
+                    // Record the owner to make debugging stuck locks easier
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(Some(LockGuard(lock_path)));
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let is_stale = lock_path
+                        .symlink_metadata()
+                        .ok()
+                        .and_then(|metadata| metadata.modified().ok())
+                        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                        .is_some_and(|age| age > STALE_LOCK_TIMEOUT);
+                    if !is_stale {
+                        return Ok(None);
+                    }
+                    debug!("removing stale shared cache lock {}", lock_path);
+                    match lock_path.remove_file() {
+                        Ok(()) => {}
+                        Err(e) if e.kind() == ErrorKind::NotFound => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Writes to a temporary file in the same directory and renames it into
+    // place. Renames within a directory are atomic on POSIX file systems and
+    // on network file systems that honor those semantics.
+    fn write_atomic(&self, path: &AbsoluteSystemPath, contents: &[u8]) -> Result<(), CacheError> {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|duration| duration.subsec_nanos())
+            .unwrap_or_default();
+        let temp_path = self.cache_directory.join_component(&format!(
+            ".{}.{}-{}.tmp",
+            path.file_name().unwrap_or_default(),
+            std::process::id(),
+            nanos
+        ));
+
+        let result = (|| {
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            let mut file = temp_path.open_with_options(options)?;
+            file.write_all(contents)?;
+            file.sync_all()?;
+            temp_path.rename(path)
+        })();
+
+        if let Err(e) = result {
+            let _ = temp_path.remove_file();
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::assert_matches::assert_matches;
+
+    use anyhow::Result;
+    use tempfile::tempdir;
+    use turbopath::{AbsoluteSystemPath, AnchoredSystemPath};
+
+    use super::*;
+    use crate::{
+        test_cases::{get_test_cases, TestCase},
+        RemoteCacheOpts,
+    };
+
+    fn signed_cache(
+        cache_dir: &AbsoluteSystemPath,
+        secret_key: &[u8],
+    ) -> Result<SharedCache, CacheError> {
+        let opts = CacheOpts {
+            remote_cache_opts: Some(RemoteCacheOpts::new(Some("my-team".to_string()), true)),
+            ..CacheOpts::default()
+        };
+        let mut cache = SharedCache::new(cache_dir.to_owned(), &opts, None)?;
+        cache.signer_verifier.as_mut().unwrap().secret_key_override = Some(secret_key.to_vec());
+        Ok(cache)
+    }
+
+    #[test]
+    fn test_shared_cache() -> Result<()> {
+        for test_case in get_test_cases() {
+            round_trip_test(&test_case)?;
+        }
+        Ok(())
+    }
+
+    fn round_trip_test(test_case: &TestCase) -> Result<()> {
+        let shared_dir = tempdir()?;
+        let shared_dir_path = AbsoluteSystemPath::from_std_path(shared_dir.path())?;
+        let writer_root = tempdir()?;
+        let writer_root_path = AbsoluteSystemPath::from_std_path(writer_root.path())?;
+        let reader_root = tempdir()?;
+        let reader_root_path = AbsoluteSystemPath::from_std_path(reader_root.path())?;
+        test_case.initialize(writer_root_path)?;
+
+        let cache = SharedCache::new(shared_dir_path.to_owned(), &CacheOpts::default(), None)?;
+        assert!(cache.exists(test_case.hash)?.is_none());
+        assert!(cache.fetch(reader_root_path, test_case.hash)?.is_none());
+
+        let files: Vec<_> = test_case
+            .files
+            .iter()
+            .map(|f| f.path().to_owned())
+            .collect();
+        cache.put(writer_root_path, test_case.hash, &files, test_case.duration)?;
+
+        let expected_hit = CacheHitMetadata {
+            source: CacheSource::Remote,
+            time_saved: test_case.duration,
+        };
+        assert_eq!(cache.exists(test_case.hash)?, Some(expected_hit));
+
+        let (status, restored_files) = cache.fetch(reader_root_path, test_case.hash)?.unwrap();
+        assert_eq!(status, expected_hit);
+        assert_eq!(restored_files.len(), files.len());
+        for test_file in &test_case.files {
+            let restored = reader_root_path.resolve(test_file.path());
+            if let Some(contents) = test_file.contents() {
+                assert_eq!(restored.read_to_string()?, contents);
+            } else {
+                assert!(restored.exists());
+            }
+        }
+
+        // No temporary files or locks should be left behind
+        let leftovers = std::fs::read_dir(shared_dir_path)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.ends_with(".tmp") || name.ends_with(".lock")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_cache_signature() -> Result<()> {
+        let shared_dir = tempdir()?;
+        let shared_dir_path = AbsoluteSystemPath::from_std_path(shared_dir.path())?;
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let file = AnchoredSystemPath::new("output.txt")?;
+        repo_root_path
+            .resolve(file)
+            .create_with_contents("signed output")?;
+
+        let cache = signed_cache(shared_dir_path, b"secret")?;
+        cache.put(repo_root_path, "signed", &[file.to_owned()], 10)?;
+        assert!(cache.fetch(repo_root_path, "signed")?.is_some());
+
+        let other_key = signed_cache(shared_dir_path, b"not the secret")?;
+        assert_matches!(
+            other_key.fetch(repo_root_path, "signed"),
+            Err(CacheError::InvalidTag(_))
+        );
+
+        // Artifacts written without a signature are rejected
+        let unsigned = SharedCache::new(shared_dir_path.to_owned(), &CacheOpts::default(), None)?;
+        unsigned.put(repo_root_path, "unsigned", &[file.to_owned()], 10)?;
+        assert_matches!(
+            cache.fetch(repo_root_path, "unsigned"),
+            Err(CacheError::ArtifactTagMissing(_))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_cache_lock() -> Result<()> {
+        let shared_dir = tempdir()?;
+        let shared_dir_path = AbsoluteSystemPath::from_std_path(shared_dir.path())?;
+        let cache = SharedCache::new(shared_dir_path.to_owned(), &CacheOpts::default(), None)?;
+
+        let lock = cache.lock("hash")?;
+        assert!(lock.is_some());
+        assert!(cache.lock("hash")?.is_none());
+        drop(lock);
+        assert!(cache.lock("hash")?.is_some());
+
+        // Locks left behind by a crashed writer are taken over
+        let lock_path = shared_dir_path.join_component("stale.lock");
+        lock_path.create_with_contents("1234")?;
+        let mut options = OpenOptions::new();
+        options.write(true);
+        lock_path
+            .open_with_options(options)?
+            .set_modified(SystemTime::now() - STALE_LOCK_TIMEOUT * 2)?;
+        assert!(cache.lock("stale")?.is_some());
+
+        Ok(())
+    }
+}
@@ -1,20 +1,48 @@
-use std::env;
+use std::{collections::HashMap, env};
 
 use base64::{prelude::BASE64_STANDARD, Engine};
 use hmac::{Hmac, Mac};
 use os_str_bytes::OsStringBytes;
-use sha2::Sha256;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use sha2::{Digest, Sha256, Sha512};
 use thiserror::Error;
+use tracing::debug;
 
 type HmacSha256 = Hmac<Sha256>;
 
+// Tags produced with Ed25519 keys look like `ed25519:<key id>:<signature>`,
+// so verifiers can pick the right public key while keys are rotated
+const ED25519_TAG_PREFIX: &str = "ed25519:";
+const ED25519_SEED_LEN: usize = 32;
+
 #[derive(Debug, Error)]
 pub enum SignatureError {
     #[error(
         "signature secret key not found. You must specify a secret key in the \
-         TURBO_REMOTE_CACHE_SIGNATURE_KEY environment variable"
+         TURBO_REMOTE_CACHE_SIGNATURE_KEY environment variable, or Ed25519 keys in the \
+         TURBO_REMOTE_CACHE_SIGNATURE_PRIVATE_KEY and TURBO_REMOTE_CACHE_SIGNATURE_PUBLIC_KEYS \
+         environment variables"
     )]
     NoSignatureSecretKey,
+    #[error(
+        "signing artifacts requires a private key. You must specify one in the \
+         TURBO_REMOTE_CACHE_SIGNATURE_PRIVATE_KEY environment variable"
+    )]
+    NoSigningKey,
+    #[error(
+        "a key ID is required to sign artifacts with a private key. You must specify one in the \
+         TURBO_REMOTE_CACHE_SIGNATURE_KEY_ID environment variable"
+    )]
+    NoKeyId,
+    #[error(
+        "invalid signature key ID '{0}': key IDs may only contain letters, digits, '-', '_' and \
+         '.'"
+    )]
+    InvalidKeyId(String),
+    #[error("invalid public key entry '{0}': expected <key id>=<base64 public key>")]
+    InvalidPublicKey(String),
+    #[error("invalid Ed25519 private key: {0}")]
+    InvalidPrivateKey(String),
     #[error("serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
     #[error("base64 encoding error: {0}")]
@@ -23,11 +51,202 @@ pub enum SignatureError {
     Hmac(#[from] hmac::digest::InvalidLength),
 }
 
+/// Ed25519 keys used in place of a shared HMAC secret. Machines that upload
+/// artifacts hold the private key, while machines that only read from the
+/// cache need just the public keys, so they can't forge artifacts.
+#[derive(Debug, Clone, Default)]
+pub struct AsymmetricKeys {
+    signing_key: Option<SigningKey>,
+    // Public keys trusted for verification, by key ID
+    verifying_keys: HashMap<String, Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+struct SigningKey {
+    key_id: String,
+    // Either a raw 32 byte seed or a PKCS#8 document
+    private_key: Vec<u8>,
+}
+
+enum SigningMode {
+    Hmac(Vec<u8>),
+    Asymmetric(AsymmetricKeys),
+}
+
+/// Computes an artifact tag incrementally, so that artifacts can be signed
+/// and verified while they are streamed rather than after buffering them in
+/// memory. Ed25519 keys sign the SHA-512 digest of the tagged data.
+pub struct TagGenerator {
+    digest: TagDigest,
+}
+
+enum TagDigest {
+    Hmac(HmacSha256),
+    Asymmetric {
+        keys: AsymmetricKeys,
+        hasher: Sha512,
+    },
+}
+
+impl TagGenerator {
+    pub fn update(&mut self, bytes: &[u8]) {
+        match &mut self.digest {
+            TagDigest::Hmac(mac) => mac.update(bytes),
+            TagDigest::Asymmetric { hasher, .. } => hasher.update(bytes),
+        }
+    }
+
+    pub fn finalize(self) -> Result<String, SignatureError> {
+        match self.digest {
+            TagDigest::Hmac(mac) => Ok(BASE64_STANDARD.encode(mac.finalize().into_bytes())),
+            TagDigest::Asymmetric { keys, hasher } => keys.sign(&hasher.finalize()),
+        }
+    }
+
+    pub fn verify(self, expected_tag: &str) -> Result<bool, SignatureError> {
+        match self.digest {
+            TagDigest::Hmac(mac) => {
+                let expected_bytes = BASE64_STANDARD.decode(expected_tag)?;
+                Ok(mac.verify_slice(&expected_bytes).is_ok())
+            }
+            TagDigest::Asymmetric { keys, hasher } => keys.verify(&hasher.finalize(), expected_tag),
+        }
+    }
+}
+
+impl AsymmetricKeys {
+    /// Reads keys from `TURBO_REMOTE_CACHE_SIGNATURE_PRIVATE_KEY`,
+    /// `TURBO_REMOTE_CACHE_SIGNATURE_KEY_ID` and
+    /// `TURBO_REMOTE_CACHE_SIGNATURE_PUBLIC_KEYS`. Returns `None` if no
+    /// Ed25519 keys are configured.
+    pub fn from_env() -> Result<Option<Self>, SignatureError> {
+        let private_key = env::var("TURBO_REMOTE_CACHE_SIGNATURE_PRIVATE_KEY").ok();
+        let public_keys = env::var("TURBO_REMOTE_CACHE_SIGNATURE_PUBLIC_KEYS").ok();
+        if private_key.is_none() && public_keys.is_none() {
+            return Ok(None);
+        }
+
+        let signing_key = private_key
+            .map(|private_key| {
+                let key_id = env::var("TURBO_REMOTE_CACHE_SIGNATURE_KEY_ID")
+                    .map_err(|_| SignatureError::NoKeyId)?;
+                Self::signing_key(&key_id, &private_key)
+            })
+            .transpose()?;
+        let verifying_keys = public_keys
+            .as_deref()
+            .map(Self::parse_public_keys)
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Some(Self {
+            signing_key,
+            verifying_keys,
+        }))
+    }
+
+    fn signing_key(key_id: &str, private_key: &str) -> Result<SigningKey, SignatureError> {
+        validate_key_id(key_id)?;
+        let private_key = BASE64_STANDARD.decode(private_key.trim())?;
+        // Fail early rather than on the first upload
+        key_pair(&private_key)?;
+        Ok(SigningKey {
+            key_id: key_id.to_string(),
+            private_key,
+        })
+    }
+
+    // Parses a comma separated list of `<key id>=<base64 public key>` entries
+    fn parse_public_keys(public_keys: &str) -> Result<HashMap<String, Vec<u8>>, SignatureError> {
+        public_keys
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (key_id, public_key) = entry
+                    .split_once('=')
+                    .ok_or_else(|| SignatureError::InvalidPublicKey(entry.to_string()))?;
+                validate_key_id(key_id)?;
+                Ok((key_id.to_string(), BASE64_STANDARD.decode(public_key)?))
+            })
+            .collect()
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<String, SignatureError> {
+        let signing_key = self
+            .signing_key
+            .as_ref()
+            .ok_or(SignatureError::NoSigningKey)?;
+        let signature = key_pair(&signing_key.private_key)?.sign(message);
+        Ok(format!(
+            "{ED25519_TAG_PREFIX}{}:{}",
+            signing_key.key_id,
+            BASE64_STANDARD.encode(signature)
+        ))
+    }
+
+    fn verify(&self, message: &[u8], tag: &str) -> Result<bool, SignatureError> {
+        let Some((key_id, signature)) = tag
+            .strip_prefix(ED25519_TAG_PREFIX)
+            .and_then(|tag| tag.split_once(':'))
+        else {
+            debug!("artifact tag was not produced by an Ed25519 key");
+            return Ok(false);
+        };
+        let Some(public_key) = self.public_key(key_id)? else {
+            debug!("no public key configured for signature key ID {key_id}");
+            return Ok(false);
+        };
+
+        let signature = BASE64_STANDARD.decode(signature)?;
+        Ok(UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(message, &signature)
+            .is_ok())
+    }
+
+    // The signing key's own public key is always trusted
+    fn public_key(&self, key_id: &str) -> Result<Option<Vec<u8>>, SignatureError> {
+        if let Some(public_key) = self.verifying_keys.get(key_id) {
+            return Ok(Some(public_key.clone()));
+        }
+        match &self.signing_key {
+            Some(signing_key) if signing_key.key_id == key_id => Ok(Some(
+                key_pair(&signing_key.private_key)?
+                    .public_key()
+                    .as_ref()
+                    .to_vec(),
+            )),
+            _ => Ok(None),
+        }
+    }
+}
+
+fn validate_key_id(key_id: &str) -> Result<(), SignatureError> {
+    let is_valid = !key_id.is_empty()
+        && key_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    match is_valid {
+        true => Ok(()),
+        false => Err(SignatureError::InvalidKeyId(key_id.to_string())),
+    }
+}
+
+fn key_pair(private_key: &[u8]) -> Result<Ed25519KeyPair, SignatureError> {
+    let key_pair = match private_key.len() {
+        ED25519_SEED_LEN => Ed25519KeyPair::from_seed_unchecked(private_key),
+        _ => Ed25519KeyPair::from_pkcs8_maybe_unchecked(private_key),
+    };
+    key_pair.map_err(|err| SignatureError::InvalidPrivateKey(err.to_string()))
+}
+
 #[derive(Debug)]
 pub struct ArtifactSignatureAuthenticator {
     pub(crate) team_id: Vec<u8>,
     // An override for testing purposes (to avoid env var race conditions)
     pub(crate) secret_key_override: Option<Vec<u8>>,
+    // Ed25519 keys, resolved once when the authenticator is built
+    pub(crate) asymmetric_keys: Option<AsymmetricKeys>,
 }
 
 impl ArtifactSignatureAuthenticator {
@@ -35,9 +254,28 @@ impl ArtifactSignatureAuthenticator {
         Self {
             team_id,
             secret_key_override,
+            asymmetric_keys: None,
         }
     }
 
+    /// Builds an authenticator that uses the Ed25519 keys from the
+    /// environment if there are any, and the shared secret otherwise.
+    pub fn from_env(team_id: Vec<u8>) -> Result<Self, SignatureError> {
+        Ok(Self {
+            team_id,
+            secret_key_override: None,
+            asymmetric_keys: AsymmetricKeys::from_env()?,
+        })
+    }
+
+    /// Whether this machine can sign artifacts. Machines configured with only
+    /// public keys can verify artifacts but must not upload any.
+    pub fn can_sign(&self) -> bool {
+        self.asymmetric_keys
+            .as_ref()
+            .map_or(true, |keys| keys.signing_key.is_some())
+    }
+
     // Gets secret key from either secret key override or environment variable.
     // HMAC_SHA256 has no key length limit, although it's generally recommended
     // to keep key length under 64 bytes since anything longer is hashed using
@@ -52,6 +290,14 @@ impl ArtifactSignatureAuthenticator {
             .into_raw_vec())
     }
 
+    // Ed25519 keys take precedence over the shared secret
+    fn signing_mode(&self) -> Result<SigningMode, SignatureError> {
+        if let Some(keys) = &self.asymmetric_keys {
+            return Ok(SigningMode::Asymmetric(keys.clone()));
+        }
+        Ok(SigningMode::Hmac(self.secret_key()?))
+    }
+
     fn construct_metadata(&self, hash: &[u8]) -> Result<Vec<u8>, SignatureError> {
         let mut metadata = hash.to_vec();
         metadata.extend_from_slice(&self.team_id);
@@ -81,17 +327,35 @@ impl ArtifactSignatureAuthenticator {
         Ok(hmac_output.into_bytes().to_vec())
     }
 
+    /// Starts computing the tag of the artifact for `hash`. Feed the
+    /// artifact body to the returned generator with
+    /// [`TagGenerator::update`].
+    pub fn tag_generator(&self, hash: &[u8]) -> Result<TagGenerator, SignatureError> {
+        let metadata = self.construct_metadata(hash)?;
+        let digest = match self.signing_mode()? {
+            SigningMode::Asymmetric(keys) => {
+                let mut hasher = Sha512::new();
+                hasher.update(&metadata);
+                TagDigest::Asymmetric { keys, hasher }
+            }
+            SigningMode::Hmac(secret_key) => {
+                let mut mac = HmacSha256::new_from_slice(&secret_key)?;
+                mac.update(&metadata);
+                TagDigest::Hmac(mac)
+            }
+        };
+        Ok(TagGenerator { digest })
+    }
+
     #[tracing::instrument(skip_all)]
     pub fn generate_tag(
         &self,
         hash: &[u8],
         artifact_body: &[u8],
     ) -> Result<String, SignatureError> {
-        let mut hmac_ctx = self.get_tag_generator(hash)?;
-
-        hmac_ctx.update(artifact_body);
-        let hmac_output = hmac_ctx.finalize();
-        Ok(BASE64_STANDARD.encode(hmac_output.into_bytes()))
+        let mut tag_generator = self.tag_generator(hash)?;
+        tag_generator.update(artifact_body);
+        tag_generator.finalize()
     }
 
     #[tracing::instrument(skip_all)]
@@ -101,18 +365,16 @@ impl ArtifactSignatureAuthenticator {
         artifact_body: &[u8],
         expected_tag: &str,
     ) -> Result<bool, SignatureError> {
-        let mut mac = HmacSha256::new_from_slice(&self.secret_key()?)?;
-        let message = self.construct_metadata(hash)?;
-        mac.update(&message);
-        mac.update(artifact_body);
-
-        let expected_bytes = BASE64_STANDARD.decode(expected_tag)?;
-        Ok(mac.verify_slice(&expected_bytes).is_ok())
+        let mut tag_generator = self.tag_generator(hash)?;
+        tag_generator.update(artifact_body);
+        tag_generator.verify(expected_tag)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::assert_matches::assert_matches;
+
     use anyhow::Result;
 
     use super::*;
@@ -248,6 +510,7 @@ mod tests {
         let signature = ArtifactSignatureAuthenticator {
             team_id: test_case.team_id.to_vec(),
             secret_key_override: None,
+            asymmetric_keys: None,
         };
 
         let hash = test_case.artifact_hash;
@@ -273,4 +536,161 @@ mod tests {
         assert!(signature.validate(hash, artifact_body, &tag)?);
         Ok(())
     }
+
+    fn generate_private_key() -> Vec<u8> {
+        Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new())
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    fn public_key(private_key: &[u8]) -> Vec<u8> {
+        key_pair(private_key)
+            .unwrap()
+            .public_key()
+            .as_ref()
+            .to_vec()
+    }
+
+    fn authenticator(keys: AsymmetricKeys) -> ArtifactSignatureAuthenticator {
+        ArtifactSignatureAuthenticator {
+            team_id: b"my-team".to_vec(),
+            secret_key_override: None,
+            asymmetric_keys: Some(keys),
+        }
+    }
+
+    fn signer(key_id: &str, private_key: &[u8]) -> AsymmetricKeys {
+        AsymmetricKeys {
+            signing_key: Some(SigningKey {
+                key_id: key_id.to_string(),
+                private_key: private_key.to_vec(),
+            }),
+            verifying_keys: HashMap::new(),
+        }
+    }
+
+    fn verifier(public_keys: &[(&str, &[u8])]) -> AsymmetricKeys {
+        AsymmetricKeys {
+            signing_key: None,
+            verifying_keys: public_keys
+                .iter()
+                .map(|(key_id, private_key)| (key_id.to_string(), public_key(private_key)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_asymmetric_signatures() -> Result<()> {
+        let private_key = generate_private_key();
+        let ci = authenticator(signer("ci-1", &private_key));
+        let developer = authenticator(verifier(&[("ci-1", &private_key)]));
+
+        for test_case in get_test_cases() {
+            let hash = test_case.artifact_hash;
+            let body = test_case.artifact_body;
+            let tag = ci.generate_tag(hash, body)?;
+            assert!(tag.starts_with("ed25519:ci-1:"));
+
+            assert!(ci.validate(hash, body, &tag)?);
+            assert!(developer.validate(hash, body, &tag)?);
+            // Tampered artifacts and tags for other hashes are rejected
+            assert!(!developer.validate(hash, b"tampered", &tag)?);
+            assert!(!developer.validate(b"other hash", body, &tag)?);
+        }
+
+        // Machines with only public keys can't produce tags
+        assert!(ci.can_sign());
+        assert!(!developer.can_sign());
+        assert_matches!(
+            developer.generate_tag(b"hash", b"body"),
+            Err(SignatureError::NoSigningKey)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_tags_match() -> Result<()> {
+        let hmac =
+            ArtifactSignatureAuthenticator::new(b"my-team".to_vec(), Some(b"secret".to_vec()));
+        let ed25519 = authenticator(signer("ci-1", &generate_private_key()));
+
+        for signer in [hmac, ed25519] {
+            let mut tag_generator = signer.tag_generator(b"hash")?;
+            for chunk in [b"first ".as_slice(), b"second ", b"third"] {
+                tag_generator.update(chunk);
+            }
+            let tag = tag_generator.finalize()?;
+            assert!(signer.validate(b"hash", b"first second third", &tag)?);
+
+            let mut tag_generator = signer.tag_generator(b"hash")?;
+            tag_generator.update(b"first second third");
+            assert!(tag_generator.verify(&tag)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_asymmetric_key_rotation() -> Result<()> {
+        let old_key = generate_private_key();
+        let new_key = generate_private_key();
+        let old_tag = authenticator(signer("2023", &old_key)).generate_tag(b"hash", b"body")?;
+        let new_tag = authenticator(signer("2024", &new_key)).generate_tag(b"hash", b"body")?;
+
+        let both = authenticator(verifier(&[("2023", &old_key), ("2024", &new_key)]));
+        assert!(both.validate(b"hash", b"body", &old_tag)?);
+        assert!(both.validate(b"hash", b"body", &new_tag)?);
+
+        // Once the old key is dropped its artifacts are no longer trusted
+        let rotated = authenticator(verifier(&[("2024", &new_key)]));
+        assert!(!rotated.validate(b"hash", b"body", &old_tag)?);
+        assert!(rotated.validate(b"hash", b"body", &new_tag)?);
+
+        // A key published under the wrong ID doesn't verify
+        let mismatched = authenticator(verifier(&[("2024", &old_key)]));
+        assert!(!mismatched.validate(b"hash", b"body", &new_tag)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_asymmetric_rejects_hmac_tags() -> Result<()> {
+        let hmac =
+            ArtifactSignatureAuthenticator::new(b"my-team".to_vec(), Some(b"secret".to_vec()));
+        let tag = hmac.generate_tag(b"hash", b"body")?;
+
+        let private_key = generate_private_key();
+        let asymmetric = authenticator(verifier(&[("ci", &private_key)]));
+        assert!(!asymmetric.validate(b"hash", b"body", &tag)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_public_keys() -> Result<()> {
+        let private_key = generate_private_key();
+        let encoded = BASE64_STANDARD.encode(public_key(&private_key));
+        let keys = AsymmetricKeys::parse_public_keys(&format!("ci-1={encoded}, ci-2={encoded},"))?;
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys["ci-1"], public_key(&private_key));
+
+        assert_matches!(
+            AsymmetricKeys::parse_public_keys(&encoded.replace('=', "")),
+            Err(SignatureError::InvalidPublicKey(_))
+        );
+        assert_matches!(
+            AsymmetricKeys::parse_public_keys(&format!("ci:1={encoded}")),
+            Err(SignatureError::InvalidKeyId(_))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_signing_key_from_seed() -> Result<()> {
+        let seed = BASE64_STANDARD.encode([7u8; ED25519_SEED_LEN]);
+        assert!(AsymmetricKeys::signing_key("ci", &seed).is_ok());
+        assert_matches!(
+            AsymmetricKeys::signing_key("ci", &BASE64_STANDARD.encode(b"too short")),
+            Err(SignatureError::InvalidPrivateKey(_))
+        );
+        Ok(())
+    }
 }
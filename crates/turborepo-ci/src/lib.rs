@@ -91,6 +91,33 @@ impl Vendor {
     pub fn get_constant() -> Option<&'static str> {
         Self::infer().map(|v| v.constant)
     }
+
+    /// Gets the ref that the changes of this CI run should be compared
+    /// against, such as the base branch of a pull request. Branches are
+    /// compared against their copy on `origin`.
+    pub fn base_ref(&self) -> Option<String> {
+        self.base_ref_from(|name| env::var(name).ok())
+    }
+
+    /// Gets the commit being built from CI environment variables
+    pub fn head_sha(&self) -> Option<String> {
+        self.sha_env_var
+            .and_then(|v| env::var(v).ok())
+            .filter(|sha| !sha.is_empty())
+    }
+
+    fn base_ref_from(&self, get_env: impl Fn(&str) -> Option<String>) -> Option<String> {
+        let get = |name: Option<&str>| name.and_then(&get_env).filter(|value| !value.is_empty());
+
+        // Pushes that create a branch report a previous commit of all zeros
+        let base_sha = get(self.base_sha_env_var).filter(|sha| sha.chars().any(|c| c != '0'));
+        base_sha.or_else(|| {
+            get(self.base_branch_env_var).map(|branch| {
+                let branch = branch.strip_prefix("refs/heads/").unwrap_or(&branch);
+                format!("origin/{branch}")
+            })
+        })
+    }
 }
 
 #[cfg(test)]
@@ -116,6 +143,33 @@ mod tests {
         want: Option<Vendor>,
     }
 
+    #[test_case::test_case("GitHub Actions", &[("GITHUB_BASE_REF", "main")], Some("origin/main") ; "github pull request")]
+    #[test_case::test_case("GitHub Actions", &[], None ; "github push")]
+    #[test_case::test_case(
+        "GitLab CI",
+        &[("CI_MERGE_REQUEST_DIFF_BASE_SHA", "abc123"), ("CI_MERGE_REQUEST_TARGET_BRANCH_NAME", "main")],
+        Some("abc123")
+        ; "gitlab prefers sha"
+    )]
+    #[test_case::test_case("Vercel", &[("VERCEL_GIT_PREVIOUS_SHA", "0000000000")], None ; "all zero sha")]
+    #[test_case::test_case(
+        "Azure Pipelines",
+        &[("SYSTEM_PULLREQUEST_TARGETBRANCH", "refs/heads/release")],
+        Some("origin/release")
+        ; "azure strips refs heads"
+    )]
+    fn test_base_ref(vendor: &str, env: &[(&str, &str)], expected: Option<&str>) {
+        let get_env = |name: &str| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        };
+        assert_eq!(
+            get_vendor(vendor).base_ref_from(get_env).as_deref(),
+            expected
+        );
+    }
+
     #[test]
     fn test_info() {
         // This is purposefully *not* using test_case
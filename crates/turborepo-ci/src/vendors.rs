@@ -17,6 +17,10 @@ pub struct Vendor {
     pub(crate) eval_env: Option<HashMap<&'static str, &'static str>>,
     pub sha_env_var: Option<&'static str>,
     pub branch_env_var: Option<&'static str>,
+    // Holds the commit a pull request or push should be compared against
+    pub base_sha_env_var: Option<&'static str>,
+    // Holds the name of the branch a pull request will be merged into
+    pub base_branch_env_var: Option<&'static str>,
     pub username_env_var: Option<&'static str>,
     pub behavior: Option<VendorBehavior>,
 }
@@ -37,6 +41,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -50,6 +56,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -63,6 +71,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -76,6 +86,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: Some("SYSTEM_PULLREQUEST_TARGETBRANCH"),
                     username_env_var: None,
                     behavior: Some(VendorBehavior::new(
                         |group_name| format!("##[group]{group_name}\r\n"),
@@ -92,6 +104,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -105,6 +119,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: Some("BITBUCKET_PR_DESTINATION_BRANCH"),
                     username_env_var: None,
                     behavior: None,
                 },
@@ -118,6 +134,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -131,6 +149,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -144,6 +164,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: Some("BUILDKITE_PULL_REQUEST_BASE_BRANCH"),
                     username_env_var: None,
                     behavior: None,
                 },
@@ -157,6 +179,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -170,6 +194,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -183,6 +209,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -196,6 +224,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -213,6 +243,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     }),
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -226,6 +258,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -239,6 +273,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -252,6 +288,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -265,6 +303,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: Some("GITHUB_SHA"),
                     branch_env_var: Some("GITHUB_REF_NAME"),
+                    base_sha_env_var: None,
+                    base_branch_env_var: Some("GITHUB_BASE_REF"),
                     username_env_var: Some("GITHUB_ACTOR"),
                     behavior: Some(
                         VendorBehavior::new(
@@ -287,6 +327,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: Some("CI_MERGE_REQUEST_DIFF_BASE_SHA"),
+                    base_branch_env_var: Some("CI_MERGE_REQUEST_TARGET_BRANCH_NAME"),
                     username_env_var: None,
                     behavior: None,
                 },
@@ -300,6 +342,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -313,6 +357,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -326,6 +372,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -339,6 +387,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -352,6 +402,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -365,6 +417,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: Some("CHANGE_TARGET"),
                     username_env_var: None,
                     behavior: None,
                 },
@@ -378,6 +432,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -391,6 +447,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: Some("CACHED_COMMIT_REF"),
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -404,6 +462,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -417,6 +477,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -430,6 +492,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -443,6 +507,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -456,6 +522,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -469,6 +537,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -482,6 +552,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -495,6 +567,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -512,6 +586,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     }),
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -525,6 +601,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -538,6 +616,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -551,6 +631,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: Some(VendorBehavior::new(
                         |group_name| format!("##teamcity[blockOpened name='{group_name}']"),
@@ -567,6 +649,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: Some(VendorBehavior::new(
                         |group_name| format!("travis_fold:start:{group_name}\r\n"),
@@ -583,6 +667,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: Some("VERCEL_GIT_COMMIT_SHA"),
                     branch_env_var: Some("VERCEL_GIT_COMMIT_REF"),
+                    base_sha_env_var: Some("VERCEL_GIT_PREVIOUS_SHA"),
+                    base_branch_env_var: None,
                     username_env_var: Some("VERCEL_GIT_COMMIT_AUTHOR_LOGIN"),
                     behavior: None,
                 },
@@ -596,6 +682,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -613,6 +701,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     }),
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -626,6 +716,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -639,6 +731,8 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     eval_env: None,
                     sha_env_var: None,
                     branch_env_var: None,
+                    base_sha_env_var: None,
+                    base_branch_env_var: None,
                     username_env_var: None,
                     behavior: None,
                 },
@@ -4,6 +4,7 @@ use std::{
     collections::HashMap,
     env,
     ops::{Deref, DerefMut},
+    path::Path,
     string::ToString,
 };
 
@@ -34,6 +35,8 @@ impl std::fmt::Display for ResolvedEnvMode {
 pub enum Error {
     #[error("Failed to parse regex: {0}")]
     Regex(#[from] regex::Error),
+    #[error("Failed to load {path}: {reason}")]
+    DotEnv { path: String, reason: String },
 }
 
 // TODO: Consider using immutable data structures here
@@ -145,6 +148,29 @@ impl EnvironmentVariableMap {
         EnvironmentVariableMap(env::vars().collect())
     }
 
+    // Loads the variables from an ordered list of `.env` files. Files that come
+    // first take precedence, and files that don't exist are skipped.
+    pub fn from_dot_env_files(paths: &[impl AsRef<Path>]) -> Result<Self, Error> {
+        let mut map = HashMap::new();
+        for path in paths {
+            let path = path.as_ref();
+            let to_error = |err: dotenvy::Error| Error::DotEnv {
+                path: path.display().to_string(),
+                reason: err.to_string(),
+            };
+            let vars = match dotenvy::from_path_iter(path) {
+                Ok(vars) => vars,
+                Err(err) if err.not_found() => continue,
+                Err(err) => return Err(to_error(err)),
+            };
+            for var in vars {
+                let (key, value) = var.map_err(to_error)?;
+                map.entry(key).or_insert(value);
+            }
+        }
+        Ok(EnvironmentVariableMap(map))
+    }
+
     pub fn into_inner(self) -> HashMap<String, String> {
         self.0
     }
@@ -317,8 +343,37 @@ pub fn get_global_hashable_env_vars(
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use test_case::test_case;
 
+    use super::EnvironmentVariableMap;
+
+    #[test]
+    fn test_from_dot_env_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join(".env.local");
+        let env = dir.path().join(".env");
+        fs::write(&local, "API_URL=http://localhost\n").unwrap();
+        fs::write(
+            &env,
+            "API_URL=https://example.com\n# comment\nMODE=\"production\"\n",
+        )
+        .unwrap();
+        let missing = dir.path().join(".env.missing");
+
+        let map = EnvironmentVariableMap::from_dot_env_files(&[local, missing, env]).unwrap();
+        let mut vars = map.into_inner().into_iter().collect::<Vec<_>>();
+        vars.sort();
+        assert_eq!(
+            vars,
+            vec![
+                ("API_URL".to_string(), "http://localhost".to_string()),
+                ("MODE".to_string(), "production".to_string()),
+            ]
+        );
+    }
+
     #[test_case("LITERAL_\\*", "LITERAL_\\*" ; "literal star")]
     #[test_case("\\*LEADING", "\\*LEADING" ; "leading literal star")]
     #[test_case("\\!LEADING", "\\\\!LEADING" ; "leading literal bang")]
@@ -0,0 +1,558 @@
+//! The `HashWatcher` keeps the hashes of package input files up to date so
+//! that task hashing can skip asking git for them.
+//!
+//! Hashes are computed the first time they're requested for a package and set
+//! of inputs, and then kept until a file system event could have changed them.
+//! Requests are cookied, so a response always reflects every change made
+//! before the request was sent.
+
+use std::{collections::HashMap, future::IntoFuture, sync::Arc, time::Duration};
+
+use notify::Event;
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{debug, warn};
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf};
+use turborepo_scm::{package_deps::GitHashes, SCM};
+use wax::{Glob, Program};
+
+use crate::{
+    cookies::{CookieError, CookieWatcher, CookieWriter, CookiedRequest},
+    ignore_matcher::GitignoreMatcher,
+    NotifyError, OptionalWatch,
+};
+
+const INPUT_INCLUDE_DEFAULT_FILES: &str = "$TURBO_DEFAULT$";
+
+/// Directories that hold git's and turbo's own state. Changes to them never
+/// affect hashes, and turbo writes to them on every run.
+const ALWAYS_IGNORED: [&str; 2] = [".git", ".turbo"];
+
+/// The files to hash: the package's directory and the task's `inputs`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HashSpec {
+    pub package_path: AnchoredSystemPathBuf,
+    pub inputs: Vec<String>,
+}
+
+impl HashSpec {
+    /// Whether a change to `path`, relative to the repository root, could
+    /// change the hashes for this spec. `ignored` is whether `path` is
+    /// gitignored.
+    fn is_affected_by(&self, path: &AnchoredSystemPath, ignored: bool) -> bool {
+        // Inputs can reach outside of the package, in which case we can't tell
+        // which changes matter
+        if self.inputs.iter().any(|input| input.starts_with("..")) {
+            return true;
+        }
+        if is_within(&self.package_path, path) {
+            return !ignored || self.inputs_match(path);
+        }
+        // A .gitignore in a parent directory changes which files are hashed
+        path.as_path().file_name() == Some(".gitignore".as_ref())
+            && path
+                .parent()
+                .map_or(true, |parent| is_within(parent, &self.package_path))
+    }
+
+    // Gitignored files are left out of the default files, but explicit inputs
+    // still pick them up
+    fn inputs_match(&self, path: &AnchoredSystemPath) -> bool {
+        let Ok(package_relative) = path.to_unix().strip_prefix(&self.package_path.to_unix()) else {
+            return true;
+        };
+        self.inputs
+            .iter()
+            .filter(|input| *input != INPUT_INCLUDE_DEFAULT_FILES && !input.starts_with('!'))
+            .any(|input| {
+                // An input we can't parse could match anything
+                Glob::new(input).map_or(true, |glob| glob.is_match(package_relative.as_str()))
+            })
+    }
+}
+
+// Whether `path` is `dir` or somewhere inside of it
+fn is_within(dir: &AnchoredSystemPath, path: &AnchoredSystemPath) -> bool {
+    dir.as_str().is_empty() || path.as_path().starts_with(dir.as_path())
+}
+
+// Whether `path` is version control or turbo state, such as task logs and
+// daemon cookies, which never change the hashes of package files
+fn is_turbo_or_git_state(path: &AnchoredSystemPath) -> bool {
+    path.components()
+        .any(|component| ALWAYS_IGNORED.contains(&component.as_str()))
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    CookieError(#[from] CookieError),
+    #[error("hashwatcher has closed")]
+    Closed,
+    #[error("hashwatcher request timed out")]
+    Timeout(#[from] tokio::time::error::Elapsed),
+    #[error("hash watching is unavailable")]
+    Unavailable,
+    #[error("failed to hash files: {0}")]
+    Hash(String),
+}
+
+impl From<mpsc::error::SendError<CookiedRequest<Query>>> for Error {
+    fn from(_: mpsc::error::SendError<CookiedRequest<Query>>) -> Self {
+        Error::Closed
+    }
+}
+
+impl From<oneshot::error::RecvError> for Error {
+    fn from(_: oneshot::error::RecvError) -> Self {
+        Error::Closed
+    }
+}
+
+pub struct HashWatcher {
+    cookie_jar: CookieWriter,
+    // _exit_ch exists to trigger a close on the receiver when an instance
+    // of this struct is dropped.
+    _exit_ch: oneshot::Sender<()>,
+    query_ch_lazy: OptionalWatch<mpsc::Sender<CookiedRequest<Query>>>,
+}
+
+#[derive(Debug)]
+pub struct Query {
+    spec: HashSpec,
+    resp: oneshot::Sender<Result<GitHashes, Error>>,
+}
+
+// Hashing runs on a blocking thread and reports back to the tracker
+struct Computed {
+    spec: HashSpec,
+    version: u64,
+    result: Result<GitHashes, String>,
+}
+
+enum HashState {
+    Hashes(GitHashes),
+    Pending {
+        // Bumped whenever the files change while hashing, so that stale
+        // results are thrown away
+        version: u64,
+        waiters: Vec<oneshot::Sender<Result<GitHashes, Error>>>,
+    },
+}
+
+struct HashTracker {
+    root: AbsoluteSystemPathBuf,
+    cookie_root: AbsoluteSystemPathBuf,
+    gitignore: GitignoreMatcher,
+    scm: Arc<SCM>,
+    hashes: HashMap<HashSpec, HashState>,
+    exit_signal: oneshot::Receiver<()>,
+    recv: broadcast::Receiver<Result<Event, NotifyError>>,
+    query_recv: mpsc::Receiver<CookiedRequest<Query>>,
+    computed_tx: mpsc::UnboundedSender<Computed>,
+    computed_rx: mpsc::UnboundedReceiver<Computed>,
+    cookie_watcher: CookieWatcher<Query>,
+}
+
+impl HashWatcher {
+    pub fn new(
+        root: AbsoluteSystemPathBuf,
+        scm: SCM,
+        cookie_jar: CookieWriter,
+        mut recv: OptionalWatch<broadcast::Receiver<Result<Event, NotifyError>>>,
+    ) -> Self {
+        let (exit_ch, exit_signal) = oneshot::channel();
+        let (query_ch_tx, query_ch_lazy) = OptionalWatch::new();
+        let cookie_root = cookie_jar.root().to_owned();
+        tokio::task::spawn(async move {
+            let Ok(recv) = recv.get().await.map(|r| r.resubscribe()) else {
+                // if this fails, it means that the filewatcher is not available
+                // so starting the hash tracker is pointless
+                return;
+            };
+
+            let (query_ch, query_recv) = mpsc::channel(128);
+            if query_ch_tx.send(Some(query_ch)).is_err() {
+                tracing::debug!("no queryers for hash watcher, exiting");
+                return;
+            }
+
+            HashTracker::new(root, scm, cookie_root, exit_signal, recv, query_recv)
+                .watch()
+                .await
+        });
+        Self {
+            cookie_jar,
+            _exit_ch: exit_ch,
+            query_ch_lazy,
+        }
+    }
+
+    /// Get the hashes of the files described by `spec`, computing them if
+    /// they aren't already known.
+    ///
+    /// This function will return `Error::Unavailable` if the hashwatcher is not
+    /// yet available.
+    pub async fn get_file_hashes(
+        &self,
+        spec: HashSpec,
+        timeout: Duration,
+    ) -> Result<GitHashes, Error> {
+        let (tx, rx) = oneshot::channel();
+        let cookied_request = self
+            .cookie_jar
+            .cookie_request(Query { spec, resp: tx })
+            .await?;
+        let mut query_ch = self.query_ch_lazy.clone();
+        let query_ch = query_ch
+            .get_immediate()
+            .ok_or(Error::Unavailable)?
+            .map(|ch| ch.clone())
+            .map_err(|_| Error::Unavailable)?;
+        query_ch.send(cookied_request).await?;
+        tokio::time::timeout(timeout, rx).await??
+    }
+}
+
+impl HashTracker {
+    fn new(
+        root: AbsoluteSystemPathBuf,
+        scm: SCM,
+        cookie_root: AbsoluteSystemPathBuf,
+        exit_signal: oneshot::Receiver<()>,
+        recv: broadcast::Receiver<Result<Event, NotifyError>>,
+        query_recv: mpsc::Receiver<CookiedRequest<Query>>,
+    ) -> Self {
+        let (computed_tx, computed_rx) = mpsc::unbounded_channel();
+        Self {
+            gitignore: GitignoreMatcher::new(root.clone()),
+            root,
+            scm: Arc::new(scm),
+            hashes: HashMap::new(),
+            exit_signal,
+            recv,
+            query_recv,
+            computed_tx,
+            computed_rx,
+            cookie_watcher: CookieWatcher::new(cookie_root.clone()),
+            cookie_root,
+        }
+    }
+
+    async fn watch(mut self) {
+        loop {
+            tokio::select! {
+                _ = &mut self.exit_signal => return,
+                Some(query) = self.query_recv.recv().into_future() => self.handle_cookied_query(query),
+                Some(computed) = self.computed_rx.recv().into_future() => self.handle_computed(computed),
+                file_event = self.recv.recv().into_future() => self.handle_file_event(file_event),
+            }
+        }
+    }
+
+    fn handle_cookied_query(&mut self, cookied_query: CookiedRequest<Query>) {
+        if let Some(query) = self.cookie_watcher.check_request(cookied_query) {
+            self.handle_query(query);
+        }
+    }
+
+    fn handle_query(&mut self, Query { spec, resp }: Query) {
+        match self.hashes.get_mut(&spec) {
+            Some(HashState::Hashes(hashes)) => {
+                // If the client has gone away, we don't care about the error
+                let _ = resp.send(Ok(hashes.clone()));
+            }
+            Some(HashState::Pending { waiters, .. }) => waiters.push(resp),
+            None => {
+                self.hashes.insert(
+                    spec.clone(),
+                    HashState::Pending {
+                        version: 0,
+                        waiters: vec![resp],
+                    },
+                );
+                self.spawn_hashing(spec, 0);
+            }
+        }
+    }
+
+    fn spawn_hashing(&self, spec: HashSpec, version: u64) {
+        let root = self.root.clone();
+        let scm = self.scm.clone();
+        let computed_tx = self.computed_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            debug!("hashing files for {:?}", spec);
+            let result = scm
+                .get_package_file_hashes(&root, &spec.package_path, &spec.inputs, None)
+                .map_err(|e| e.to_string());
+            // The tracker is gone if this fails, so nobody wants the result
+            let _ = computed_tx.send(Computed {
+                spec,
+                version,
+                result,
+            });
+        });
+    }
+
+    fn handle_computed(&mut self, computed: Computed) {
+        let Computed {
+            spec,
+            version,
+            result,
+        } = computed;
+        let Some(HashState::Pending {
+            version: current_version,
+            ..
+        }) = self.hashes.get(&spec)
+        else {
+            return;
+        };
+        if *current_version != version {
+            // Files changed while hashing, a newer result is on its way
+            return;
+        }
+        let Some(HashState::Pending { waiters, .. }) = self.hashes.remove(&spec) else {
+            unreachable!("checked above");
+        };
+        match result {
+            Ok(hashes) => {
+                for waiter in waiters {
+                    let _ = waiter.send(Ok(hashes.clone()));
+                }
+                self.hashes.insert(spec, HashState::Hashes(hashes));
+            }
+            // Errors aren't kept, the next request tries again
+            Err(e) => {
+                for waiter in waiters {
+                    let _ = waiter.send(Err(Error::Hash(e.clone())));
+                }
+            }
+        }
+    }
+
+    fn handle_file_event(
+        &mut self,
+        file_event: Result<Result<Event, NotifyError>, broadcast::error::RecvError>,
+    ) {
+        match file_event {
+            Err(broadcast::error::RecvError::Closed) => (),
+            Err(e @ broadcast::error::RecvError::Lagged(_)) => self.on_error(&e),
+            Ok(Err(error)) => self.on_error(&error),
+            Ok(Ok(file_event)) => {
+                for path in file_event.paths {
+                    let path = AbsoluteSystemPathBuf::try_from(path)
+                        .expect("filewatching should produce absolute paths");
+                    if let Some(queries) = self
+                        .cookie_watcher
+                        .pop_ready_requests(file_event.kind, &path)
+                    {
+                        for query in queries {
+                            self.handle_query(query);
+                        }
+                        continue;
+                    }
+                    if path.starts_with(&self.cookie_root) {
+                        continue;
+                    }
+                    let Ok(path) = self.root.anchor(&path) else {
+                        // irrelevant filesystem update
+                        continue;
+                    };
+                    if is_turbo_or_git_state(&path) {
+                        continue;
+                    }
+                    let ignored = self.gitignore.is_ignored(&path);
+                    self.invalidate(|spec| spec.is_affected_by(&path, ignored));
+                }
+            }
+        }
+    }
+
+    /// on_error takes the conservative approach of considering everything
+    /// changed in the event of any error related to filewatching
+    fn on_error(&mut self, err: &dyn std::error::Error) {
+        warn!(
+            "encountered filewatching error, flushing all hashes: {}",
+            err
+        );
+        self.invalidate(|_| true);
+    }
+
+    fn invalidate(&mut self, is_affected: impl Fn(&HashSpec) -> bool) {
+        let mut rehash = Vec::new();
+        self.hashes.retain(|spec, state| {
+            if !is_affected(spec) {
+                return true;
+            }
+            match state {
+                HashState::Hashes(_) => false,
+                // Someone is waiting on these hashes, so start over
+                HashState::Pending { version, .. } => {
+                    *version += 1;
+                    rehash.push((spec.clone(), *version));
+                    true
+                }
+            }
+        });
+        for (spec, version) in rehash {
+            self.spawn_hashing(spec, version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use notify::{
+        event::{EventKind, ModifyKind},
+        Event,
+    };
+    use test_case::test_case;
+    use tokio::sync::{broadcast, mpsc, oneshot};
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+    use turborepo_scm::{package_deps::GitHashes, SCM};
+
+    use super::{HashSpec, HashState, HashTracker, HashWatcher};
+    use crate::{cookies::CookieWriter, FileSystemWatcher, NotifyError};
+
+    fn spec(package_path: &str, inputs: &[&str]) -> HashSpec {
+        HashSpec {
+            package_path: AnchoredSystemPathBuf::from_raw(package_path).unwrap(),
+            inputs: inputs.iter().map(|input| input.to_string()).collect(),
+        }
+    }
+
+    #[test_case("apps/web", &[], "apps/web/src/index.ts", false, true ; "file in package")]
+    #[test_case("apps/web", &[], "apps/docs/index.ts", false, false ; "file in other package")]
+    #[test_case("apps/web", &[], "apps/website/index.ts", false, false ; "package with same prefix")]
+    #[test_case("apps/web", &[], ".gitignore", false, true ; "root gitignore")]
+    #[test_case("apps/web", &[], "apps/docs/.gitignore", false, false ; "other package gitignore")]
+    #[test_case("apps/web", &["../../shared/**"], "shared/index.ts", false, true ; "inputs outside package")]
+    #[test_case("", &[], "apps/docs/index.ts", false, true ; "root package")]
+    #[test_case("apps/web", &[], "apps/web/dist/index.js", true, false ; "ignored file")]
+    #[test_case("", &[], "apps/web/dist/index.js", true, false ; "ignored file in root package")]
+    #[test_case("apps/web", &["dist/**"], "apps/web/dist/index.js", true, true ; "ignored file in inputs")]
+    #[test_case("apps/web", &["$TURBO_DEFAULT$", "src/**"], "apps/web/dist/index.js", true, false ; "ignored file outside inputs")]
+    fn test_is_affected_by(
+        package_path: &str,
+        inputs: &[&str],
+        path: &str,
+        ignored: bool,
+        expected: bool,
+    ) {
+        let path = AnchoredSystemPathBuf::from_raw(path).unwrap();
+        assert_eq!(
+            spec(package_path, inputs).is_affected_by(&path, ignored),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ignored_changes_keep_hashes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        repo_root
+            .join_component(".gitignore")
+            .create_with_contents("dist/\n")
+            .unwrap();
+        let cookie_root = repo_root.join_components(&[".turbo", "cookies"]);
+
+        let (_exit_tx, exit_signal) = oneshot::channel();
+        let (_event_tx, recv) = broadcast::channel(1);
+        let (_query_tx, query_recv) = mpsc::channel(1);
+        let mut tracker = HashTracker::new(
+            repo_root.clone(),
+            SCM::new(&repo_root),
+            cookie_root,
+            exit_signal,
+            recv,
+            query_recv,
+        );
+        let specs = [spec("my-pkg", &[]), spec("", &[])];
+        for spec in &specs {
+            tracker
+                .hashes
+                .insert(spec.clone(), HashState::Hashes(GitHashes::new()));
+        }
+        let event =
+            |path: &str| -> Result<Result<Event, NotifyError>, broadcast::error::RecvError> {
+                let path = repo_root.join_components(&path.split('/').collect::<Vec<_>>());
+                Ok(Ok(Event::new(EventKind::Modify(ModifyKind::Any))
+                    .add_path(path.as_std_path().to_owned())))
+            };
+
+        for path in [
+            "my-pkg/.turbo/turbo-build.log",
+            "my-pkg/dist/index.js",
+            ".turbo/cookies/1.cookie",
+            ".git/index",
+        ] {
+            tracker.handle_file_event(event(path));
+        }
+        for spec in &specs {
+            assert!(
+                matches!(tracker.hashes.get(spec), Some(HashState::Hashes(_))),
+                "hashes for {spec:?} were invalidated"
+            );
+        }
+
+        tracker.handle_file_event(event("my-pkg/src/index.ts"));
+        assert!(tracker.hashes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_hashes_follow_changes() {
+        let timeout = Duration::from_secs(2);
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+        let pkg_path = repo_root.join_component("my-pkg");
+        pkg_path.create_dir_all().unwrap();
+        let file = pkg_path.join_component("index.js");
+        file.create_with_contents("one").unwrap();
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).unwrap();
+        let recv = watcher.watch();
+        let cookie_jar = CookieWriter::new(watcher.cookie_dir(), timeout, recv.clone());
+        // Not a git repository, so files are hashed manually
+        let scm = SCM::new(&repo_root);
+        let hash_watcher = HashWatcher::new(repo_root.clone(), scm, cookie_jar, recv);
+
+        // Wait for the watcher to be ready to take queries
+        let spec = spec("my-pkg", &[]);
+        let first = loop {
+            match hash_watcher.get_file_hashes(spec.clone(), timeout).await {
+                Ok(hashes) => break hashes,
+                Err(super::Error::Unavailable) => {
+                    tokio::time::sleep(Duration::from_millis(10)).await
+                }
+                Err(e) => panic!("failed to get hashes: {e}"),
+            }
+        };
+        let index = RelativeUnixPathBuf::new("index.js").unwrap();
+        assert!(first.contains_key(&index));
+
+        let unchanged = hash_watcher
+            .get_file_hashes(spec.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(unchanged, first);
+
+        file.create_with_contents("two").unwrap();
+        let changed = hash_watcher
+            .get_file_hashes(spec.clone(), timeout)
+            .await
+            .unwrap();
+        assert_ne!(changed.get(&index), first.get(&index));
+
+        pkg_path
+            .join_component("new.js")
+            .create_with_contents("")
+            .unwrap();
+        let added = hash_watcher.get_file_hashes(spec, timeout).await.unwrap();
+        assert!(added.contains_key(&RelativeUnixPathBuf::new("new.js").unwrap()));
+    }
+}
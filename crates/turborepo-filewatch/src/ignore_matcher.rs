@@ -0,0 +1,56 @@
+//! Matches changed paths against the repository's `.gitignore` files, so that
+//! consumers of file events can skip changes to files git doesn't track.
+
+use std::collections::HashMap;
+
+use ignore::gitignore::Gitignore;
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
+
+/// Answers whether a path is gitignored, caching parsed `.gitignore` files.
+/// A change to a `.gitignore` file drops the cached copy, so callers should
+/// run every changed path through `is_ignored`.
+pub struct GitignoreMatcher {
+    repo_root: AbsoluteSystemPathBuf,
+    gitignores: HashMap<AbsoluteSystemPathBuf, Option<Gitignore>>,
+}
+
+impl GitignoreMatcher {
+    pub fn new(repo_root: AbsoluteSystemPathBuf) -> Self {
+        Self {
+            repo_root,
+            gitignores: HashMap::new(),
+        }
+    }
+
+    pub fn is_ignored(&mut self, path: &AnchoredSystemPath) -> bool {
+        let path = self.repo_root.resolve(path);
+        if path.file_name() == Some(".gitignore") {
+            if let Some(dir) = path.parent() {
+                self.gitignores.remove(&dir.to_owned());
+            }
+        }
+
+        let is_dir = path.as_std_path().is_dir();
+        // Walk up from the closest directory since nested .gitignore files take
+        // precedence over those closer to the root
+        for dir in path.ancestors().skip(1) {
+            let gitignore = self.gitignores.entry(dir.to_owned()).or_insert_with(|| {
+                let file = dir.join_component(".gitignore");
+                file.exists().then(|| Gitignore::new(&file).0)
+            });
+            if let Some(gitignore) = gitignore {
+                let matched = gitignore.matched_path_or_any_parents(&path, is_dir);
+                if matched.is_ignore() {
+                    return true;
+                } else if matched.is_whitelist() {
+                    return false;
+                }
+            }
+            if dir == &*self.repo_root {
+                break;
+            }
+        }
+
+        false
+    }
+}
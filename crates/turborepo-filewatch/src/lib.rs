@@ -39,9 +39,13 @@ pub mod cookies;
 #[cfg(target_os = "macos")]
 mod fsevent;
 pub mod globwatcher;
+pub mod hash_watcher;
+pub mod ignore_matcher;
 mod optional_watch;
 pub mod package_watcher;
 
+// Re-exported so consumers don't need to depend on the same notify version
+pub use notify;
 pub use optional_watch::OptionalWatch;
 
 #[cfg(not(target_os = "macos"))]
@@ -5,7 +5,7 @@ use thiserror::Error;
 use turborepo_repository::package_graph;
 
 use crate::{
-    commands::{bin, generate, prune},
+    commands::{bin, boundaries, cache, deps, generate, prune, query},
     daemon::DaemonError,
     rewrite_json::RewriteError,
     run,
@@ -18,6 +18,9 @@ pub enum Error {
     #[error("{0}")]
     Bin(#[from] bin::Error, #[backtrace] backtrace::Backtrace),
     #[error(transparent)]
+    #[diagnostic(transparent)]
+    Boundaries(#[from] boundaries::Error),
+    #[error(transparent)]
     Path(#[from] turbopath::PathError),
     #[error("at least one task must be specified")]
     NoTasks(#[backtrace] backtrace::Backtrace),
@@ -33,12 +36,19 @@ pub enum Error {
     #[error(transparent)]
     Auth(#[from] turborepo_auth::Error),
     #[error(transparent)]
+    Cache(#[from] cache::Error),
+    #[error(transparent)]
     Daemon(#[from] DaemonError),
     #[error(transparent)]
+    Deps(#[from] deps::Error),
+    #[error(transparent)]
     Generate(#[from] generate::Error),
     #[error(transparent)]
     Prune(#[from] prune::Error),
     #[error(transparent)]
+    #[diagnostic(transparent)]
+    Query(#[from] query::Error),
+    #[error(transparent)]
     PackageJson(#[from] turborepo_repository::package_json::Error),
     #[error(transparent)]
     PackageManager(#[from] turborepo_repository::package_manager::Error),
@@ -46,5 +56,8 @@ pub enum Error {
     #[diagnostic(transparent)]
     Run(#[from] run::Error),
     #[error(transparent)]
+    #[diagnostic(transparent)]
+    Watch(#[from] run::watch::Error),
+    #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
 }
@@ -24,8 +24,8 @@ use turborepo_ui::UI;
 
 use crate::{
     commands::{
-        bin, daemon, generate, info, link, login, logout, prune, run, telemetry, unlink,
-        CommandBase,
+        bin, boundaries, cache, daemon, deps, generate, info, link, login, logout, prune, query,
+        run, telemetry, unlink, watch, CommandBase,
     },
     get_version,
     shim::TurboState,
@@ -236,6 +236,35 @@ impl From<Verbosity> for u8 {
     }
 }
 
+#[derive(Subcommand, Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "command")]
+pub enum CacheCommand {
+    /// Lists the artifacts stored in the local cache
+    Ls {
+        /// Pass --json to report the artifacts in JSON format
+        #[clap(long)]
+        json: bool,
+    },
+    /// Removes artifacts from the local cache, least recently used first.
+    /// Uses the configured cache limits if no flags are provided
+    Prune {
+        /// Remove artifacts until the cache is smaller than the given size
+        /// (e.g. 10GB)
+        #[clap(long)]
+        max_size: Option<String>,
+        /// Remove artifacts that haven't been used within the given age
+        /// (e.g. 7d)
+        #[clap(long)]
+        max_age: Option<String>,
+        /// Remove every artifact in the local cache
+        #[clap(long, conflicts_with_all = ["max_size", "max_age"])]
+        all: bool,
+        /// Print the artifacts that would be removed without removing them
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
 #[derive(Subcommand, Copy, Clone, Debug, Serialize, PartialEq)]
 #[serde(tag = "command")]
 pub enum DaemonCommand {
@@ -258,6 +287,54 @@ pub enum DaemonCommand {
     Logs,
 }
 
+#[derive(Subcommand, Copy, Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "command")]
+pub enum DepsCommand {
+    /// Reports version drift in the dependencies of your workspaces
+    ///
+    /// Lists external dependencies resolved to different versions, internal
+    /// dependencies that don't use the workspace: protocol, and packages
+    /// installed more than once. Exits with 1 if any of the first two are
+    /// found, packages installed more than once are only informational.
+    Check {
+        /// Pass --json to report the issues in JSON format
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "command")]
+pub enum QueryCommand {
+    /// Lists every package with its direct dependencies and dependents
+    Packages,
+    /// Lists the packages that depend on the given package
+    Dependents {
+        /// The package to query
+        package: String,
+        /// Only include packages that depend on the package directly
+        #[clap(long)]
+        direct: bool,
+    },
+    /// Lists the packages that the given package depends on
+    Dependencies {
+        /// The package to query
+        package: String,
+        /// Only include direct dependencies of the package
+        #[clap(long)]
+        direct: bool,
+    },
+    /// Lists the transitive external dependencies of the given package as
+    /// resolved from the lockfile
+    ExternalDependencies {
+        /// The package to query
+        package: String,
+    },
+    /// Lists the tasks that would run for the given tasks and filters,
+    /// along with their resolved definitions
+    Tasks(Box<RunArgs>),
+}
+
 #[derive(Subcommand, Copy, Clone, Debug, Serialize, PartialEq)]
 #[serde(tag = "command")]
 pub enum TelemetryCommand {
@@ -312,10 +389,26 @@ impl Args {
                     run_args.single_package = is_single_package
                 }
 
-                if let Some(Command::Run(ref mut run_args)) = args.command {
+                if let Some(
+                    Command::Run(ref mut run_args)
+                    | Command::Watch(ref mut run_args)
+                    | Command::Query {
+                        command: QueryCommand::Tasks(ref mut run_args),
+                        ..
+                    },
+                ) = args.command
+                {
                     run_args.single_package = is_single_package;
                 }
 
+                if let Some(Command::Query {
+                    ref mut single_package,
+                    ..
+                }) = args.command
+                {
+                    *single_package = is_single_package;
+                }
+
                 args
             }
             // Don't use error logger when displaying help text
@@ -361,7 +454,12 @@ impl Args {
 
     pub fn get_tasks(&self) -> &[String] {
         match &self.command {
-            Some(Command::Run(box RunArgs { tasks, .. })) => tasks,
+            Some(Command::Run(box RunArgs { tasks, .. }))
+            | Some(Command::Watch(box RunArgs { tasks, .. }))
+            | Some(Command::Query {
+                command: QueryCommand::Tasks(box RunArgs { tasks, .. }),
+                ..
+            }) => tasks,
             _ => self
                 .run_args
                 .as_ref()
@@ -420,9 +518,20 @@ pub enum Command {
     // them as `{ "Bin": {} }` instead of as `"Bin"`.
     /// Get the path to the Turbo binary
     Bin {},
+    /// Check that packages only import what they declare
+    Boundaries {},
     /// Generate the autocompletion script for the specified shell
     #[serde(skip)]
     Completion { shell: Shell },
+    /// Inspect and clean up the local filesystem cache
+    Cache {
+        /// Override the filesystem cache directory.
+        #[clap(long, global = true)]
+        cache_dir: Option<Utf8PathBuf>,
+        #[clap(subcommand)]
+        #[serde(flatten)]
+        command: CacheCommand,
+    },
     /// Runs the Turborepo background daemon
     Daemon {
         /// Set the idle timeout for turbod
@@ -432,6 +541,11 @@ pub enum Command {
         #[serde(flatten)]
         command: Option<DaemonCommand>,
     },
+    /// Check the external and internal dependencies of your monorepo
+    Deps {
+        #[clap(subcommand)]
+        command: DepsCommand,
+    },
     /// Generate a new app / package
     #[clap(aliases = ["g", "gen"])]
     Generate {
@@ -506,6 +620,15 @@ pub enum Command {
         #[clap(long = "out-dir", default_value_t = String::from(prune::DEFAULT_OUTPUT_DIR), value_parser)]
         output_dir: String,
     },
+    /// Query the package and task graph of your monorepo as JSON
+    Query {
+        #[clap(subcommand)]
+        command: QueryCommand,
+        // `--single-package` is stripped before parsing and added back in
+        // for queries the same way as for runs
+        #[clap(skip)]
+        single_package: bool,
+    },
 
     /// Run tasks across projects in your monorepo
     ///
@@ -524,6 +647,12 @@ pub enum Command {
         #[clap(long, value_enum, default_value_t = LinkTarget::RemoteCache)]
         target: LinkTarget,
     },
+    /// Re-run tasks in affected packages when files change
+    ///
+    /// Runs the given tasks once and then watches the repository. When files
+    /// change, tasks in the packages containing them and in their dependents
+    /// are re-executed. Persistent tasks in affected packages are restarted.
+    Watch(Box<RunArgs>),
 }
 
 #[derive(Parser, Clone, Debug, Default, Serialize, PartialEq)]
@@ -617,6 +746,10 @@ pub struct RunArgs {
     /// one-at-a-time) execution.
     #[clap(long)]
     pub concurrency: Option<String>,
+    /// Set the capacity of a named resource that tasks can reserve, e.g.
+    /// `--resource memory=8`. Overrides the capacity set in turbo.json.
+    #[clap(long = "resource", value_name = "NAME=CAPACITY", action = ArgAction::Append)]
+    pub resources: Vec<String>,
     /// Continue execution even if a task exits with an error or non-zero
     /// exit code. The default behavior is to bail
     #[clap(long = "continue")]
@@ -657,6 +790,13 @@ pub struct RunArgs {
     #[clap(short = 'F', long, group = "scope-filter-group")]
     pub filter: Vec<String>,
 
+    /// Run only in packages that changed since the base of this CI run, and
+    /// in the packages that depend on them. The base is read from the CI
+    /// provider, like the target branch of a pull request, and falls back to
+    /// the default branch of the repository.
+    #[clap(long, group = "scope-filter-group")]
+    pub affected: bool,
+
     /// DEPRECATED: Specify package(s) to act as entry
     /// points for task execution. Supports globs.
     #[clap(long, group = "scope-filter-group")]
@@ -747,9 +887,22 @@ pub struct RunArgs {
     #[clap(long, env = "TURBO_REMOTE_CACHE_READ_ONLY", value_name = "BOOL", action = ArgAction::Set, default_value = "false", default_missing_value = "true", num_args = 0..=1)]
     #[serde(skip)]
     pub remote_cache_read_only: bool,
-    /// Generate a summary of the turbo run
-    #[clap(long, env = "TURBO_RUN_SUMMARY", default_missing_value = "true")]
-    pub summarize: Option<Option<bool>>,
+    /// Generate a summary of the turbo run. Use `junit:<path>` or
+    /// `markdown:<path>` to also write a JUnit XML or Markdown report of the
+    /// executed tasks. Can be passed multiple times
+    #[clap(long, env = "TURBO_RUN_SUMMARY", value_name = "SUMMARIZE", default_missing_value = "true", num_args = 0..=1, action = ArgAction::Append)]
+    pub summarize: Vec<String>,
+    /// Explain why tasks missed the cache by comparing their hash inputs
+    /// against the most recent run summary that contains each task. Implies
+    /// --summarize
+    #[clap(long)]
+    pub explain_miss: bool,
+    /// Ask tasks to trace the files and env vars they access, and report
+    /// accesses that aren't declared as task inputs along with declared
+    /// inputs that went unused. Only tools that write a task access trace
+    /// can be audited
+    #[clap(long)]
+    pub audit_inputs: bool,
 
     /// Use "none" to remove prefixes from task logs. Use "task" to get task id
     /// prefixing. Use "auto" to let turbo decide how to prefix the logs
@@ -801,6 +954,7 @@ impl RunArgs {
 
         // default to true
         track_usage!(telemetry, self.continue_execution, |val| val);
+        track_usage!(telemetry, self.affected, |val| val);
         track_usage!(telemetry, self.include_dependencies, |val| val);
         track_usage!(telemetry, self.single_package, |val| val);
         track_usage!(telemetry, self.no_deps, |val| val);
@@ -811,6 +965,8 @@ impl RunArgs {
         track_usage!(telemetry, self.parallel, |val| val);
         track_usage!(telemetry, self.remote_only, |val| val);
         track_usage!(telemetry, self.remote_cache_read_only, |val| val);
+        track_usage!(telemetry, self.explain_miss, |val| val);
+        track_usage!(telemetry, self.audit_inputs, |val| val);
 
         // default to None
         track_usage!(telemetry, &self.cache_dir, Option::is_some);
@@ -819,7 +975,9 @@ impl RunArgs {
         track_usage!(telemetry, &self.since, Option::is_some);
         track_usage!(telemetry, &self.pkg_inference_root, Option::is_some);
         track_usage!(telemetry, &self.anon_profile, Option::is_some);
-        track_usage!(telemetry, &self.summarize, Option::is_some);
+        track_usage!(telemetry, &self.summarize, |summarize: &Vec<String>| {
+            !summarize.is_empty()
+        });
         track_usage!(telemetry, &self.experimental_space_id, Option::is_some);
 
         // track values
@@ -835,6 +993,10 @@ impl RunArgs {
             telemetry.track_arg_value("concurrency", concurrency, EventType::NonSensitive);
         }
 
+        if !self.resources.is_empty() {
+            telemetry.track_arg_value("resource", self.resources.len(), EventType::NonSensitive);
+        }
+
         if !self.global_deps.is_empty() {
             telemetry.track_arg_value("global-deps", self.cache_workers, EventType::NonSensitive);
         }
@@ -965,7 +1127,13 @@ pub async fn run(
     };
 
     // Set some run flags if we have the data and are executing a Run
-    if let Command::Run(run_args) = &mut command {
+    if let Command::Run(run_args)
+    | Command::Watch(run_args)
+    | Command::Query {
+        command: QueryCommand::Tasks(run_args),
+        ..
+    } = &mut command
+    {
         // Don't overwrite the flag if it's already been set for whatever reason
         run_args.single_package = run_args.single_package
             || repo_state
@@ -998,6 +1166,15 @@ pub async fn run(
         }
     }
 
+    // Package queries need to know about single package mode just like runs
+    if let Command::Query { single_package, .. } = &mut command {
+        *single_package = *single_package
+            || repo_state
+                .as_ref()
+                .map(|repo_state| matches!(repo_state.mode, RepoMode::SinglePackage))
+                .unwrap_or(false);
+    }
+
     // TODO: make better use of RepoState, here and below. We've already inferred
     // the repo root, we don't need to calculate it again, along with package
     // manager inference.
@@ -1034,6 +1211,24 @@ pub async fn run(
 
             Ok(0)
         }
+        Command::Boundaries { .. } => {
+            CommandEventBuilder::new("boundaries")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            Ok(boundaries::run(base).await?)
+        }
+        Command::Cache { cache_dir, command } => {
+            CommandEventBuilder::new("cache")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let cache_dir = cache_dir.clone();
+            let command = command.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            cache::run(&base, cache_dir.as_deref(), &command)?;
+
+            Ok(0)
+        }
         #[allow(unused_variables)]
         Command::Daemon { command, idle_time } => {
             CommandEventBuilder::new("daemon")
@@ -1048,6 +1243,14 @@ pub async fn run(
 
             Ok(0)
         }
+        Command::Deps { command } => {
+            CommandEventBuilder::new("deps")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let command = *command;
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            Ok(deps::run(base, &command).await?)
+        }
         Command::Generate {
             tag,
             generator_name,
@@ -1181,6 +1384,19 @@ pub async fn run(
             })?;
             Ok(exit_code)
         }
+        Command::Watch(args) => {
+            let event = CommandEventBuilder::new("watch").with_parent(&root_telemetry);
+            event.track_call();
+            if args.tasks.is_empty() {
+                return Err(Error::NoTasks(backtrace::Backtrace::capture()));
+            }
+
+            let base = CommandBase::new(cli_args.clone(), repo_root, version, ui);
+
+            args.track(&event);
+            let exit_code = watch::run(base, event).await?;
+            Ok(exit_code)
+        }
         Command::Prune {
             scope,
             scope_arg,
@@ -1201,6 +1417,26 @@ pub async fn run(
             prune::prune(&base, &scope, docker, &output_dir, event_child).await?;
             Ok(0)
         }
+        Command::Query {
+            command,
+            single_package,
+        } => {
+            let event = CommandEventBuilder::new("query").with_parent(&root_telemetry);
+            event.track_call();
+            if let QueryCommand::Tasks(args) = command {
+                if args.tasks.is_empty() {
+                    return Err(Error::NoTasks(backtrace::Backtrace::capture()));
+                }
+                args.track(&event);
+            }
+
+            let command = command.clone();
+            let single_package = *single_package;
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            let event_child = event.child();
+            query::run(base, &command, single_package, event_child).await?;
+            Ok(0)
+        }
         Command::Completion { shell } => {
             CommandEventBuilder::new("completion")
                 .with_parent(&root_telemetry)
@@ -1285,7 +1521,8 @@ mod test {
     use anyhow::Result;
 
     use crate::cli::{
-        Args, Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, RunArgs, Verbosity,
+        Args, CacheCommand, Command, DepsCommand, DryRunMode, EnvMode, LogOrder, LogPrefix,
+        OutputLogsMode, QueryCommand, RunArgs, Verbosity,
     };
 
     #[test_case::test_case(
@@ -1451,6 +1688,17 @@ mod test {
             ..Args::default()
         }
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--resource", "memory=8", "--resource", "browser=1"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                resources: vec!["memory=8".to_string(), "browser=1".to_string()],
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build", "--continue"],
         Args {
@@ -1613,6 +1861,31 @@ mod test {
         } ;
         "multiple ignores"
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--affected", "--ignore", "foo.js"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                affected: true,
+                ignore: vec!["foo.js".to_string()],
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        } ;
+        "affected with ignore"
+	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--audit-inputs"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                audit_inputs: true,
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        } ;
+        "audit inputs"
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build", "--scope", "test", "--include-dependencies"],
         Args {
@@ -1842,6 +2115,18 @@ mod test {
 		} ;
         "remote_only=false works"
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--summarize=junit:out/a,b.xml", "--summarize"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                summarize: vec!["junit:out/a,b.xml".to_string(), "true".to_string()],
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+		} ;
+        "summarize can be repeated and keeps commas in paths"
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build", "--scope", "foo", "--scope", "bar"],
         Args {
@@ -2021,6 +2306,122 @@ mod test {
         .test();
     }
 
+    #[test]
+    fn test_parse_deps() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "deps", "check", "--json"]).unwrap(),
+            Args {
+                command: Some(Command::Deps {
+                    command: DepsCommand::Check { json: true },
+                }),
+                ..Args::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "cache", "ls", "--json"]).unwrap(),
+            Args {
+                command: Some(Command::Cache {
+                    cache_dir: None,
+                    command: CacheCommand::Ls { json: true },
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert_eq!(
+            Args::try_parse_from([
+                "turbo",
+                "cache",
+                "prune",
+                "--max-size",
+                "10GB",
+                "--max-age",
+                "7d",
+                "--cache-dir",
+                "my-cache"
+            ])
+            .unwrap(),
+            Args {
+                command: Some(Command::Cache {
+                    cache_dir: Some(Utf8PathBuf::from("my-cache")),
+                    command: CacheCommand::Prune {
+                        max_size: Some("10GB".to_string()),
+                        max_age: Some("7d".to_string()),
+                        all: false,
+                        dry_run: false,
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert!(
+            Args::try_parse_from(["turbo", "cache", "prune", "--all", "--max-size", "1GB"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_watch() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "watch", "dev", "--filter", "web"]).unwrap(),
+            Args {
+                command: Some(Command::Watch(Box::new(RunArgs {
+                    tasks: vec!["dev".to_string()],
+                    filter: vec!["web".to_string()],
+                    ..get_default_run_args()
+                }))),
+                ..Args::default()
+            }
+        );
+    }
+
+    #[test_case::test_case(
+        &["turbo", "query", "packages"],
+        QueryCommand::Packages;
+        "packages"
+    )]
+    #[test_case::test_case(
+        &["turbo", "query", "dependents", "ui", "--direct"],
+        QueryCommand::Dependents { package: "ui".to_string(), direct: true };
+        "direct dependents"
+    )]
+    #[test_case::test_case(
+        &["turbo", "query", "dependencies", "web"],
+        QueryCommand::Dependencies { package: "web".to_string(), direct: false };
+        "dependencies"
+    )]
+    #[test_case::test_case(
+        &["turbo", "query", "external-dependencies", "web"],
+        QueryCommand::ExternalDependencies { package: "web".to_string() };
+        "external dependencies"
+    )]
+    #[test_case::test_case(
+        &["turbo", "query", "tasks", "build", "--filter", "web"],
+        QueryCommand::Tasks(Box::new(RunArgs {
+            tasks: vec!["build".to_string()],
+            filter: vec!["web".to_string()],
+            ..get_default_run_args()
+        }));
+        "tasks"
+    )]
+    fn test_parse_query(args: &[&str], expected: QueryCommand) {
+        assert_eq!(
+            Args::try_parse_from(args).unwrap(),
+            Args {
+                command: Some(Command::Query {
+                    command: expected,
+                    single_package: false
+                }),
+                ..Args::default()
+            }
+        );
+    }
+
     #[test]
     fn test_parse_unlink() {
         assert_eq!(
@@ -0,0 +1,771 @@
+//! `turbo boundaries` checks that packages only import what they declare.
+//!
+//! The package graph is built from the dependencies in each package.json, so
+//! an import of a package that isn't declared goes unnoticed by `--filter`
+//! and `turbo prune`. This scans the JS/TS sources of every package for
+//! import specifiers and reports:
+//! - imports of packages that aren't dependencies of the importing package
+//! - imports that reach into another package's files, either with a relative
+//!   path or through a subpath that the package doesn't export
+//! - imports that break the tag rules in a package's turbo.json
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::OnceLock,
+};
+
+use miette::Diagnostic;
+use regex::Regex;
+use serde_json::Value;
+use turborepo_repository::{
+    package_graph::{self, PackageGraph, PackageInfo, PackageName},
+    package_json::{self, PackageJson},
+};
+use turborepo_scm::SCM;
+use turborepo_ui::{color, BOLD, BOLD_GREEN, BOLD_RED, UI};
+
+use super::CommandBase;
+use crate::{
+    config,
+    turbo_json::{RawBoundariesOptions, TurboJson, CONFIG_FILE},
+};
+
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum Error {
+    #[error(transparent)]
+    PackageJson(#[from] package_json::Error),
+    #[error(transparent)]
+    PackageGraph(#[from] package_graph::builder::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    TurboJson(#[from] config::Error),
+    #[error("failed to list files in {package}: {source}")]
+    Scm {
+        package: String,
+        source: turborepo_scm::Error,
+    },
+}
+
+const SOURCE_EXTENSIONS: [&str; 8] = ["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"];
+
+const NODE_BUILTINS: [&str; 37] = [
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "timers",
+    "tls",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+enum Violation {
+    // A workspace package or an installed external package that isn't in the
+    // importer's package.json
+    Undeclared { package: String },
+    // A relative import that resolves into another package's directory
+    RelativeIntoPackage { package: String },
+    // A subpath that isn't part of the package's `exports`
+    PrivateSubpath { package: String, subpath: String },
+    NotAllowedTag { package: String },
+    DeniedTag { package: String, tag: String },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Undeclared { package } => {
+                write!(f, "{package} is imported but isn't a dependency")
+            }
+            Violation::RelativeIntoPackage { package } => write!(
+                f,
+                "relative import reaches into {package}, import it by name instead"
+            ),
+            Violation::PrivateSubpath { package, subpath } => {
+                write!(f, "{subpath} isn't exported by {package}")
+            }
+            Violation::NotAllowedTag { package } => write!(
+                f,
+                "{package} doesn't have any of the tags this package is allowed to import"
+            ),
+            Violation::DeniedTag { package, tag } => {
+                write!(
+                    f,
+                    "{package} is tagged {tag}, which this package may not import"
+                )
+            }
+        }
+    }
+}
+
+struct Boundaries<'a> {
+    package_graph: &'a PackageGraph,
+    // Workspace package directories relative to the repo root, used to find
+    // the package a relative import resolves into
+    package_dirs: Vec<(String, &'a PackageName)>,
+    tags: HashMap<&'a PackageName, Vec<String>>,
+    rules: HashMap<&'a PackageName, RawBoundariesOptions>,
+}
+
+pub async fn run(base: CommandBase) -> Result<i32, Error> {
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        .build()
+        .await?;
+
+    let mut tags = HashMap::new();
+    let mut rules = HashMap::new();
+    for (name, info) in workspaces(&package_graph) {
+        let turbo_json_path = info.package_path().join_component(CONFIG_FILE);
+        let turbo_json = match TurboJson::read(&base.repo_root, &turbo_json_path) {
+            Ok(turbo_json) => turbo_json,
+            Err(config::Error::Io(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        tags.insert(name, turbo_json.tags);
+        if let Some(boundaries) = turbo_json.boundaries {
+            rules.insert(name, boundaries);
+        }
+    }
+    let boundaries = Boundaries::new(&package_graph, tags, rules);
+
+    let scm = SCM::new(&base.repo_root);
+    let mut violations = BTreeMap::new();
+    for (name, info) in workspaces(&package_graph) {
+        let package_path = info.package_path();
+        let files = scm
+            .get_package_file_hashes::<&str>(&base.repo_root, package_path, &[], None)
+            .map_err(|source| Error::Scm {
+                package: name.to_string(),
+                source,
+            })?;
+        for file in files.keys() {
+            if !is_source_file(file.as_str()) {
+                continue;
+            }
+            let Ok(source) = base
+                .repo_root
+                .resolve(package_path)
+                .join_unix_path(file)
+                .and_then(|path| Ok(path.read_to_string()?))
+            else {
+                continue;
+            };
+            let file = format!("{}/{}", package_path.to_unix(), file);
+            for specifier in import_specifiers(&source) {
+                for violation in boundaries.check(name, info, &file, specifier) {
+                    violations
+                        .entry(file.clone())
+                        .or_insert_with(Vec::new)
+                        .push((specifier.to_string(), violation));
+                }
+            }
+        }
+    }
+
+    print_violations(base.ui, &violations);
+    Ok(if violations.is_empty() { 0 } else { 1 })
+}
+
+fn workspaces(package_graph: &PackageGraph) -> Vec<(&PackageName, &PackageInfo)> {
+    let mut workspaces = package_graph
+        .packages()
+        .filter(|(name, _)| **name != PackageName::Root)
+        .collect::<Vec<_>>();
+    workspaces.sort_by(|(a, _), (b, _)| a.cmp(b));
+    workspaces
+}
+
+fn print_violations(ui: UI, violations: &BTreeMap<String, Vec<(String, Violation)>>) {
+    if violations.is_empty() {
+        println!("{}", color!(ui, BOLD_GREEN, "No boundary violations found"));
+        return;
+    }
+    for (file, file_violations) in violations {
+        println!("{}", color!(ui, BOLD, "{}", file));
+        for (specifier, violation) in file_violations {
+            println!("  import \"{specifier}\": {violation}");
+        }
+    }
+    let count = violations.values().map(|v| v.len()).sum::<usize>();
+    println!();
+    println!(
+        "{}",
+        color!(ui, BOLD_RED, "{} boundary violation(s) found", count)
+    );
+}
+
+impl<'a> Boundaries<'a> {
+    fn new(
+        package_graph: &'a PackageGraph,
+        tags: HashMap<&'a PackageName, Vec<String>>,
+        rules: HashMap<&'a PackageName, RawBoundariesOptions>,
+    ) -> Self {
+        let mut package_dirs = workspaces(package_graph)
+            .into_iter()
+            .map(|(name, info)| (info.package_path().to_unix().to_string(), name))
+            .collect::<Vec<_>>();
+        // Longest first, so that nested packages are found before their parents
+        package_dirs.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+        Self {
+            package_graph,
+            package_dirs,
+            tags,
+            rules,
+        }
+    }
+
+    fn check(
+        &self,
+        importer: &PackageName,
+        info: &PackageInfo,
+        file: &str,
+        specifier: &str,
+    ) -> Vec<Violation> {
+        if specifier.starts_with('.') || specifier.starts_with('/') {
+            return self.check_relative(importer, file, specifier);
+        }
+        let Some((package, subpath)) = split_specifier(specifier) else {
+            return Vec::new();
+        };
+        if is_builtin(package) || importer.as_ref() == package {
+            return Vec::new();
+        }
+
+        let package_name = PackageName::from(package);
+        let Some(target) = self.package_graph.package_info(&package_name) else {
+            return self.check_external(info, package).into_iter().collect();
+        };
+
+        let mut violations = Vec::new();
+        if !declares(&info.package_json, package) {
+            violations.push(Violation::Undeclared {
+                package: package.to_string(),
+            });
+        }
+        if let Some(subpath) = subpath {
+            let exports = target.package_json.other.get("exports");
+            if !is_exported(exports, &subpath) {
+                violations.push(Violation::PrivateSubpath {
+                    package: package.to_string(),
+                    subpath: subpath.to_string(),
+                });
+            }
+        }
+        violations.extend(self.check_tags(importer, &package_name));
+        violations
+    }
+
+    fn check_relative(
+        &self,
+        importer: &PackageName,
+        file: &str,
+        specifier: &str,
+    ) -> Vec<Violation> {
+        let Some(resolved) = resolve_relative(file, specifier) else {
+            return Vec::new();
+        };
+        match self.package_for_path(&resolved) {
+            Some(package) if package != importer => vec![Violation::RelativeIntoPackage {
+                package: package.to_string(),
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    // Undeclared external packages are only reported when the lockfile shows
+    // they are installed for some other package, anything else could be a
+    // path alias
+    fn check_external(&self, info: &PackageInfo, package: &str) -> Option<Violation> {
+        if declares(&info.package_json, package)
+            || declares(self.package_graph.root_package_json(), package)
+        {
+            return None;
+        }
+        let installed = self.package_graph.packages().any(|(_, other)| {
+            let Some(version) = dependency_version(&other.package_json, package) else {
+                return false;
+            };
+            match self.package_graph.lockfile() {
+                Some(lockfile) => lockfile
+                    .resolve_package(other.package_path().as_str(), package, version)
+                    .map_or(false, |resolved| resolved.is_some()),
+                None => true,
+            }
+        });
+        installed.then(|| Violation::Undeclared {
+            package: package.to_string(),
+        })
+    }
+
+    fn check_tags(&self, importer: &PackageName, target: &PackageName) -> Vec<Violation> {
+        let Some(rules) = self.rules.get(importer) else {
+            return Vec::new();
+        };
+        let target_tags = self.tags.get(target).map(Vec::as_slice).unwrap_or_default();
+        let mut violations = Vec::new();
+        if let Some(allow) = &rules.allow {
+            if !target_tags.iter().any(|tag| allow.contains(tag)) {
+                violations.push(Violation::NotAllowedTag {
+                    package: target.to_string(),
+                });
+            }
+        }
+        for tag in target_tags {
+            if rules.deny.iter().flatten().any(|denied| denied == tag) {
+                violations.push(Violation::DeniedTag {
+                    package: target.to_string(),
+                    tag: tag.clone(),
+                });
+            }
+        }
+        violations
+    }
+
+    fn package_for_path(&self, path: &str) -> Option<&'a PackageName> {
+        self.package_dirs
+            .iter()
+            .find(|(dir, _)| {
+                path.strip_prefix(dir.as_str())
+                    .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .map(|(_, name)| *name)
+    }
+}
+
+fn is_source_file(path: &str) -> bool {
+    path.rsplit_once('.').map_or(false, |(_, extension)| {
+        SOURCE_EXTENSIONS.contains(&extension)
+    }) && !path.ends_with(".d.ts")
+}
+
+fn import_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?:\bfrom|\bimport|\brequire\s*\(|\bimport\s*\()\s*["']([^"'\n]+)["']"#)
+            .unwrap()
+    })
+}
+
+/// Finds the specifiers of `import`, `export ... from` and `require` in a
+/// JS/TS source file, skipping commented out code
+fn import_specifiers(source: &str) -> Vec<&str> {
+    let code = blank_comments(source);
+    import_regex()
+        .captures_iter(&code)
+        .map(|capture| &source[capture.get(1).unwrap().range()])
+        .collect()
+}
+
+/// Replaces comments with spaces, keeping newlines and byte offsets intact.
+/// `//` and `/*` inside string and template literals don't start a comment.
+/// Regex literals and `${}` inside templates aren't tracked.
+fn blank_comments(source: &str) -> String {
+    #[derive(Clone, Copy)]
+    enum State {
+        Code,
+        Str(u8),
+        LineComment,
+        BlockComment,
+    }
+
+    let mut code = source.as_bytes().to_vec();
+    let mut state = State::Code;
+    let mut i = 0;
+    while i < code.len() {
+        let byte = code[i];
+        let next = code.get(i + 1).copied();
+        match state {
+            State::Code => match (byte, next) {
+                (b'"' | b'\'' | b'`', _) => state = State::Str(byte),
+                (b'/', Some(b'/')) => state = State::LineComment,
+                (b'/', Some(b'*')) => {
+                    // Skip past the opening `/*` so that `/*/` isn't closed
+                    code[i] = b' ';
+                    code[i + 1] = b' ';
+                    state = State::BlockComment;
+                    i += 2;
+                    continue;
+                }
+                _ => (),
+            },
+            State::Str(_) if byte == b'\\' => {
+                // Skip the escaped character
+                i += 2;
+                continue;
+            }
+            State::Str(quote) if byte == quote || (byte == b'\n' && quote != b'`') => {
+                state = State::Code
+            }
+            State::Str(_) => (),
+            State::LineComment if byte == b'\n' => state = State::Code,
+            State::BlockComment if byte == b'*' && next == Some(b'/') => {
+                code[i] = b' ';
+                code[i + 1] = b' ';
+                state = State::Code;
+                i += 2;
+                continue;
+            }
+            State::LineComment | State::BlockComment => (),
+        }
+        if matches!(state, State::LineComment | State::BlockComment) && code[i] != b'\n' {
+            code[i] = b' ';
+        }
+        i += 1;
+    }
+    String::from_utf8(code).expect("only whole comments are replaced with ascii")
+}
+
+/// Splits a bare specifier into the package name and the subpath within it,
+/// e.g. `@repo/ui/button` into `@repo/ui` and `./button`. Returns `None` for
+/// specifiers that can't be package names, like path aliases.
+fn split_specifier(specifier: &str) -> Option<(&str, Option<String>)> {
+    if specifier.contains(':') || (specifier.starts_with(['#', '~', '@']) && specifier.len() < 2) {
+        return None;
+    }
+    let name_end = if let Some(scoped) = specifier.strip_prefix('@') {
+        let scope_end = scoped.find('/')?;
+        if scope_end == 0 {
+            return None;
+        }
+        let rest = &scoped[scope_end + 1..];
+        1 + scope_end + 1 + rest.find('/').unwrap_or(rest.len())
+    } else {
+        if specifier.starts_with(['#', '~']) {
+            return None;
+        }
+        specifier.find('/').unwrap_or(specifier.len())
+    };
+    let (name, subpath) = specifier.split_at(name_end);
+    if name.is_empty() || name.ends_with('/') {
+        return None;
+    }
+    let subpath = subpath
+        .strip_prefix('/')
+        .filter(|subpath| !subpath.is_empty())
+        .map(|subpath| format!("./{subpath}"));
+    Some((name, subpath))
+}
+
+fn is_builtin(package: &str) -> bool {
+    NODE_BUILTINS.contains(&package)
+}
+
+fn dependency_version<'a>(package_json: &'a PackageJson, package: &str) -> Option<&'a str> {
+    [
+        &package_json.dependencies,
+        &package_json.dev_dependencies,
+        &package_json.optional_dependencies,
+        &package_json.peer_dependencies,
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(|dependencies| dependencies.get(package))
+    .map(|version| version.as_str())
+}
+
+fn declares(package_json: &PackageJson, package: &str) -> bool {
+    dependency_version(package_json, package).is_some()
+        // Type-only imports are usually satisfied by a @types package
+        || dependency_version(package_json, &types_package(package)).is_some()
+}
+
+fn types_package(package: &str) -> String {
+    match package.strip_prefix('@') {
+        Some(scoped) => format!("@types/{}", scoped.replacen('/', "__", 1)),
+        None => format!("@types/{package}"),
+    }
+}
+
+/// Whether `subpath` (e.g. `./button`) can be imported from a package with
+/// the given `exports` field. Without `exports` every file is importable.
+fn is_exported(exports: Option<&Value>, subpath: &str) -> bool {
+    let Some(Value::Object(exports)) = exports else {
+        // A string or an array only exports the package root
+        return exports.is_none();
+    };
+    if !exports.keys().any(|key| key.starts_with('.')) {
+        // An object of conditions, which also only exports the root
+        return false;
+    }
+    let mut best_match: Option<(&str, &Value)> = None;
+    for (key, target) in exports {
+        let matches = match key.split_once('*') {
+            Some((prefix, suffix)) => {
+                subpath.len() >= prefix.len() + suffix.len()
+                    && subpath.starts_with(prefix)
+                    && subpath.ends_with(suffix)
+            }
+            None if key.ends_with('/') => subpath.starts_with(key.as_str()),
+            None => key == subpath,
+        };
+        // The most specific key wins, with exact matches beating patterns
+        let is_better = match best_match {
+            None => true,
+            Some((best, _)) => key == subpath || (best != subpath && key.len() > best.len()),
+        };
+        if matches && is_better {
+            best_match = Some((key, target));
+        }
+    }
+    best_match.map_or(false, |(_, target)| !target.is_null())
+}
+
+/// Resolves a relative specifier against the file importing it, returning
+/// the repo relative path it points to
+fn resolve_relative(file: &str, specifier: &str) -> Option<String> {
+    if specifier.starts_with('/') {
+        return None;
+    }
+    let mut segments = file.split('/').collect::<Vec<_>>();
+    // Drop the file name to get the directory
+    segments.pop();
+    for segment in specifier.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => {
+                // Imports that leave the repository can't reach a package
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_repository::package_manager::PackageManager;
+
+    use super::*;
+    use crate::test_utils::{mock_package_graph_builder, mock_repo_root, package_jsons};
+
+    #[test]
+    fn test_import_specifiers() {
+        let source = r#"
+import React from "react";
+import { Button, type ButtonProps } from '@repo/ui/button';
+import type { Config } from "@repo/config";
+import "./styles.css";
+export * from "../shared";
+export { helper } from "./helper";
+const lazy = await import("@repo/lazy");
+const fs = require('node:fs');
+// import { commented } from "commented-out";
+/*
+import { alsoCommented } from "block-comment";
+*/
+const text = "this string isn't an import";
+const url = "https://turbo.build"; import { afterUrl } from "after-url";
+const glob = '**/*.ts'; import { afterGlob } from "after-glob";
+// don't import "quoted-in-comment"
+const template = `//${url}`; import { afterTemplate } from "after-template";
+"#;
+        assert_eq!(
+            import_specifiers(source),
+            vec![
+                "react",
+                "@repo/ui/button",
+                "@repo/config",
+                "./styles.css",
+                "../shared",
+                "./helper",
+                "@repo/lazy",
+                "node:fs",
+                "after-url",
+                "after-glob",
+                "after-template",
+            ]
+        );
+    }
+
+    #[test_case("react", Some(("react", None)) ; "package")]
+    #[test_case("react-dom/client", Some(("react-dom", Some("./client"))) ; "subpath")]
+    #[test_case("@repo/ui", Some(("@repo/ui", None)) ; "scoped package")]
+    #[test_case("@repo/ui/src/button", Some(("@repo/ui", Some("./src/button"))) ; "scoped subpath")]
+    #[test_case("@/components/button", None ; "path alias")]
+    #[test_case("~/utils", None ; "home alias")]
+    #[test_case("#internal", None ; "subpath import")]
+    #[test_case("node:fs", None ; "node protocol")]
+    fn test_split_specifier(specifier: &str, expected: Option<(&str, Option<&str>)>) {
+        let expected = expected.map(|(name, subpath)| (name, subpath.map(|s| s.to_string())));
+        assert_eq!(split_specifier(specifier), expected);
+    }
+
+    #[test_case(None, "./src/button", true ; "no exports")]
+    #[test_case(Some(json!("./index.js")), "./src/button", false ; "string exports")]
+    #[test_case(Some(json!({ "import": "./index.mjs", "require": "./index.js" })), "./button", false ; "conditional exports")]
+    #[test_case(Some(json!({ ".": "./index.js", "./button": "./button.js" })), "./button", true ; "exact subpath")]
+    #[test_case(Some(json!({ ".": "./index.js", "./button": "./button.js" })), "./src/button", false ; "unexported subpath")]
+    #[test_case(Some(json!({ "./*": "./src/*.js" })), "./button", true ; "pattern")]
+    #[test_case(Some(json!({ "./*": "./src/*.js", "./internal/*": null })), "./internal/secret", false ; "excluded pattern")]
+    #[test_case(Some(json!({ "./utils/": "./src/utils/" })), "./utils/format", true ; "folder")]
+    fn test_is_exported(exports: Option<serde_json::Value>, subpath: &str, expected: bool) {
+        assert_eq!(is_exported(exports.as_ref(), subpath), expected);
+    }
+
+    #[test_case("apps/web/src/index.ts", "../../../packages/ui/src/button", Some("packages/ui/src/button") ; "into other package")]
+    #[test_case("apps/web/src/index.ts", "./button", Some("apps/web/src/button") ; "sibling")]
+    #[test_case("index.ts", "../outside", None ; "outside repo")]
+    fn test_resolve_relative(file: &str, specifier: &str, expected: Option<&str>) {
+        assert_eq!(
+            resolve_relative(file, specifier),
+            expected.map(|s| s.to_string())
+        );
+    }
+
+    // web depends on @repo/ui, but not on @repo/utils. @repo/utils is the
+    // only package that installs lodash.
+    async fn package_graph(root: &AbsoluteSystemPathBuf) -> PackageGraph {
+        mock_package_graph_builder(
+            root,
+            package_jsons! {
+                root,
+                "apps/web" => json!({ "name": "web", "dependencies": { "@repo/ui": "workspace:*" } }),
+                "packages/ui" => json!({
+                    "name": "@repo/ui",
+                    "exports": { ".": "./index.js", "./button": "./button.js" }
+                }),
+                "packages/utils" => json!({ "name": "@repo/utils", "dependencies": { "lodash": "^4.17.21" } })
+            },
+            PackageManager::Npm,
+        )
+        .build()
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        let root = mock_repo_root();
+        let graph = package_graph(&root).await;
+        let name = |name: &str| {
+            graph
+                .packages()
+                .map(|(name, _)| name)
+                .find(|package| package.as_ref() == name)
+                .unwrap()
+        };
+        let (web, utils) = (name("web"), name("@repo/utils"));
+        let tags = HashMap::from([(utils, vec!["server".to_string()])]);
+        let rules = HashMap::from([(
+            web,
+            RawBoundariesOptions {
+                deny: Some(vec!["server".to_string()]),
+                ..Default::default()
+            },
+        )]);
+        let boundaries = Boundaries::new(&graph, tags, rules);
+        let web_info = graph.package_info(web).unwrap();
+        let check =
+            |specifier: &str| boundaries.check(web, web_info, "apps/web/src/index.ts", specifier);
+
+        assert_eq!(check("@repo/ui"), vec![]);
+        assert_eq!(check("@repo/ui/button"), vec![]);
+        assert_eq!(
+            check("@repo/ui/src/secret"),
+            vec![Violation::PrivateSubpath {
+                package: "@repo/ui".to_string(),
+                subpath: "./src/secret".to_string(),
+            }]
+        );
+        assert_eq!(
+            check("@repo/utils"),
+            vec![
+                Violation::Undeclared {
+                    package: "@repo/utils".to_string(),
+                },
+                Violation::DeniedTag {
+                    package: "@repo/utils".to_string(),
+                    tag: "server".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            check("../../../packages/ui/src/button"),
+            vec![Violation::RelativeIntoPackage {
+                package: "@repo/ui".to_string(),
+            }]
+        );
+        // Installed for @repo/utils, but not declared by web
+        assert_eq!(
+            check("lodash"),
+            vec![Violation::Undeclared {
+                package: "lodash".to_string(),
+            }]
+        );
+        // Not installed anywhere, so this could be a path alias
+        assert_eq!(check("components/button"), vec![]);
+        assert_eq!(check("node:fs"), vec![]);
+        assert_eq!(check("path"), vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_check_tags() {
+        let root = mock_repo_root();
+        let graph = package_graph(&root).await;
+        let name = |name: &str| {
+            graph
+                .packages()
+                .map(|(name, _)| name)
+                .find(|package| package.as_ref() == name)
+                .unwrap()
+        };
+        let (web, ui, utils) = (name("web"), name("@repo/ui"), name("@repo/utils"));
+        let tags = HashMap::from([
+            (ui, vec!["ui".to_string()]),
+            (utils, vec!["server".to_string()]),
+        ]);
+        let rules = HashMap::from([(
+            web,
+            RawBoundariesOptions {
+                allow: Some(vec!["ui".to_string()]),
+                deny: None,
+            },
+        )]);
+        let boundaries = Boundaries::new(&graph, tags, rules);
+
+        assert_eq!(boundaries.check_tags(web, ui), vec![]);
+        assert_eq!(
+            boundaries.check_tags(web, utils),
+            vec![Violation::NotAllowedTag {
+                package: "@repo/utils".to_string(),
+            }]
+        );
+        // Packages without rules can import anything
+        assert_eq!(boundaries.check_tags(ui, utils), vec![]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    io::{self, Write},
+    time::SystemTime,
+};
+
+use camino::Utf8Path;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use tabwriter::TabWriter;
+use turborepo_cache::{
+    eviction::{parse_age, parse_size, select_evictions, CacheEntry},
+    fs::FSCache,
+    CacheError, EvictionOpts,
+};
+use turborepo_ui::{color, BOLD, GREY};
+
+use super::CommandBase;
+use crate::cli::CacheCommand;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Cache(#[from] CacheError),
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[error(transparent)]
+    InvalidLimit(#[from] turborepo_cache::eviction::ParseError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("no limits provided, pass --max-size, --max-age, or --all")]
+    NoLimits,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EntrySummary<'a> {
+    hash: &'a str,
+    size: u64,
+    time_saved: u64,
+    last_used: DateTime<Local>,
+}
+
+impl<'a> From<&'a CacheEntry> for EntrySummary<'a> {
+    fn from(entry: &'a CacheEntry) -> Self {
+        Self {
+            hash: &entry.hash,
+            size: entry.size,
+            time_saved: entry.duration,
+            last_used: entry.last_accessed.into(),
+        }
+    }
+}
+
+pub fn run(
+    base: &CommandBase,
+    cache_dir: Option<&Utf8Path>,
+    command: &CacheCommand,
+) -> Result<(), Error> {
+    let cache = FSCache::new(cache_dir, &base.repo_root, None)?;
+    match command {
+        CacheCommand::Ls { json } => ls(base, &cache, *json),
+        CacheCommand::Prune {
+            max_size,
+            max_age,
+            all,
+            dry_run,
+        } => {
+            let opts = if *all {
+                EvictionOpts {
+                    max_size: Some(0),
+                    max_age: None,
+                }
+            } else if max_size.is_none() && max_age.is_none() {
+                // Fall back to the limits used during `turbo run`
+                base.config()?.eviction_opts()?
+            } else {
+                EvictionOpts {
+                    max_size: max_size.as_deref().map(parse_size).transpose()?,
+                    max_age: max_age.as_deref().map(parse_age).transpose()?,
+                }
+            };
+            if !opts.is_enabled() {
+                return Err(Error::NoLimits);
+            }
+            prune(base, &cache, &opts, *dry_run)
+        }
+    }
+}
+
+fn ls(base: &CommandBase, cache: &FSCache, json: bool) -> Result<(), Error> {
+    let entries = cache.entries()?;
+    if json {
+        let summaries = entries.iter().map(EntrySummary::from).collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No artifacts found in the local cache");
+        return Ok(());
+    }
+
+    let mut tab_writer = TabWriter::new(io::stdout()).minwidth(0).padding(2);
+    writeln!(
+        tab_writer,
+        "{}",
+        color!(base.ui, BOLD, "Hash\tSize\tTime Saved\tLast Used")
+    )?;
+    for entry in &entries {
+        let summary = EntrySummary::from(entry);
+        writeln!(
+            tab_writer,
+            "{}\t{}\t{}ms\t{}",
+            summary.hash,
+            format_size(summary.size),
+            summary.time_saved,
+            summary.last_used.format("%Y-%m-%d %H:%M:%S"),
+        )?;
+    }
+    tab_writer.flush()?;
+
+    let total_size = entries.iter().map(|entry| entry.size).sum();
+    println!(
+        "\n{} artifacts, {}",
+        entries.len(),
+        color!(base.ui, GREY, "{}", format_size(total_size))
+    );
+    Ok(())
+}
+
+fn prune(
+    base: &CommandBase,
+    cache: &FSCache,
+    opts: &EvictionOpts,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let evicted = if dry_run {
+        let entries = cache.entries()?;
+        select_evictions(&entries, opts, SystemTime::now())
+            .into_iter()
+            .cloned()
+            .collect()
+    } else {
+        cache.evict(opts)?
+    };
+
+    let freed = evicted.iter().map(|entry| entry.size).sum();
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for entry in &evicted {
+        println!("{} {}", color!(base.ui, GREY, "{verb}"), entry.hash);
+    }
+    println!(
+        "{verb} {} artifacts, freeing {}",
+        evicted.len(),
+        color!(base.ui, BOLD, "{}", format_size(freed))
+    );
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::format_size;
+
+    #[test_case(0, "0 B" ; "empty")]
+    #[test_case(1023, "1023 B" ; "bytes")]
+    #[test_case(1536, "1.5 KB" ; "kilobytes")]
+    #[test_case(10 * 1024 * 1024 * 1024, "10.0 GB" ; "gigabytes")]
+    fn test_format_size(bytes: u64, expected: &str) {
+        assert_eq!(format_size(bytes), expected);
+    }
+}
@@ -0,0 +1,484 @@
+//! `turbo deps check` reports version drift between the dependencies of
+//! workspaces:
+//! - external packages that resolve to different versions in different
+//!   workspaces
+//! - workspaces that depend on an internal package with a version range instead
+//!   of the `workspace:` protocol
+//! - external packages with more than one version installed across the
+//!   transitive dependencies of the repo
+//!
+//! Duplicates are usually expected, e.g. when two libraries need different
+//! majors of a shared dependency, so they're reported for information only
+//! and don't affect the exit code.
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::Serialize;
+use turborepo_lockfiles::{Lockfile, Package};
+use turborepo_repository::{
+    package_graph::{self, PackageGraph, PackageName, PackageNode},
+    package_json::{self, PackageJson},
+    package_manager::PackageManager,
+};
+use turborepo_ui::{color, BOLD, BOLD_GREEN, BOLD_RED, GREY, UI};
+
+use super::CommandBase;
+use crate::cli::DepsCommand;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    PackageJson(#[from] package_json::Error),
+    #[error(transparent)]
+    PackageGraph(#[from] package_graph::builder::Error),
+    #[error(transparent)]
+    Lockfile(#[from] turborepo_lockfiles::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("cannot check external dependencies without a parsed lockfile")]
+    MissingLockfile,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    version_mismatches: Vec<VersionMismatch>,
+    internal_ranges: Vec<InternalRange>,
+    duplicates: Vec<Duplicate>,
+}
+
+/// An external package that workspaces depend on directly, resolved to
+/// different versions
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VersionMismatch {
+    name: String,
+    // Resolved version -> the workspaces using it
+    versions: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// A dependency on an internal package that doesn't use the `workspace:`
+/// protocol
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct InternalRange {
+    package: String,
+    dependency: String,
+    range: String,
+    // Whether the range matches the local version. If it doesn't, the package
+    // manager installs the dependency from the registry instead.
+    satisfied: bool,
+}
+
+/// An external package with more than one version in the transitive
+/// dependencies of the repo
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Duplicate {
+    name: String,
+    versions: BTreeSet<String>,
+}
+
+impl Report {
+    // Duplicates are informational, so they aren't counted as issues
+    fn has_issues(&self) -> bool {
+        !self.version_mismatches.is_empty() || !self.internal_ranges.is_empty()
+    }
+}
+
+pub async fn run(base: CommandBase, command: &DepsCommand) -> Result<i32, Error> {
+    let DepsCommand::Check { json } = command;
+
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        .build()
+        .await?;
+    let lockfile = package_graph.lockfile().ok_or(Error::MissingLockfile)?;
+
+    let mut direct_versions = BTreeMap::new();
+    let mut transitive_versions = BTreeMap::new();
+    for (name, info) in package_graph.packages() {
+        let Some(external_deps) = &info.unresolved_external_dependencies else {
+            continue;
+        };
+        let workspace_path = info.package_path().to_unix();
+        for (dependency, version) in
+            resolve_direct(lockfile, workspace_path.as_str(), external_deps)?
+        {
+            direct_versions
+                .entry(dependency)
+                .or_insert_with(BTreeMap::new)
+                .entry(version)
+                .or_insert_with(BTreeSet::new)
+                .insert(name.to_string());
+        }
+        for (dependency, versions) in
+            resolve_closure(lockfile, workspace_path.as_str(), external_deps)?
+        {
+            transitive_versions
+                .entry(dependency)
+                .or_insert_with(BTreeSet::new)
+                .extend(versions);
+        }
+    }
+
+    let report = Report {
+        version_mismatches: version_mismatches(direct_versions),
+        internal_ranges: internal_ranges(&package_graph),
+        duplicates: duplicates(transitive_versions),
+    };
+
+    if *json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(base.ui, &report);
+    }
+
+    Ok(if report.has_issues() { 1 } else { 0 })
+}
+
+/// Resolves each direct dependency of a workspace to the version installed
+/// for it
+fn resolve_direct<'a>(
+    lockfile: &dyn Lockfile,
+    workspace_path: &str,
+    dependencies: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> Result<Vec<(String, String)>, turborepo_lockfiles::Error> {
+    let mut resolved = Vec::new();
+    for (name, specifier) in dependencies {
+        if let Some(package) = lockfile.resolve_package(workspace_path, name, specifier)? {
+            resolved.push((name.clone(), package.version));
+        }
+    }
+    Ok(resolved)
+}
+
+/// Walks the dependencies of a workspace the same way as
+/// `turborepo_lockfiles::transitive_closure`, but keeps track of package names
+/// so versions of the same package can be compared
+fn resolve_closure<'a>(
+    lockfile: &dyn Lockfile,
+    workspace_path: &str,
+    dependencies: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> Result<BTreeMap<String, BTreeSet<String>>, turborepo_lockfiles::Error> {
+    let mut versions = BTreeMap::new();
+    let mut seen = HashSet::new();
+    let mut queue = dependencies
+        .into_iter()
+        .map(|(name, specifier)| (name.clone(), specifier.clone()))
+        .collect::<Vec<_>>();
+    while let Some((name, specifier)) = queue.pop() {
+        let Some(package) = lockfile.resolve_package(workspace_path, &name, &specifier)? else {
+            continue;
+        };
+        if !seen.insert(package.clone()) {
+            continue;
+        }
+        let Package { key, version } = package;
+        versions
+            .entry(package_name(&name).to_string())
+            .or_insert_with(BTreeSet::new)
+            .insert(version);
+        if let Some(dependencies) = lockfile.all_dependencies(&key)? {
+            queue.extend(dependencies);
+        }
+    }
+    Ok(versions)
+}
+
+// npm lockfiles return the key of each dependency instead of its name, e.g.
+// `node_modules/a/node_modules/b` for `b`
+fn package_name(dependency: &str) -> &str {
+    dependency
+        .rsplit_once("node_modules/")
+        .map_or(dependency, |(_, name)| name)
+}
+
+fn version_mismatches(
+    direct_versions: BTreeMap<String, BTreeMap<String, BTreeSet<String>>>,
+) -> Vec<VersionMismatch> {
+    direct_versions
+        .into_iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, versions)| VersionMismatch { name, versions })
+        .collect()
+}
+
+fn duplicates(transitive_versions: BTreeMap<String, BTreeSet<String>>) -> Vec<Duplicate> {
+    transitive_versions
+        .into_iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, versions)| Duplicate { name, versions })
+        .collect()
+}
+
+fn internal_ranges(package_graph: &PackageGraph) -> Vec<InternalRange> {
+    // npm and yarn 1 don't support the `workspace:` protocol, so a range is
+    // only a problem if it doesn't match the local version
+    let has_workspace_protocol = !matches!(
+        package_graph.package_manager(),
+        PackageManager::Npm | PackageManager::Yarn
+    );
+
+    let mut ranges = Vec::new();
+    for (name, info) in package_graph.packages() {
+        let node = PackageNode::Workspace(name.clone());
+        let internal_deps = package_graph
+            .immediate_dependencies(&node)
+            .unwrap_or_default();
+        let package_json = &info.package_json;
+        let dependencies = [
+            &package_json.dependencies,
+            &package_json.dev_dependencies,
+            &package_json.optional_dependencies,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten();
+        for (dependency, range) in dependencies {
+            let dependency_name = PackageName::from(dependency.as_str());
+            if &dependency_name == name
+                || package_graph.package_info(&dependency_name).is_none()
+                || range.starts_with("workspace:")
+            {
+                continue;
+            }
+            let satisfied = internal_deps.contains(&PackageNode::Workspace(dependency_name));
+            if has_workspace_protocol || !satisfied {
+                ranges.push(InternalRange {
+                    package: name.to_string(),
+                    dependency: dependency.clone(),
+                    range: range.clone(),
+                    satisfied,
+                });
+            }
+        }
+    }
+    ranges.sort_by(|a, b| (&a.package, &a.dependency).cmp(&(&b.package, &b.dependency)));
+    ranges
+}
+
+fn print_report(ui: UI, report: &Report) {
+    if !report.version_mismatches.is_empty() {
+        println!(
+            "{}",
+            color!(ui, BOLD, "Dependencies resolved to different versions")
+        );
+        for mismatch in &report.version_mismatches {
+            println!("  {}", mismatch.name);
+            for (version, workspaces) in &mismatch.versions {
+                let workspaces = workspaces.iter().cloned().collect::<Vec<_>>();
+                println!(
+                    "    {version} {}",
+                    color!(ui, GREY, "{}", workspaces.join(", "))
+                );
+            }
+        }
+        println!();
+    }
+
+    if !report.internal_ranges.is_empty() {
+        println!(
+            "{}",
+            color!(ui, BOLD, "Internal dependencies not using workspace:")
+        );
+        for range in &report.internal_ranges {
+            let note = if range.satisfied {
+                ""
+            } else {
+                " (doesn't match the local version)"
+            };
+            println!(
+                "  {} depends on {}@{}{note}",
+                range.package, range.dependency, range.range
+            );
+        }
+        println!();
+    }
+
+    if !report.duplicates.is_empty() {
+        println!(
+            "{} {}",
+            color!(ui, BOLD, "Packages installed more than once"),
+            color!(ui, GREY, "(informational)")
+        );
+        for duplicate in &report.duplicates {
+            let versions = duplicate.versions.iter().cloned().collect::<Vec<_>>();
+            println!("  {} {}", duplicate.name, versions.join(", "));
+        }
+        println!();
+    }
+
+    if !report.has_issues() {
+        println!("{}", color!(ui, BOLD_GREEN, "No dependency issues found"));
+        return;
+    }
+
+    let count = report.version_mismatches.len() + report.internal_ranges.len();
+    println!(
+        "{}",
+        color!(ui, BOLD_RED, "{} dependency issue(s) found", count)
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+    use turborepo_lockfiles::NpmLockfile;
+
+    use super::*;
+    use crate::test_utils::{mock_package_graph_builder, mock_repo_root, package_jsons};
+
+    const LOCKFILE: &str = r#"{
+  "name": "monorepo",
+  "lockfileVersion": 3,
+  "requires": true,
+  "packages": {
+    "": { "name": "monorepo", "workspaces": ["apps/*"] },
+    "apps/a": { "name": "a", "dependencies": { "lodash": "^4.0.0", "react": "^18.0.0" } },
+    "apps/b": { "name": "b", "dependencies": { "react": "^17.0.0" } },
+    "apps/b/node_modules/react": {
+      "version": "17.0.2",
+      "dependencies": { "loose-envify": "^1.1.0" }
+    },
+    "node_modules/lodash": { "version": "4.17.21" },
+    "node_modules/loose-envify": { "version": "1.4.0" },
+    "node_modules/react": {
+      "version": "18.2.0",
+      "dependencies": { "loose-envify": "^1.1.0" }
+    }
+  }
+}"#;
+
+    fn deps(deps: &[(&str, &str)]) -> BTreeMap<String, String> {
+        deps.iter()
+            .map(|(name, specifier)| (name.to_string(), specifier.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_direct() {
+        let lockfile = NpmLockfile::load(LOCKFILE.as_bytes()).unwrap();
+        let a = deps(&[("lodash", "^4.0.0"), ("react", "^18.0.0")]);
+        let b = deps(&[("react", "^17.0.0")]);
+        assert_eq!(
+            resolve_direct(&lockfile, "apps/a", &a).unwrap(),
+            vec![
+                ("lodash".to_string(), "4.17.21".to_string()),
+                ("react".to_string(), "18.2.0".to_string())
+            ]
+        );
+        assert_eq!(
+            resolve_direct(&lockfile, "apps/b", &b).unwrap(),
+            vec![("react".to_string(), "17.0.2".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_resolve_closure() {
+        let lockfile = NpmLockfile::load(LOCKFILE.as_bytes()).unwrap();
+        let b = deps(&[("react", "^17.0.0")]);
+        let versions = resolve_closure(&lockfile, "apps/b", &b).unwrap();
+        assert_eq!(
+            versions
+                .iter()
+                .map(|(name, versions)| (name.as_str(), versions.iter().cloned().collect()))
+                .collect::<Vec<(&str, Vec<String>)>>(),
+            vec![
+                ("loose-envify", vec!["1.4.0".to_string()]),
+                ("react", vec!["17.0.2".to_string()]),
+            ]
+        );
+    }
+
+    // web uses the workspace: protocol for ui, docs uses a range that matches
+    // the local version of utils and one that doesn't match ui
+    async fn package_graph(package_manager: PackageManager) -> PackageGraph {
+        let root = mock_repo_root();
+        mock_package_graph_builder(
+            &root,
+            package_jsons! {
+                root,
+                "apps/web" => json!({ "name": "web", "dependencies": { "ui": "workspace:*" } }),
+                "apps/docs" => json!({
+                    "name": "docs",
+                    "dependencies": { "utils": "^1.0.0" },
+                    "devDependencies": { "ui": "^2.0.0" }
+                }),
+                "packages/ui" => json!({ "name": "ui", "version": "1.0.0" }),
+                "packages/utils" => json!({ "name": "utils", "version": "1.2.0" })
+            },
+            package_manager,
+        )
+        .build()
+        .await
+        .unwrap()
+    }
+
+    #[test_case(PackageManager::Pnpm, &[("ui", "^2.0.0", false), ("utils", "^1.0.0", true)] ; "pnpm")]
+    #[test_case(PackageManager::Berry, &[("ui", "^2.0.0", false), ("utils", "^1.0.0", true)] ; "berry")]
+    #[test_case(PackageManager::Npm, &[("ui", "^2.0.0", false)] ; "npm")]
+    #[test_case(PackageManager::Yarn, &[("ui", "^2.0.0", false)] ; "yarn1")]
+    #[tokio::test]
+    async fn test_internal_ranges(
+        package_manager: PackageManager,
+        expected: &[(&str, &str, bool)],
+    ) {
+        let graph = package_graph(package_manager).await;
+        let expected = expected
+            .iter()
+            .map(|(dependency, range, satisfied)| InternalRange {
+                package: "docs".to_string(),
+                dependency: dependency.to_string(),
+                range: range.to_string(),
+                satisfied: *satisfied,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(internal_ranges(&graph), expected);
+    }
+
+    #[test]
+    fn test_version_mismatches() {
+        let workspaces = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<BTreeSet<_>>()
+        };
+        let direct_versions = BTreeMap::from([
+            (
+                "lodash".to_string(),
+                BTreeMap::from([("4.17.21".to_string(), workspaces(&["docs", "web"]))]),
+            ),
+            (
+                "react".to_string(),
+                BTreeMap::from([
+                    ("17.0.2".to_string(), workspaces(&["docs"])),
+                    ("18.2.0".to_string(), workspaces(&["web"])),
+                ]),
+            ),
+        ]);
+        assert_eq!(
+            version_mismatches(direct_versions),
+            vec![VersionMismatch {
+                name: "react".to_string(),
+                versions: BTreeMap::from([
+                    ("17.0.2".to_string(), workspaces(&["docs"])),
+                    ("18.2.0".to_string(), workspaces(&["web"])),
+                ]),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_duplicates_are_not_issues() {
+        let report = Report {
+            duplicates: duplicates(BTreeMap::from([(
+                "react".to_string(),
+                BTreeSet::from(["17.0.2".to_string(), "18.2.0".to_string()]),
+            )])),
+            ..Default::default()
+        };
+        assert_eq!(report.duplicates.len(), 1);
+        assert!(!report.has_issues());
+    }
+}
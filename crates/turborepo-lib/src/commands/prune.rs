@@ -1,21 +1,24 @@
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
-use std::sync::OnceLock;
+use std::{str::FromStr, sync::OnceLock};
 
+use globwalk::{ValidatedGlob, WalkType};
 use lazy_static::lazy_static;
-use tracing::trace;
+use tracing::{trace, warn};
 use turbopath::{
     AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf, RelativeUnixPath,
+    RelativeUnixPathBuf,
 };
 use turborepo_repository::{
     package_graph::{self, PackageGraph, PackageName, PackageNode},
     package_json::PackageJson,
+    package_manager::PackageManager,
 };
 use turborepo_telemetry::events::command::CommandEventBuilder;
 use turborepo_ui::BOLD;
 
 use super::CommandBase;
-use crate::turbo_json::RawTurboJson;
+use crate::turbo_json::{PruneDestination, RawTurboJson};
 
 pub const DEFAULT_OUTPUT_DIR: &str = "out";
 
@@ -37,6 +40,10 @@ pub enum Error {
     PackageGraph(#[from] package_graph::Error),
     #[error(transparent)]
     Lockfile(#[from] turborepo_lockfiles::Error),
+    #[error(transparent)]
+    Glob(#[from] globwalk::GlobError),
+    #[error(transparent)]
+    Globwalk(#[from] globwalk::WalkError),
     #[error("turbo doesn't support workspaces at file system root")]
     WorkspaceAtFilesystemRoot,
     #[error("at least one target must be specified")]
@@ -96,10 +103,7 @@ pub async fn prune(
 
     let prune = Prune::new(base, scope, docker, output_dir).await?;
 
-    if matches!(
-        prune.package_graph.package_manager(),
-        turborepo_repository::package_manager::PackageManager::Bun
-    ) {
+    if matches!(prune.package_graph.package_manager(), PackageManager::Bun) {
         return Err(Error::BunUnsupported);
     }
 
@@ -181,7 +185,17 @@ pub async fn prune(
         prune.copy_directory(&path, *required_for_install)?;
     }
 
-    prune.copy_turbo_json(&workspace_names)?;
+    if let Some(turbo_json) = prune.copy_turbo_json(&workspace_names)? {
+        let include = turbo_json
+            .prune
+            .and_then(|prune| prune.include)
+            .unwrap_or_default();
+        for (entry, destination) in &include {
+            if !prune.copy_extra_files(entry, *destination)? {
+                warn!("prune.include entry `{entry}` in turbo.json didn't match any files");
+            }
+        }
+    }
 
     let original_patches = prune
         .package_graph
@@ -226,8 +240,10 @@ pub async fn prune(
                 Some(CopyDestination::Docker),
             )?;
         }
+        prune.copy_referenced_files(&pruned_json)?;
     } else {
         prune.copy_file(package_json(), Some(CopyDestination::Docker))?;
+        prune.copy_referenced_files(prune.package_graph.root_package_json())?;
     }
 
     Ok(())
@@ -244,6 +260,8 @@ struct Prune<'a> {
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum CopyDestination {
+    // Copies to json only
+    Json,
     // Copies to full and json
     Docker,
     // Copies to out, full, and json
@@ -332,8 +350,10 @@ impl<'a> Prune<'a> {
             trace!("{from_path} doesn't exist, skipping copying");
             return Ok(());
         }
-        let full_to = self.full_directory.resolve(path);
-        turborepo_fs::copy_file(&from_path, full_to)?;
+        if !matches!(destination, Some(CopyDestination::Json)) {
+            let full_to = self.full_directory.resolve(path);
+            turborepo_fs::copy_file(&from_path, full_to)?;
+        }
         if matches!(destination, Some(CopyDestination::All)) {
             let out_to = self.out_directory.resolve(path);
             turborepo_fs::copy_file(&from_path, out_to)?;
@@ -341,7 +361,9 @@ impl<'a> Prune<'a> {
         if self.docker
             && matches!(
                 destination,
-                Some(CopyDestination::Docker) | Some(CopyDestination::All)
+                Some(CopyDestination::Json)
+                    | Some(CopyDestination::Docker)
+                    | Some(CopyDestination::All)
             )
         {
             let docker_to = self.docker_directory().resolve(path);
@@ -360,8 +382,10 @@ impl<'a> Prune<'a> {
             trace!("{from_path} doesn't exist, skipping copying");
             return Ok(());
         }
-        let full_to = self.full_directory.resolve(path);
-        turborepo_fs::recursive_copy(&from_path, full_to)?;
+        if !matches!(destination, Some(CopyDestination::Json)) {
+            let full_to = self.full_directory.resolve(path);
+            turborepo_fs::recursive_copy(&from_path, full_to)?;
+        }
         if matches!(destination, Some(CopyDestination::All)) {
             let out_to = self.out_directory.resolve(path);
             turborepo_fs::recursive_copy(&from_path, out_to)?;
@@ -369,7 +393,9 @@ impl<'a> Prune<'a> {
         if self.docker
             && matches!(
                 destination,
-                Some(CopyDestination::Docker) | Some(CopyDestination::All)
+                Some(CopyDestination::Json)
+                    | Some(CopyDestination::Docker)
+                    | Some(CopyDestination::All)
             )
         {
             let docker_to = self.docker_directory().resolve(path);
@@ -378,6 +404,47 @@ impl<'a> Prune<'a> {
         Ok(())
     }
 
+    // Copies a `prune.include` entry from turbo.json, which can be a file, a
+    // directory or a glob of files. Entries are validated to be relative and
+    // inside the repository when turbo.json is parsed. Returns whether
+    // anything was copied.
+    fn copy_extra_files(&self, entry: &str, destination: PruneDestination) -> Result<bool, Error> {
+        let destination = match destination {
+            PruneDestination::Json => Some(CopyDestination::Json),
+            PruneDestination::Full => None,
+            PruneDestination::Both => Some(CopyDestination::Docker),
+        };
+        let entry = entry.strip_prefix("./").unwrap_or(entry);
+        let path = RelativeUnixPathBuf::new(entry)?.to_anchored_system_path_buf();
+        if self.root.resolve(&path).as_std_path().is_dir() {
+            self.copy_directory(&path, destination)?;
+            return Ok(true);
+        }
+        let files = globwalk::globwalk(
+            &self.root,
+            &[ValidatedGlob::from_str(entry)?],
+            &[],
+            WalkType::Files,
+        )?;
+        for file in &files {
+            let path = AnchoredSystemPathBuf::relative_path_between(&self.root, file);
+            self.copy_file(&path, destination)?;
+        }
+        Ok(!files.is_empty())
+    }
+
+    // Copies the files that a root package.json refers to, which the package
+    // manager needs to install
+    fn copy_referenced_files(&self, package_json: &PackageJson) -> Result<(), Error> {
+        for path in referenced_files(package_json) {
+            self.copy_file(
+                &path.to_anchored_system_path_buf(),
+                Some(CopyDestination::Docker),
+            )?;
+        }
+        Ok(())
+    }
+
     fn copy_workspace(&self, package_json_path: &AnchoredSystemPath) -> Result<(), Error> {
         let package_json_path = self.root.resolve(package_json_path);
         let original_dir = package_json_path
@@ -423,7 +490,7 @@ impl<'a> Prune<'a> {
         names
     }
 
-    fn copy_turbo_json(&self, workspaces: &[String]) -> Result<(), Error> {
+    fn copy_turbo_json(&self, workspaces: &[String]) -> Result<Option<RawTurboJson>, Error> {
         let anchored_turbo_path = turbo_json();
         let original_turbo_path = self.root.resolve(anchored_turbo_path);
         let new_turbo_path = self.full_directory.resolve(anchored_turbo_path);
@@ -432,7 +499,7 @@ impl<'a> Prune<'a> {
             Ok(contents) => contents,
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
                 // If turbo.json doesn't exist skip copying
-                return Ok(());
+                return Ok(None);
             }
             Err(e) => return Err(e.into()),
         };
@@ -442,6 +509,181 @@ impl<'a> Prune<'a> {
         let pruned_turbo_json = turbo_json.prune_tasks(workspaces);
         new_turbo_path.create_with_contents(serde_json::to_string_pretty(&pruned_turbo_json)?)?;
 
-        Ok(())
+        Ok(Some(turbo_json))
+    }
+}
+
+// Patches referenced by `pnpm.patchedDependencies`, bun's `patchedDependencies`
+// and yarn's `patch:` protocol in `resolutions`
+fn referenced_files(package_json: &PackageJson) -> Vec<RelativeUnixPathBuf> {
+    let pnpm_patches = package_json
+        .pnpm
+        .as_ref()
+        .and_then(|pnpm| pnpm.patched_dependencies.as_ref())
+        .into_iter()
+        .flat_map(|patches| patches.values().map(|path| path.to_string()));
+    let bun_patches = package_json
+        .other
+        .get("patchedDependencies")
+        .and_then(|patches| patches.as_object())
+        .into_iter()
+        .flat_map(|patches| patches.values())
+        .filter_map(|path| path.as_str().map(|path| path.to_string()));
+    let yarn_patches = package_json
+        .resolutions
+        .iter()
+        .flat_map(|resolutions| resolutions.values())
+        .filter_map(|resolution| yarn_patch_path(resolution).map(|path| path.to_string()));
+
+    let mut files = pnpm_patches
+        .chain(bun_patches)
+        .chain(yarn_patches)
+        .filter_map(|path| RelativeUnixPathBuf::new(path).ok())
+        .collect::<Vec<_>>();
+    files.sort();
+    files.dedup();
+    files
+}
+
+// e.g. `patch:lodash@npm%3A4.17.21#./.yarn/patches/lodash.patch::locator=root`
+fn yarn_patch_path(resolution: &str) -> Option<&str> {
+    let (_, path) = resolution.strip_prefix("patch:")?.rsplit_once('#')?;
+    let path = path.split_once("::").map_or(path, |(path, _)| path);
+    path.strip_prefix("./").or_else(|| path.strip_prefix("~/"))
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use tempdir::TempDir;
+    use test_case::test_case;
+    use turborepo_repository::discovery::{DiscoveryResponse, PackageDiscovery};
+
+    use super::*;
+
+    struct MockDiscovery;
+    impl PackageDiscovery for MockDiscovery {
+        async fn discover_packages(
+            &self,
+        ) -> Result<DiscoveryResponse, turborepo_repository::discovery::Error> {
+            Ok(DiscoveryResponse {
+                package_manager: PackageManager::Npm,
+                workspaces: vec![],
+            })
+        }
+    }
+
+    #[test]
+    fn test_referenced_files_pnpm() {
+        let package_json = PackageJson::from_value(json!({
+            "name": "root",
+            "pnpm": {
+                "patchedDependencies": {
+                    "lodash@4.17.21": "patches/lodash@4.17.21.patch",
+                    "is-odd@3.0.1": "patches/is-odd@3.0.1.patch",
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            referenced_files(&package_json),
+            vec![
+                RelativeUnixPathBuf::new("patches/is-odd@3.0.1.patch").unwrap(),
+                RelativeUnixPathBuf::new("patches/lodash@4.17.21.patch").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_referenced_files_bun() {
+        let package_json = PackageJson::from_value(json!({
+            "name": "root",
+            "patchedDependencies": {
+                "lodash@4.17.21": "patches/lodash@4.17.21.patch",
+                "not-a-path": 1,
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            referenced_files(&package_json),
+            vec![RelativeUnixPathBuf::new("patches/lodash@4.17.21.patch").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_referenced_files_none() {
+        let package_json = PackageJson::from_value(json!({ "name": "root" })).unwrap();
+        assert!(referenced_files(&package_json).is_empty());
+    }
+
+    #[test_case(PruneDestination::Json, false, true ; "json")]
+    #[test_case(PruneDestination::Full, true, false ; "full")]
+    #[test_case(PruneDestination::Both, true, true ; "both")]
+    #[tokio::test]
+    async fn test_copy_extra_files(destination: PruneDestination, in_full: bool, in_json: bool) {
+        let tmp = TempDir::new("prune").unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        root.join_component("tsconfig.base.json")
+            .create_with_contents("{}")
+            .unwrap();
+        root.join_component("config").create_dir_all().unwrap();
+        root.join_components(&["config", "a.json"])
+            .create_with_contents("{}")
+            .unwrap();
+        root.join_components(&["config", "b.json"])
+            .create_with_contents("{}")
+            .unwrap();
+        root.join_components(&["config", "c.txt"])
+            .create_with_contents("")
+            .unwrap();
+
+        let package_graph = PackageGraph::builder(&root, PackageJson::default())
+            .with_package_discovery(MockDiscovery)
+            .with_single_package_mode(true)
+            .build()
+            .await
+            .unwrap();
+        let out_directory = root.join_component("out");
+        let prune = Prune {
+            package_graph,
+            root: root.clone(),
+            full_directory: out_directory.join_component("full"),
+            out_directory: out_directory.clone(),
+            docker: true,
+            scope: &[],
+        };
+
+        assert!(prune
+            .copy_extra_files("tsconfig.base.json", destination)
+            .unwrap());
+        assert!(prune
+            .copy_extra_files("config/*.json", destination)
+            .unwrap());
+        assert!(!prune.copy_extra_files("missing.json", destination).unwrap());
+
+        for (file, expected) in [
+            (&["tsconfig.base.json"][..], true),
+            (&["config", "a.json"], true),
+            (&["config", "b.json"], true),
+            (&["config", "c.txt"], false),
+        ] {
+            let in_output = |output: &str| {
+                out_directory
+                    .join_component(output)
+                    .join_components(file)
+                    .exists()
+            };
+            assert_eq!(in_output("full"), expected && in_full, "{file:?} in full");
+            assert_eq!(in_output("json"), expected && in_json, "{file:?} in json");
+        }
+    }
+
+    #[test_case("^4.17.21", None ; "not a patch")]
+    #[test_case("patch:lodash@npm%3A4.17.21#./.yarn/patches/lodash.patch", Some(".yarn/patches/lodash.patch") ; "relative")]
+    #[test_case("patch:lodash@npm%3A4.17.21#~/patches/lodash.patch", Some("patches/lodash.patch") ; "project root")]
+    #[test_case("patch:lodash@npm%3A4.17.21#./patches/lodash.patch::locator=root%40workspace%3A.", Some("patches/lodash.patch") ; "with locator")]
+    #[test_case("patch:lodash@npm%3A4.17.21#builtin<compat/lodash>", None ; "builtin")]
+    fn test_yarn_patch_path(resolution: &str, expected: Option<&str>) {
+        assert_eq!(yarn_patch_path(resolution), expected);
     }
 }
@@ -0,0 +1,392 @@
+//! `turbo query` prints the package graph and the task graph as JSON so
+//! that other tooling can answer questions about the monorepo without
+//! reimplementing turbo's package discovery and task resolution.
+use std::collections::HashSet;
+
+use miette::Diagnostic;
+use serde::Serialize;
+use turbopath::AnchoredSystemPath;
+use turborepo_lockfiles::Package;
+use turborepo_repository::{
+    package_graph::{self, PackageGraph, PackageName, PackageNode},
+    package_json::{self, PackageJson},
+};
+use turborepo_telemetry::events::command::CommandEventBuilder;
+
+use super::CommandBase;
+use crate::{
+    cli::QueryCommand,
+    engine::{Engine, TaskNode},
+    run::{self, summary::TaskSummaryTaskDefinition, task_id::TaskId, Run},
+};
+
+#[derive(Debug, thiserror::Error, Diagnostic)]
+pub enum Error {
+    #[error(transparent)]
+    PackageJson(#[from] package_json::Error),
+    #[error(transparent)]
+    PackageGraph(#[from] package_graph::builder::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Run(#[from] run::Error),
+    #[error("json error while querying: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("package `{0}` not found")]
+    MissingPackage(String),
+    #[error("cannot resolve external dependencies without a parsed lockfile")]
+    MissingLockfile,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageDetails<'a> {
+    name: &'a str,
+    path: &'a AnchoredSystemPath,
+    dependencies: Vec<&'a str>,
+    dependents: Vec<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskDetails<'a> {
+    task_id: &'a TaskId<'static>,
+    package: &'a str,
+    task: &'a str,
+    directory: Option<&'a AnchoredSystemPath>,
+    command: Option<&'a str>,
+    dependencies: Vec<String>,
+    dependents: Vec<String>,
+    definition: TaskSummaryTaskDefinition,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum PackageQuery<'a> {
+    Packages(Vec<PackageDetails<'a>>),
+    ExternalDependencies(Vec<&'a Package>),
+}
+
+pub async fn run(
+    base: CommandBase,
+    command: &QueryCommand,
+    single_package: bool,
+    telemetry: CommandEventBuilder,
+) -> Result<(), Error> {
+    let output = match command {
+        QueryCommand::Tasks(_) => {
+            let run = Run::new(base, None)?;
+            let scope = run.resolve_scope().await?;
+            let engine = run.build_scope_engine(&scope)?;
+            serde_json::to_string_pretty(&task_details(&scope.pkg_dep_graph, &engine))?
+        }
+        command => {
+            if let QueryCommand::Dependents { direct, .. }
+            | QueryCommand::Dependencies { direct, .. } = command
+            {
+                telemetry.track_arg_usage("direct", *direct);
+            }
+            let package_graph = package_graph(&base, single_package).await?;
+            serde_json::to_string_pretty(&query_package_graph(&package_graph, command)?)?
+        }
+    };
+
+    println!("{output}");
+
+    Ok(())
+}
+
+async fn package_graph(base: &CommandBase, single_package: bool) -> Result<PackageGraph, Error> {
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        .with_single_package_mode(single_package)
+        .build()
+        .await?;
+    Ok(package_graph)
+}
+
+fn query_package_graph<'a>(
+    package_graph: &'a PackageGraph,
+    command: &QueryCommand,
+) -> Result<PackageQuery<'a>, Error> {
+    let packages = match command {
+        QueryCommand::Packages => package_graph
+            .packages()
+            .map(|(name, _)| name)
+            .collect::<HashSet<_>>(),
+        QueryCommand::Dependents { package, direct } => {
+            let node = package_node(package_graph, package)?;
+            let dependents = if *direct {
+                package_graph.immediate_ancestors(&node).unwrap_or_default()
+            } else {
+                package_graph.ancestors(&node)
+            };
+            workspaces(dependents)
+        }
+        QueryCommand::Dependencies { package, direct } => {
+            let node = package_node(package_graph, package)?;
+            let dependencies = if *direct {
+                package_graph
+                    .immediate_dependencies(&node)
+                    .unwrap_or_default()
+            } else {
+                package_graph.dependencies(&node)
+            };
+            workspaces(dependencies)
+        }
+        QueryCommand::ExternalDependencies { package } => {
+            let node = package_node(package_graph, package)?;
+            let info = package_graph
+                .package_info(node.as_package_name())
+                .expect("package node was found in graph");
+            let mut external_deps = info
+                .transitive_dependencies
+                .as_ref()
+                .ok_or(Error::MissingLockfile)?
+                .iter()
+                .collect::<Vec<&Package>>();
+            external_deps.sort();
+            return Ok(PackageQuery::ExternalDependencies(external_deps));
+        }
+        QueryCommand::Tasks(_) => unreachable!("task queries need a run, not just a package graph"),
+    };
+    Ok(PackageQuery::Packages(package_details(
+        package_graph,
+        packages,
+    )))
+}
+
+fn package_node(package_graph: &PackageGraph, package: &str) -> Result<PackageNode, Error> {
+    let name = PackageName::from(package);
+    if package_graph.package_info(&name).is_none() {
+        return Err(Error::MissingPackage(package.to_string()));
+    }
+    Ok(PackageNode::Workspace(name))
+}
+
+// Drops the synthetic root node that every package without internal
+// dependencies points at
+fn workspaces<'a>(nodes: impl IntoIterator<Item = &'a PackageNode>) -> HashSet<&'a PackageName> {
+    nodes
+        .into_iter()
+        .filter_map(|node| match node {
+            PackageNode::Workspace(name) => Some(name),
+            PackageNode::Root => None,
+        })
+        .collect()
+}
+
+fn package_details<'a>(
+    package_graph: &'a PackageGraph,
+    packages: HashSet<&'a PackageName>,
+) -> Vec<PackageDetails<'a>> {
+    let mut details = packages
+        .into_iter()
+        .filter_map(|name| {
+            let info = package_graph.package_info(name)?;
+            let node = PackageNode::Workspace(name.clone());
+            let sorted_names = |nodes: Option<HashSet<&'a PackageNode>>| {
+                let mut names = workspaces(nodes.unwrap_or_default())
+                    .into_iter()
+                    .map(|name| name.as_ref())
+                    .collect::<Vec<_>>();
+                names.sort();
+                names
+            };
+            Some(PackageDetails {
+                name: name.as_ref(),
+                path: info.package_path(),
+                dependencies: sorted_names(package_graph.immediate_dependencies(&node)),
+                dependents: sorted_names(package_graph.immediate_ancestors(&node)),
+            })
+        })
+        .collect::<Vec<_>>();
+    details.sort_by(|a, b| a.name.cmp(b.name));
+    details
+}
+
+fn task_details<'a>(package_graph: &'a PackageGraph, engine: &'a Engine) -> Vec<TaskDetails<'a>> {
+    let sorted_ids = |nodes: Option<HashSet<&TaskNode>>| {
+        let mut ids = nodes
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|node| match node {
+                TaskNode::Task(task_id) => Some(task_id.to_string()),
+                TaskNode::Root => None,
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+
+    let mut details = engine
+        .tasks()
+        .filter_map(|node| match node {
+            TaskNode::Task(task_id) => Some(task_id),
+            TaskNode::Root => None,
+        })
+        .map(|task_id| {
+            let package_name = PackageName::from(task_id.package());
+            let command = package_graph
+                .package_json(&package_name)
+                .and_then(|package_json| package_json.scripts.get(task_id.task()))
+                .map(|script| script.as_str());
+            TaskDetails {
+                task_id,
+                package: task_id.package(),
+                task: task_id.task(),
+                directory: package_graph.package_dir(&package_name),
+                command,
+                dependencies: sorted_ids(engine.dependencies(task_id)),
+                dependents: sorted_ids(engine.dependents(task_id)),
+                definition: engine
+                    .task_definition(task_id)
+                    .cloned()
+                    .unwrap_or_default()
+                    .into(),
+            }
+        })
+        .collect::<Vec<_>>();
+    details.sort_by(|a, b| a.task_id.cmp(b.task_id));
+    details
+}
+
+#[cfg(test)]
+mod test {
+    use std::assert_matches::assert_matches;
+
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_repository::package_manager::PackageManager;
+
+    use super::*;
+    use crate::test_utils::{mock_package_graph_builder, mock_repo_root, package_jsons};
+
+    // a depends on b which depends on c, d stands alone
+    async fn package_graph(root: &AbsoluteSystemPathBuf, single_package: bool) -> PackageGraph {
+        mock_package_graph_builder(
+            root,
+            package_jsons! {
+                root,
+                "a" => ["b"],
+                "b" => ["c"],
+                "c" => [],
+                "d" => []
+            },
+            PackageManager::Npm,
+        )
+        .with_single_package_mode(single_package)
+        .build()
+        .await
+        .unwrap()
+    }
+
+    fn names(query: PackageQuery) -> Vec<String> {
+        match query {
+            PackageQuery::Packages(details) => details
+                .into_iter()
+                .map(|details| details.name.to_string())
+                .collect(),
+            PackageQuery::ExternalDependencies(_) => panic!("expected a package query"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_packages() {
+        let root = mock_repo_root();
+        let graph = package_graph(&root, false).await;
+        let query = query_package_graph(&graph, &QueryCommand::Packages).unwrap();
+        let PackageQuery::Packages(details) = query else {
+            panic!("expected a package query");
+        };
+        assert_eq!(
+            details
+                .iter()
+                .map(|details| details.name)
+                .collect::<Vec<_>>(),
+            vec!["//", "a", "b", "c", "d"]
+        );
+        let b = details.iter().find(|details| details.name == "b").unwrap();
+        assert_eq!(b.dependencies, vec!["c"]);
+        assert_eq!(b.dependents, vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn test_query_dependencies() {
+        let root = mock_repo_root();
+        let graph = package_graph(&root, false).await;
+        let dependencies = |direct| QueryCommand::Dependencies {
+            package: "a".to_string(),
+            direct,
+        };
+        assert_eq!(
+            names(query_package_graph(&graph, &dependencies(true)).unwrap()),
+            vec!["b"]
+        );
+        assert_eq!(
+            names(query_package_graph(&graph, &dependencies(false)).unwrap()),
+            vec!["b", "c"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_dependents() {
+        let root = mock_repo_root();
+        let graph = package_graph(&root, false).await;
+        let dependents = |direct| QueryCommand::Dependents {
+            package: "c".to_string(),
+            direct,
+        };
+        assert_eq!(
+            names(query_package_graph(&graph, &dependents(true)).unwrap()),
+            vec!["b"]
+        );
+        assert_eq!(
+            names(query_package_graph(&graph, &dependents(false)).unwrap()),
+            vec!["a", "b"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_unknown_package() {
+        let root = mock_repo_root();
+        let graph = package_graph(&root, false).await;
+        let result = query_package_graph(
+            &graph,
+            &QueryCommand::Dependencies {
+                package: "missing".to_string(),
+                direct: false,
+            },
+        );
+        assert_matches!(result, Err(Error::MissingPackage(package)) if package == "missing");
+    }
+
+    #[tokio::test]
+    async fn test_query_external_dependencies_without_lockfile() {
+        let root = mock_repo_root();
+        let graph = package_graph(&root, false).await;
+        let result = query_package_graph(
+            &graph,
+            &QueryCommand::ExternalDependencies {
+                package: "a".to_string(),
+            },
+        );
+        assert_matches!(result, Err(Error::MissingLockfile));
+    }
+
+    #[tokio::test]
+    async fn test_query_single_package() {
+        let root = mock_repo_root();
+        let graph = package_graph(&root, true).await;
+        assert_eq!(
+            names(query_package_graph(&graph, &QueryCommand::Packages).unwrap()),
+            vec!["//"]
+        );
+        let result = query_package_graph(
+            &graph,
+            &QueryCommand::Dependents {
+                package: "a".to_string(),
+                direct: true,
+            },
+        );
+        assert_matches!(result, Err(Error::MissingPackage(_)));
+    }
+}
@@ -1,8 +1,12 @@
+use std::future::Future;
+
 use turborepo_telemetry::events::command::CommandEventBuilder;
 
 use crate::{commands::CommandBase, run, run::Run, signal::SignalHandler};
 
-pub async fn run(base: CommandBase, telemetry: CommandEventBuilder) -> Result<i32, run::Error> {
+/// Returns a future that completes when the process receives an interrupt or
+/// termination signal.
+pub(crate) fn get_signal() -> Result<impl Future<Output = Option<()>> + Send, run::Error> {
     #[cfg(windows)]
     let signal = {
         let mut ctrl_c = tokio::signal::windows::ctrl_c().map_err(run::Error::SignalHandler)?;
@@ -27,7 +31,11 @@ pub async fn run(base: CommandBase, telemetry: CommandEventBuilder) -> Result<i3
         }
     };
 
-    let handler = SignalHandler::new(signal);
+    Ok(signal)
+}
+
+pub async fn run(base: CommandBase, telemetry: CommandEventBuilder) -> Result<i32, run::Error> {
+    let handler = SignalHandler::new(get_signal()?);
 
     let api_auth = base.api_auth()?;
     let api_client = base.api_client()?;
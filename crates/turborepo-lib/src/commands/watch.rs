@@ -0,0 +1,17 @@
+use turborepo_telemetry::events::command::CommandEventBuilder;
+
+use crate::{
+    commands::{run::get_signal, CommandBase},
+    run::watch::{self, WatchClient},
+    signal::SignalHandler,
+};
+
+pub async fn run(base: CommandBase, telemetry: CommandEventBuilder) -> Result<i32, watch::Error> {
+    let handler = SignalHandler::new(get_signal()?);
+
+    let client = WatchClient::new(base, telemetry)?;
+    let exit_code = client.start(&handler).await?;
+    // Make sure anything else that subscribed to the handler gets to clean up
+    handler.close().await;
+    Ok(exit_code)
+}
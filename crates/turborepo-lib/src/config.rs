@@ -6,6 +6,10 @@ use serde::Deserialize;
 use struct_iterable::Iterable;
 use thiserror::Error;
 use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
+use turborepo_cache::{
+    eviction::{parse_age, parse_size},
+    EvictionOpts,
+};
 use turborepo_dirs::config_dir;
 use turborepo_errors::TURBO_SITE;
 use turborepo_repository::package_json::{Error as PackageJsonError, PackageJson};
@@ -117,6 +121,10 @@ pub enum Error {
     InvalidRemoteCacheTimeout(#[source] std::num::ParseIntError),
     #[error("TURBO_PREFLIGHT should be either 1 or 0.")]
     InvalidPreflight,
+    #[error("TURBO_OTEL_HEADERS: expected comma separated key=value pairs.")]
+    InvalidOtelHeaders,
+    #[error(transparent)]
+    InvalidCacheLimit(#[from] turborepo_cache::eviction::ParseError),
     #[error(transparent)]
     #[diagnostic(transparent)]
     TurboJsonParseError(#[from] turbo_json::parser::Error),
@@ -134,6 +142,7 @@ macro_rules! create_builder {
 const DEFAULT_API_URL: &str = "https://vercel.com/api";
 const DEFAULT_LOGIN_URL: &str = "https://vercel.com";
 const DEFAULT_TIMEOUT: u64 = 30;
+const DEFAULT_OTEL_SERVICE_NAME: &str = "turbo";
 
 // We intentionally don't derive Serialize so that different parts
 // of the code that want to display the config can tune how they
@@ -163,6 +172,13 @@ pub struct ConfigurationOptions {
     pub(crate) timeout: Option<u64>,
     pub(crate) enabled: Option<bool>,
     pub(crate) spaces_id: Option<String>,
+    pub(crate) cache_max_size: Option<String>,
+    pub(crate) cache_max_age: Option<String>,
+    pub(crate) shared_directory: Option<String>,
+    pub(crate) otel_endpoint: Option<String>,
+    pub(crate) otel_service_name: Option<String>,
+    // Comma separated `key=value` pairs, e.g. "x-api-key=abc,x-team=web"
+    pub(crate) otel_headers: Option<String>,
 }
 
 #[derive(Default)]
@@ -219,6 +235,50 @@ impl ConfigurationOptions {
     pub fn spaces_id(&self) -> Option<&str> {
         self.spaces_id.as_deref()
     }
+
+    /// A directory shared between machines that is used as a remote cache
+    pub fn shared_directory(&self) -> Option<&str> {
+        non_empty_str(self.shared_directory.as_deref())
+    }
+
+    /// Limits for the local file system cache, if any are configured
+    pub fn eviction_opts(&self) -> Result<EvictionOpts, Error> {
+        let max_size = non_empty_str(self.cache_max_size.as_deref())
+            .map(parse_size)
+            .transpose()?;
+        let max_age = non_empty_str(self.cache_max_age.as_deref())
+            .map(parse_age)
+            .transpose()?;
+        Ok(EvictionOpts { max_size, max_age })
+    }
+
+    /// The OTLP collector that traces of each run are exported to, if any
+    pub fn otel_endpoint(&self) -> Option<&str> {
+        non_empty_str(self.otel_endpoint.as_deref())
+    }
+
+    pub fn otel_service_name(&self) -> &str {
+        non_empty_str(self.otel_service_name.as_deref()).unwrap_or(DEFAULT_OTEL_SERVICE_NAME)
+    }
+
+    /// Headers sent along with exported traces, such as credentials for the
+    /// collector
+    pub fn otel_headers(&self) -> Result<Vec<(String, String)>, Error> {
+        let Some(headers) = non_empty_str(self.otel_headers.as_deref()) else {
+            return Ok(Vec::new());
+        };
+        headers
+            .split(',')
+            .map(|header| {
+                let (key, value) = header.split_once('=').ok_or(Error::InvalidOtelHeaders)?;
+                let key = key.trim();
+                if key.is_empty() {
+                    return Err(Error::InvalidOtelHeaders);
+                }
+                Ok((key.to_string(), value.trim().to_string()))
+            })
+            .collect()
+    }
 }
 
 // Maps Some("") to None to emulate how Go handles empty strings
@@ -252,6 +312,14 @@ impl ResolvedConfigurationOptions for RawTurboJson {
         } else {
             ConfigurationOptions::default()
         };
+        if let Some(local_cache_options) = &self.local_cache {
+            opts.cache_max_size = local_cache_options.max_size.clone();
+            opts.cache_max_age = local_cache_options.max_age.clone();
+        }
+        if let Some(otel_options) = &self.otel {
+            opts.otel_endpoint = otel_options.endpoint.clone();
+            opts.otel_service_name = otel_options.service_name.clone();
+        }
         // Don't allow token to be set for shared config.
         opts.token = None;
         opts.spaces_id = self
@@ -285,6 +353,14 @@ fn get_env_var_config(
     turbo_mapping.insert(OsString::from("turbo_teamid"), "team_id");
     turbo_mapping.insert(OsString::from("turbo_token"), "token");
     turbo_mapping.insert(OsString::from("turbo_remote_cache_timeout"), "timeout");
+    turbo_mapping.insert(OsString::from("turbo_cache_max_size"), "cache_max_size");
+    turbo_mapping.insert(OsString::from("turbo_cache_max_age"), "cache_max_age");
+    turbo_mapping.insert(OsString::from("turbo_otel_endpoint"), "otel_endpoint");
+    turbo_mapping.insert(
+        OsString::from("turbo_otel_service_name"),
+        "otel_service_name",
+    );
+    turbo_mapping.insert(OsString::from("turbo_otel_headers"), "otel_headers");
 
     // We do not enable new config sources:
     // turbo_mapping.insert(String::from("turbo_signature"), "signature"); // new
@@ -374,6 +450,16 @@ fn get_env_var_config(
         // Processed numbers
         timeout,
         spaces_id,
+
+        // Validated when the cache is created
+        cache_max_size: output_map.get("cache_max_size").cloned(),
+        cache_max_age: output_map.get("cache_max_age").cloned(),
+        shared_directory: None,
+
+        // Validated when the run is exported
+        otel_endpoint: output_map.get("otel_endpoint").cloned(),
+        otel_service_name: output_map.get("otel_service_name").cloned(),
+        otel_headers: output_map.get("otel_headers").cloned(),
     };
 
     Ok(output)
@@ -418,6 +504,12 @@ fn get_override_env_var_config(
         enabled: None,
         timeout: None,
         spaces_id: None,
+        cache_max_size: None,
+        cache_max_age: None,
+        shared_directory: None,
+        otel_endpoint: None,
+        otel_service_name: None,
+        otel_headers: None,
     };
 
     Ok(output)
@@ -507,6 +599,8 @@ impl TurborepoConfigBuilder {
     create_builder!(with_enabled, enabled, Option<bool>);
     create_builder!(with_preflight, preflight, Option<bool>);
     create_builder!(with_timeout, timeout, Option<u64>);
+    create_builder!(with_cache_max_size, cache_max_size, Option<String>);
+    create_builder!(with_cache_max_age, cache_max_age, Option<String>);
 
     pub fn build(&self) -> Result<ConfigurationOptions, Error> {
         // Priority, from least significant to most significant:
@@ -591,6 +685,24 @@ impl TurborepoConfigBuilder {
                     if let Some(spaces_id) = current_source_config.spaces_id {
                         acc.spaces_id = Some(spaces_id);
                     }
+                    if let Some(cache_max_size) = current_source_config.cache_max_size {
+                        acc.cache_max_size = Some(cache_max_size);
+                    }
+                    if let Some(cache_max_age) = current_source_config.cache_max_age {
+                        acc.cache_max_age = Some(cache_max_age);
+                    }
+                    if let Some(shared_directory) = current_source_config.shared_directory {
+                        acc.shared_directory = Some(shared_directory);
+                    }
+                    if let Some(otel_endpoint) = current_source_config.otel_endpoint {
+                        acc.otel_endpoint = Some(otel_endpoint);
+                    }
+                    if let Some(otel_service_name) = current_source_config.otel_service_name {
+                        acc.otel_service_name = Some(otel_service_name);
+                    }
+                    if let Some(otel_headers) = current_source_config.otel_headers {
+                        acc.otel_headers = Some(otel_headers);
+                    }
 
                     acc
                 })
@@ -601,10 +713,11 @@ impl TurborepoConfigBuilder {
 
 #[cfg(test)]
 mod test {
-    use std::{collections::HashMap, ffi::OsString};
+    use std::{collections::HashMap, ffi::OsString, time::Duration};
 
     use tempfile::TempDir;
     use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_cache::EvictionOpts;
 
     use crate::config::{
         get_env_var_config, get_override_env_var_config, ConfigurationOptions,
@@ -624,6 +737,11 @@ mod test {
         assert!(!defaults.preflight());
         assert_eq!(defaults.timeout(), DEFAULT_TIMEOUT);
         assert_eq!(defaults.spaces_id(), None);
+        assert_eq!(defaults.eviction_opts().unwrap(), Default::default());
+        assert_eq!(defaults.shared_directory(), None);
+        assert_eq!(defaults.otel_endpoint(), None);
+        assert_eq!(defaults.otel_service_name(), "turbo");
+        assert_eq!(defaults.otel_headers().unwrap(), vec![]);
     }
 
     #[test]
@@ -636,6 +754,8 @@ mod test {
         let turbo_teamid = "team_nLlpyC6REAqxydlFKbrMDlud";
         let turbo_token = "abcdef1234567890abcdef";
         let turbo_remote_cache_timeout = 200;
+        let turbo_cache_max_size = "10GB";
+        let turbo_cache_max_age = "7d";
 
         env.insert("turbo_api".into(), turbo_api.into());
         env.insert("turbo_login".into(), turbo_login.into());
@@ -646,8 +766,17 @@ mod test {
             "turbo_remote_cache_timeout".into(),
             turbo_remote_cache_timeout.to_string().into(),
         );
+        env.insert("turbo_cache_max_size".into(), turbo_cache_max_size.into());
+        env.insert("turbo_cache_max_age".into(), turbo_cache_max_age.into());
 
         let config = get_env_var_config(&env).unwrap();
+        assert_eq!(
+            config.eviction_opts().unwrap(),
+            EvictionOpts {
+                max_size: Some(10 * 1024 * 1024 * 1024),
+                max_age: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+            }
+        );
         assert_eq!(turbo_api, config.api_url.unwrap());
         assert_eq!(turbo_login, config.login_url.unwrap());
         assert_eq!(turbo_team, config.team_slug.unwrap());
@@ -656,6 +785,37 @@ mod test {
         assert_eq!(turbo_remote_cache_timeout, config.timeout.unwrap());
     }
 
+    #[test]
+    fn test_invalid_cache_limits() {
+        let mut env: HashMap<OsString, OsString> = HashMap::new();
+        env.insert("turbo_cache_max_size".into(), "lots".into());
+
+        let config = get_env_var_config(&env).unwrap();
+        assert!(config.eviction_opts().is_err());
+    }
+
+    #[test]
+    fn test_otel_headers() {
+        let mut env: HashMap<OsString, OsString> = HashMap::new();
+        env.insert(
+            "turbo_otel_headers".into(),
+            "x-api-key=abc=, x-team = web".into(),
+        );
+
+        let config = get_env_var_config(&env).unwrap();
+        assert_eq!(
+            config.otel_headers().unwrap(),
+            vec![
+                ("x-api-key".to_string(), "abc=".to_string()),
+                ("x-team".to_string(), "web".to_string()),
+            ]
+        );
+
+        env.insert("turbo_otel_headers".into(), "x-api-key".into());
+        let config = get_env_var_config(&env).unwrap();
+        assert!(config.otel_headers().is_err());
+    }
+
     #[test]
     fn test_empty_env_setting() {
         let mut env: HashMap<OsString, OsString> = HashMap::new();
@@ -705,7 +865,9 @@ mod test {
 
         repo_root
             .join_component("turbo.json")
-            .create_with_contents(r#"{"experimentalSpaces": {"id": "my-spaces-id"}}"#)
+            .create_with_contents(
+                r#"{"experimentalSpaces": {"id": "my-spaces-id"}, "localCache": {"maxSize": "1GB", "maxAge": "1w"}, "remoteCache": {"sharedDirectory": "/mnt/turbo-cache"}, "otel": {"endpoint": "http://localhost:4318", "serviceName": "monorepo"}}"#,
+            )
             .unwrap();
 
         let turbo_teamid = "team_nLlpyC6REAqxydlFKbrMDlud";
@@ -714,6 +876,11 @@ mod test {
         let vercel_artifacts_token = "correct-horse-battery-staple";
 
         let mut env: HashMap<OsString, OsString> = HashMap::new();
+        env.insert("turbo_cache_max_age".into(), "1d".into());
+        env.insert(
+            "turbo_otel_endpoint".into(),
+            "https://otel.example.com".into(),
+        );
         env.insert("turbo_teamid".into(), turbo_teamid.into());
         env.insert("turbo_token".into(), turbo_token.into());
         env.insert(
@@ -742,5 +909,15 @@ mod test {
         assert_eq!(config.team_id().unwrap(), vercel_artifacts_owner);
         assert_eq!(config.token().unwrap(), vercel_artifacts_token);
         assert_eq!(config.spaces_id().unwrap(), "my-spaces-id");
+        assert_eq!(config.shared_directory(), Some("/mnt/turbo-cache"));
+        assert_eq!(config.otel_endpoint(), Some("https://otel.example.com"));
+        assert_eq!(config.otel_service_name(), "monorepo");
+        assert_eq!(
+            config.eviction_opts().unwrap(),
+            EvictionOpts {
+                max_size: Some(1024 * 1024 * 1024),
+                max_age: Some(Duration::from_secs(24 * 60 * 60)),
+            }
+        );
     }
 }
@@ -4,7 +4,8 @@ use globwalk::ValidatedGlob;
 use thiserror::Error;
 use tonic::{Code, Status};
 use tracing::info;
-use turbopath::AbsoluteSystemPathBuf;
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath, RelativeUnixPathBuf};
+use turborepo_scm::package_deps::GitHashes;
 
 use super::{
     connector::{DaemonConnector, DaemonConnectorError},
@@ -133,6 +134,33 @@ impl<T> DaemonClient<T> {
 
         Ok(response)
     }
+
+    /// Get the hashes of a package's input files from the daemon, which keeps
+    /// them cached while the files are unchanged.
+    pub async fn get_file_hashes(
+        &mut self,
+        package_path: &AnchoredSystemPath,
+        inputs: &[String],
+    ) -> Result<GitHashes, DaemonError> {
+        let response = self
+            .client
+            .get_file_hashes(proto::GetFileHashesRequest {
+                package_path: package_path.to_string(),
+                input_globs: inputs.to_vec(),
+            })
+            .await?
+            .into_inner();
+
+        response
+            .file_hashes
+            .into_iter()
+            .map(|(path, hash)| {
+                RelativeUnixPathBuf::new(path)
+                    .map(|path| (path, hash))
+                    .map_err(|_| DaemonError::MalformedResponse)
+            })
+            .collect()
+    }
 }
 
 impl DaemonClient<DaemonConnector> {
@@ -23,16 +23,18 @@ use tokio::{
 use tonic::transport::{NamedService, Server};
 use tower::ServiceBuilder;
 use tracing::{error, info, trace, warn};
-use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf, PathError};
 use turborepo_filewatch::{
     cookies::CookieWriter,
     globwatcher::{Error as GlobWatcherError, GlobError, GlobSet, GlobWatcher},
+    hash_watcher::{Error as HashWatcherError, HashSpec, HashWatcher},
     package_watcher::{PackageWatcher, WatchingPackageDiscovery},
     FileSystemWatcher, WatchError,
 };
 use turborepo_repository::discovery::{
     LocalPackageDiscoveryBuilder, PackageDiscovery, PackageDiscoveryBuilder,
 };
+use turborepo_scm::SCM;
 
 use super::{bump_timeout::BumpTimeout, endpoint::SocketOpenError, proto};
 use crate::daemon::{bump_timeout_layer::BumpTimeoutLayer, endpoint::listen_socket, Paths};
@@ -56,6 +58,7 @@ pub struct FileWatching {
     watcher: Arc<FileSystemWatcher>,
     pub glob_watcher: Arc<GlobWatcher>,
     pub package_watcher: Arc<PackageWatcher>,
+    pub hash_watcher: Arc<HashWatcher>,
 }
 
 #[derive(Debug, Error)]
@@ -68,6 +71,10 @@ enum RpcError {
     GlobWatching(#[from] GlobWatcherError),
     #[error("filewatching unavailable")]
     NoFileWatching,
+    #[error("invalid package path: {0}")]
+    InvalidPackagePath(#[from] PathError),
+    #[error("file hashing failed: {0}")]
+    FileHashing(#[from] HashWatcherError),
 }
 
 impl From<RpcError> for tonic::Status {
@@ -79,6 +86,8 @@ impl From<RpcError> for tonic::Status {
             RpcError::InvalidGlob(e) => tonic::Status::invalid_argument(e.to_string()),
             RpcError::GlobWatching(e) => tonic::Status::unavailable(e.to_string()),
             RpcError::NoFileWatching => tonic::Status::unavailable("filewatching unavailable"),
+            RpcError::InvalidPackagePath(e) => tonic::Status::invalid_argument(e.to_string()),
+            RpcError::FileHashing(e) => tonic::Status::unavailable(e.to_string()),
         }
     }
 }
@@ -104,6 +113,12 @@ impl FileWatching {
         );
         let glob_watcher = Arc::new(GlobWatcher::new(
             repo_root.clone(),
+            cookie_watcher.clone(),
+            recv.clone(),
+        ));
+        let hash_watcher = Arc::new(HashWatcher::new(
+            repo_root.clone(),
+            SCM::new(&repo_root),
             cookie_watcher,
             recv.clone(),
         ));
@@ -116,6 +131,7 @@ impl FileWatching {
             watcher,
             glob_watcher,
             package_watcher,
+            hash_watcher,
         })
     }
 }
@@ -361,6 +377,26 @@ where
             .await?;
         Ok((changed_globs, time_saved))
     }
+
+    async fn get_file_hashes(
+        &self,
+        package_path: String,
+        inputs: Vec<String>,
+    ) -> Result<HashMap<String, String>, RpcError> {
+        let spec = HashSpec {
+            package_path: AnchoredSystemPathBuf::from_raw(package_path)?,
+            inputs,
+        };
+        let hashes = self
+            .file_watching
+            .hash_watcher
+            .get_file_hashes(spec, REQUEST_TIMEOUT)
+            .await?;
+        Ok(hashes
+            .into_iter()
+            .map(|(path, hash)| (path.to_string(), hash))
+            .collect())
+    }
 }
 
 async fn watch_root(
@@ -527,6 +563,19 @@ impl<PD: PackageDiscovery + Send + Sync + 'static> proto::turbod_server::Turbod
                 }
             })
     }
+
+    async fn get_file_hashes(
+        &self,
+        request: tonic::Request<proto::GetFileHashesRequest>,
+    ) -> Result<tonic::Response<proto::GetFileHashesResponse>, tonic::Status> {
+        let inner = request.into_inner();
+        let file_hashes = self
+            .get_file_hashes(inner.package_path, inner.input_globs)
+            .await?;
+        Ok(tonic::Response::new(proto::GetFileHashesResponse {
+            file_hashes,
+        }))
+    }
 }
 
 /// Determine whether a server can serve a client's request based on its
@@ -424,100 +424,13 @@ mod test {
     use tempdir::TempDir;
     use test_case::test_case;
     use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
-    use turborepo_lockfiles::Lockfile;
-    use turborepo_repository::{
-        discovery::PackageDiscovery, package_json::PackageJson, package_manager::PackageManager,
-    };
 
     use super::*;
-    use crate::{engine::TaskNode, turbo_json::RawTurboJson};
-
-    // Only used to prevent package graph construction from attempting to read
-    // lockfile from disk
-    #[derive(Debug)]
-    struct MockLockfile;
-    impl Lockfile for MockLockfile {
-        fn resolve_package(
-            &self,
-            _workspace_path: &str,
-            _name: &str,
-            _version: &str,
-        ) -> Result<Option<turborepo_lockfiles::Package>, turborepo_lockfiles::Error> {
-            unreachable!()
-        }
-
-        fn all_dependencies(
-            &self,
-            _key: &str,
-        ) -> Result<Option<HashMap<String, String>>, turborepo_lockfiles::Error> {
-            unreachable!()
-        }
-
-        fn subgraph(
-            &self,
-            _workspace_packages: &[String],
-            _packages: &[String],
-        ) -> Result<Box<dyn Lockfile>, turborepo_lockfiles::Error> {
-            unreachable!()
-        }
-
-        fn encode(&self) -> Result<Vec<u8>, turborepo_lockfiles::Error> {
-            unreachable!()
-        }
-
-        fn global_change(&self, _other: &dyn Lockfile) -> bool {
-            unreachable!()
-        }
-    }
-
-    struct MockDiscovery;
-    impl PackageDiscovery for MockDiscovery {
-        async fn discover_packages(
-            &self,
-        ) -> Result<
-            turborepo_repository::discovery::DiscoveryResponse,
-            turborepo_repository::discovery::Error,
-        > {
-            Ok(turborepo_repository::discovery::DiscoveryResponse {
-                package_manager: PackageManager::Npm,
-                workspaces: vec![], // we don't care about this
-            })
-        }
-    }
-
-    macro_rules! package_jsons {
-        {$root:expr, $($name:expr => $deps:expr),+} => {
-            {
-                let mut _map = HashMap::new();
-                $(
-                    let path = $root.join_components(&["packages", $name, "package.json"]);
-                    let dependencies = Some($deps.iter().map(|dep: &&str| (dep.to_string(), "workspace:*".to_string())).collect());
-                    let package_json = PackageJson { name: Some($name.to_string()), dependencies, ..Default::default() };
-                    _map.insert(path, package_json);
-                )+
-                _map
-            }
-        };
-    }
-
-    fn mock_package_graph(
-        repo_root: &AbsoluteSystemPath,
-        jsons: HashMap<AbsoluteSystemPathBuf, PackageJson>,
-    ) -> PackageGraph {
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .unwrap();
-
-        rt.block_on(
-            PackageGraph::builder(repo_root, PackageJson::default())
-                .with_package_discovery(MockDiscovery)
-                .with_lockfile(Some(Box::new(MockLockfile)))
-                .with_package_jsons(Some(jsons))
-                .build(),
-        )
-        .unwrap()
-    }
+    use crate::{
+        engine::TaskNode,
+        test_utils::{mock_package_graph, package_jsons},
+        turbo_json::RawTurboJson,
+    };
 
     #[test]
     fn test_turbo_json_loading() {
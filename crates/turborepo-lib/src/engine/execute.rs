@@ -1,4 +1,7 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
 
 use futures::{stream::FuturesUnordered, StreamExt};
 use tokio::sync::{mpsc, oneshot, Semaphore};
@@ -18,10 +21,12 @@ pub struct Message<T, U> {
 type VisitorData = TaskId<'static>;
 type VisitorResult = Result<(), StopExecution>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExecutionOptions {
     parallel: bool,
     concurrency: usize,
+    // Capacity of each named resource that tasks can reserve
+    resources: BTreeMap<String, u32>,
 }
 
 impl ExecutionOptions {
@@ -29,8 +34,14 @@ impl ExecutionOptions {
         Self {
             parallel,
             concurrency,
+            resources: BTreeMap::new(),
         }
     }
+
+    pub fn with_resources(mut self, resources: BTreeMap<String, u32>) -> Self {
+        self.resources = resources;
+        self
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -72,8 +83,17 @@ impl Engine {
         let ExecutionOptions {
             parallel,
             concurrency,
+            resources,
         } = options;
         let sema = Arc::new(Semaphore::new(concurrency));
+        let resource_semas: Arc<BTreeMap<String, (u32, Semaphore)>> = Arc::new(
+            resources
+                .into_iter()
+                .map(|(resource, capacity)| {
+                    (resource, (capacity, Semaphore::new(capacity as usize)))
+                })
+                .collect(),
+        );
         let mut tasks: FuturesUnordered<tokio::task::JoinHandle<Result<(), ExecuteError>>> =
             FuturesUnordered::new();
 
@@ -83,6 +103,7 @@ impl Engine {
         while let Some((node_id, done)) = nodes.recv().await {
             let visitor = visitor.clone();
             let sema = sema.clone();
+            let resource_semas = resource_semas.clone();
             let walker = walker.clone();
             let this = self.clone();
 
@@ -102,6 +123,26 @@ impl Engine {
                     return Ok(());
                 };
 
+                // Reserve the resources the task needs before taking a concurrency slot so
+                // a task waiting on a resource doesn't block unrelated tasks. Resources are
+                // always acquired in the same order to avoid tasks deadlocking each other.
+                let mut _resource_permits = Vec::new();
+                if !parallel {
+                    let task_resources = this
+                        .task_definition(task_id)
+                        .map(|definition| &definition.resources);
+                    for (resource, amount) in task_resources.into_iter().flatten() {
+                        let Some((capacity, resource_sema)) = resource_semas.get(resource) else {
+                            // Resources without a configured capacity are unlimited
+                            continue;
+                        };
+                        // A task can never reserve more than the entire capacity otherwise it
+                        // would never get scheduled
+                        _resource_permits
+                            .push(resource_sema.acquire_many((*amount).min(*capacity)).await?);
+                    }
+                }
+
                 // Acquire the semaphore unless parallel
                 let _permit = match parallel {
                     false => Some(sema.acquire().await.expect(
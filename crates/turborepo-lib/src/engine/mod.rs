@@ -3,10 +3,12 @@ mod execute;
 
 mod dot;
 mod mermaid;
+mod svg;
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt,
+    time::Duration,
 };
 
 pub use builder::{EngineBuilder, Error as BuilderError};
@@ -148,6 +150,7 @@ impl Engine<Built> {
         &self,
         package_graph: &PackageGraph,
         concurrency: u32,
+        resources: &BTreeMap<String, u32>,
     ) -> Result<(), Vec<ValidateError>> {
         // TODO(olszewski) once this is hooked up to a real run, we should
         // see if using rayon to parallelize would provide a speedup
@@ -211,10 +214,40 @@ impl Engine<Built> {
                     // handle legacy behaviour from go where an empty string may appear
                     .map_or(false, |script| !script.is_empty());
 
-                let task_is_persistent = self
-                    .task_definitions
-                    .get(task_id)
-                    .map_or(false, |task_def| task_def.persistent);
+                let task_definition = self.task_definitions.get(task_id);
+                for (resource, &amount) in task_definition
+                    .map(|task_def| &task_def.resources)
+                    .into_iter()
+                    .flatten()
+                {
+                    match resources.get(resource) {
+                        Some(&capacity) if amount > capacity => {
+                            return Err(ValidateError::ResourceExceedsCapacity {
+                                task_id: task_id.to_string(),
+                                resource: resource.clone(),
+                                amount,
+                                capacity,
+                            });
+                        }
+                        _ => (),
+                    }
+                }
+
+                if let Some(task_def) = task_definition.filter(|_| package_has_task) {
+                    if task_def.timeout == Some(Duration::ZERO) {
+                        return Err(ValidateError::InvalidTimeout {
+                            task_id: task_id.to_string(),
+                        });
+                    }
+                    if task_def.persistent && task_def.retries > 0 {
+                        return Err(ValidateError::RetriesOnPersistentTask {
+                            task_id: task_id.to_string(),
+                        });
+                    }
+                }
+
+                let task_is_persistent =
+                    task_definition.map_or(false, |task_def| task_def.persistent);
 
                 Ok(task_is_persistent && package_has_task)
             })
@@ -241,6 +274,61 @@ impl Engine<Built> {
             false => Err(validation_errors),
         }
     }
+
+    /// Interactive tasks take turns reading turbo's stdin. Without a terminal
+    /// to show which task is prompting, they can't run in parallel with other
+    /// tasks. A persistent interactive task never gives up stdin, so it can't
+    /// share it with other interactive tasks.
+    pub fn validate_interactive(
+        &self,
+        package_graph: &PackageGraph,
+        is_tty: bool,
+        is_parallel: bool,
+    ) -> Result<(), ValidateError> {
+        let runnable_tasks = self
+            .task_graph
+            .node_weights()
+            .filter_map(|node| match node {
+                TaskNode::Task(task_id) => Some(task_id),
+                TaskNode::Root => None,
+            })
+            .filter(|task_id| {
+                package_graph
+                    .package_json(&PackageName::from(task_id.package()))
+                    .and_then(|package_json| package_json.scripts.get(task_id.task()))
+                    .map_or(false, |script| !script.is_empty())
+            })
+            .collect::<Vec<_>>();
+        let mut interactive_tasks = runnable_tasks
+            .iter()
+            .filter_map(|task_id| {
+                let definition = self.task_definitions.get(*task_id)?;
+                definition
+                    .interactive
+                    .then(|| (task_id.to_string(), definition.persistent))
+            })
+            .collect::<Vec<_>>();
+        interactive_tasks.sort();
+
+        let Some((task_id, _)) = interactive_tasks.first() else {
+            return Ok(());
+        };
+        if !is_tty && is_parallel && runnable_tasks.len() > 1 {
+            return Err(ValidateError::InteractiveTaskInParallel {
+                task_id: task_id.clone(),
+            });
+        }
+        if interactive_tasks.len() > 1 {
+            if let Some((task_id, _)) = interactive_tasks.iter().find(|(_, persistent)| *persistent)
+            {
+                return Err(ValidateError::PersistentInteractiveTask {
+                    task_id: task_id.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Error, Diagnostic)]
@@ -265,6 +353,33 @@ pub enum ValidateError {
         persistent_count: u32,
         concurrency: u32,
     },
+    #[error(
+        "\"{task_id}\" requires {amount} of resource \"{resource}\" but only {capacity} is \
+         available. Increase the capacity with --resource {resource}={amount}"
+    )]
+    ResourceExceedsCapacity {
+        task_id: String,
+        resource: String,
+        amount: u32,
+        capacity: u32,
+    },
+    #[error("\"{task_id}\" has a timeout of 0 seconds. The timeout must be at least 1 second")]
+    InvalidTimeout { task_id: String },
+    #[error(
+        "\"{task_id}\" is a persistent task and can't be retried because it never exits. Remove \
+         its retries"
+    )]
+    RetriesOnPersistentTask { task_id: String },
+    #[error(
+        "\"{task_id}\" is an interactive task and can't run in parallel with other tasks outside \
+         of a terminal. Run it on its own or with --concurrency=1"
+    )]
+    InteractiveTaskInParallel { task_id: String },
+    #[error(
+        "\"{task_id}\" is a persistent interactive task and would keep other interactive tasks \
+         from reading input. Run it on its own"
+    )]
+    PersistentInteractiveTask { task_id: String },
 }
 
 impl fmt::Display for TaskNode {
@@ -278,8 +393,7 @@ impl fmt::Display for TaskNode {
 
 #[cfg(test)]
 mod test {
-
-    use std::collections::BTreeMap;
+    use std::assert_matches::assert_matches;
 
     use tempdir::TempDir;
     use turbopath::AbsoluteSystemPath;
@@ -373,16 +487,151 @@ mod test {
         let graph = graph_builder.build().await.unwrap();
 
         // if our limit is less than, it should fail
-        engine.validate(&graph, 1).expect_err("not enough");
+        engine
+            .validate(&graph, 1, &BTreeMap::new())
+            .expect_err("not enough");
 
         // if our limit is less than, it should fail
-        engine.validate(&graph, 2).expect_err("not enough");
+        engine
+            .validate(&graph, 2, &BTreeMap::new())
+            .expect_err("not enough");
 
         // we have two persistent tasks, and a slot for all other tasks, so this should
         // pass
-        engine.validate(&graph, 3).expect("ok");
+        engine.validate(&graph, 3, &BTreeMap::new()).expect("ok");
 
         // if our limit is greater, then it should pass
-        engine.validate(&graph, 4).expect("ok");
+        engine.validate(&graph, 4, &BTreeMap::new()).expect("ok");
+    }
+
+    #[tokio::test]
+    async fn test_resource_exceeds_capacity() {
+        let tmp = tempdir::TempDir::new("resources").unwrap();
+
+        let mut engine = Engine::new();
+        let task_id = TaskId::new("a", "build");
+        engine.get_index(&task_id);
+        engine.add_definition(
+            task_id,
+            TaskDefinition {
+                resources: [("memory".to_string(), 4)].into_iter().collect(),
+                ..Default::default()
+            },
+        );
+        let engine = engine.seal();
+
+        let graph = PackageGraph::builder(
+            AbsoluteSystemPath::from_std_path(tmp.path()).unwrap(),
+            PackageJson::default(),
+        )
+        .with_package_discovery(DummyDiscovery(&tmp))
+        .build()
+        .await
+        .unwrap();
+
+        let resources = |capacity| [("memory".to_string(), capacity)].into_iter().collect();
+        engine
+            .validate(&graph, 10, &resources(2))
+            .expect_err("not enough memory");
+        engine.validate(&graph, 10, &resources(4)).expect("ok");
+        // resources without a capacity are unlimited
+        engine.validate(&graph, 10, &BTreeMap::new()).expect("ok");
+    }
+
+    #[tokio::test]
+    async fn test_validate_interactive() {
+        let tmp = tempdir::TempDir::new("interactive").unwrap();
+
+        let build_engine = |definitions: [TaskDefinition; 2]| {
+            let mut engine = Engine::new();
+            for (package, definition) in ["a", "b"].into_iter().zip(definitions) {
+                let task_id = TaskId::new(package, "build");
+                engine.get_index(&task_id);
+                engine.add_definition(task_id, definition);
+            }
+            engine.seal()
+        };
+        let interactive = |persistent| TaskDefinition {
+            interactive: true,
+            persistent,
+            ..Default::default()
+        };
+
+        let graph = PackageGraph::builder(
+            AbsoluteSystemPath::from_std_path(tmp.path()).unwrap(),
+            PackageJson::default(),
+        )
+        .with_package_discovery(DummyDiscovery(&tmp))
+        .build()
+        .await
+        .unwrap();
+
+        let engine = build_engine([interactive(false), TaskDefinition::default()]);
+        assert_matches!(
+            engine.validate_interactive(&graph, false, true),
+            Err(ValidateError::InteractiveTaskInParallel { task_id }) if task_id == "a#build"
+        );
+        engine
+            .validate_interactive(&graph, true, true)
+            .expect("a terminal can show which task is prompting");
+        engine
+            .validate_interactive(&graph, false, false)
+            .expect("tasks run one at a time");
+
+        let engine = build_engine([interactive(true), interactive(false)]);
+        assert_matches!(
+            engine.validate_interactive(&graph, true, true),
+            Err(ValidateError::PersistentInteractiveTask { task_id }) if task_id == "a#build"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate_timeout_and_retries() {
+        let tmp = tempdir::TempDir::new("timeout").unwrap();
+
+        let build_engine = |definition: TaskDefinition| {
+            let mut engine = Engine::new();
+            let task_id = TaskId::new("a", "build");
+            engine.get_index(&task_id);
+            engine.add_definition(task_id, definition);
+            engine.seal()
+        };
+
+        let graph = PackageGraph::builder(
+            AbsoluteSystemPath::from_std_path(tmp.path()).unwrap(),
+            PackageJson::default(),
+        )
+        .with_package_discovery(DummyDiscovery(&tmp))
+        .build()
+        .await
+        .unwrap();
+
+        let engine = build_engine(TaskDefinition {
+            timeout: Some(Duration::ZERO),
+            ..Default::default()
+        });
+        let errors = engine.validate(&graph, 10, &BTreeMap::new()).unwrap_err();
+        assert_matches!(
+            errors.as_slice(),
+            [ValidateError::InvalidTimeout { task_id }] if task_id == "a#build"
+        );
+
+        let engine = build_engine(TaskDefinition {
+            persistent: true,
+            retries: 2,
+            ..Default::default()
+        });
+        let errors = engine.validate(&graph, 10, &BTreeMap::new()).unwrap_err();
+        assert_matches!(
+            errors.as_slice(),
+            [ValidateError::RetriesOnPersistentTask { task_id }] if task_id == "a#build"
+        );
+
+        let engine = build_engine(TaskDefinition {
+            timeout: Some(Duration::from_secs(60)),
+            retries: 2,
+            ..Default::default()
+        });
+        engine.validate(&graph, 10, &BTreeMap::new()).expect("ok");
     }
 }
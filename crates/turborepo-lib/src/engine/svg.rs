@@ -0,0 +1,303 @@
+use std::{collections::HashMap, io};
+
+use petgraph::{algo::toposort, graph::NodeIndex, visit::EdgeRef, Direction, Graph};
+
+use super::{Built, Engine, TaskNode};
+
+// Layout dimensions in pixels
+const CHAR_WIDTH: usize = 7;
+const NODE_PADDING: usize = 16;
+const NODE_HEIGHT: usize = 36;
+const HORIZONTAL_GAP: usize = 24;
+const VERTICAL_GAP: usize = 64;
+const MARGIN: usize = 20;
+// Number of barycenter passes used to reduce edge crossings
+const ORDERING_PASSES: usize = 4;
+
+impl Engine<Built> {
+    /// Renders the task graph as an SVG image using a built-in layered
+    /// layout, so no external tooling is required.
+    pub fn svg_graph<W: io::Write>(&self, writer: W, is_single: bool) -> Result<(), io::Error> {
+        let display_node = match is_single {
+            true => |node: &TaskNode| match node {
+                TaskNode::Root => node.to_string(),
+                TaskNode::Task(task) => task.task().to_string(),
+            },
+            false => |node: &TaskNode| node.to_string(),
+        };
+        render_graph(&self.task_graph, display_node, writer)
+    }
+}
+
+struct Layout {
+    width: usize,
+    height: usize,
+    // Top left corner and width of each node
+    nodes: HashMap<NodeIndex, (usize, usize, usize)>,
+}
+
+fn render_graph<N>(
+    graph: &Graph<N, ()>,
+    display_node: impl Fn(&N) -> String,
+    mut writer: impl io::Write,
+) -> Result<(), io::Error> {
+    let labels = graph
+        .node_indices()
+        .map(|index| (index, display_node(&graph[index])))
+        .collect::<HashMap<_, _>>();
+    let layout = layout(graph, &labels);
+
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\">",
+        width = layout.width,
+        height = layout.height
+    )?;
+    writeln!(
+        writer,
+        "  <defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+         markerWidth=\"8\" markerHeight=\"8\" orient=\"auto\"><path d=\"M0,0 L10,5 L0,10 z\" \
+         fill=\"#666\"/></marker></defs>"
+    )?;
+
+    let mut edges = graph
+        .edge_references()
+        .map(|edge| (edge.source(), edge.target()))
+        .collect::<Vec<_>>();
+    edges.sort_by(|a, b| (&labels[&a.0], &labels[&a.1]).cmp(&(&labels[&b.0], &labels[&b.1])));
+    for (source, target) in edges {
+        let (source_x, source_y, source_width) = layout.nodes[&source];
+        let (target_x, target_y, target_width) = layout.nodes[&target];
+        writeln!(
+            writer,
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#666\" \
+             marker-end=\"url(#arrow)\"/>",
+            source_x + source_width / 2,
+            source_y + NODE_HEIGHT,
+            target_x + target_width / 2,
+            target_y
+        )?;
+    }
+
+    let mut nodes = layout.nodes.iter().collect::<Vec<_>>();
+    nodes.sort_by_key(|(_, (x, y, _))| (*y, *x));
+    for (index, (x, y, width)) in nodes {
+        writeln!(
+            writer,
+            "  <g><rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{NODE_HEIGHT}\" rx=\"6\" \
+             fill=\"#fff\" stroke=\"#333\"/><text x=\"{}\" y=\"{}\" text-anchor=\"middle\" \
+             dominant-baseline=\"central\" font-family=\"monospace\" \
+             font-size=\"12\">{}</text></g>",
+            x + width / 2,
+            y + NODE_HEIGHT / 2,
+            escape(&labels[index])
+        )?;
+    }
+
+    writeln!(writer, "</svg>")?;
+    Ok(())
+}
+
+fn layout<N>(graph: &Graph<N, ()>, labels: &HashMap<NodeIndex, String>) -> Layout {
+    let layers = assign_layers(graph);
+    let rows = order_rows(graph, labels, &layers);
+
+    let node_width =
+        |index: &NodeIndex| labels[index].chars().count() * CHAR_WIDTH + NODE_PADDING * 2;
+    let row_width = |row: &Vec<NodeIndex>| {
+        row.iter().map(node_width).sum::<usize>() + row.len().saturating_sub(1) * HORIZONTAL_GAP
+    };
+    let content_width = rows.iter().map(row_width).max().unwrap_or_default();
+
+    // Dependents are drawn above their dependencies, so the highest layer is
+    // the first row
+    let mut nodes = HashMap::new();
+    for (row_number, row) in rows.iter().rev().enumerate() {
+        let y = MARGIN + row_number * (NODE_HEIGHT + VERTICAL_GAP);
+        let mut x = MARGIN + (content_width - row_width(row)) / 2;
+        for index in row {
+            let width = node_width(index);
+            nodes.insert(*index, (x, y, width));
+            x += width + HORIZONTAL_GAP;
+        }
+    }
+
+    let height = match rows.len() {
+        0 => 0,
+        rows => rows * NODE_HEIGHT + (rows - 1) * VERTICAL_GAP,
+    };
+    Layout {
+        width: content_width + MARGIN * 2,
+        height: height + MARGIN * 2,
+        nodes,
+    }
+}
+
+// Places every node one layer above the highest of its dependencies, so
+// every edge points downwards
+fn assign_layers<N>(graph: &Graph<N, ()>) -> HashMap<NodeIndex, usize> {
+    let Ok(sorted) = toposort(graph, None) else {
+        // The engine rejects cycles before we get here, but don't fail to
+        // render if one slips through
+        return graph.node_indices().map(|index| (index, 0)).collect();
+    };
+
+    let mut layers = HashMap::new();
+    for index in sorted.into_iter().rev() {
+        let layer = graph
+            .neighbors_directed(index, Direction::Outgoing)
+            .map(|dependency| layers[&dependency] + 1)
+            .max()
+            .unwrap_or_default();
+        layers.insert(index, layer);
+    }
+    layers
+}
+
+// Orders the nodes within each layer by the average position of their
+// neighbors, sweeping up and down the layers to reduce edge crossings
+fn order_rows<N>(
+    graph: &Graph<N, ()>,
+    labels: &HashMap<NodeIndex, String>,
+    layers: &HashMap<NodeIndex, usize>,
+) -> Vec<Vec<NodeIndex>> {
+    let layer_count = layers.values().max().map_or(0, |max| max + 1);
+    let mut rows = vec![Vec::new(); layer_count];
+    for (index, layer) in layers {
+        rows[*layer].push(*index);
+    }
+    for row in &mut rows {
+        row.sort_by(|a, b| labels[a].cmp(&labels[b]));
+    }
+
+    for _ in 0..ORDERING_PASSES {
+        // Top down, ordering each layer by the dependents above it
+        for layer in (0..layer_count).rev() {
+            reorder_row(graph, labels, &mut rows, layer, Direction::Incoming);
+        }
+        // Bottom up, ordering each layer by the dependencies below it
+        for layer in 0..layer_count {
+            reorder_row(graph, labels, &mut rows, layer, Direction::Outgoing);
+        }
+    }
+    rows
+}
+
+fn reorder_row<N>(
+    graph: &Graph<N, ()>,
+    labels: &HashMap<NodeIndex, String>,
+    rows: &mut [Vec<NodeIndex>],
+    layer: usize,
+    direction: Direction,
+) {
+    // Relative position of every node within its row
+    let positions = rows
+        .iter()
+        .flat_map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(position, index)| (*index, (position as f64 + 0.5) / row.len() as f64))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let barycenter = |index: &NodeIndex| {
+        let neighbors = graph
+            .neighbors_directed(*index, direction)
+            .map(|neighbor| positions[&neighbor])
+            .collect::<Vec<_>>();
+        match neighbors.is_empty() {
+            true => positions[index],
+            false => neighbors.iter().sum::<f64>() / neighbors.len() as f64,
+        }
+    };
+
+    let mut keyed = rows[layer]
+        .iter()
+        .map(|index| (barycenter(index), *index))
+        .collect::<Vec<_>>();
+    keyed.sort_by(|(a_center, a), (b_center, b)| {
+        a_center
+            .total_cmp(b_center)
+            .then_with(|| labels[a].cmp(&labels[b]))
+    });
+    rows[layer] = keyed.into_iter().map(|(_, index)| index).collect();
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_layers_point_downwards() {
+        let mut graph = Graph::new();
+        let root = graph.add_node("___ROOT___");
+        let lib = graph.add_node("lib#build");
+        let app = graph.add_node("app#build");
+        graph.add_edge(lib, root, ());
+        graph.add_edge(app, lib, ());
+        graph.add_edge(app, root, ());
+
+        let layers = assign_layers(&graph);
+        assert_eq!(layers[&root], 0);
+        assert_eq!(layers[&lib], 1);
+        assert_eq!(layers[&app], 2);
+    }
+
+    #[test]
+    fn test_ordering_reduces_crossings() {
+        let mut graph = Graph::new();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let x = graph.add_node("x");
+        let y = graph.add_node("y");
+        // a depends on y and b depends on x, so alphabetical order would
+        // cross the edges
+        graph.add_edge(a, y, ());
+        graph.add_edge(b, x, ());
+        let labels = graph
+            .node_indices()
+            .map(|index| (index, graph[index].to_string()))
+            .collect::<HashMap<_, _>>();
+
+        let rows = order_rows(&graph, &labels, &assign_layers(&graph));
+        let names =
+            |row: &Vec<NodeIndex>| row.iter().map(|index| graph[*index]).collect::<Vec<_>>();
+        let top = names(&rows[1]);
+        let bottom = names(&rows[0]);
+        assert_eq!(
+            top.iter().position(|n| *n == "a") < top.iter().position(|n| *n == "b"),
+            bottom.iter().position(|n| *n == "y") < bottom.iter().position(|n| *n == "x"),
+        );
+    }
+
+    #[test]
+    fn test_simple_graph_output() {
+        let mut bytes = Vec::new();
+        let mut graph = Graph::new();
+        let root = graph.add_node("___ROOT___");
+        let build = graph.add_node("<build>");
+        graph.add_edge(build, root, ());
+        render_graph(&graph, |n| n.to_string(), &mut bytes).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="142" height="176" viewBox="0 0 142 176">
+  <defs><marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8" markerHeight="8" orient="auto"><path d="M0,0 L10,5 L0,10 z" fill="#666"/></marker></defs>
+  <line x1="70" y1="56" x2="71" y2="120" stroke="#666" marker-end="url(#arrow)"/>
+  <g><rect x="30" y="20" width="81" height="36" rx="6" fill="#fff" stroke="#333"/><text x="70" y="38" text-anchor="middle" dominant-baseline="central" font-family="monospace" font-size="12">&lt;build&gt;</text></g>
+  <g><rect x="20" y="120" width="102" height="36" rx="6" fill="#fff" stroke="#333"/><text x="71" y="138" text-anchor="middle" dominant-baseline="central" font-family="monospace" font-size="12">___ROOT___</text></g>
+</svg>
+"##
+        );
+    }
+}
@@ -30,6 +30,8 @@ mod shim;
 mod signal;
 mod task_graph;
 mod task_hash;
+#[cfg(test)]
+mod test_utils;
 mod tracing;
 mod turbo_json;
 mod unescape;
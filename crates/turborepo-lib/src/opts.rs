@@ -1,11 +1,13 @@
-use std::backtrace;
+use std::{backtrace, collections::BTreeMap};
 
 use thiserror::Error;
 use turbopath::AnchoredSystemPathBuf;
 use turborepo_cache::CacheOpts;
 
 use crate::{
-    cli::{Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, RunArgs},
+    cli::{
+        Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, QueryCommand, RunArgs,
+    },
     run::task_id::TaskId,
     Args,
 };
@@ -26,6 +28,16 @@ pub enum Error {
          or equal to 1: {1}"
     )]
     ConcurrencyOutOfBounds(#[backtrace] backtrace::Backtrace, String),
+    #[error(
+        "invalid value for --resource CLI flag. This should be a resource name and a positive \
+         integer capacity, e.g. memory=8: {1}"
+    )]
+    InvalidResource(#[backtrace] backtrace::Backtrace, String),
+    #[error(
+        "invalid value for --summarize CLI flag. This should be true, false, junit:<path> or \
+         markdown:<path>: {1}"
+    )]
+    InvalidSummarize(#[backtrace] backtrace::Backtrace, String),
     #[error(transparent)]
     Path(#[from] turbopath::PathError),
 }
@@ -51,6 +63,10 @@ impl Opts {
             cmd.push_str(pattern);
         }
 
+        if self.scope_opts.affected {
+            cmd.push_str(" --affected");
+        }
+
         if self.run_opts.parallel {
             cmd.push_str(" --parallel");
         }
@@ -83,7 +99,15 @@ impl<'a> TryFrom<&'a Args> for Opts {
     type Error = self::Error;
 
     fn try_from(args: &'a Args) -> Result<Self, Self::Error> {
-        let Some(Command::Run(run_args)) = &args.command else {
+        let Some(
+            Command::Run(run_args)
+            | Command::Watch(run_args)
+            | Command::Query {
+                command: QueryCommand::Tasks(run_args),
+                ..
+            },
+        ) = &args.command
+        else {
             return Err(Error::ExpectedRun);
         };
         let run_opts = RunOpts::try_from(run_args.as_ref())?;
@@ -121,6 +145,8 @@ impl<'a> From<&'a RunArgs> for RunCacheOpts {
 pub struct RunOpts {
     pub(crate) tasks: Vec<String>,
     pub(crate) concurrency: u32,
+    // Resource capacities set via the CLI, these take precedence over turbo.json
+    pub(crate) resources: BTreeMap<String, u32>,
     pub(crate) parallel: bool,
     pub(crate) env_mode: EnvMode,
     // Whether or not to infer the framework for each workspace.
@@ -135,8 +161,14 @@ pub struct RunOpts {
     pub(crate) single_package: bool,
     pub log_prefix: ResolvedLogPrefix,
     pub log_order: ResolvedLogOrder,
-    pub summarize: Option<Option<bool>>,
+    // Whether to write turbo's JSON summary to .turbo/runs
+    pub summarize: bool,
+    pub(crate) summary_reports: Vec<SummaryReport>,
+    pub(crate) explain_miss: bool,
+    pub(crate) audit_inputs: bool,
     pub(crate) experimental_space_id: Option<String>,
+    // Where to export a trace of the run, set from config rather than flags
+    pub(crate) otel: Option<OtelOpts>,
     pub is_github_actions: bool,
 }
 
@@ -155,6 +187,36 @@ impl RunOpts {
     }
 }
 
+/// A report rendered from the run summary, written to a path relative to
+/// the repository root
+#[derive(Debug, Clone, PartialEq)]
+pub enum SummaryReport {
+    Junit(String),
+    Markdown(String),
+}
+
+/// An OTLP/HTTP collector that each run's trace is exported to
+#[derive(Clone, PartialEq)]
+pub struct OtelOpts {
+    pub endpoint: String,
+    pub service_name: String,
+    pub headers: Vec<(String, String)>,
+}
+
+// Headers usually hold credentials, so only their names are printed
+impl std::fmt::Debug for OtelOpts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OtelOpts")
+            .field("endpoint", &self.endpoint)
+            .field("service_name", &self.service_name)
+            .field(
+                "headers",
+                &self.headers.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
 #[derive(Debug)]
 pub enum GraphOpts {
     Stdout,
@@ -186,6 +248,14 @@ impl<'a> TryFrom<&'a RunArgs> for RunOpts {
             .transpose()?
             .unwrap_or(DEFAULT_CONCURRENCY);
 
+        let resources = args
+            .resources
+            .iter()
+            .map(|resource| parse_resource(resource))
+            .collect::<Result<_, _>>()?;
+
+        let (summarize, summary_reports) = parse_summarize(&args.summarize)?;
+
         let graph = args.graph.as_deref().map(|file| match file {
             "" => GraphOpts::Stdout,
             f => GraphOpts::File(f.to_string()),
@@ -212,11 +282,16 @@ impl<'a> TryFrom<&'a RunArgs> for RunOpts {
             tasks: args.tasks.clone(),
             log_prefix,
             log_order,
-            summarize: args.summarize,
+            summarize,
+            summary_reports,
+            explain_miss: args.explain_miss,
+            audit_inputs: args.audit_inputs,
             experimental_space_id: args.experimental_space_id.clone(),
+            otel: None,
             framework_inference: args.framework_inference,
             env_mode: args.env_mode,
             concurrency,
+            resources,
             parallel: args.parallel,
             profile: args.profile.clone(),
             continue_on_error: args.continue_execution,
@@ -252,6 +327,37 @@ fn parse_concurrency(concurrency_raw: &str) -> Result<u32, self::Error> {
     }
 }
 
+fn parse_resource(resource_raw: &str) -> Result<(String, u32), self::Error> {
+    let invalid = || Error::InvalidResource(backtrace::Backtrace::capture(), resource_raw.into());
+    let (name, capacity) = resource_raw.split_once('=').ok_or_else(invalid)?;
+    let name = name.trim();
+    match capacity.trim().parse::<u32>() {
+        Ok(capacity) if capacity >= 1 && !name.is_empty() => Ok((name.to_string(), capacity)),
+        Ok(_) | Err(_) => Err(invalid()),
+    }
+}
+
+// The last true or false value decides whether the JSON summary is written,
+// while every report value adds a report
+fn parse_summarize(values: &[String]) -> Result<(bool, Vec<SummaryReport>), self::Error> {
+    let mut summarize = false;
+    let mut reports = Vec::new();
+    for value in values {
+        let invalid = || Error::InvalidSummarize(backtrace::Backtrace::capture(), value.into());
+        match value.split_once(':') {
+            Some(("junit", path)) if !path.is_empty() => {
+                reports.push(SummaryReport::Junit(path.to_string()))
+            }
+            Some(("markdown", path)) if !path.is_empty() => {
+                reports.push(SummaryReport::Markdown(path.to_string()))
+            }
+            Some(_) => return Err(invalid()),
+            None => summarize = value.parse().map_err(|_| invalid())?,
+        }
+    }
+    Ok((summarize, reports))
+}
+
 impl From<LogPrefix> for ResolvedLogPrefix {
     fn from(value: LogPrefix) -> Self {
         match value {
@@ -313,6 +419,7 @@ pub struct ScopeOpts {
     pub global_deps: Vec<String>,
     pub filter_patterns: Vec<String>,
     pub ignore_patterns: Vec<String>,
+    pub affected: bool,
 }
 
 impl<'a> TryFrom<&'a RunArgs> for ScopeOpts {
@@ -337,6 +444,7 @@ impl<'a> TryFrom<&'a RunArgs> for ScopeOpts {
             legacy_filter,
             filter_patterns: args.filter.clone(),
             ignore_patterns: args.ignore.clone(),
+            affected: args.affected,
         })
     }
 }
@@ -376,7 +484,7 @@ mod test {
     use test_case::test_case;
     use turborepo_cache::CacheOpts;
 
-    use super::{LegacyFilter, RunOpts};
+    use super::{parse_resource, parse_summarize, LegacyFilter, RunOpts, SummaryReport};
     use crate::{
         cli::DryRunMode,
         opts::{Opts, RunCacheOpts, ScopeOpts},
@@ -516,6 +624,7 @@ mod test {
         let run_opts = RunOpts {
             tasks: opts_input.tasks,
             concurrency: 10,
+            resources: Default::default(),
             parallel: opts_input.parallel,
             env_mode: crate::cli::EnvMode::Loose,
             framework_inference: true,
@@ -529,8 +638,12 @@ mod test {
             single_package: false,
             log_prefix: crate::opts::ResolvedLogPrefix::Task,
             log_order: crate::opts::ResolvedLogOrder::Stream,
-            summarize: None,
+            summarize: false,
+            summary_reports: Vec::new(),
+            explain_miss: false,
+            audit_inputs: false,
             experimental_space_id: None,
+            otel: None,
             is_github_actions: false,
         };
         let cache_opts = CacheOpts::default();
@@ -542,6 +655,7 @@ mod test {
             global_deps: vec![],
             filter_patterns: opts_input.filter_patterns,
             ignore_patterns: vec![],
+            affected: false,
         };
         let opts = Opts {
             run_opts,
@@ -552,4 +666,34 @@ mod test {
         let synthesized = opts.synthesize_command();
         assert_eq!(synthesized, expected);
     }
+
+    #[test_case("memory=8", Some(("memory", 8)) ; "valid")]
+    #[test_case(" browser = 2 ", Some(("browser", 2)) ; "whitespace")]
+    #[test_case("memory", None ; "missing capacity")]
+    #[test_case("memory=0", None ; "zero capacity")]
+    #[test_case("memory=-1", None ; "negative capacity")]
+    #[test_case("=4", None ; "missing name")]
+    fn test_parse_resource(input: &str, expected: Option<(&str, u32)>) {
+        let actual = parse_resource(input).ok();
+        assert_eq!(
+            actual,
+            expected.map(|(name, capacity)| (name.to_string(), capacity))
+        );
+    }
+
+    #[test_case(&[], Some((false, vec![])) ; "not passed")]
+    #[test_case(&["true"], Some((true, vec![])) ; "enabled")]
+    #[test_case(&["true", "false"], Some((false, vec![])) ; "last value wins")]
+    #[test_case(
+        &["junit:reports/turbo.xml", "markdown:summary.md"],
+        Some((false, vec![SummaryReport::Junit("reports/turbo.xml".into()), SummaryReport::Markdown("summary.md".into())]))
+        ; "reports"
+    )]
+    #[test_case(&["junit:"], None ; "missing path")]
+    #[test_case(&["html:out.html"], None ; "unknown report")]
+    #[test_case(&["yes"], None ; "invalid bool")]
+    fn test_parse_summarize(input: &[&str], expected: Option<(bool, Vec<SummaryReport>)>) {
+        let input = input.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_summarize(&input).ok(), expected);
+    }
 }
@@ -26,14 +26,14 @@ use std::{
 
 use portable_pty::{native_pty_system, Child as PtyChild, MasterPty as PtyController};
 use tokio::{
-    io::{AsyncBufRead, AsyncBufReadExt, BufReader},
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader},
     join,
     process::Command as TokioCommand,
     sync::{mpsc, watch, RwLock},
 };
 use tracing::debug;
 
-use super::Command;
+use super::{Command, ResourceUsage};
 
 #[derive(Debug)]
 pub enum ChildState {
@@ -84,6 +84,7 @@ impl From<std::io::Error> for ShutdownFailed {
 struct ChildHandle {
     pid: Option<u32>,
     imp: ChildHandleImpl,
+    usage: Arc<Mutex<Option<ResourceUsage>>>,
 }
 
 enum ChildHandleImpl {
@@ -125,6 +126,7 @@ impl ChildHandle {
             handle: Self {
                 pid,
                 imp: ChildHandleImpl::Tokio(child),
+                usage: Arc::new(Mutex::new(None)),
             },
             io: ChildIO {
                 stdin,
@@ -203,6 +205,7 @@ impl ChildHandle {
             handle: Self {
                 pid,
                 imp: ChildHandleImpl::Pty(child),
+                usage: Arc::new(Mutex::new(None)),
             },
             io: ChildIO {
                 stdin: stdin.map(ChildInput::Pty),
@@ -217,6 +220,22 @@ impl ChildHandle {
     }
 
     pub async fn wait(&mut self) -> io::Result<Option<i32>> {
+        // The final usage has to be collected before the child gets reaped
+        #[cfg(target_os = "linux")]
+        if let Some(pid) = self.pid {
+            // The usage is only recorded if this future wasn't cancelled, as
+            // the pid could belong to a different child by the time it exits
+            if let Ok(Some(usage)) =
+                tokio::task::spawn_blocking(move || super::usage::wait_for_exit(pid)).await
+            {
+                self.usage
+                    .lock()
+                    .expect("lock poisoned")
+                    .get_or_insert_with(ResourceUsage::default)
+                    .update(usage);
+            }
+        }
+
         match &mut self.imp {
             ChildHandleImpl::Tokio(child) => child.wait().await.map(|status| status.code()),
             ChildHandleImpl::Pty(child) => {
@@ -406,6 +425,7 @@ pub struct Child {
     stdin: Arc<Mutex<Option<ChildInput>>>,
     output: Arc<Mutex<Option<ChildOutput>>>,
     label: String,
+    usage: Arc<Mutex<Option<ResourceUsage>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -465,6 +485,12 @@ impl Child {
         let state = Arc::new(RwLock::new(ChildState::Running(command_tx)));
         let task_state = state.clone();
 
+        let usage = child.usage.clone();
+        #[cfg(target_os = "linux")]
+        if let Some(pid) = pid {
+            tokio::spawn(super::usage::monitor(pid, exit_rx.clone(), usage.clone()));
+        }
+
         let _task = tokio::spawn(async move {
             // On Windows it is important that this gets dropped once the child process
             // exits
@@ -495,6 +521,7 @@ impl Child {
             stdin: Arc::new(Mutex::new(stdin)),
             output: Arc::new(Mutex::new(output)),
             label,
+            usage,
         })
     }
 
@@ -571,6 +598,39 @@ impl Child {
         self.stdin.lock().unwrap().take()
     }
 
+    /// Writes everything received from `input` to the child's stdin until
+    /// the child exits. When `input` closes, the child's stdin is closed as
+    /// well. The command must have been spawned with an open stdin.
+    pub async fn forward_stdin(&mut self, input: &mut mpsc::Receiver<Vec<u8>>) -> io::Result<()> {
+        let Some(mut stdin) = self.stdin() else {
+            return Ok(());
+        };
+        let mut exit = self.exit_channel.clone();
+        if exit.borrow().is_some() {
+            return Ok(());
+        }
+        loop {
+            let bytes = tokio::select! {
+                _ = exit.changed() => return Ok(()),
+                bytes = input.recv() => bytes,
+            };
+            // Dropping stdin sends EOF to the child
+            let Some(bytes) = bytes else {
+                return Ok(());
+            };
+            match &mut stdin {
+                ChildInput::Std(stdin) => {
+                    stdin.write_all(&bytes).await?;
+                    stdin.flush().await?;
+                }
+                ChildInput::Pty(stdin) => {
+                    stdin.write_all(&bytes)?;
+                    stdin.flush()?;
+                }
+            }
+        }
+    }
+
     fn outputs(&mut self) -> Option<ChildOutput> {
         self.output.lock().unwrap().take()
     }
@@ -723,6 +783,12 @@ impl Child {
     pub fn label(&self) -> &str {
         &self.label
     }
+
+    /// The CPU time and peak memory used by the process and its descendants,
+    /// if it could be measured on this platform.
+    pub fn resource_usage(&self) -> Option<ResourceUsage> {
+        *self.usage.lock().expect("lock poisoned")
+    }
 }
 
 // Adds a trailing newline if necessary to the buffer
@@ -800,7 +866,10 @@ mod test {
 
     use futures::{stream::FuturesUnordered, StreamExt};
     use test_case::test_case;
-    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        sync::mpsc,
+    };
     use tracing_test::traced_test;
     use turbopath::AbsoluteSystemPathBuf;
 
@@ -837,6 +906,23 @@ mod test {
         assert_matches!(&*state, ChildState::Exited(ChildExit::Killed));
     }
 
+    #[cfg(target_os = "linux")]
+    #[test_case(false)]
+    #[test_case(TEST_PTY)]
+    #[tokio::test]
+    async fn test_resource_usage(use_pty: bool) {
+        let script = find_script_dir().join_component("busy_wait.js");
+        let mut cmd = Command::new("node");
+        cmd.args([script.as_std_path()]);
+        let mut child = Child::spawn(cmd, ShutdownStyle::Kill, use_pty).unwrap();
+
+        let exit = child.wait().await;
+        assert_matches!(exit, Some(ChildExit::Finished(Some(0))));
+        let usage = child.resource_usage().expect("usage should be sampled");
+        assert!(usage.user_cpu_ms + usage.system_cpu_ms > 0);
+        assert!(usage.peak_rss_bytes > 0);
+    }
+
     #[test_case(false)]
     #[test_case(TEST_PTY)]
     #[tokio::test]
@@ -962,6 +1048,32 @@ mod test {
         assert_matches!(&*state, ChildState::Exited(ChildExit::Finished(Some(0))));
     }
 
+    #[test_case(false)]
+    #[test_case(TEST_PTY)]
+    #[tokio::test]
+    async fn test_forward_stdin(use_pty: bool) {
+        let script = find_script_dir().join_component("stdin_stdout.js");
+        let mut cmd = Command::new("node");
+        cmd.args([script.as_std_path()]);
+        cmd.open_stdin();
+        let mut child = Child::spawn(cmd, ShutdownStyle::Kill, use_pty).unwrap();
+
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        let (tx, mut rx) = mpsc::channel(1);
+        tx.send(b"hello world\n".to_vec()).await.unwrap();
+        // closing the input closes the child's stdin, which lets it exit
+        drop(tx);
+        child.forward_stdin(&mut rx).await.unwrap();
+
+        let mut output = Vec::new();
+        let exit = child.wait_with_piped_outputs(&mut output).await.unwrap();
+        assert_eq!(exit, Some(ChildExit::Finished(Some(0))));
+
+        let output = String::from_utf8(output).expect("Failed to parse stdout");
+        assert!(output.contains("hello world"), "got: {}", output);
+    }
+
     #[test_case(false)]
     #[test_case(TEST_PTY)]
     #[tokio::test]
@@ -11,6 +11,8 @@
 
 mod child;
 mod command;
+mod stdin;
+mod usage;
 
 use std::{
     io,
@@ -23,7 +25,11 @@ use futures::Future;
 use tokio::task::JoinSet;
 use tracing::{debug, trace};
 
-pub use self::child::{Child, ChildExit};
+pub use self::{
+    child::{Child, ChildExit},
+    stdin::SharedStdin,
+    usage::ResourceUsage,
+};
 
 /// A process manager that is responsible for spawning and managing child
 /// processes. When the manager is Open, new child processes can be spawned
@@ -59,6 +65,11 @@ impl ProcessManager {
         let use_pty = !cfg!(windows) && atty::is(atty::Stream::Stdout);
         Self::new(use_pty)
     }
+
+    /// Whether children are spawned in a pseudoterminal
+    pub fn use_pty(&self) -> bool {
+        self.use_pty
+    }
 }
 
 impl ProcessManager {
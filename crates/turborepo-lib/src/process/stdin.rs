@@ -0,0 +1,122 @@
+//! `stdin`
+//!
+//! turbo's stdin, shared between interactive tasks. Reads from stdin block
+//! and can't be cancelled, so a single thread reads it for the rest of the
+//! run and hands what it reads to whichever task holds the lock. Only one
+//! task holds the lock at a time so input is never split between tasks.
+
+use std::{
+    io::{self, Read},
+    sync::{Arc, OnceLock},
+    thread,
+};
+
+use tokio::sync::{mpsc, Mutex, OwnedMutexGuard};
+use tracing::debug;
+
+const BUFFER_SIZE: usize = 1024;
+
+#[derive(Debug, Default)]
+pub struct SharedStdin {
+    // The reader is only started once a task asks for stdin
+    input: OnceLock<Arc<Mutex<mpsc::Receiver<Vec<u8>>>>>,
+}
+
+impl SharedStdin {
+    /// Waits until no other task holds stdin. If the task runs in a
+    /// pseudoterminal, it takes over echoing and line editing from turbo's
+    /// terminal until the lock is released.
+    pub async fn lock(&self, use_pty: bool) -> StdinGuard {
+        let input = self
+            .input
+            .get_or_init(|| Arc::new(Mutex::new(spawn_reader())))
+            .clone();
+        let input = input.lock_owned().await;
+        let _terminal_mode = use_pty.then(TerminalMode::forward).flatten();
+        StdinGuard {
+            input,
+            _terminal_mode,
+        }
+    }
+}
+
+pub struct StdinGuard {
+    input: OwnedMutexGuard<mpsc::Receiver<Vec<u8>>>,
+    _terminal_mode: Option<TerminalMode>,
+}
+
+impl StdinGuard {
+    pub fn input(&mut self) -> &mut mpsc::Receiver<Vec<u8>> {
+        &mut self.input
+    }
+}
+
+fn spawn_reader() -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel(16);
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        let mut buffer = [0; BUFFER_SIZE];
+        loop {
+            match stdin.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if tx.blocking_send(buffer[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => {
+                    debug!("unable to read stdin: {e}");
+                    break;
+                }
+            }
+        }
+        // Dropping the sender tells the task holding stdin that it was closed
+    });
+    rx
+}
+
+// turbo's terminal settings, restored once the task is done with stdin
+#[cfg_attr(not(unix), allow(dead_code))]
+struct TerminalMode {
+    #[cfg(unix)]
+    original: nix::sys::termios::Termios,
+}
+
+impl TerminalMode {
+    // The task's pseudoterminal echoes and edits input itself, so turbo's
+    // terminal passes along each key as it's pressed. Signals are still
+    // handled by turbo, so Ctrl-C stops the run as usual.
+    #[cfg(unix)]
+    fn forward() -> Option<Self> {
+        use nix::sys::termios::{self, LocalFlags, SetArg, SpecialCharacterIndices};
+
+        // Fails if stdin isn't a terminal, in which case there's nothing to change
+        let original = termios::tcgetattr(libc::STDIN_FILENO).ok()?;
+        let mut forward = original.clone();
+        forward.local_flags &= !(LocalFlags::ICANON | LocalFlags::ECHO);
+        forward.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        forward.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+        if let Err(e) = termios::tcsetattr(libc::STDIN_FILENO, SetArg::TCSANOW, &forward) {
+            debug!("unable to forward keys to task: {e}");
+            return None;
+        }
+        Some(Self { original })
+    }
+
+    #[cfg(not(unix))]
+    fn forward() -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(unix)]
+impl Drop for TerminalMode {
+    fn drop(&mut self) {
+        use nix::sys::termios::{self, SetArg};
+
+        if let Err(e) = termios::tcsetattr(libc::STDIN_FILENO, SetArg::TCSANOW, &self.original) {
+            debug!("unable to restore terminal: {e}");
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! `usage`
+//!
+//! Accounting of the CPU time and memory used by a child process and all of
+//! its descendants. On Linux the process tree is sampled from procfs while the
+//! child runs, which catches descendants that exit without being waited on by
+//! the child. Once the child exits, the kernel's accounting for it and the
+//! descendants it waited on is read before it gets reaped, so short lived
+//! tasks and memory spikes between samples are still counted. Other platforms
+//! don't report any usage.
+
+use serde::Serialize;
+
+/// CPU time and memory used by a task's process tree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub user_cpu_ms: u64,
+    pub system_cpu_ms: u64,
+    // Largest combined resident set size of the process tree seen in a sample
+    pub peak_rss_bytes: u64,
+}
+
+impl ResourceUsage {
+    /// Combines the usage of two runs of a task, such as retried attempts
+    pub fn combine(self, other: Self) -> Self {
+        Self {
+            user_cpu_ms: self.user_cpu_ms + other.user_cpu_ms,
+            system_cpu_ms: self.system_cpu_ms + other.system_cpu_ms,
+            peak_rss_bytes: self.peak_rss_bytes.max(other.peak_rss_bytes),
+        }
+    }
+
+    // Samples are cumulative, so the latest sample only replaces the
+    // previous one if it saw more CPU time. Processes that exit and get
+    // reaped by a process outside of the tree can make a later sample smaller.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub(super) fn update(&mut self, sample: Self) {
+        self.user_cpu_ms = self.user_cpu_ms.max(sample.user_cpu_ms);
+        self.system_cpu_ms = self.system_cpu_ms.max(sample.system_cpu_ms);
+        self.peak_rss_bytes = self.peak_rss_bytes.max(sample.peak_rss_bytes);
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) use linux::{monitor, wait_for_exit};
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{
+        collections::HashSet,
+        fs, io,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::sync::watch;
+
+    use super::ResourceUsage;
+    use crate::process::ChildExit;
+
+    const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Samples the usage of the process tree rooted at `pid` until the child
+    /// exits, keeping the totals in `usage`.
+    pub(crate) async fn monitor(
+        pid: u32,
+        mut exit: watch::Receiver<Option<ChildExit>>,
+        usage: Arc<Mutex<Option<ResourceUsage>>>,
+    ) {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        loop {
+            tokio::select! {
+                // Either the child exited or its manager went away
+                _ = exit.changed() => break,
+                _ = interval.tick() => {
+                    // Reading procfs doesn't block on disk
+                    let Some(sample) = sample_tree(pid) else {
+                        continue;
+                    };
+                    usage
+                        .lock()
+                        .expect("lock poisoned")
+                        .get_or_insert_with(ResourceUsage::default)
+                        .update(sample);
+                }
+            }
+        }
+    }
+
+    /// Blocks until the process `pid` has exited and returns the usage the
+    /// kernel accounted to it and the descendants it waited on. The process
+    /// is left waitable so its exit status can still be collected.
+    pub(crate) fn wait_for_exit(pid: u32) -> Option<ResourceUsage> {
+        loop {
+            // SAFETY: both are plain C structs for which all zeroes is valid
+            let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+            let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+            // The libc wrapper for waitid doesn't expose the rusage argument
+            // of the syscall. SAFETY: both pointers are valid for the call.
+            let result = unsafe {
+                libc::syscall(
+                    libc::SYS_waitid,
+                    libc::P_PID,
+                    pid,
+                    &mut info as *mut libc::siginfo_t,
+                    libc::WEXITED | libc::WNOWAIT,
+                    &mut rusage as *mut libc::rusage,
+                )
+            };
+            if result == 0 {
+                return Some(from_rusage(&rusage));
+            }
+            if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+                return None;
+            }
+        }
+    }
+
+    fn from_rusage(rusage: &libc::rusage) -> ResourceUsage {
+        let to_ms = |time: libc::timeval| {
+            u64::try_from(time.tv_sec).unwrap_or_default() * 1000
+                + u64::try_from(time.tv_usec).unwrap_or_default() / 1000
+        };
+        ResourceUsage {
+            user_cpu_ms: to_ms(rusage.ru_utime),
+            system_cpu_ms: to_ms(rusage.ru_stime),
+            // ru_maxrss is in kilobytes
+            peak_rss_bytes: u64::try_from(rusage.ru_maxrss).unwrap_or_default() * 1024,
+        }
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub(super) struct ProcStat {
+        // All times are in clock ticks
+        pub user: u64,
+        pub system: u64,
+        pub children_user: u64,
+        pub children_system: u64,
+        // Pages
+        pub rss: u64,
+    }
+
+    fn sample_tree(root: u32) -> Option<ResourceUsage> {
+        // SAFETY: sysconf has no preconditions
+        let (ticks_per_second, page_size) = unsafe {
+            (
+                libc::sysconf(libc::_SC_CLK_TCK),
+                libc::sysconf(libc::_SC_PAGESIZE),
+            )
+        };
+        let ticks_per_second = u64::try_from(ticks_per_second).ok().filter(|t| *t > 0)?;
+        let page_size = u64::try_from(page_size).ok()?;
+
+        let mut user_ticks = 0;
+        let mut system_ticks = 0;
+        let mut rss_pages = 0;
+        let mut seen = HashSet::new();
+        let mut queue = vec![root];
+        while let Some(pid) = queue.pop() {
+            if !seen.insert(pid) {
+                continue;
+            }
+            let Some(stat) = fs::read_to_string(format!("/proc/{pid}/stat"))
+                .ok()
+                .and_then(|stat| parse_stat(&stat))
+            else {
+                // Once the root process has exited there's nothing left to sample
+                if pid == root {
+                    return None;
+                }
+                continue;
+            };
+            user_ticks += stat.user + stat.children_user;
+            system_ticks += stat.system + stat.children_system;
+            rss_pages += stat.rss;
+            queue.extend(children(pid));
+        }
+
+        let to_ms = |ticks: u64| ticks * 1000 / ticks_per_second;
+        Some(ResourceUsage {
+            user_cpu_ms: to_ms(user_ticks),
+            system_cpu_ms: to_ms(system_ticks),
+            peak_rss_bytes: rss_pages * page_size,
+        })
+    }
+
+    // Every thread of a process keeps its own list of child processes
+    fn children(pid: u32) -> Vec<u32> {
+        let Ok(tasks) = fs::read_dir(format!("/proc/{pid}/task")) else {
+            return Vec::new();
+        };
+        tasks
+            .filter_map(|task| task.ok())
+            .filter_map(|task| fs::read_to_string(task.path().join("children")).ok())
+            .flat_map(|children| {
+                children
+                    .split_whitespace()
+                    .filter_map(|child| child.parse().ok())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Parses the fields we need out of `/proc/<pid>/stat`. The command name
+    /// is wrapped in parentheses and can contain spaces, so fields are
+    /// counted from the last closing parenthesis.
+    pub(super) fn parse_stat(stat: &str) -> Option<ProcStat> {
+        let (_, fields) = stat.rsplit_once(')')?;
+        let fields = fields.split_whitespace().collect::<Vec<_>>();
+        // Fields after the command name start at the process state, which is
+        // field 3 in proc(5)
+        let field = |number: usize| -> Option<u64> { fields.get(number - 3)?.parse().ok() };
+        Some(ProcStat {
+            user: field(14)?,
+            system: field(15)?,
+            children_user: field(16)?,
+            children_system: field(17)?,
+            rss: field(24)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ResourceUsage;
+
+    #[test]
+    fn test_combine() {
+        let first = ResourceUsage {
+            user_cpu_ms: 100,
+            system_cpu_ms: 10,
+            peak_rss_bytes: 2048,
+        };
+        let second = ResourceUsage {
+            user_cpu_ms: 50,
+            system_cpu_ms: 5,
+            peak_rss_bytes: 4096,
+        };
+        assert_eq!(
+            first.combine(second),
+            ResourceUsage {
+                user_cpu_ms: 150,
+                system_cpu_ms: 15,
+                peak_rss_bytes: 4096,
+            }
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parse_stat() {
+        use super::linux::{parse_stat, ProcStat};
+
+        let stat = "4242 (node (worker) 1) S 4241 4242 4242 0 -1 4194560 3212 0 0 0 173 21 4 2 20 \
+                    0 11 0 2519817 1102434304 12345 18446744073709551615 1 1 0 0 0 0 0 4096 \
+                    134234626 0 0 0 17 3 0 0 0 0 0";
+        assert_eq!(
+            parse_stat(stat),
+            Some(ProcStat {
+                user: 173,
+                system: 21,
+                children_user: 4,
+                children_system: 2,
+                rss: 12345,
+            })
+        );
+        assert_eq!(parse_stat("4242 (node) S 1"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_wait_for_exit() {
+        use std::process::Command;
+
+        // Exits well before the first procfs sample would be taken
+        let mut child = Command::new("sh")
+            .args(["-c", "i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done"])
+            .spawn()
+            .unwrap();
+        let usage = super::wait_for_exit(child.id()).unwrap();
+        assert!(usage.user_cpu_ms + usage.system_cpu_ms > 0);
+        assert!(usage.peak_rss_bytes > 0);
+        // The child is left for the caller to reap
+        assert!(child.wait().unwrap().success());
+    }
+}
@@ -13,6 +13,8 @@ use crate::{
 pub enum Error {
     #[error("error preparing engine: Invalid persistent task configuration:\n{0}")]
     EngineValidation(String),
+    #[error("error preparing engine: {0}")]
+    InteractiveTasks(engine::ValidateError),
     #[error(transparent)]
     Graph(#[from] graph_visualizer::Error),
     #[error(transparent)]
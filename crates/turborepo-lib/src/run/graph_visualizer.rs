@@ -39,6 +39,8 @@ pub(crate) fn write_graph(
                 render_mermaid_graph(&filename, engine, single_package)?;
             } else if extension == "html" {
                 render_html(&filename, engine, single_package)?;
+            } else if extension == "svg" {
+                render_svg_graph(&filename, engine, single_package)?;
             } else if let Ok(dot_path) = which("dot") {
                 let mut cmd = Command::new(dot_path);
                 cmd.stdin(Stdio::piped())
@@ -62,7 +64,7 @@ pub(crate) fn write_graph(
 fn write_graphviz_warning(ui: UI) -> Result<(), io::Error> {
     let stderr = io::stderr();
     cwrite!(&stderr, ui, BOLD_YELLOW_REVERSE, " WARNING ")?;
-    cwriteln!(&stderr, ui, YELLOW, " `turbo` uses Graphviz to generate an image of your\ngraph, but Graphviz isn't installed on this machine.\n\nYou can download Graphviz from https://graphviz.org/download.\n\nIn the meantime, you can use this string output with an\nonline Dot graph viewer.")?;
+    cwriteln!(&stderr, ui, YELLOW, " `turbo` uses Graphviz to generate an image of your\ngraph, but Graphviz isn't installed on this machine.\n\nYou can download Graphviz from https://graphviz.org/download.\n\nIn the meantime, you can use this string output with an\nonline Dot graph viewer, or use a .svg filename which `turbo` can\nrender without Graphviz.")?;
     Ok(())
 }
 
@@ -81,6 +83,21 @@ fn render_mermaid_graph(
         .map_err(Error::GraphOutput)
 }
 
+fn render_svg_graph(
+    filename: &AbsoluteSystemPath,
+    engine: &Engine,
+    single_package: bool,
+) -> Result<(), Error> {
+    let mut opts = OpenOptions::new();
+    opts.truncate(true).create(true).write(true);
+    let file = filename
+        .open_with_options(opts)
+        .map_err(Error::GraphOutput)?;
+    engine
+        .svg_graph(file, single_package)
+        .map_err(Error::GraphOutput)
+}
+
 fn render_dot_graph<W: io::Write>(
     writer: W,
     engine: &Engine,
@@ -0,0 +1,461 @@
+//! Audits task inputs by comparing what tasks accessed, according to their
+//! task access traces, against the files and env vars they declare.
+//!
+//! Accessed files and env vars that aren't declared can change a task's
+//! output without changing its hash, while declarations that are never
+//! accessed cause needless cache misses.
+use std::collections::{BTreeSet, HashSet};
+
+use turbopath::{AbsoluteSystemPathBuf, RelativeUnixPathBuf};
+use turborepo_env::EnvironmentVariableMap;
+use turborepo_repository::package_graph::PackageGraph;
+use turborepo_ui::{color, BOLD, GREY, UI};
+use wax::{Glob, Program};
+
+use super::task_access::{TaskAccessTraceFile, TASK_ACCESS_ENV_KEY};
+use crate::{
+    engine::{Engine, TaskNode},
+    task_graph::TaskDefinition,
+    task_hash::TaskHashTracker,
+};
+
+const TURBO_DEFAULT: &str = "$TURBO_DEFAULT$";
+
+// Env vars that turbo sets itself or always passes through to tasks
+const IMPLICIT_ENV_VARS: [&str; 7] = [
+    "TURBO_HASH",
+    TASK_ACCESS_ENV_KEY,
+    "SHELL",
+    "PATH",
+    "SYSTEMROOT",
+    "Path",
+    "SystemRoot",
+];
+
+// Directories that never hold task inputs: dependencies are covered by the
+// lockfile and the others belong to tooling
+const IGNORED_DIRS: [&str; 3] = ["node_modules", ".git", ".turbo"];
+
+pub struct InputAuditor {
+    repo_root: AbsoluteSystemPathBuf,
+    // Files that are part of the global hash
+    global_files: HashSet<RelativeUnixPathBuf>,
+    // `globalEnv` and `globalPassThroughEnv` wildcards
+    global_env: Vec<String>,
+}
+
+// What a task declares, with paths relative to the repository root
+struct Declarations<'a> {
+    package_path: String,
+    definition: &'a TaskDefinition,
+    // Files in packages with tasks this task depends on are covered by those
+    // tasks' hashes
+    dependency_paths: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct TaskInputAudit {
+    undeclared_files: BTreeSet<String>,
+    unused_inputs: BTreeSet<String>,
+    undeclared_env: BTreeSet<String>,
+    unused_env: BTreeSet<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct InputAuditReport {
+    tasks: Vec<(String, TaskInputAudit)>,
+    untraced: Vec<String>,
+}
+
+impl InputAuditor {
+    pub fn new(
+        repo_root: AbsoluteSystemPathBuf,
+        global_files: HashSet<RelativeUnixPathBuf>,
+        global_env: Vec<String>,
+    ) -> Self {
+        Self {
+            repo_root,
+            global_files,
+            global_env,
+        }
+    }
+
+    /// Audits every task in the run that left a task access trace
+    pub fn audit(
+        &self,
+        engine: &Engine,
+        package_graph: &PackageGraph,
+        hash_tracker: &TaskHashTracker,
+    ) -> Result<InputAuditReport, turborepo_env::Error> {
+        let mut report = InputAuditReport::default();
+        for task in engine.tasks() {
+            let TaskNode::Task(task_id) = task else {
+                continue;
+            };
+            let (Some(hash), Some(definition), Some(package)) = (
+                hash_tracker.hash(task_id),
+                engine.task_definition(task_id),
+                package_graph.package_info(&task_id.to_workspace_name()),
+            ) else {
+                continue;
+            };
+            let Some(trace) = TaskAccessTraceFile::read(&self.repo_root, &hash) else {
+                report.untraced.push(task_id.to_string());
+                continue;
+            };
+
+            let dependency_paths = engine
+                .dependencies(task_id)
+                .into_iter()
+                .flatten()
+                .filter_map(|dependency| match dependency {
+                    TaskNode::Task(dependency) => {
+                        package_graph.package_info(&dependency.to_workspace_name())
+                    }
+                    TaskNode::Root => None,
+                })
+                .map(|info| info.package_path().to_unix().to_string())
+                .collect();
+            let declarations = Declarations {
+                package_path: package.package_path().to_unix().to_string(),
+                definition,
+                dependency_paths,
+            };
+            let audit = self.audit_task(&trace, &declarations)?;
+            if audit != TaskInputAudit::default() {
+                report.tasks.push((task_id.to_string(), audit));
+            }
+        }
+        report.tasks.sort_by(|(a, _), (b, _)| a.cmp(b));
+        report.untraced.sort();
+        Ok(report)
+    }
+
+    fn audit_task(
+        &self,
+        trace: &TaskAccessTraceFile,
+        declarations: &Declarations,
+    ) -> Result<TaskInputAudit, turborepo_env::Error> {
+        let definition = declarations.definition;
+        let package_path = declarations.package_path.as_str();
+        let mut audit = TaskInputAudit::default();
+
+        let accessed_files = trace
+            .accessed
+            .file_paths
+            .iter()
+            .filter_map(|path| self.repo_relative(path.as_ref()))
+            .collect::<BTreeSet<_>>();
+
+        let outputs = definition
+            .outputs
+            .inclusions
+            .iter()
+            .filter_map(|output| repo_glob(package_path, output))
+            .collect::<Vec<_>>();
+        let mut inputs = Vec::new();
+        let mut excluded_inputs = Vec::new();
+        for input in definition.inputs.iter().filter(|i| *i != TURBO_DEFAULT) {
+            match input.strip_prefix('!') {
+                Some(exclusion) => excluded_inputs.extend(repo_glob(package_path, exclusion)),
+                None => inputs.extend(repo_glob(package_path, input).map(|glob| (input, glob))),
+            }
+        }
+        let includes_package = definition.inputs.is_empty()
+            || definition.inputs.iter().any(|input| input == TURBO_DEFAULT);
+        let dot_env = definition
+            .dot_env
+            .iter()
+            .flatten()
+            .map(|file| join(package_path, file.as_str()))
+            .collect::<HashSet<_>>();
+
+        for file in &accessed_files {
+            // Files written by the task aren't inputs
+            if outputs.iter().any(|output| output.is_match(file.as_str())) {
+                continue;
+            }
+            let declared = self
+                .global_files
+                .iter()
+                .any(|global| global.as_str() == file)
+                || dot_env.contains(file)
+                || declarations
+                    .dependency_paths
+                    .iter()
+                    .any(|dependency| is_within(dependency, file))
+                || (!excluded_inputs
+                    .iter()
+                    .any(|glob| glob.is_match(file.as_str()))
+                    && ((includes_package && is_within(package_path, file))
+                        || inputs.iter().any(|(_, glob)| glob.is_match(file.as_str()))));
+            if !declared {
+                audit.undeclared_files.insert(file.clone());
+            }
+        }
+        audit.unused_inputs = inputs
+            .iter()
+            .filter(|(_, glob)| {
+                !accessed_files
+                    .iter()
+                    .any(|file| glob.is_match(file.as_str()))
+            })
+            .map(|(input, _)| input.to_string())
+            .collect();
+
+        let accessed_env = EnvironmentVariableMap::from(
+            trace
+                .accessed
+                .env_var_keys
+                .iter()
+                .map(|key| (key.to_string(), String::new()))
+                .filter(|(key, _)| !IMPLICIT_ENV_VARS.contains(&key.as_str()))
+                .collect::<std::collections::HashMap<_, _>>(),
+        );
+        let task_env = definition
+            .env
+            .iter()
+            .chain(definition.pass_through_env.iter().flatten())
+            .cloned()
+            .collect::<Vec<_>>();
+        let declared_env = accessed_env.from_wildcards(
+            &task_env
+                .iter()
+                .chain(self.global_env.iter())
+                .collect::<Vec<_>>(),
+        )?;
+        audit.undeclared_env = accessed_env
+            .keys()
+            .filter(|key| !declared_env.contains_key(*key))
+            .cloned()
+            .collect();
+        for pattern in task_env.iter().filter(|pattern| !pattern.starts_with('!')) {
+            if accessed_env.from_wildcards(&[pattern])?.is_empty() {
+                audit.unused_env.insert(pattern.clone());
+            }
+        }
+
+        Ok(audit)
+    }
+
+    // The path relative to the repository root, if it's a file that could be a
+    // task input
+    fn repo_relative(&self, path: &str) -> Option<String> {
+        let path = AbsoluteSystemPathBuf::new(path).ok()?;
+        let anchored = self.repo_root.anchor(path).ok()?;
+        let path = anchored.to_unix().to_string();
+        if path.is_empty()
+            || path
+                .split('/')
+                .any(|segment| IGNORED_DIRS.contains(&segment))
+        {
+            return None;
+        }
+        Some(path)
+    }
+}
+
+impl InputAuditReport {
+    pub fn print(&self, ui: UI) {
+        println!("{}", color!(ui, BOLD, "Task input audit:"));
+        if self.tasks.is_empty() {
+            println!(
+                "  {}",
+                color!(ui, GREY, "all traced accesses match the declared inputs")
+            );
+        }
+        for (task_id, audit) in &self.tasks {
+            println!("  {task_id}:");
+            for file in &audit.undeclared_files {
+                println!("    file {file} was read but isn't an input");
+            }
+            for env in &audit.undeclared_env {
+                println!("    env var {env} was read but isn't declared");
+            }
+            for input in &audit.unused_inputs {
+                println!("    input {input} matched no files that were read");
+            }
+            for env in &audit.unused_env {
+                println!("    env {env} matched no env vars that were read");
+            }
+        }
+        if !self.untraced.is_empty() {
+            println!(
+                "  {}",
+                color!(
+                    ui,
+                    GREY,
+                    "no access trace was written by: {}",
+                    self.untraced.join(", ")
+                )
+            );
+        }
+        println!();
+    }
+}
+
+// Whether `path` is inside of the directory `dir`, both relative to the repo
+// root
+fn is_within(dir: &str, path: &str) -> bool {
+    dir.is_empty()
+        || path
+            .strip_prefix(dir)
+            .map_or(false, |rest| rest.starts_with('/'))
+}
+
+// Joins a path relative to the package onto the package's path, resolving any
+// `.` or `..` segments so the result is relative to the repo root
+fn join(package_path: &str, path: &str) -> String {
+    let mut segments = package_path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+fn repo_glob(package_path: &str, glob: &str) -> Option<Glob<'static>> {
+    Glob::new(&join(package_path, glob))
+        .ok()
+        .map(|glob| glob.into_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use serde_json::json;
+    use test_case::test_case;
+    use turbopath::{AbsoluteSystemPathBuf, RelativeUnixPathBuf};
+
+    use super::{join, Declarations, InputAuditor};
+    use crate::{
+        run::task_access::TaskAccessTraceFile,
+        task_graph::{TaskDefinition, TaskOutputs},
+    };
+
+    #[test_case("apps/web", "src/**", "apps/web/src/**" ; "nested glob")]
+    #[test_case("apps/web", "../../tsconfig.json", "tsconfig.json" ; "parent directory")]
+    #[test_case("apps/web", "./README.md", "apps/web/README.md" ; "current directory")]
+    #[test_case("", "src/index.ts", "src/index.ts" ; "root package")]
+    fn test_join(package_path: &str, path: &str, expected: &str) {
+        assert_eq!(join(package_path, path), expected);
+    }
+
+    #[test]
+    fn test_audit_task() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let file = |path: &str| {
+            repo_root
+                .join_unix_path(RelativeUnixPathBuf::new(path).unwrap())
+                .unwrap()
+                .to_string()
+        };
+        let trace: TaskAccessTraceFile = serde_json::from_value(json!({
+            "accessed": {
+                "network": false,
+                "filePaths": [
+                    file("apps/web/src/index.ts"),
+                    file("apps/web/dist/index.js"),
+                    file("apps/web/node_modules/react/index.js"),
+                    file("packages/ui/src/button.tsx"),
+                    file("packages/config/tsconfig.json"),
+                    file("tsconfig.json"),
+                ],
+                "envVarKeys": ["PATH", "API_URL", "NEXT_PUBLIC_ANALYTICS", "SECRET"],
+            },
+            "outputs": [],
+        }))
+        .unwrap();
+        let definition = TaskDefinition {
+            inputs: vec!["src/**".into(), "README.md".into()],
+            outputs: TaskOutputs {
+                inclusions: vec!["dist/**".into()],
+                exclusions: vec![],
+            },
+            env: vec!["API_URL".into(), "UNUSED_*".into()],
+            pass_through_env: Some(vec!["NEXT_PUBLIC_*".into()]),
+            ..Default::default()
+        };
+        let auditor = InputAuditor::new(
+            repo_root.clone(),
+            HashSet::from([RelativeUnixPathBuf::new("tsconfig.json").unwrap()]),
+            vec![],
+        );
+        let audit = auditor
+            .audit_task(
+                &trace,
+                &Declarations {
+                    package_path: "apps/web".into(),
+                    definition: &definition,
+                    dependency_paths: vec!["packages/ui".into()],
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            audit.undeclared_files.into_iter().collect::<Vec<_>>(),
+            vec!["packages/config/tsconfig.json"]
+        );
+        assert_eq!(
+            audit.unused_inputs.into_iter().collect::<Vec<_>>(),
+            vec!["README.md"]
+        );
+        assert_eq!(
+            audit.undeclared_env.into_iter().collect::<Vec<_>>(),
+            vec!["SECRET"]
+        );
+        assert_eq!(
+            audit.unused_env.into_iter().collect::<Vec<_>>(),
+            vec!["UNUSED_*"]
+        );
+    }
+
+    #[test]
+    fn test_audit_default_inputs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let trace: TaskAccessTraceFile = serde_json::from_value(json!({
+            "accessed": {
+                "network": false,
+                "filePaths": [
+                    repo_root.join_components(&["apps", "web", "next.config.js"]).to_string(),
+                    repo_root.join_components(&["apps", "docs", "next.config.js"]).to_string(),
+                ],
+                "envVarKeys": [],
+            },
+            "outputs": [],
+        }))
+        .unwrap();
+        let definition = TaskDefinition {
+            inputs: vec!["$TURBO_DEFAULT$".into(), "!README.md".into()],
+            ..Default::default()
+        };
+        let auditor = InputAuditor::new(repo_root.clone(), HashSet::new(), vec![]);
+        let audit = auditor
+            .audit_task(
+                &trace,
+                &Declarations {
+                    package_path: "apps/web".into(),
+                    definition: &definition,
+                    dependency_paths: vec![],
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            audit.undeclared_files.into_iter().collect::<Vec<_>>(),
+            vec!["apps/docs/next.config.js"]
+        );
+        assert!(audit.unused_inputs.is_empty());
+    }
+}
@@ -4,20 +4,23 @@ mod cache;
 mod error;
 pub(crate) mod global_hash;
 mod graph_visualizer;
+pub(crate) mod input_audit;
 pub(crate) mod package_discovery;
 mod scope;
 pub(crate) mod summary;
 pub mod task_access;
 pub mod task_id;
+pub(crate) mod watch;
 
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     io::{IsTerminal, Write},
     sync::Arc,
     time::SystemTime,
 };
 
 pub use cache::{ConfigCache, RunCache, TaskCache};
+use camino::Utf8PathBuf;
 use chrono::{DateTime, Local};
 use itertools::Itertools;
 use rayon::iter::ParallelBridge;
@@ -56,13 +59,16 @@ use crate::{
     commands::CommandBase,
     daemon::DaemonConnector,
     engine::{Engine, EngineBuilder},
-    opts::Opts,
+    opts::{Opts, OtelOpts},
     process::ProcessManager,
-    run::{global_hash::get_global_hash_inputs, summary::RunTracker, task_access::TaskAccess},
+    run::{
+        global_hash::get_global_hash_inputs, input_audit::InputAuditor, summary::RunTracker,
+        task_access::TaskAccess,
+    },
     shim::TurboState,
     signal::{SignalHandler, SignalSubscriber},
     task_graph::Visitor,
-    task_hash::{get_external_deps_hash, PackageInputsHashes},
+    task_hash::{get_external_deps_hash, DaemonFileHashes, PackageInputsHashes},
     turbo_json::TurboJson,
 };
 
@@ -73,6 +79,16 @@ pub struct Run {
     repo_root: AbsoluteSystemPathBuf,
     ui: UI,
     version: &'static str,
+    filtered_pkgs: Option<HashSet<PackageName>>,
+}
+
+/// The packages a run operates on, along with the repository state that was
+/// used to resolve them.
+pub(crate) struct RunScope {
+    pub pkg_dep_graph: PackageGraph,
+    pub filtered_pkgs: HashSet<PackageName>,
+    pub global_deps: Vec<String>,
+    pub root_turbo_json: TurboJson,
 }
 
 impl Run {
@@ -93,13 +109,27 @@ impl Run {
         // configured team_id matches the final resolved team_id.
         let unused_remote_cache_opts_team_id = config.team_id().map(|team_id| team_id.to_string());
         let signature = config.signature();
-        opts.cache_opts.remote_cache_opts = Some(RemoteCacheOpts::new(
-            unused_remote_cache_opts_team_id,
-            signature,
-        ));
+        // A shared directory doesn't require linking, but is still disabled along
+        // with the rest of the remote cache
+        let shared_dir = config
+            .shared_directory()
+            .filter(|_| config.enabled())
+            .map(Utf8PathBuf::from);
+        opts.cache_opts.remote_cache_opts = Some(
+            RemoteCacheOpts::new(unused_remote_cache_opts_team_id, signature)
+                .with_shared_dir(shared_dir),
+        );
+        opts.cache_opts.eviction_opts = config.eviction_opts()?;
         if opts.run_opts.experimental_space_id.is_none() {
             opts.run_opts.experimental_space_id = config.spaces_id().map(|s| s.to_owned());
         }
+        if let Some(endpoint) = config.otel_endpoint() {
+            opts.run_opts.otel = Some(OtelOpts {
+                endpoint: endpoint.to_string(),
+                service_name: config.otel_service_name().to_string(),
+                headers: config.otel_headers()?,
+            });
+        }
         let version = base.version();
         let CommandBase { repo_root, ui, .. } = base;
         Ok(Self {
@@ -109,9 +139,82 @@ impl Run {
             repo_root,
             ui,
             version,
+            filtered_pkgs: None,
+        })
+    }
+
+    /// Restrict the run to exactly `packages` instead of resolving the
+    /// scope from `--filter`.
+    pub(crate) fn with_packages(mut self, packages: HashSet<PackageName>) -> Self {
+        self.filtered_pkgs = Some(packages);
+        self
+    }
+
+    /// Build the package graph and resolve the packages in scope without
+    /// executing any tasks.
+    pub(crate) async fn resolve_scope(&self) -> Result<RunScope, Error> {
+        let scm = SCM::new(&self.repo_root);
+        let package_json_path = self.repo_root.join_component("package.json");
+        let root_package_json = PackageJson::load(&package_json_path)?;
+        let pkg_dep_graph = PackageGraph::builder(&self.repo_root, root_package_json.clone())
+            .with_single_package_mode(self.opts.run_opts.single_package)
+            .build()
+            .await?;
+        pkg_dep_graph.validate()?;
+
+        let root_turbo_json = TurboJson::load(
+            &self.repo_root,
+            AnchoredSystemPath::empty(),
+            &root_package_json,
+            self.opts.run_opts.single_package,
+        )?;
+        let filtered_pkgs =
+            self.resolve_filtered_packages(&pkg_dep_graph, &root_turbo_json, &scm)?;
+
+        Ok(RunScope {
+            pkg_dep_graph,
+            filtered_pkgs,
+            global_deps: root_turbo_json.global_deps.clone(),
+            root_turbo_json,
         })
     }
 
+    /// Build the task graph for a resolved scope without executing it.
+    pub(crate) fn build_scope_engine(&self, scope: &RunScope) -> Result<Engine, Error> {
+        self.build_engine(
+            &scope.pkg_dep_graph,
+            &scope.root_turbo_json,
+            &scope.filtered_pkgs,
+        )
+    }
+
+    fn resolve_filtered_packages(
+        &self,
+        pkg_dep_graph: &PackageGraph,
+        root_turbo_json: &TurboJson,
+        scm: &SCM,
+    ) -> Result<HashSet<PackageName>, Error> {
+        let (mut filtered_pkgs, is_all_packages) =
+            scope::resolve_packages(&self.opts.scope_opts, &self.repo_root, pkg_dep_graph, scm)?;
+
+        if is_all_packages {
+            for target in self.opts.run_opts.tasks.iter() {
+                let mut task_name = TaskName::from(target.as_str());
+                // If it's not a package task, we convert to a root task
+                if !task_name.is_package_task() {
+                    task_name = task_name.into_root_task()
+                }
+
+                if root_turbo_json.pipeline.contains_key(&task_name) {
+                    filtered_pkgs.insert(PackageName::Root);
+                    break;
+                }
+            }
+        };
+
+        Ok(filtered_pkgs)
+    }
+
     fn connect_process_manager(&self, signal_subscriber: SignalSubscriber) {
         let manager = self.processes.clone();
         tokio::spawn(async move {
@@ -328,7 +431,12 @@ impl Run {
         )?;
 
         // restore config from task access trace if it's enabled
-        let task_access = TaskAccess::new(self.repo_root.clone(), async_cache.clone(), &scm);
+        let task_access = TaskAccess::new(
+            self.repo_root.clone(),
+            async_cache.clone(),
+            &scm,
+            self.opts.run_opts.audit_inputs,
+        );
         task_access.restore_config().await;
 
         let root_turbo_json = TurboJson::load(
@@ -340,30 +448,9 @@ impl Run {
 
         pkg_dep_graph.validate()?;
 
-        let filtered_pkgs = {
-            let (mut filtered_pkgs, is_all_packages) = scope::resolve_packages(
-                &self.opts.scope_opts,
-                &self.repo_root,
-                &pkg_dep_graph,
-                &scm,
-            )?;
-
-            if is_all_packages {
-                for target in self.opts.run_opts.tasks.iter() {
-                    let mut task_name = TaskName::from(target.as_str());
-                    // If it's not a package task, we convert to a root task
-                    if !task_name.is_package_task() {
-                        task_name = task_name.into_root_task()
-                    }
-
-                    if root_turbo_json.pipeline.contains_key(&task_name) {
-                        filtered_pkgs.insert(PackageName::Root);
-                        break;
-                    }
-                }
-            };
-
-            filtered_pkgs
+        let filtered_pkgs = match &self.filtered_pkgs {
+            Some(filtered_pkgs) => filtered_pkgs.clone(),
+            None => self.resolve_filtered_packages(&pkg_dep_graph, &root_turbo_json, &scm)?,
         };
 
         let env_at_execution_start = EnvironmentVariableMap::infer();
@@ -403,6 +490,20 @@ impl Run {
 
         let color_selector = ColorSelector::default();
 
+        let workspaces = pkg_dep_graph.packages().collect();
+        let daemon_file_hashes = match &daemon {
+            Some(daemon) => {
+                PackageInputsHashes::fetch_daemon_file_hashes(
+                    daemon,
+                    engine.tasks(),
+                    &workspaces,
+                    engine.task_definitions(),
+                )
+                .await
+            }
+            None => DaemonFileHashes::default(),
+        };
+
         let runcache = Arc::new(RunCache::new(
             async_cache,
             &self.repo_root,
@@ -429,12 +530,12 @@ impl Run {
             global_env_mode = EnvMode::Strict;
         }
 
-        let workspaces = pkg_dep_graph.packages().collect();
         let package_inputs_hashes = PackageInputsHashes::calculate_file_hashes(
             &scm,
             engine.tasks().par_bridge(),
             workspaces,
             engine.task_definitions(),
+            &daemon_file_hashes,
             &self.repo_root,
             &run_telemetry,
         )?;
@@ -460,6 +561,23 @@ impl Run {
         let pkg_dep_graph = Arc::new(pkg_dep_graph);
         let engine = Arc::new(engine);
 
+        let input_auditor = self.opts.run_opts.audit_inputs.then(|| {
+            InputAuditor::new(
+                self.repo_root.clone(),
+                global_hash_inputs
+                    .global_file_hash_map
+                    .keys()
+                    .cloned()
+                    .collect(),
+                global_hash_inputs
+                    .env
+                    .iter()
+                    .chain(global_hash_inputs.pass_through_env.unwrap_or_default())
+                    .cloned()
+                    .collect(),
+            )
+        });
+
         let global_env = {
             let mut env = env_at_execution_start
                 .from_wildcards(global_hash_inputs.pass_through_env.unwrap_or_default())
@@ -469,6 +587,15 @@ impl Run {
             }
             env
         };
+        let global_dot_env = {
+            let paths = root_turbo_json
+                .global_dot_env
+                .iter()
+                .flatten()
+                .map(|path| self.repo_root.join_unix_path(path))
+                .collect::<Result<Vec<_>, _>>()?;
+            EnvironmentVariableMap::from_dot_env_files(&paths)?
+        };
 
         let run_tracker = RunTracker::new(
             start_at,
@@ -499,6 +626,9 @@ impl Run {
             self.processes.clone(),
             &self.repo_root,
             global_env,
+            global_dot_env,
+            self.resource_capacities(&root_turbo_json),
+            input_auditor,
         );
 
         if self.opts.run_opts.dry_run.is_some() {
@@ -541,6 +671,13 @@ impl Run {
         Ok(exit_code)
     }
 
+    // Resource capacities from turbo.json, any set via the CLI take precedence
+    fn resource_capacities(&self, root_turbo_json: &TurboJson) -> BTreeMap<String, u32> {
+        let mut resources = root_turbo_json.resources.clone();
+        resources.extend(self.opts.run_opts.resources.clone());
+        resources
+    }
+
     fn build_engine(
         &self,
         pkg_dep_graph: &PackageGraph,
@@ -571,7 +708,11 @@ impl Run {
 
         if !self.opts.run_opts.parallel {
             engine
-                .validate(pkg_dep_graph, self.opts.run_opts.concurrency)
+                .validate(
+                    pkg_dep_graph,
+                    self.opts.run_opts.concurrency,
+                    &self.resource_capacities(root_turbo_json),
+                )
                 .map_err(|errors| {
                     Error::EngineValidation(
                         errors
@@ -583,6 +724,14 @@ impl Run {
                 })?;
         }
 
+        engine
+            .validate_interactive(
+                pkg_dep_graph,
+                self.processes.use_pty(),
+                self.opts.run_opts.parallel || self.opts.run_opts.concurrency > 1,
+            )
+            .map_err(Error::InteractiveTasks)?;
+
         Ok(engine)
     }
 }
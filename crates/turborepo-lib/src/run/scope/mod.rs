@@ -6,7 +6,9 @@ mod target_selector;
 use std::collections::HashSet;
 
 use filter::{FilterResolver, PackageInference};
+use tracing::debug;
 use turbopath::AbsoluteSystemPath;
+use turborepo_ci::Vendor;
 use turborepo_repository::package_graph::{PackageGraph, PackageName};
 use turborepo_scm::SCM;
 
@@ -24,6 +26,32 @@ pub fn resolve_packages(
         PackageInference::calculate(turbo_root, pkg_inference_path, pkg_graph)
     });
 
-    FilterResolver::new(opts, pkg_graph, turbo_root, pkg_inference, scm)
-        .resolve(&opts.get_filters())
+    let mut filters = opts.get_filters();
+    if opts.affected {
+        filters.push(affected_filter(Vendor::infer(), turbo_root, scm)?);
+    }
+
+    FilterResolver::new(opts, pkg_graph, turbo_root, pkg_inference, scm).resolve(&filters)
+}
+
+/// Builds a filter selecting the packages changed between the base and head
+/// of this CI run along with their dependents. Outside of CI, or when the
+/// vendor doesn't expose a base, changes are compared against the default
+/// branch. Since git compares `base...head` from their merge base, commits
+/// that landed on the base after the branch was created are ignored.
+fn affected_filter(
+    vendor: Option<&Vendor>,
+    turbo_root: &AbsoluteSystemPath,
+    scm: &SCM,
+) -> Result<String, ResolutionError> {
+    let base = match vendor.and_then(|vendor| vendor.base_ref()) {
+        Some(base) => base,
+        None => scm.default_branch(turbo_root)?,
+    };
+    let head = vendor
+        .and_then(|vendor| vendor.head_sha())
+        .unwrap_or_else(|| "HEAD".to_string());
+    debug!("finding packages affected between {base} and {head}");
+
+    Ok(format!("...[{base}...{head}]"))
 }
@@ -7,7 +7,10 @@ use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
 use turborepo_ui::{color, cprintln, BOLD, BOLD_GREEN, BOLD_RED, MAGENTA, UI, YELLOW};
 
 use super::TurboDuration;
-use crate::run::{summary::task::TaskSummary, task_id::TaskId};
+use crate::{
+    process::ResourceUsage,
+    run::{summary::task::TaskSummary, task_id::TaskId},
+};
 
 // Just used to make changing the type that gets passed to the state management
 // thread easy
@@ -26,7 +29,7 @@ pub struct ExecutionTracker {
 #[serde(rename_all = "camelCase")]
 pub struct ExecutionSummary<'a> {
     // a synthesized turbo command to produce this invocation
-    command: String,
+    pub(crate) command: String,
     // the (possibly empty) path from the turborepo root to where the command was run
     #[serde(rename = "repoPath")]
     repo_path: &'a AnchoredSystemPath,
@@ -195,6 +198,9 @@ pub struct TaskTracker<T> {
     sender: mpsc::Sender<Message>,
     started_at: T,
     task_id: TaskId<'static>,
+    attempts: u32,
+    timed_out: bool,
+    resources: Option<ResourceUsage>,
 }
 
 #[derive(Debug, Clone)]
@@ -222,6 +228,20 @@ pub struct TaskExecutionSummary {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     pub exit_code: Option<i32>,
+    // Number of times the task was run, only reported if it was retried
+    #[serde(skip_serializing_if = "is_single_attempt")]
+    pub attempts: u32,
+    // Whether the last attempt was stopped for exceeding the task timeout
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    // CPU time and peak memory of the task's processes, only reported where
+    // they can be measured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceUsage>,
+}
+
+fn is_single_attempt(attempts: &u32) -> bool {
+    *attempts <= 1
 }
 
 impl TaskExecutionSummary {
@@ -264,6 +284,9 @@ impl ExecutionTracker {
             sender: self.sender.clone(),
             task_id,
             started_at: (),
+            attempts: 1,
+            timed_out: false,
+            resources: None,
         }
     }
 
@@ -288,7 +311,12 @@ impl TaskTracker<()> {
     // Start the tracker
     pub async fn start(self) -> TaskTracker<DateTime<Local>> {
         let TaskTracker {
-            sender, task_id, ..
+            sender,
+            task_id,
+            attempts,
+            timed_out,
+            resources,
+            ..
         } = self;
         let started_at = Local::now();
         sender
@@ -302,6 +330,9 @@ impl TaskTracker<()> {
             sender,
             started_at,
             task_id,
+            attempts,
+            timed_out,
+            resources,
         }
     }
 
@@ -329,11 +360,28 @@ impl TaskTracker<chrono::DateTime<Local>> {
     // internal turbo error
     pub fn cancel(self) {}
 
+    // Record how many times the task was run and if the final run timed out
+    pub fn with_attempts(self, attempts: u32, timed_out: bool) -> Self {
+        Self {
+            attempts,
+            timed_out,
+            ..self
+        }
+    }
+
+    // Record the resources used by the task's processes
+    pub fn with_resource_usage(self, resources: Option<ResourceUsage>) -> Self {
+        Self { resources, ..self }
+    }
+
     pub async fn cached(self) -> TaskExecutionSummary {
         let Self {
             sender,
             started_at,
             task_id,
+            attempts,
+            timed_out,
+            resources,
         } = self;
 
         let ended_at = Local::now();
@@ -343,6 +391,9 @@ impl TaskTracker<chrono::DateTime<Local>> {
             // Go synthesizes a zero exit code on cache hits
             exit_code: Some(0),
             error: None,
+            attempts,
+            timed_out,
+            resources,
         };
 
         let state = TaskState {
@@ -364,6 +415,9 @@ impl TaskTracker<chrono::DateTime<Local>> {
             sender,
             started_at,
             task_id,
+            attempts,
+            timed_out,
+            resources,
         } = self;
 
         let ended_at = Local::now();
@@ -372,6 +426,9 @@ impl TaskTracker<chrono::DateTime<Local>> {
             end_time: ended_at.timestamp_millis(),
             exit_code: Some(exit_code),
             error: None,
+            attempts,
+            timed_out,
+            resources,
         };
 
         let state = TaskState {
@@ -397,6 +454,9 @@ impl TaskTracker<chrono::DateTime<Local>> {
             sender,
             started_at,
             task_id,
+            attempts,
+            timed_out,
+            resources,
         } = self;
 
         let ended_at = Local::now();
@@ -405,6 +465,9 @@ impl TaskTracker<chrono::DateTime<Local>> {
             end_time: ended_at.timestamp_millis(),
             exit_code,
             error: Some(error.to_string()),
+            attempts,
+            timed_out,
+            resources,
         };
 
         let state = TaskState {
@@ -518,7 +581,10 @@ mod test {
             start_time: 123,
             end_time: 234,
             exit_code: Some(0),
-            error: None
+            error: None,
+            attempts: 1,
+            timed_out: false,
+            resources: None,
         },
         json!({ "startTime": 123, "endTime": 234, "exitCode": 0 })
         ; "success"
@@ -529,10 +595,59 @@ mod test {
             end_time: 234,
             exit_code: Some(1),
             error: Some("cannot find anything".into()),
+            attempts: 1,
+            timed_out: false,
+            resources: None,
         },
         json!({ "startTime": 123, "endTime": 234, "exitCode": 1, "error": "cannot find anything" })
         ; "failure"
     )]
+    #[test_case(
+        TaskExecutionSummary {
+            start_time: 123,
+            end_time: 234,
+            exit_code: None,
+            error: Some("task timed out after 10s".into()),
+            attempts: 3,
+            timed_out: true,
+            resources: None,
+        },
+        json!({
+            "startTime": 123,
+            "endTime": 234,
+            "exitCode": null,
+            "error": "task timed out after 10s",
+            "attempts": 3,
+            "timedOut": true
+        })
+        ; "retried timeout"
+    )]
+    #[test_case(
+        TaskExecutionSummary {
+            start_time: 123,
+            end_time: 234,
+            exit_code: Some(0),
+            error: None,
+            attempts: 1,
+            timed_out: false,
+            resources: Some(ResourceUsage {
+                user_cpu_ms: 1500,
+                system_cpu_ms: 250,
+                peak_rss_bytes: 104857600,
+            }),
+        },
+        json!({
+            "startTime": 123,
+            "endTime": 234,
+            "exitCode": 0,
+            "resources": {
+                "userCpuMs": 1500,
+                "systemCpuMs": 250,
+                "peakRssBytes": 104857600
+            }
+        })
+        ; "resources"
+    )]
     fn test_serialization(value: impl serde::Serialize, expected: serde_json::Value) {
         assert_eq!(serde_json::to_value(value).unwrap(), expected);
     }
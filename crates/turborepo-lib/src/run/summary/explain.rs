@@ -0,0 +1,686 @@
+//! Explains why tasks missed the cache by comparing their hash inputs
+//! against the same tasks in the most recent saved run summary that
+//! contains them.
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt,
+};
+
+use serde::Deserialize;
+use tracing::debug;
+use turbopath::AbsoluteSystemPath;
+use turborepo_ui::{color, BOLD, GREY, UI};
+
+// The subset of a saved run summary that contributes to task hashes
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SummaryInputs {
+    id: String,
+    #[serde(rename = "globalCacheInputs")]
+    global: GlobalInputs,
+    tasks: Vec<TaskInputs>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalInputs {
+    root_key: String,
+    files: BTreeMap<String, String>,
+    hash_of_external_dependencies: String,
+    global_dot_env: Option<Vec<String>>,
+    environment_variables: EnvInputs,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskInputs {
+    task_id: String,
+    hash: String,
+    inputs: BTreeMap<String, String>,
+    hash_of_external_dependencies: String,
+    cache: CacheInputs,
+    cli_arguments: Vec<String>,
+    dependencies: Vec<String>,
+    resolved_task_definition: serde_json::Value,
+    environment_variables: EnvInputs,
+    dot_env: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CacheInputs {
+    status: String,
+}
+
+// Both task and global summaries record env vars as `NAME=hash` pairs
+#[derive(Debug, Deserialize)]
+struct EnvInputs {
+    configured: Option<Vec<String>>,
+    inferred: Option<Vec<String>>,
+    #[serde(rename = "passthrough")]
+    pass_through: Option<Vec<String>>,
+}
+
+impl EnvInputs {
+    fn by_name(&self) -> BTreeMap<&str, &str> {
+        [&self.configured, &self.inferred, &self.pass_through]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|pair| pair.split_once('='))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Change {
+    GlobalCacheKey,
+    GlobalFile { path: String, kind: ChangeKind },
+    GlobalExternalDependencies,
+    GlobalDotEnv,
+    GlobalEnvVar { name: String, kind: ChangeKind },
+    File { path: String, kind: ChangeKind },
+    ExternalDependencies,
+    EnvVar { name: String, kind: ChangeKind },
+    Upstream { task_id: String, kind: ChangeKind },
+    Definition,
+    CliArguments,
+    DotEnv,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Explanation {
+    // The task wasn't part of any previous run
+    NoPreviousRun,
+    // The hash matches, so the artifact is missing from the cache rather
+    // than the inputs having changed
+    HashUnchanged {
+        previous_id: String,
+    },
+    Changed {
+        previous_id: String,
+        changes: Vec<Change>,
+    },
+}
+
+#[derive(Debug)]
+pub(crate) struct MissReport {
+    // Changes to global inputs, by the previous run they were compared to
+    global: Vec<(String, Vec<Change>)>,
+    tasks: Vec<(String, Explanation)>,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+        })
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::GlobalCacheKey => write!(f, "global cache key changed"),
+            Change::GlobalFile { path, kind } => write!(f, "global file {path} was {kind}"),
+            Change::GlobalExternalDependencies => {
+                write!(f, "root external dependencies changed")
+            }
+            Change::GlobalDotEnv => write!(f, "globalDotEnv files changed"),
+            Change::GlobalEnvVar { name, kind } => {
+                write!(f, "global env var {name} was {kind}")
+            }
+            Change::File { path, kind } => write!(f, "file {path} was {kind}"),
+            Change::ExternalDependencies => write!(f, "external dependencies changed"),
+            Change::EnvVar { name, kind } => write!(f, "env var {name} was {kind}"),
+            Change::Upstream {
+                task_id,
+                kind: ChangeKind::Modified,
+            } => write!(f, "upstream task {task_id} changed hash"),
+            Change::Upstream { task_id, kind } => {
+                write!(f, "upstream task {task_id} was {kind}")
+            }
+            Change::Definition => write!(f, "task definition changed"),
+            Change::CliArguments => write!(f, "arguments passed to the task changed"),
+            Change::DotEnv => write!(f, "dotEnv files changed"),
+        }
+    }
+}
+
+/// Finds the run summaries in `runs_dir` that the cache misses in `current`
+/// should be compared against: for each missed task, the most recent earlier
+/// summary that contains it. Summaries are returned newest first. Summary
+/// file names are KSUIDs, so they sort by creation time.
+pub(crate) fn previous_summaries(
+    runs_dir: &AbsoluteSystemPath,
+    current: &SummaryInputs,
+) -> Vec<SummaryInputs> {
+    let mut unresolved = current
+        .tasks
+        .iter()
+        .filter(|task| task.cache.status == "MISS")
+        .map(|task| task.task_id.as_str())
+        .collect::<HashSet<_>>();
+    let Ok(entries) = runs_dir.as_std_path().read_dir() else {
+        return Vec::new();
+    };
+    let mut ids = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| name.strip_suffix(".json").map(|id| id.to_string()))
+        .filter(|id| *id != current.id)
+        .collect::<Vec<_>>();
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut summaries = Vec::new();
+    for id in ids {
+        if unresolved.is_empty() {
+            break;
+        }
+        let Some(summary) = read_summary(runs_dir, &id) else {
+            continue;
+        };
+        let unresolved_before = unresolved.len();
+        for task in &summary.tasks {
+            unresolved.remove(task.task_id.as_str());
+        }
+        if unresolved.len() < unresolved_before {
+            summaries.push(summary);
+        }
+    }
+    summaries
+}
+
+fn read_summary(runs_dir: &AbsoluteSystemPath, id: &str) -> Option<SummaryInputs> {
+    let contents = runs_dir
+        .join_component(&format!("{id}.json"))
+        .read_to_string()
+        .ok()?;
+    serde_json::from_str(&contents)
+        .map_err(|err| debug!("unable to parse run summary {id}: {err}"))
+        .ok()
+}
+
+/// Explains every task in `current` that missed the cache, comparing each one
+/// against the first summary in `previous` that contains it
+pub(crate) fn explain_misses(previous: &[SummaryInputs], current: &SummaryInputs) -> MissReport {
+    let previous_tasks = previous
+        .iter()
+        .map(|summary| {
+            summary
+                .tasks
+                .iter()
+                .map(|task| (task.task_id.as_str(), task))
+                .collect::<BTreeMap<_, _>>()
+        })
+        .collect::<Vec<_>>();
+    let current_tasks = current
+        .tasks
+        .iter()
+        .map(|task| (task.task_id.as_str(), task))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut compared_runs = BTreeSet::new();
+    let tasks = current
+        .tasks
+        .iter()
+        .filter(|task| task.cache.status == "MISS")
+        .map(|task| {
+            let previous_run = previous.iter().zip(&previous_tasks).enumerate().find_map(
+                |(index, (summary, tasks))| {
+                    let prev = tasks.get(task.task_id.as_str())?;
+                    Some((index, summary, tasks, prev))
+                },
+            );
+            let explanation = match previous_run {
+                None => Explanation::NoPreviousRun,
+                Some((index, summary, tasks, prev)) => {
+                    compared_runs.insert(index);
+                    let previous_id = summary.id.clone();
+                    if prev.hash == task.hash {
+                        Explanation::HashUnchanged { previous_id }
+                    } else {
+                        Explanation::Changed {
+                            previous_id,
+                            changes: task_changes(prev, task, tasks, &current_tasks),
+                        }
+                    }
+                }
+            };
+            (task.task_id.clone(), explanation)
+        })
+        .collect();
+
+    let global = compared_runs
+        .into_iter()
+        .map(|index| &previous[index])
+        .map(|summary| {
+            (
+                summary.id.clone(),
+                global_changes(&summary.global, &current.global),
+            )
+        })
+        .filter(|(_, changes)| !changes.is_empty())
+        .collect();
+
+    MissReport { global, tasks }
+}
+
+fn global_changes(previous: &GlobalInputs, current: &GlobalInputs) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if previous.root_key != current.root_key {
+        changes.push(Change::GlobalCacheKey);
+    }
+    changes.extend(
+        map_changes(&previous.files, &current.files)
+            .map(|(path, kind)| Change::GlobalFile { path, kind }),
+    );
+    if previous.hash_of_external_dependencies != current.hash_of_external_dependencies {
+        changes.push(Change::GlobalExternalDependencies);
+    }
+    if previous.global_dot_env != current.global_dot_env {
+        changes.push(Change::GlobalDotEnv);
+    }
+    changes.extend(
+        map_changes(
+            &previous.environment_variables.by_name(),
+            &current.environment_variables.by_name(),
+        )
+        .map(|(name, kind)| Change::GlobalEnvVar { name, kind }),
+    );
+    changes
+}
+
+fn task_changes(
+    previous: &TaskInputs,
+    current: &TaskInputs,
+    previous_tasks: &BTreeMap<&str, &TaskInputs>,
+    current_tasks: &BTreeMap<&str, &TaskInputs>,
+) -> Vec<Change> {
+    let mut changes = map_changes(&previous.inputs, &current.inputs)
+        .map(|(path, kind)| Change::File { path, kind })
+        .collect::<Vec<_>>();
+    if previous.hash_of_external_dependencies != current.hash_of_external_dependencies {
+        changes.push(Change::ExternalDependencies);
+    }
+    changes.extend(
+        map_changes(
+            &previous.environment_variables.by_name(),
+            &current.environment_variables.by_name(),
+        )
+        .map(|(name, kind)| Change::EnvVar { name, kind }),
+    );
+
+    let upstream_hash = |tasks: &BTreeMap<&str, &TaskInputs>, task_id: &str| {
+        tasks.get(task_id).map(|task| task.hash.clone())
+    };
+    let previous_upstream = previous
+        .dependencies
+        .iter()
+        .map(|task_id| (task_id.as_str(), upstream_hash(previous_tasks, task_id)))
+        .collect::<BTreeMap<_, _>>();
+    let current_upstream = current
+        .dependencies
+        .iter()
+        .map(|task_id| (task_id.as_str(), upstream_hash(current_tasks, task_id)))
+        .collect::<BTreeMap<_, _>>();
+    changes.extend(
+        map_changes(&previous_upstream, &current_upstream)
+            .map(|(task_id, kind)| Change::Upstream { task_id, kind }),
+    );
+
+    if previous.resolved_task_definition != current.resolved_task_definition {
+        changes.push(Change::Definition);
+    }
+    if previous.cli_arguments != current.cli_arguments {
+        changes.push(Change::CliArguments);
+    }
+    if previous.dot_env != current.dot_env {
+        changes.push(Change::DotEnv);
+    }
+    changes
+}
+
+// Yields the keys that differ between two maps along with how they changed
+fn map_changes<'a, K: AsRef<str> + Ord, V: PartialEq>(
+    previous: &'a BTreeMap<K, V>,
+    current: &'a BTreeMap<K, V>,
+) -> impl Iterator<Item = (String, ChangeKind)> + 'a {
+    let keys = previous
+        .keys()
+        .chain(current.keys())
+        .collect::<BTreeSet<_>>();
+    keys.into_iter().filter_map(|key| {
+        let kind = match (previous.get(key), current.get(key)) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            (Some(prev), Some(curr)) if prev != curr => ChangeKind::Modified,
+            _ => return None,
+        };
+        Some((key.as_ref().to_string(), kind))
+    })
+}
+
+impl MissReport {
+    pub(crate) fn print(&self, ui: UI) {
+        if self.tasks.is_empty() {
+            return;
+        }
+
+        println!(
+            "{}",
+            color!(ui, BOLD, "Cache misses compared to previous runs:")
+        );
+        for (previous_id, changes) in &self.global {
+            println!("  global inputs since run {previous_id}:");
+            for change in changes {
+                println!("    {change}");
+            }
+        }
+        for (task_id, explanation) in &self.tasks {
+            match explanation {
+                Explanation::NoPreviousRun => println!(
+                    "  {task_id}: {}",
+                    color!(ui, GREY, "not part of any previous run")
+                ),
+                Explanation::HashUnchanged { previous_id } => println!(
+                    "  {task_id}: {}",
+                    color!(
+                        ui,
+                        GREY,
+                        "hash unchanged since run {}, no artifact found in the cache",
+                        previous_id
+                    )
+                ),
+                Explanation::Changed {
+                    previous_id,
+                    changes,
+                } if changes.is_empty() => println!(
+                    "  {task_id}: {}",
+                    color!(ui, GREY, "global inputs changed since run {}", previous_id)
+                ),
+                Explanation::Changed {
+                    previous_id,
+                    changes,
+                } => {
+                    println!("  {task_id} (since run {previous_id}):");
+                    for change in changes {
+                        println!("    {change}");
+                    }
+                }
+            }
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn summary(global_files: serde_json::Value, tasks: serde_json::Value) -> SummaryInputs {
+        serde_json::from_value(summary_json("run", global_files, tasks)).unwrap()
+    }
+
+    fn summary_json(
+        id: &str,
+        global_files: serde_json::Value,
+        tasks: serde_json::Value,
+    ) -> serde_json::Value {
+        json!({
+            "id": id,
+            "globalCacheInputs": {
+                "rootKey": "key",
+                "files": global_files,
+                "hashOfExternalDependencies": "root-deps",
+                "globalDotEnv": null,
+                "environmentVariables": {
+                    "configured": [],
+                    "inferred": [],
+                    "passthrough": null
+                }
+            },
+            "tasks": tasks,
+        })
+    }
+
+    fn task(
+        task_id: &str,
+        hash: &str,
+        status: &str,
+        extra: serde_json::Value,
+    ) -> serde_json::Value {
+        let mut task = json!({
+            "taskId": task_id,
+            "hash": hash,
+            "inputs": {},
+            "hashOfExternalDependencies": "deps",
+            "cache": { "status": status },
+            "cliArguments": [],
+            "dependencies": [],
+            "resolvedTaskDefinition": { "outputs": [] },
+            "environmentVariables": {
+                "configured": [],
+                "inferred": [],
+                "passthrough": null
+            },
+            "dotEnv": null
+        });
+        for (key, value) in extra.as_object().unwrap() {
+            task[key] = value.clone();
+        }
+        task
+    }
+
+    #[test_case(
+        json!({ "inputs": { "src/index.ts": "a", "old.ts": "b" } }),
+        json!({ "inputs": { "src/index.ts": "c", "new.ts": "d" } }),
+        vec![
+            Change::File { path: "new.ts".into(), kind: ChangeKind::Added },
+            Change::File { path: "old.ts".into(), kind: ChangeKind::Removed },
+            Change::File { path: "src/index.ts".into(), kind: ChangeKind::Modified },
+        ] ;
+        "files"
+    )]
+    #[test_case(
+        json!({ "environmentVariables": { "configured": ["API_URL=1"], "inferred": ["NEXT_PUBLIC_A=2"] } }),
+        json!({ "environmentVariables": { "configured": ["API_URL=3"], "inferred": [] } }),
+        vec![
+            Change::EnvVar { name: "API_URL".into(), kind: ChangeKind::Modified },
+            Change::EnvVar { name: "NEXT_PUBLIC_A".into(), kind: ChangeKind::Removed },
+        ] ;
+        "env vars"
+    )]
+    #[test_case(
+        json!({ "resolvedTaskDefinition": { "outputs": [] }, "cliArguments": [] }),
+        json!({ "resolvedTaskDefinition": { "outputs": ["dist/**"] }, "cliArguments": ["--watch"] }),
+        vec![Change::Definition, Change::CliArguments] ;
+        "definition and args"
+    )]
+    fn test_task_changes(
+        previous: serde_json::Value,
+        current: serde_json::Value,
+        expected: Vec<Change>,
+    ) {
+        let previous = summary(json!({}), json!([task("web#build", "1", "HIT", previous)]));
+        let current = summary(json!({}), json!([task("web#build", "2", "MISS", current)]));
+        let report = explain_misses(&[previous], &current);
+        assert!(report.global.is_empty());
+        assert_eq!(
+            report.tasks,
+            vec![(
+                "web#build".to_string(),
+                Explanation::Changed {
+                    previous_id: "run".into(),
+                    changes: expected
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_upstream_changes() {
+        let previous = summary(
+            json!({}),
+            json!([
+                task("ui#build", "ui-1", "HIT", json!({})),
+                task(
+                    "web#build",
+                    "web-1",
+                    "HIT",
+                    json!({ "dependencies": ["ui#build"] })
+                ),
+            ]),
+        );
+        let current = summary(
+            json!({}),
+            json!([
+                task(
+                    "ui#build",
+                    "ui-2",
+                    "MISS",
+                    json!({ "inputs": { "a.ts": "x" } })
+                ),
+                task(
+                    "web#build",
+                    "web-2",
+                    "MISS",
+                    json!({ "dependencies": ["ui#build"] })
+                ),
+            ]),
+        );
+        let report = explain_misses(&[previous], &current);
+        assert_eq!(
+            report.tasks[1],
+            (
+                "web#build".to_string(),
+                Explanation::Changed {
+                    previous_id: "run".into(),
+                    changes: vec![Change::Upstream {
+                        task_id: "ui#build".into(),
+                        kind: ChangeKind::Modified
+                    }]
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_global_and_unchanged() {
+        let previous = summary(
+            json!({ "turbo.json": "a" }),
+            json!([task("web#build", "1", "HIT", json!({}))]),
+        );
+        let current = summary(
+            json!({ "turbo.json": "b" }),
+            json!([
+                task("web#build", "1", "MISS", json!({})),
+                task("docs#build", "3", "MISS", json!({})),
+                task("api#build", "4", "HIT", json!({})),
+            ]),
+        );
+        let report = explain_misses(&[previous], &current);
+        assert_eq!(
+            report.global,
+            vec![(
+                "run".to_string(),
+                vec![Change::GlobalFile {
+                    path: "turbo.json".into(),
+                    kind: ChangeKind::Modified
+                }]
+            )]
+        );
+        assert_eq!(
+            report.tasks,
+            vec![
+                (
+                    "web#build".to_string(),
+                    Explanation::HashUnchanged {
+                        previous_id: "run".into()
+                    }
+                ),
+                ("docs#build".to_string(), Explanation::NoPreviousRun),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_previous_run_per_task() {
+        let tmp = tempfile::tempdir().unwrap();
+        let runs_dir = AbsoluteSystemPath::from_std_path(tmp.path()).unwrap();
+        let write = |id: &str, tasks: serde_json::Value| {
+            runs_dir
+                .join_component(&format!("{id}.json"))
+                .create_with_contents(summary_json(id, json!({}), tasks).to_string())
+                .unwrap();
+        };
+        write(
+            "run-1",
+            json!([
+                task("web#build", "web-1", "MISS", json!({})),
+                task("web#lint", "lint-1", "MISS", json!({})),
+            ]),
+        );
+        write(
+            "run-2",
+            json!([task("web#build", "web-2", "HIT", json!({}))]),
+        );
+        // An unrelated run, e.g. of a different task or filter
+        write(
+            "run-3",
+            json!([task("docs#test", "docs-1", "MISS", json!({}))]),
+        );
+        let current = serde_json::from_value(summary_json(
+            "run-4",
+            json!({}),
+            json!([
+                task("web#build", "web-3", "MISS", json!({})),
+                task("web#lint", "lint-1", "MISS", json!({})),
+                task("api#build", "api-1", "MISS", json!({})),
+            ]),
+        ))
+        .unwrap();
+        write("run-4", json!([]));
+
+        let previous = previous_summaries(runs_dir, &current);
+        assert_eq!(
+            previous
+                .iter()
+                .map(|summary| summary.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["run-2", "run-1"]
+        );
+
+        let report = explain_misses(&previous, &current);
+        assert_eq!(
+            report.tasks,
+            vec![
+                (
+                    "web#build".to_string(),
+                    Explanation::Changed {
+                        previous_id: "run-2".into(),
+                        changes: vec![]
+                    }
+                ),
+                (
+                    "web#lint".to_string(),
+                    Explanation::HashUnchanged {
+                        previous_id: "run-1".into()
+                    }
+                ),
+                ("api#build".to_string(), Explanation::NoPreviousRun),
+            ]
+        );
+    }
+}
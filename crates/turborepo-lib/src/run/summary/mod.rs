@@ -6,7 +6,10 @@
 #[allow(dead_code)]
 mod duration;
 mod execution;
+mod explain;
 mod global_hash;
+mod otel;
+mod report;
 mod scm;
 mod spaces;
 mod task;
@@ -22,6 +25,7 @@ use serde::Serialize;
 pub use spaces::{SpacesTaskClient, SpacesTaskInformation};
 use svix_ksuid::{Ksuid, KsuidLike};
 use tabwriter::TabWriter;
+pub use task::TaskSummaryTaskDefinition;
 use thiserror::Error;
 use tracing::{error, log::warn};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
@@ -39,7 +43,7 @@ use crate::{
     cli,
     cli::DryRunMode,
     engine::Engine,
-    opts::RunOpts,
+    opts::{OtelOpts, RunOpts, SummaryReport},
     run::summary::{
         execution::{ExecutionSummary, ExecutionTracker},
         scm::SCMState,
@@ -125,9 +129,17 @@ pub struct RunSummary<'a> {
     #[serde(skip)]
     should_save: bool,
     #[serde(skip)]
+    explain_miss: bool,
+    #[serde(skip)]
+    reports: &'a [SummaryReport],
+    #[serde(skip)]
+    github_step_summary: Option<AbsoluteSystemPathBuf>,
+    #[serde(skip)]
     run_type: RunType,
     #[serde(skip)]
     spaces_client_handle: Option<SpacesClientHandle>,
+    #[serde(skip)]
+    otel: Option<&'a OtelOpts>,
 }
 
 /// We use this to track the run, so it's constructed before the run.
@@ -208,7 +220,20 @@ impl RunTracker {
         task_factory: TaskSummaryFactory<'a>,
     ) -> Result<RunSummary<'a>, Error> {
         let single_package = run_opts.single_package;
-        let should_save = run_opts.summarize.flatten().is_some_and(|s| s);
+        // Explaining misses needs a summary from this run to compare the next one
+        // against
+        let should_save = run_opts.summarize || run_opts.explain_miss;
+        // On GitHub Actions, asking for any kind of summary also adds one to the
+        // job's summary page
+        let github_step_summary = if (run_opts.summarize || !run_opts.summary_reports.is_empty())
+            && turborepo_ci::Vendor::get_constant() == Some("GITHUB_ACTIONS")
+        {
+            std::env::var("GITHUB_STEP_SUMMARY")
+                .ok()
+                .and_then(|path| AbsoluteSystemPathBuf::new(path).ok())
+        } else {
+            None
+        };
 
         let run_type = match run_opts.dry_run {
             None => RunType::Real,
@@ -248,8 +273,12 @@ impl RunTracker {
             monorepo: !single_package,
             repo_root,
             should_save,
+            explain_miss: run_opts.explain_miss,
+            reports: &run_opts.summary_reports,
+            github_step_summary,
             run_type,
             spaces_client_handle: self.spaces_client_handle,
+            otel: run_opts.otel.as_ref(),
         })
     }
 
@@ -391,6 +420,21 @@ impl<'a> RunSummary<'a> {
             }
         }
 
+        if let Err(err) = self.write_reports() {
+            warn!("Error writing run summary report: {}", err)
+        }
+
+        if let (Some(otel), Some(execution)) = (self.otel, &self.execution) {
+            // Failing to export the trace shouldn't fail the run
+            if let Err(err) = otel::export(otel, self.turbo_version, execution, &self.tasks).await {
+                warn!("Error exporting run trace: {}", err)
+            }
+        }
+
+        if self.explain_miss {
+            self.explain_misses(ui);
+        }
+
         if let Some(execution) = &self.execution {
             let path = self.get_path();
             let failed_tasks = self.get_failed_tasks();
@@ -434,6 +478,23 @@ impl<'a> RunSummary<'a> {
         }
     }
 
+    fn explain_misses(&mut self, ui: UI) {
+        let runs_dir = self.repo_root.join_components(&[".turbo", "runs"]);
+        let current = match self
+            .format_json()
+            .and_then(|json| Ok(serde_json::from_str(&json)?))
+        {
+            Ok(current) => current,
+            Err(err) => {
+                warn!("Error explaining cache misses: {}", err);
+                return;
+            }
+        };
+
+        let previous = explain::previous_summaries(&runs_dir, &current);
+        explain::explain_misses(&previous, &current).print(ui);
+    }
+
     fn print_errors(errors: &[Error]) {
         if errors.is_empty() {
             return;
@@ -626,6 +687,34 @@ impl<'a> RunSummary<'a> {
                 "  Log File\t=\t{}",
                 task.shared.log_file
             )?;
+            if let Some(resources) = task
+                .shared
+                .execution
+                .as_ref()
+                .and_then(|execution| execution.resources)
+            {
+                cwriteln!(
+                    tab_writer,
+                    ui,
+                    GREY,
+                    "  CPU Time (User)\t=\t{:.2}s",
+                    resources.user_cpu_ms as f64 / 1000.0
+                )?;
+                cwriteln!(
+                    tab_writer,
+                    ui,
+                    GREY,
+                    "  CPU Time (System)\t=\t{:.2}s",
+                    resources.system_cpu_ms as f64 / 1000.0
+                )?;
+                cwriteln!(
+                    tab_writer,
+                    ui,
+                    GREY,
+                    "  Peak Memory\t=\t{:.1} MiB",
+                    resources.peak_rss_bytes as f64 / (1024.0 * 1024.0)
+                )?;
+            }
 
             let dependencies = if !self.monorepo {
                 task.shared
@@ -713,6 +802,15 @@ impl<'a> RunSummary<'a> {
                     .as_ref()
                     .map_or_else(String::new, |vars| vars.join(", "))
             )?;
+            if !task.shared.environment_variables.dot_env.is_empty() {
+                cwriteln!(
+                    tab_writer,
+                    ui,
+                    GREY,
+                    "  .env Vars Values\t=\t{}",
+                    task.shared.environment_variables.dot_env.join(", ")
+                )?;
+            }
 
             // If there's an error, we can silently ignore it, we don't need to block the
             // entire print.
@@ -797,6 +895,43 @@ impl<'a> RunSummary<'a> {
             .collect()
     }
 
+    fn write_reports(&mut self) -> Result<(), Error> {
+        if self.reports.is_empty() && self.github_step_summary.is_none() {
+            return Ok(());
+        }
+        self.normalize();
+
+        let command = self
+            .execution
+            .as_ref()
+            .map_or("turbo run", |execution| execution.command.as_str());
+        let tasks = self
+            .tasks
+            .iter()
+            .map(|task| report::ReportTask::new(self.repo_root, task))
+            .collect::<Vec<_>>();
+
+        for report in self.reports {
+            let (path, contents) = match report {
+                SummaryReport::Junit(path) => (path, report::junit(command, &tasks)),
+                SummaryReport::Markdown(path) => (path, report::markdown(command, &tasks)),
+            };
+            let path = AbsoluteSystemPathBuf::from_unknown(self.repo_root, path);
+            path.ensure_dir()?;
+            path.create_with_contents(contents)?;
+        }
+
+        if let Some(step_summary) = &self.github_step_summary {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(step_summary)?;
+            writeln!(file, "{}", report::markdown(command, &tasks))?;
+        }
+
+        Ok(())
+    }
+
     fn save(&mut self) -> Result<(), Error> {
         let json = self.format_json()?;
 
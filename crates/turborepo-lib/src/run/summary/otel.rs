@@ -0,0 +1,525 @@
+//! Exports a trace of each run to an OpenTelemetry collector using OTLP over
+//! HTTP with protobuf encoding. The run is the root span and every task that
+//! ran is one of its children. Only the parts of the OTLP schema that turbo
+//! sends are defined here.
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+use self::proto::{
+    any_value, status, AnyValue, ExportTraceServiceRequest, InstrumentationScope, KeyValue,
+    Resource, ResourceSpans, ScopeSpans, Span, Status,
+};
+use super::{
+    execution::{ExecutionSummary, TaskExecutionSummary},
+    task::TaskSummary,
+};
+use crate::opts::OtelOpts;
+
+const TRACES_PATH: &str = "/v1/traces";
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+// SPAN_KIND_INTERNAL
+const SPAN_KIND: i32 = 1;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to send trace: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("collector responded with {0}")]
+    Status(reqwest::StatusCode),
+}
+
+/// Sends a trace of the run to the configured collector
+pub async fn export(
+    opts: &OtelOpts,
+    turbo_version: &str,
+    execution: &ExecutionSummary<'_>,
+    tasks: &[TaskSummary],
+) -> Result<(), Error> {
+    let run = TracedRun::new(execution);
+    let tasks = tasks.iter().filter_map(TracedTask::new).collect::<Vec<_>>();
+    let request = trace_request(&opts.service_name, turbo_version, &run, &tasks);
+    send(opts, &request).await
+}
+
+async fn send(opts: &OtelOpts, request: &ExportTraceServiceRequest) -> Result<(), Error> {
+    let client = reqwest::Client::builder().timeout(EXPORT_TIMEOUT).build()?;
+    let mut request_builder = client
+        .post(traces_url(&opts.endpoint))
+        .header("Content-Type", "application/x-protobuf")
+        .body(prost::Message::encode_to_vec(request));
+    for (key, value) in &opts.headers {
+        request_builder = request_builder.header(key, value);
+    }
+
+    let response = request_builder.send().await?;
+    if !response.status().is_success() {
+        return Err(Error::Status(response.status()));
+    }
+    Ok(())
+}
+
+// Like other OTLP exporters, the endpoint is the base URL of the collector
+// unless it already points at the traces path
+fn traces_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with(TRACES_PATH) {
+        endpoint.to_string()
+    } else {
+        format!("{endpoint}{TRACES_PATH}")
+    }
+}
+
+/// The parts of a run summary that end up in the root span
+#[derive(Debug)]
+struct TracedRun<'a> {
+    command: &'a str,
+    exit_code: i32,
+    // Milliseconds since the epoch
+    start_time: i64,
+    end_time: i64,
+}
+
+impl<'a> TracedRun<'a> {
+    fn new(execution: &'a ExecutionSummary) -> Self {
+        Self {
+            command: &execution.command,
+            exit_code: execution.exit_code,
+            start_time: execution.start_time,
+            end_time: execution.end_time,
+        }
+    }
+}
+
+/// The parts of a task summary that end up in its span
+#[derive(Debug)]
+struct TracedTask<'a> {
+    task_id: String,
+    task: &'a str,
+    package: &'a str,
+    hash: &'a str,
+    cache_hit: bool,
+    execution: &'a TaskExecutionSummary,
+}
+
+impl<'a> TracedTask<'a> {
+    // Tasks that never ran, like those after a failure, have nothing to trace
+    fn new(task: &'a TaskSummary) -> Option<Self> {
+        Some(Self {
+            task_id: task.task_id.to_string(),
+            task: &task.task,
+            package: &task.package,
+            hash: &task.shared.hash,
+            cache_hit: task.shared.cache.is_hit(),
+            execution: task.shared.execution.as_ref()?,
+        })
+    }
+}
+
+fn trace_request(
+    service_name: &str,
+    turbo_version: &str,
+    run: &TracedRun,
+    tasks: &[TracedTask],
+) -> ExportTraceServiceRequest {
+    let trace_id = rand::random::<[u8; 16]>().to_vec();
+    let root_id = rand::random::<[u8; 8]>().to_vec();
+
+    let root = Span {
+        trace_id: trace_id.clone(),
+        span_id: root_id.clone(),
+        parent_span_id: Vec::new(),
+        name: "turbo run".to_string(),
+        kind: SPAN_KIND,
+        start_time_unix_nano: unix_nanos(run.start_time),
+        end_time_unix_nano: unix_nanos(run.end_time),
+        attributes: vec![
+            string_attribute("turbo.command", run.command),
+            int_attribute("turbo.exit_code", run.exit_code.into()),
+        ],
+        status: (run.exit_code != 0)
+            .then(|| error_status(format!("exited with code {}", run.exit_code))),
+    };
+
+    let task_spans = tasks.iter().map(|task| {
+        let mut attributes = vec![
+            string_attribute("turbo.task.id", &task.task_id),
+            string_attribute("turbo.task.name", task.task),
+            string_attribute("turbo.package", task.package),
+            string_attribute("turbo.task.hash", task.hash),
+            string_attribute(
+                "turbo.cache.status",
+                if task.cache_hit { "HIT" } else { "MISS" },
+            ),
+        ];
+        if let Some(exit_code) = task.execution.exit_code {
+            attributes.push(int_attribute("turbo.task.exit_code", exit_code.into()));
+        }
+        let status = task.execution.is_failure().then(|| {
+            error_status(
+                task.execution
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| format!("{} failed", task.task_id)),
+            )
+        });
+        Span {
+            trace_id: trace_id.clone(),
+            span_id: rand::random::<[u8; 8]>().to_vec(),
+            parent_span_id: root_id.clone(),
+            name: task.task_id.clone(),
+            kind: SPAN_KIND,
+            start_time_unix_nano: unix_nanos(task.execution.start_time),
+            end_time_unix_nano: unix_nanos(task.execution.end_time),
+            attributes,
+            status,
+        }
+    });
+
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![
+                    string_attribute("service.name", service_name),
+                    string_attribute("service.version", turbo_version),
+                ],
+            }),
+            scope_spans: vec![ScopeSpans {
+                scope: Some(InstrumentationScope {
+                    name: "turbo".to_string(),
+                    version: turbo_version.to_string(),
+                }),
+                spans: std::iter::once(root).chain(task_spans).collect(),
+            }],
+        }],
+    }
+}
+
+// Run summaries record times in milliseconds since the epoch
+fn unix_nanos(millis: i64) -> u64 {
+    u64::try_from(millis).unwrap_or_default() * 1_000_000
+}
+
+fn string_attribute(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value.to_string())),
+        }),
+    }
+}
+
+fn int_attribute(key: &str, value: i64) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::IntValue(value)),
+        }),
+    }
+}
+
+fn error_status(message: String) -> Status {
+    Status {
+        message,
+        code: status::STATUS_CODE_ERROR,
+    }
+}
+
+/// The subset of `opentelemetry/proto/collector/trace/v1` and the messages
+/// it depends on that turbo uses. Field numbers match the upstream schema.
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportTraceServiceRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub resource_spans: Vec<ResourceSpans>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResourceSpans {
+        #[prost(message, optional, tag = "1")]
+        pub resource: Option<Resource>,
+        #[prost(message, repeated, tag = "2")]
+        pub scope_spans: Vec<ScopeSpans>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Resource {
+        #[prost(message, repeated, tag = "1")]
+        pub attributes: Vec<KeyValue>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ScopeSpans {
+        #[prost(message, optional, tag = "1")]
+        pub scope: Option<InstrumentationScope>,
+        #[prost(message, repeated, tag = "2")]
+        pub spans: Vec<Span>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct InstrumentationScope {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub version: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Span {
+        #[prost(bytes = "vec", tag = "1")]
+        pub trace_id: Vec<u8>,
+        #[prost(bytes = "vec", tag = "2")]
+        pub span_id: Vec<u8>,
+        #[prost(bytes = "vec", tag = "4")]
+        pub parent_span_id: Vec<u8>,
+        #[prost(string, tag = "5")]
+        pub name: String,
+        #[prost(int32, tag = "6")]
+        pub kind: i32,
+        #[prost(fixed64, tag = "7")]
+        pub start_time_unix_nano: u64,
+        #[prost(fixed64, tag = "8")]
+        pub end_time_unix_nano: u64,
+        #[prost(message, repeated, tag = "9")]
+        pub attributes: Vec<KeyValue>,
+        #[prost(message, optional, tag = "15")]
+        pub status: Option<Status>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Status {
+        #[prost(string, tag = "2")]
+        pub message: String,
+        #[prost(int32, tag = "3")]
+        pub code: i32,
+    }
+
+    pub mod status {
+        pub const STATUS_CODE_ERROR: i32 = 2;
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct KeyValue {
+        #[prost(string, tag = "1")]
+        pub key: String,
+        #[prost(message, optional, tag = "2")]
+        pub value: Option<AnyValue>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AnyValue {
+        #[prost(oneof = "any_value::Value", tags = "1, 3")]
+        pub value: Option<any_value::Value>,
+    }
+
+    pub mod any_value {
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Value {
+            #[prost(string, tag = "1")]
+            StringValue(String),
+            #[prost(int64, tag = "3")]
+            IntValue(i64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::TcpListener;
+
+    use axum::{
+        body::Bytes,
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use prost::Message;
+    use test_case::test_case;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn execution(exit_code: Option<i32>) -> TaskExecutionSummary {
+        TaskExecutionSummary {
+            start_time: 1_700_000_000_000,
+            end_time: 1_700_000_001_500,
+            error: None,
+            exit_code,
+            attempts: 1,
+            timed_out: false,
+            resources: None,
+        }
+    }
+
+    fn task<'a>(package: &'a str, execution: &'a TaskExecutionSummary) -> TracedTask<'a> {
+        TracedTask {
+            task_id: format!("{package}#build"),
+            task: "build",
+            package,
+            hash: "abc123",
+            cache_hit: package == "docs",
+            execution,
+        }
+    }
+
+    fn attribute(attributes: &[KeyValue], key: &str) -> Option<any_value::Value> {
+        attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .and_then(|attribute| attribute.value.clone()?.value)
+    }
+
+    fn string(value: &str) -> Option<any_value::Value> {
+        Some(any_value::Value::StringValue(value.to_string()))
+    }
+
+    fn request() -> ExportTraceServiceRequest {
+        let run = TracedRun {
+            command: "turbo run build",
+            exit_code: 1,
+            start_time: 1_700_000_000_000,
+            end_time: 1_700_000_002_000,
+        };
+        let cached = execution(Some(0));
+        let failed = execution(Some(1));
+        let tasks = [task("docs", &cached), task("web", &failed)];
+        trace_request("monorepo", "1.2.3", &run, &tasks)
+    }
+
+    #[test_case("http://localhost:4318", "http://localhost:4318/v1/traces" ; "base url")]
+    #[test_case("http://localhost:4318/", "http://localhost:4318/v1/traces" ; "trailing slash")]
+    #[test_case("https://otel.example.com/v1/traces", "https://otel.example.com/v1/traces" ; "traces url")]
+    fn test_traces_url(endpoint: &str, expected: &str) {
+        assert_eq!(traces_url(endpoint), expected);
+    }
+
+    #[test]
+    fn test_trace_request() {
+        let request = request();
+        let resource_spans = &request.resource_spans[0];
+        let resource = resource_spans.resource.as_ref().unwrap();
+        assert_eq!(
+            attribute(&resource.attributes, "service.name"),
+            string("monorepo")
+        );
+        assert_eq!(
+            attribute(&resource.attributes, "service.version"),
+            string("1.2.3")
+        );
+
+        let [root, docs, web] = resource_spans.scope_spans[0].spans.as_slice() else {
+            panic!("expected a root span and a span per task");
+        };
+        assert_eq!(root.name, "turbo run");
+        assert!(root.parent_span_id.is_empty());
+        assert_eq!(root.trace_id.len(), 16);
+        assert_eq!(root.start_time_unix_nano, 1_700_000_000_000_000_000);
+        assert_eq!(
+            attribute(&root.attributes, "turbo.command"),
+            string("turbo run build")
+        );
+        assert_eq!(
+            root.status.as_ref().map(|status| status.code),
+            Some(status::STATUS_CODE_ERROR)
+        );
+
+        for span in [docs, web] {
+            assert_eq!(span.trace_id, root.trace_id);
+            assert_eq!(span.parent_span_id, root.span_id);
+            assert_eq!(span.span_id.len(), 8);
+            assert_eq!(span.end_time_unix_nano, 1_700_000_001_500_000_000);
+            assert_eq!(
+                attribute(&span.attributes, "turbo.task.hash"),
+                string("abc123")
+            );
+        }
+        assert_eq!(docs.name, "docs#build");
+        assert_eq!(attribute(&docs.attributes, "turbo.package"), string("docs"));
+        assert_eq!(
+            attribute(&docs.attributes, "turbo.cache.status"),
+            string("HIT")
+        );
+        assert_eq!(docs.status, None);
+        assert_eq!(
+            attribute(&web.attributes, "turbo.cache.status"),
+            string("MISS")
+        );
+        assert_eq!(
+            attribute(&web.attributes, "turbo.task.exit_code"),
+            Some(any_value::Value::IntValue(1))
+        );
+        assert_eq!(
+            web.status.as_ref().map(|status| status.message.as_str()),
+            Some("web#build failed")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_to_collector() {
+        // A stand-in for an OTLP collector that hands back what it receives
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let collector = Router::new()
+            .route(
+                "/v1/traces",
+                post(
+                    |State(tx): State<mpsc::UnboundedSender<(HeaderMap, Bytes)>>,
+                     headers: HeaderMap,
+                     body: Bytes| async move {
+                        tx.send((headers, body)).expect("test is listening");
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .with_state(tx);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(collector.into_make_service()),
+        );
+
+        let opts = OtelOpts {
+            endpoint: format!("http://{addr}"),
+            service_name: "monorepo".to_string(),
+            headers: vec![("x-api-key".to_string(), "secret".to_string())],
+        };
+        let request = request();
+        send(&opts, &request).await.unwrap();
+
+        let (headers, body) = rx.recv().await.unwrap();
+        assert_eq!(headers["content-type"], "application/x-protobuf");
+        assert_eq!(headers["x-api-key"], "secret");
+        assert_eq!(ExportTraceServiceRequest::decode(body).unwrap(), request);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_export_rejected() {
+        let collector =
+            Router::new().route("/v1/traces", post(|| async { StatusCode::UNAUTHORIZED }));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(collector.into_make_service()),
+        );
+
+        let opts = OtelOpts {
+            endpoint: format!("http://{addr}"),
+            service_name: "turbo".to_string(),
+            headers: vec![],
+        };
+        let result = send(&opts, &request()).await;
+        assert!(matches!(
+            result,
+            Err(Error::Status(StatusCode::UNAUTHORIZED))
+        ));
+
+        server.abort();
+    }
+}
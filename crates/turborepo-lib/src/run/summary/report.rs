@@ -0,0 +1,366 @@
+//! Reports rendered from a run summary for CI systems: JUnit XML, which most
+//! CI providers display as test results, and Markdown suitable for GitHub
+//! Actions job summaries.
+use std::fmt::Write;
+
+use itertools::Itertools;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
+
+use super::task::TaskSummary;
+
+// Number of lines from the end of a failed task's log included in reports
+const LOG_TAIL_LINES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success,
+    Failed(Option<i32>),
+    // The task never ran, usually because a dependency failed
+    NotRun,
+}
+
+/// The parts of a task summary that end up in reports
+#[derive(Debug)]
+pub(crate) struct ReportTask {
+    task_id: String,
+    package: String,
+    task: String,
+    hash: String,
+    cache_hit: bool,
+    outcome: Outcome,
+    // Seconds
+    duration: f64,
+    log_file: Option<AbsoluteSystemPathBuf>,
+}
+
+impl ReportTask {
+    pub fn new(repo_root: &AbsoluteSystemPath, task: &TaskSummary) -> Self {
+        let outcome = match &task.shared.execution {
+            None => Outcome::NotRun,
+            Some(execution) if execution.is_failure() => Outcome::Failed(execution.exit_code),
+            Some(_) => Outcome::Success,
+        };
+        let duration = task.shared.execution.as_ref().map_or(0.0, |execution| {
+            (execution.end_time - execution.start_time) as f64 / 1000.0
+        });
+        let log_file = AnchoredSystemPath::new(&task.shared.log_file)
+            .ok()
+            .map(|log_file| repo_root.resolve(log_file));
+        Self {
+            task_id: task.task_id.to_string(),
+            package: task.package.clone(),
+            task: task.task.clone(),
+            hash: task.shared.hash.clone(),
+            cache_hit: task.shared.cache.is_hit(),
+            outcome,
+            duration,
+            log_file,
+        }
+    }
+
+    fn is_failure(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed(_))
+    }
+
+    fn is_skipped(&self) -> bool {
+        matches!(self.outcome, Outcome::NotRun)
+    }
+
+    fn cache_status(&self) -> &'static str {
+        match self.cache_hit {
+            true => "HIT",
+            false => "MISS",
+        }
+    }
+
+    fn failure_message(&self) -> String {
+        match self.outcome {
+            Outcome::Failed(Some(exit_code)) => {
+                format!("{} exited with code {exit_code}", self.task_id)
+            }
+            _ => format!("{} failed to run", self.task_id),
+        }
+    }
+
+    fn log_tail(&self) -> String {
+        self.log_file
+            .as_ref()
+            .and_then(|log_file| log_file.read_to_string().ok())
+            .map(|log| tail(&log))
+            .unwrap_or_default()
+    }
+}
+
+/// Renders tasks as JUnit test cases grouped into a test suite per package.
+/// Failures include the end of the task's log, and every test case links
+/// to its full log as an attachment.
+pub(crate) fn junit(command: &str, tasks: &[ReportTask]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    // Writing to a String can't fail
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"{}\" {}>",
+        escape_xml(command),
+        suite_attributes(tasks.iter())
+    );
+
+    let packages = tasks
+        .iter()
+        .into_group_map_by(|task| task.package.as_str())
+        .into_iter()
+        .sorted_by_key(|(package, _)| *package);
+    for (package, package_tasks) in packages {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" {}>",
+            escape_xml(package),
+            suite_attributes(package_tasks.iter().copied())
+        );
+        for task in package_tasks {
+            write_test_case(&mut xml, task);
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn suite_attributes<'a>(tasks: impl Iterator<Item = &'a ReportTask> + Clone) -> String {
+    format!(
+        "tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\"",
+        tasks.clone().count(),
+        tasks.clone().filter(|task| task.is_failure()).count(),
+        tasks.clone().filter(|task| task.is_skipped()).count(),
+        tasks.map(|task| task.duration).sum::<f64>()
+    )
+}
+
+fn write_test_case(xml: &mut String, task: &ReportTask) {
+    let _ = writeln!(
+        xml,
+        "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">",
+        escape_xml(&task.package),
+        escape_xml(&task.task),
+        task.duration,
+    );
+    let _ = writeln!(
+        xml,
+        "      <properties><property name=\"hash\" value=\"{}\"/><property name=\"cache\" \
+         value=\"{}\"/></properties>",
+        escape_xml(&task.hash),
+        task.cache_status(),
+    );
+    match task.outcome {
+        Outcome::Failed(_) => {
+            let _ = writeln!(
+                xml,
+                "      <failure message=\"{}\">{}</failure>",
+                escape_xml(&task.failure_message()),
+                escape_xml(&task.log_tail()),
+            );
+        }
+        Outcome::NotRun => xml.push_str("      <skipped message=\"task did not run\"/>\n"),
+        Outcome::Success => (),
+    }
+    // Jenkins and GitLab attach files referenced with this marker
+    if let Some(log_file) = task.log_file.as_ref().filter(|log_file| log_file.exists()) {
+        let _ = writeln!(
+            xml,
+            "      <system-out>[[ATTACHMENT|{}]]</system-out>",
+            escape_xml(log_file.as_str())
+        );
+    }
+    xml.push_str("    </testcase>\n");
+}
+
+/// Renders a table of every task and its outcome, followed by the end of
+/// the logs of failed tasks.
+pub(crate) fn markdown(command: &str, tasks: &[ReportTask]) -> String {
+    let failed = tasks.iter().filter(|task| task.is_failure()).count();
+    let skipped = tasks.iter().filter(|task| task.is_skipped()).count();
+    let cached = tasks.iter().filter(|task| task.cache_hit).count();
+
+    let mut markdown = String::new();
+    let _ = writeln!(markdown, "### `{command}`\n");
+    let _ = writeln!(
+        markdown,
+        "{} successful, {failed} failed, {skipped} not run, {cached} cached, {} total\n",
+        tasks.len() - failed - skipped,
+        tasks.len(),
+    );
+    markdown.push_str("| Task | Status | Cache | Duration |\n");
+    markdown.push_str("| --- | --- | --- | --- |\n");
+    for task in tasks {
+        let (status, duration) = match task.outcome {
+            Outcome::Success => ("Success".to_string(), format!("{:.2}s", task.duration)),
+            Outcome::Failed(Some(exit_code)) => (
+                format!("**Failed** (exit code {exit_code})"),
+                format!("{:.2}s", task.duration),
+            ),
+            Outcome::Failed(None) => ("**Failed**".to_string(), format!("{:.2}s", task.duration)),
+            Outcome::NotRun => ("Not run".to_string(), "-".to_string()),
+        };
+        let _ = writeln!(
+            markdown,
+            "| `{}` | {status} | {} | {duration} |",
+            task.task_id,
+            task.cache_status()
+        );
+    }
+
+    for task in tasks.iter().filter(|task| task.is_failure()) {
+        let _ = write!(
+            markdown,
+            "\n<details><summary><code>{}</code> logs</summary>\n\n````\n{}\n````\n\n</details>\n",
+            task.task_id,
+            task.log_tail()
+        );
+    }
+
+    markdown
+}
+
+fn tail(log: &str) -> String {
+    let log = console::strip_ansi_codes(log);
+    let lines = log.lines().collect::<Vec<_>>();
+    lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].join("\n")
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Other control characters aren't allowed in XML 1.0
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn task(
+        package: &str,
+        name: &str,
+        outcome: Outcome,
+        log_file: Option<&AbsoluteSystemPath>,
+    ) -> ReportTask {
+        ReportTask {
+            task_id: format!("{package}#{name}"),
+            package: package.to_string(),
+            task: name.to_string(),
+            hash: format!("{package}-hash"),
+            cache_hit: false,
+            outcome,
+            duration: 1.5,
+            log_file: log_file.map(|log_file| log_file.to_owned()),
+        }
+    }
+
+    fn tasks() -> (TempDir, Vec<ReportTask>) {
+        let dir = tempfile::tempdir().unwrap();
+        let log_file = AbsoluteSystemPathBuf::try_from(dir.path())
+            .unwrap()
+            .join_component("turbo-build.log");
+        log_file
+            .create_with_contents("\u{1b}[31mbuild <failed>\u{1b}[0m\n")
+            .unwrap();
+
+        let mut cached = task("docs", "build", Outcome::Success, None);
+        cached.cache_hit = true;
+        let tasks = vec![
+            cached,
+            task("web", "build", Outcome::Failed(Some(1)), Some(&log_file)),
+            task("web", "test", Outcome::NotRun, None),
+        ];
+        (dir, tasks)
+    }
+
+    #[test]
+    fn test_junit() {
+        let (_dir, tasks) = tasks();
+        let xml = junit("turbo run build test", &tasks);
+        let log_file = tasks[1].log_file.as_ref().unwrap();
+        assert_eq!(
+            xml,
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="turbo run build test" tests="3" failures="1" skipped="1" time="4.500">
+  <testsuite name="docs" tests="1" failures="0" skipped="0" time="1.500">
+    <testcase classname="docs" name="build" time="1.500">
+      <properties><property name="hash" value="docs-hash"/><property name="cache" value="HIT"/></properties>
+    </testcase>
+  </testsuite>
+  <testsuite name="web" tests="2" failures="1" skipped="1" time="3.000">
+    <testcase classname="web" name="build" time="1.500">
+      <properties><property name="hash" value="web-hash"/><property name="cache" value="MISS"/></properties>
+      <failure message="web#build exited with code 1">build &lt;failed&gt;</failure>
+      <system-out>[[ATTACHMENT|{log_file}]]</system-out>
+    </testcase>
+    <testcase classname="web" name="test" time="1.500">
+      <properties><property name="hash" value="web-hash"/><property name="cache" value="MISS"/></properties>
+      <skipped message="task did not run"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        let (_dir, tasks) = tasks();
+        assert_eq!(
+            markdown("turbo run build test", &tasks),
+            r#"### `turbo run build test`
+
+1 successful, 1 failed, 1 not run, 1 cached, 3 total
+
+| Task | Status | Cache | Duration |
+| --- | --- | --- | --- |
+| `docs#build` | Success | HIT | 1.50s |
+| `web#build` | **Failed** (exit code 1) | MISS | 1.50s |
+| `web#test` | Not run | MISS | - |
+
+<details><summary><code>web#build</code> logs</summary>
+
+````
+build <failed>
+````
+
+</details>
+"#
+        );
+    }
+
+    #[test_case("a < b & \"c\"", "a &lt; b &amp; &quot;c&quot;" ; "special characters")]
+    #[test_case("\u{1b}[31mred\u{1b}[0m\n", "[31mred[0m\n" ; "control characters")]
+    fn test_escape_xml(input: &str, expected: &str) {
+        assert_eq!(escape_xml(input), expected);
+    }
+
+    #[test]
+    fn test_tail_truncates() {
+        let log = (0..LOG_TAIL_LINES + 10)
+            .map(|i| format!("line {i}"))
+            .join("\n");
+        let tail = tail(&log);
+        assert_eq!(tail.lines().count(), LOG_TAIL_LINES);
+        assert!(tail.starts_with("line 10\n"));
+    }
+}
@@ -102,9 +102,22 @@ pub struct TaskSummaryTaskDefinition {
     inputs: Vec<String>,
     output_mode: OutputLogsMode,
     persistent: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    interactive: bool,
     env: Vec<String>,
     pass_through_env: Option<Vec<String>>,
     dot_env: Option<Vec<RelativeUnixPathBuf>>,
+    // Timeout in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+    #[serde(skip_serializing_if = "is_zero")]
+    retries: u32,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    resources: BTreeMap<String, u32>,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -115,6 +128,9 @@ pub struct TaskEnvVarSummary {
     pub inferred: Vec<String>,
     #[serde(rename = "passthrough")]
     pub pass_through: Option<Vec<String>>,
+    // Variables loaded from `dotEnv` and `globalDotEnv` files
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dot_env: Vec<String>,
 }
 
 impl TaskCacheSummary {
@@ -127,6 +143,10 @@ impl TaskCacheSummary {
             source: None,
         }
     }
+
+    pub fn is_hit(&self) -> bool {
+        matches!(self.status, CacheStatus::Hit)
+    }
 }
 
 impl From<Option<CacheHitMetadata>> for TaskCacheSummary {
@@ -172,6 +192,7 @@ impl TaskEnvVarSummary {
     pub fn new(
         task_definition: &TaskDefinition,
         env_vars: DetailedMap,
+        dot_env_vars: Option<EnvironmentVariableMap>,
         env_at_execution_start: &EnvironmentVariableMap,
     ) -> Result<Self, turborepo_env::Error> {
         // TODO: this operation differs from the actual env that gets passed in during
@@ -195,6 +216,7 @@ impl TaskEnvVarSummary {
             configured: env_vars.by_source.explicit.to_secret_hashable(),
             inferred: env_vars.by_source.matching.to_secret_hashable(),
             pass_through,
+            dot_env: dot_env_vars.unwrap_or_default().to_secret_hashable(),
         })
     }
 }
@@ -282,6 +304,10 @@ impl From<TaskDefinition> for TaskSummaryTaskDefinition {
             mut inputs,
             output_mode,
             persistent,
+            interactive,
+            timeout,
+            retries,
+            resources,
         } = value;
 
         let mut outputs = inclusions;
@@ -313,10 +339,14 @@ impl From<TaskDefinition> for TaskSummaryTaskDefinition {
             inputs,
             output_mode,
             persistent,
+            interactive,
             env,
             pass_through_env,
             // This should _not_ be sorted.
             dot_env,
+            timeout: timeout.map(|timeout| timeout.as_secs()),
+            retries,
+            resources,
         }
     }
 }
@@ -378,6 +408,29 @@ mod test {
         })
         ; "resolved task definition"
     )]
+    #[test_case(
+        TaskSummaryTaskDefinition {
+            timeout: Some(300),
+            retries: 2,
+            interactive: true,
+            ..Default::default()
+        },
+        json!({
+            "outputs": [],
+            "cache": false,
+            "dependsOn": [],
+            "inputs": [],
+            "outputMode": "full",
+            "persistent": false,
+            "interactive": true,
+            "env": [],
+            "passThroughEnv": null,
+            "dotEnv": null,
+            "timeout": 300,
+            "retries": 2,
+        })
+        ; "task definition with timeout, retries and interactive"
+    )]
     fn test_serialization(value: impl serde::Serialize, expected: serde_json::Value) {
         assert_eq!(serde_json::to_value(value).unwrap(), expected);
     }
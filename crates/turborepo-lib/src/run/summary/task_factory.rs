@@ -194,6 +194,7 @@ impl<'a> TaskSummaryFactory<'a> {
             environment_variables: TaskEnvVarSummary::new(
                 task_definition,
                 env_vars,
+                self.hash_tracker.dot_env_vars(task_id),
                 self.env_at_start,
             )
             .expect("invalid glob in task definition should have been caught earlier"),
@@ -18,7 +18,7 @@ use crate::{
 
 // Environment variable key that will be used to enable, and set the expected
 // trace location
-const TASK_ACCESS_ENV_KEY: &str = "TURBOREPO_TRACE_FILE";
+pub(crate) const TASK_ACCESS_ENV_KEY: &str = "TURBOREPO_TRACE_FILE";
 /// File name where the task is expected to leave a trace result
 const TASK_ACCESS_TRACE_NAME: &str = "trace.json";
 // Path to the config file that will be used to store the trace results
@@ -143,10 +143,18 @@ pub struct TaskAccess {
     trace_by_task: Arc<Mutex<HashMap<String, TaskAccessTraceFile>>>,
     config_cache: Option<ConfigCache>,
     enabled: bool,
+    // Traces are also requested when auditing task inputs, without enabling
+    // automatic caching
+    audit_inputs: bool,
 }
 
 impl TaskAccess {
-    pub fn new(repo_root: AbsoluteSystemPathBuf, cache: AsyncCache, scm: &SCM) -> Self {
+    pub fn new(
+        repo_root: AbsoluteSystemPathBuf,
+        cache: AsyncCache,
+        scm: &SCM,
+        audit_inputs: bool,
+    ) -> Self {
         let root = repo_root.clone();
         let enabled = task_access_trace_enabled(&root).unwrap_or(false);
         let trace_by_task = Arc::new(Mutex::new(HashMap::<String, TaskAccessTraceFile>::new()));
@@ -180,6 +188,7 @@ impl TaskAccess {
             trace_by_task,
             enabled,
             config_cache,
+            audit_inputs,
         }
     }
 
@@ -187,6 +196,11 @@ impl TaskAccess {
         self.enabled
     }
 
+    /// Whether tasks should be asked to write a trace file
+    pub fn is_tracing(&self) -> bool {
+        self.enabled || self.audit_inputs
+    }
+
     pub async fn restore_config(&self) {
         match (self.enabled, &self.config_cache) {
             (true, Some(config_cache)) => match config_cache.restore().await {
@@ -0,0 +1,603 @@
+//! `turbo watch` keeps the repository's task graph alive and re-runs tasks in
+//! affected packages whenever files change.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    time::Duration,
+};
+
+use futures::{future::LocalBoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
+use itertools::Itertools;
+use miette::Diagnostic;
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+};
+use turborepo_api_client::APIClient;
+use turborepo_filewatch::{
+    ignore_matcher::GitignoreMatcher,
+    notify::{Event, EventKind},
+    FileSystemWatcher, NotifyError, WatchError,
+};
+use turborepo_repository::{
+    change_mapper::{ChangeMapError, ChangeMapper, LockfileChange, PackageChanges},
+    package_graph::{PackageGraph, PackageName, PackageNode},
+};
+use turborepo_telemetry::events::{command::CommandEventBuilder, EventBuilder};
+use turborepo_ui::{cprintln, GREY};
+
+use crate::{
+    cli::Command,
+    commands::CommandBase,
+    run::{self, Run},
+    signal::SignalHandler,
+};
+
+/// How long the filesystem needs to be quiet before a batch of changes is
+/// acted on. Editors and package managers tend to write several files at
+/// once, and we only want to re-run once for all of them.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Paths that never trigger a re-run, regardless of `.gitignore`.
+const ALWAYS_IGNORED: [&str; 3] = [".git", ".turbo", "node_modules"];
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum Error {
+    #[error("--dry-run is not supported in watch mode")]
+    DryRun,
+    #[error("--graph is not supported in watch mode")]
+    Graph,
+    #[error("failed to start file watcher: {0}")]
+    FileWatching(#[from] WatchError),
+    #[error("file watcher stopped unexpectedly")]
+    WatcherClosed,
+    #[error("failed to map changed files to packages: {0}")]
+    ChangeMap(#[from] ChangeMapError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Run(#[from] run::Error),
+}
+
+/// A batch of files that changed while watching.
+#[derive(Debug, PartialEq)]
+enum ChangedFiles {
+    /// We missed events and can't know what changed
+    All,
+    Some(HashSet<AnchoredSystemPathBuf>),
+}
+
+/// A run that was started in response to a change, along with the packages it
+/// is executing tasks in.
+struct Iteration {
+    packages: HashSet<PackageName>,
+    handler: SignalHandler,
+}
+
+type IterationFuture = LocalBoxFuture<'static, (usize, Result<i32, run::Error>)>;
+
+pub struct WatchClient {
+    base: CommandBase,
+    telemetry: CommandEventBuilder,
+    iterations: HashMap<usize, Iteration>,
+    running: FuturesUnordered<IterationFuture>,
+    next_id: usize,
+}
+
+impl WatchClient {
+    pub fn new(base: CommandBase, telemetry: CommandEventBuilder) -> Result<Self, Error> {
+        if let Some(Command::Watch(run_args)) = &base.args().command {
+            if run_args.dry_run.is_some() {
+                return Err(Error::DryRun);
+            }
+            if run_args.graph.is_some() {
+                return Err(Error::Graph);
+            }
+        }
+
+        Ok(Self {
+            base,
+            telemetry,
+            iterations: HashMap::new(),
+            running: FuturesUnordered::new(),
+            next_id: 0,
+        })
+    }
+
+    /// Run the tasks once and then re-run them for affected packages on every
+    /// change until `signal_handler` fires.
+    pub async fn start(mut self, signal_handler: &SignalHandler) -> Result<i32, Error> {
+        let Some(subscriber) = signal_handler.subscribe() else {
+            // We're already shutting down
+            return Ok(0);
+        };
+        let shutdown = subscriber.listen();
+        tokio::pin!(shutdown);
+
+        let repo_root = self.base.repo_root.clone();
+        // We use our own cookie directory so we don't race with the daemon, which
+        // clears `.turbo/cookies` on startup.
+        let watcher = FileSystemWatcher::new(
+            &repo_root,
+            repo_root.join_components(&[".turbo", "watch-cookies"]),
+        )?;
+        let events = watcher
+            .subscribe()
+            .await
+            .map_err(|_| Error::WatcherClosed)?;
+        let mut changes = debounce_events(repo_root.clone(), events);
+
+        let (run, _) = self.new_run()?;
+        let mut scope = run.resolve_scope().await?;
+        let mut lockfile_contents = read_lockfile(&repo_root, &scope.pkg_dep_graph);
+
+        self.start_iteration(scope.filtered_pkgs.clone())?;
+
+        loop {
+            tokio::select! {
+                biased;
+                guard = &mut shutdown => {
+                    self.stop_iterations(|_| true).await;
+                    drop(guard);
+                    return Ok(0);
+                }
+                Some((id, result)) = self.running.next() => {
+                    // Iterations that we stopped have already been removed
+                    if self.iterations.remove(&id).is_some() {
+                        match result {
+                            Ok(0) => {}
+                            Ok(code) => error!("run failed: command exited ({code})"),
+                            Err(err) => error!("run failed: {err}"),
+                        }
+                        cprintln!(self.base.ui, GREY, "• Waiting for changes...");
+                    }
+                }
+                changed = changes.recv() => {
+                    let Some(changed) = changed else {
+                        return Err(Error::WatcherClosed);
+                    };
+
+                    let lockfile_path = scope
+                        .pkg_dep_graph
+                        .package_manager()
+                        .lockfile_path(&repo_root);
+                    let (changes, reload_graph) = match changed {
+                        ChangedFiles::All => (PackageChanges::All, true),
+                        ChangedFiles::Some(files) => {
+                            let lockfile_change =
+                                ChangeMapper::lockfile_changed(&repo_root, &files, &lockfile_path)
+                                    .then(|| match lockfile_contents.take() {
+                                        Some(contents) => LockfileChange::WithContent(contents),
+                                        None => LockfileChange::Empty,
+                                    });
+                            // Dependency changes can alter the shape of the package graph
+                            let reload_graph = lockfile_change.is_some()
+                                || files.iter().any(|file| {
+                                    file.as_path().file_name()
+                                        == Some(OsStr::new("package.json"))
+                                });
+                            let changes = ChangeMapper::new(
+                                &scope.pkg_dep_graph,
+                                scope.global_deps.clone(),
+                                Vec::new(),
+                            )
+                            .changed_packages(files, lockfile_change)?;
+                            (changes, reload_graph)
+                        }
+                    };
+
+                    if reload_graph || matches!(changes, PackageChanges::All) {
+                        // Re-resolve what's in scope. If the repository is in a broken state
+                        // (e.g. a half-written package.json) we keep the previous scope and
+                        // try again on the next change.
+                        match self.new_run() {
+                            Ok((run, _)) => match run.resolve_scope().await {
+                                Ok(new_scope) => scope = new_scope,
+                                Err(err) => error!("failed to reload packages: {err}"),
+                            },
+                            Err(err) => error!("failed to reload packages: {err}"),
+                        }
+                        lockfile_contents = read_lockfile(&repo_root, &scope.pkg_dep_graph);
+                    }
+
+                    let affected =
+                        affected_packages(&scope.pkg_dep_graph, changes, &scope.filtered_pkgs);
+                    if affected.is_empty() {
+                        debug!("no packages in scope affected by changes");
+                        continue;
+                    }
+                    cprintln!(
+                        self.base.ui,
+                        GREY,
+                        "• Changes detected in {}, re-running",
+                        affected.iter().map(|pkg| pkg.to_string()).sorted().join(", ")
+                    );
+
+                    // Any run that touches an affected package needs to be restarted, which
+                    // also restarts its persistent tasks. Runs in unaffected packages are
+                    // left alone.
+                    let mut packages = affected.clone();
+                    let stopped = self
+                        .stop_iterations(|iteration| !iteration.packages.is_disjoint(&affected))
+                        .await;
+                    packages.extend(stopped);
+                    self.start_iteration(packages)?;
+                }
+            }
+        }
+    }
+
+    fn new_run(&self) -> Result<(Run, APIClient), run::Error> {
+        let base = CommandBase::new(
+            self.base.args().clone(),
+            self.base.repo_root.clone(),
+            self.base.version(),
+            self.base.ui,
+        );
+        let api_auth = base.api_auth()?;
+        let api_client = base.api_client()?;
+        Ok((Run::new(base, api_auth)?, api_client))
+    }
+
+    fn start_iteration(&mut self, packages: HashSet<PackageName>) -> Result<(), Error> {
+        let (run, api_client) = self.new_run()?;
+        let run = run.with_packages(packages.clone());
+        // Each iteration gets its own handler so it can be stopped without
+        // affecting the others. It only fires when we close it.
+        let handler = SignalHandler::new(futures::future::pending());
+        let telemetry = self.telemetry.child();
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.iterations.insert(
+            id,
+            Iteration {
+                packages,
+                handler: handler.clone(),
+            },
+        );
+        self.running.push(
+            async move {
+                let result = tokio::select! {
+                    biased;
+                    _ = handler.done() => Ok(1),
+                    result = run.run(&handler, telemetry, api_client) => {
+                        handler.close().await;
+                        result
+                    }
+                };
+                (id, result)
+            }
+            .boxed_local(),
+        );
+        Ok(())
+    }
+
+    /// Stops all iterations matching `predicate` and waits for them to exit.
+    /// Returns the packages the stopped iterations were running in.
+    async fn stop_iterations(
+        &mut self,
+        predicate: impl Fn(&Iteration) -> bool,
+    ) -> HashSet<PackageName> {
+        let ids = self
+            .iterations
+            .iter()
+            .filter(|(_, iteration)| predicate(iteration))
+            .map(|(id, _)| *id)
+            .collect::<HashSet<_>>();
+
+        let mut packages = HashSet::new();
+        for id in &ids {
+            let iteration = self
+                .iterations
+                .remove(id)
+                .expect("iteration ids were just collected");
+            iteration.handler.close().await;
+            packages.extend(iteration.packages);
+        }
+
+        // Closing the handler stops the child processes, but we also wait for the
+        // runs themselves to finish so they don't race with their replacements.
+        let mut remaining = ids;
+        while !remaining.is_empty() {
+            let Some((id, result)) = self.running.next().await else {
+                break;
+            };
+            if !remaining.remove(&id) && self.iterations.remove(&id).is_some() {
+                // An unrelated run happened to finish while we were waiting
+                if let Err(err) = result {
+                    error!("run failed: {err}");
+                }
+            }
+        }
+
+        packages
+    }
+}
+
+fn read_lockfile(repo_root: &AbsoluteSystemPath, pkg_graph: &PackageGraph) -> Option<Vec<u8>> {
+    pkg_graph
+        .package_manager()
+        .lockfile_path(repo_root)
+        .read()
+        .ok()
+}
+
+/// Computes the packages in `scope` that need to be re-run: the changed
+/// packages themselves and everything that depends on them.
+fn affected_packages(
+    pkg_graph: &PackageGraph,
+    changes: PackageChanges,
+    scope: &HashSet<PackageName>,
+) -> HashSet<PackageName> {
+    let PackageChanges::Some(changed) = changes else {
+        return scope.clone();
+    };
+
+    let mut affected = HashSet::new();
+    for package in changed {
+        let node = PackageNode::Workspace(package.name.clone());
+        affected.extend(
+            pkg_graph
+                .ancestors(&node)
+                .into_iter()
+                .map(|node| node.as_package_name().clone()),
+        );
+        affected.insert(package.name);
+    }
+    affected.retain(|package| scope.contains(package));
+    affected
+}
+
+/// Collects file events into batches, only sending a batch once the
+/// filesystem has been quiet for `DEBOUNCE`.
+fn debounce_events(
+    repo_root: AbsoluteSystemPathBuf,
+    mut events: broadcast::Receiver<Result<Event, NotifyError>>,
+) -> mpsc::Receiver<ChangedFiles> {
+    let (tx, rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        let mut ignore = IgnoreMatcher::new(repo_root.clone());
+        loop {
+            let mut changed = HashSet::new();
+            let mut missed_events = false;
+
+            let mut next = Some(events.recv().await);
+            while let Some(event) = next {
+                match event {
+                    Ok(Ok(event)) => {
+                        if !matches!(event.kind, EventKind::Access(_)) {
+                            changed.extend(ignore.filter_event(&event));
+                        }
+                    }
+                    Ok(Err(err)) => {
+                        debug!("file watching error: {err}");
+                        missed_events = true;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => missed_events = true,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+                next = tokio::time::timeout(DEBOUNCE, events.recv()).await.ok();
+            }
+
+            let batch = if missed_events {
+                ChangedFiles::All
+            } else if changed.is_empty() {
+                continue;
+            } else {
+                ChangedFiles::Some(changed)
+            };
+            if tx.send(batch).await.is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+/// Filters out paths that shouldn't trigger a re-run: version control and
+/// turbo's own state, installed dependencies, and anything gitignored, which
+/// covers the outputs of most tasks.
+struct IgnoreMatcher {
+    repo_root: AbsoluteSystemPathBuf,
+    gitignore: GitignoreMatcher,
+}
+
+impl IgnoreMatcher {
+    fn new(repo_root: AbsoluteSystemPathBuf) -> Self {
+        Self {
+            gitignore: GitignoreMatcher::new(repo_root.clone()),
+            repo_root,
+        }
+    }
+
+    fn filter_event(&mut self, event: &Event) -> Vec<AnchoredSystemPathBuf> {
+        event
+            .paths
+            .iter()
+            .filter_map(|path| {
+                let path = AbsoluteSystemPathBuf::try_from(path.as_path()).ok()?;
+                let anchored = self.repo_root.anchor(path).ok()?;
+                (!anchored.as_str().is_empty() && !self.is_ignored(&anchored)).then_some(anchored)
+            })
+            .collect()
+    }
+
+    fn is_ignored(&mut self, path: &AnchoredSystemPath) -> bool {
+        path.components()
+            .any(|component| ALWAYS_IGNORED.contains(&component.as_str()))
+            || self.gitignore.is_ignored(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashSet, fs};
+
+    use test_case::test_case;
+    use tokio::sync::broadcast;
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+    use turborepo_filewatch::{
+        notify::{
+            self,
+            event::{AccessKind, ModifyKind},
+            Event, EventKind,
+        },
+        NotifyError,
+    };
+    use turborepo_repository::{
+        change_mapper::PackageChanges,
+        package_graph::{PackageName, WorkspacePackage},
+    };
+
+    use super::{affected_packages, debounce_events, ChangedFiles, IgnoreMatcher, DEBOUNCE};
+    use crate::test_utils::{mock_package_graph, mock_repo_root, package_jsons};
+
+    fn names(names: &[&str]) -> HashSet<PackageName> {
+        names.iter().map(|name| PackageName::from(*name)).collect()
+    }
+
+    // a depends on b which depends on c, d stands alone
+    #[test_case(&["c"], &["a", "b", "c", "d"], &["a", "b", "c"] ; "dependents")]
+    #[test_case(&["a"], &["a", "b", "c", "d"], &["a"] ; "no dependents")]
+    #[test_case(&["c", "d"], &["a", "b", "c", "d"], &["a", "b", "c", "d"] ; "multiple changes")]
+    #[test_case(&["c"], &["b", "d"], &["b"] ; "filtered dependents")]
+    #[test_case(&["d"], &["a", "b"], &[] ; "change outside of filter")]
+    fn test_affected_packages(changed: &[&str], scope: &[&str], expected: &[&str]) {
+        let root = mock_repo_root();
+        let graph = mock_package_graph(
+            &root,
+            package_jsons! {
+                root,
+                "a" => ["b"],
+                "b" => ["c"],
+                "c" => [],
+                "d" => []
+            },
+        );
+        let changed = changed
+            .iter()
+            .map(|name| WorkspacePackage {
+                name: PackageName::from(*name),
+                path: AnchoredSystemPathBuf::from_raw(format!("packages/{name}")).unwrap(),
+            })
+            .collect();
+
+        assert_eq!(
+            affected_packages(&graph, PackageChanges::Some(changed), &names(scope)),
+            names(expected)
+        );
+    }
+
+    #[test]
+    fn test_affected_packages_all() {
+        let root = mock_repo_root();
+        let graph = mock_package_graph(
+            &root,
+            package_jsons! {
+                root,
+                "a" => ["b"],
+                "b" => []
+            },
+        );
+        // Every package in scope re-runs, but nothing outside of it
+        assert_eq!(
+            affected_packages(&graph, PackageChanges::All, &names(&["b"])),
+            names(&["b"])
+        );
+    }
+
+    fn modify(repo_root: &AbsoluteSystemPathBuf, path: &str) -> Result<Event, NotifyError> {
+        let path = repo_root.join_components(&path.split('/').collect::<Vec<_>>());
+        Ok(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(path.as_std_path().to_owned()))
+    }
+
+    fn changed_files(paths: &[&str]) -> ChangedFiles {
+        ChangedFiles::Some(
+            paths
+                .iter()
+                .map(|path| AnchoredSystemPathBuf::from_raw(path).unwrap())
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_debounce_batches_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let (tx, rx) = broadcast::channel(16);
+        let mut changes = debounce_events(repo_root.clone(), rx);
+
+        // Changes in quick succession are sent as one batch, without the
+        // ignored ones
+        tx.send(modify(&repo_root, "a.txt")).unwrap();
+        tx.send(modify(&repo_root, "node_modules/dep/index.js"))
+            .unwrap();
+        tx.send(Ok(Event::new(EventKind::Access(AccessKind::Any)).add_path(
+            repo_root.join_component("c.txt").as_std_path().to_owned(),
+        )))
+        .unwrap();
+        tx.send(modify(&repo_root, "b.txt")).unwrap();
+        assert_eq!(
+            changes.recv().await,
+            Some(changed_files(&["a.txt", "b.txt"]))
+        );
+
+        // Batches that only contain ignored changes aren't sent at all
+        tx.send(modify(&repo_root, ".turbo/watch-cookies/1.cookie"))
+            .unwrap();
+        tokio::time::sleep(DEBOUNCE * 3).await;
+        tx.send(modify(&repo_root, "a.txt")).unwrap();
+        assert_eq!(changes.recv().await, Some(changed_files(&["a.txt"])));
+
+        drop(tx);
+        assert_eq!(changes.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_debounce_missed_events() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+
+        // Falling behind the watcher means we don't know what changed
+        let (tx, rx) = broadcast::channel(1);
+        let mut changes = debounce_events(repo_root.clone(), rx);
+        tx.send(modify(&repo_root, "a.txt")).unwrap();
+        tx.send(modify(&repo_root, "b.txt")).unwrap();
+        assert_eq!(changes.recv().await, Some(ChangedFiles::All));
+
+        // So does an error from the watcher
+        let (tx, rx) = broadcast::channel(16);
+        let mut changes = debounce_events(repo_root.clone(), rx);
+        tx.send(modify(&repo_root, "a.txt")).unwrap();
+        tx.send(Err(NotifyError::from(notify::Error::generic(
+            "watch failed",
+        ))))
+        .unwrap();
+        assert_eq!(changes.recv().await, Some(ChangedFiles::All));
+    }
+
+    #[test_case("src/index.ts", false ; "source file")]
+    #[test_case("node_modules/react/index.js", true ; "dependency")]
+    #[test_case("packages/ui/node_modules/.bin/tsc", true ; "nested dependency")]
+    #[test_case(".turbo/watch-cookies/1.cookie", true ; "cookie")]
+    #[test_case(".git/index", true ; "git")]
+    #[test_case("dist/index.js", true ; "root gitignore")]
+    #[test_case("packages/ui/out/index.js", true ; "nested gitignore")]
+    #[test_case("packages/ui/keep.log", false ; "nested negation")]
+    #[test_case("packages/ui/debug.log", true ; "root glob")]
+    fn test_ignore_matcher(path: &str, expected: bool) {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        fs::write(repo_root.join_component(".gitignore"), "dist/\n*.log\n").unwrap();
+        let ui = repo_root.join_components(&["packages", "ui"]);
+        ui.create_dir_all().unwrap();
+        fs::write(ui.join_component(".gitignore"), "out\n!keep.log\n").unwrap();
+
+        let mut matcher = IgnoreMatcher::new(repo_root);
+        let path = AnchoredSystemPathBuf::from_raw(path).unwrap();
+        assert_eq!(matcher.is_ignored(&path), expected);
+    }
+}
@@ -1,6 +1,6 @@
 mod visitor;
 
-use std::str::FromStr;
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
 
 use globwalk::{GlobError, ValidatedGlob};
 use serde::{Deserialize, Serialize};
@@ -72,6 +72,23 @@ pub struct TaskDefinition {
     // Persistent indicates whether the Task is expected to exit or not
     // Tasks marked Persistent do not exit (e.g. --watch mode or dev servers)
     pub persistent: bool,
+
+    // Interactive indicates whether the Task reads from stdin, like a prompt.
+    // turbo forwards its own stdin to one interactive Task at a time
+    pub(crate) interactive: bool,
+
+    // Timeout is how long the Task is allowed to run before it is stopped and
+    // treated as a failure
+    pub(crate) timeout: Option<Duration>,
+
+    // Retries is how many additional times the Task is run if it fails or
+    // times out
+    pub(crate) retries: u32,
+
+    // Resources are the amounts of named resources the Task reserves while it
+    // runs. The engine won't run Tasks concurrently if they'd exceed the
+    // configured capacity of a resource.
+    pub(crate) resources: BTreeMap<String, u32>,
 }
 
 impl Default for TaskDefinition {
@@ -86,7 +103,11 @@ impl Default for TaskDefinition {
             inputs: Default::default(),
             output_mode: Default::default(),
             persistent: Default::default(),
+            interactive: Default::default(),
             dot_env: Default::default(),
+            timeout: Default::default(),
+            retries: Default::default(),
+            resources: Default::default(),
         }
     }
 }
@@ -1,6 +1,6 @@
 use std::{
     borrow::Cow,
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     io::Write,
     sync::{Arc, Mutex, OnceLock},
     time::{Duration, Instant},
@@ -10,7 +10,7 @@ use console::{Style, StyledObject};
 use futures::{stream::FuturesUnordered, StreamExt};
 use regex::Regex;
 use tokio::sync::{mpsc, oneshot};
-use tracing::{debug, error, Instrument, Span};
+use tracing::{debug, error, warn, Instrument, Span};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
 use turborepo_ci::{Vendor, VendorBehavior};
 use turborepo_env::{EnvironmentVariableMap, ResolvedEnvMode};
@@ -28,9 +28,10 @@ use crate::{
     cli::EnvMode,
     engine::{Engine, ExecutionOptions, StopExecution},
     opts::RunOpts,
-    process::{ChildExit, Command, ProcessManager},
+    process::{Child, ChildExit, Command, ProcessManager, ResourceUsage, SharedStdin},
     run::{
         global_hash::GlobalHashableInputs,
+        input_audit::InputAuditor,
         summary::{
             self, GlobalHashSummary, RunTracker, SpacesTaskClient, SpacesTaskInformation,
             TaskExecutionSummary, TaskTracker,
@@ -58,6 +59,9 @@ pub struct Visitor<'a> {
     sink: OutputSink<StdWriter>,
     task_hasher: TaskHasher<'a>,
     ui: UI,
+    resources: BTreeMap<String, u32>,
+    stdin: Arc<SharedStdin>,
+    input_auditor: Option<InputAuditor>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -101,12 +105,16 @@ impl<'a> Visitor<'a> {
         manager: ProcessManager,
         repo_root: &'a AbsoluteSystemPath,
         global_env: EnvironmentVariableMap,
+        global_dot_env: EnvironmentVariableMap,
+        resources: BTreeMap<String, u32>,
+        input_auditor: Option<InputAuditor>,
     ) -> Self {
         let task_hasher = TaskHasher::new(
             package_inputs_hashes,
             run_opts,
             env_at_execution_start,
             global_hash,
+            global_dot_env,
         );
         let sink = Self::sink(run_opts, silent);
         let color_cache = ColorSelector::default();
@@ -126,6 +134,9 @@ impl<'a> Visitor<'a> {
             task_hasher,
             ui,
             global_env,
+            resources,
+            input_auditor,
+            stdin: Arc::default(),
         }
     }
 
@@ -139,7 +150,9 @@ impl<'a> Visitor<'a> {
         let (node_sender, mut node_stream) = mpsc::channel(concurrency);
         let engine_handle = {
             let engine = engine.clone();
-            tokio::spawn(engine.execute(ExecutionOptions::new(false, concurrency), node_sender))
+            let options =
+                ExecutionOptions::new(false, concurrency).with_resources(self.resources.clone());
+            tokio::spawn(engine.execute(options, node_sender))
         };
         let mut tasks = FuturesUnordered::new();
         let errors = Arc::new(Mutex::new(Vec::new()));
@@ -214,9 +227,14 @@ impl<'a> Visitor<'a> {
             // We do this calculation earlier than we do in Go due to the `task_hasher`
             // being !Send. In the future we can look at doing this right before
             // task execution instead.
-            let execution_env =
-                self.task_hasher
-                    .env(&info, task_env_mode, task_definition, &self.global_env)?;
+            let workspace_directory = self.repo_root.resolve(workspace_info.package_path());
+            let execution_env = self.task_hasher.env(
+                &info,
+                task_env_mode,
+                task_definition,
+                &workspace_directory,
+                &self.global_env,
+            )?;
 
             let task_cache = self.run_cache.task_cache(
                 task_definition,
@@ -248,8 +266,6 @@ impl<'a> Visitor<'a> {
                         continue;
                     }
 
-                    let workspace_directory = self.repo_root.resolve(workspace_info.package_path());
-
                     let persistent = task_definition.persistent;
                     let mut exec_context = factory.exec_context(
                         info.clone(),
@@ -258,6 +274,9 @@ impl<'a> Visitor<'a> {
                         workspace_directory,
                         execution_env,
                         persistent,
+                        task_definition.interactive,
+                        task_definition.timeout,
+                        task_definition.retries,
                         self.task_access.clone(),
                     );
 
@@ -297,6 +316,17 @@ impl<'a> Visitor<'a> {
         // Write out the traced-config.json file if we have one
         self.task_access.save().await;
 
+        if let Some(input_auditor) = &self.input_auditor {
+            match input_auditor.audit(
+                &engine,
+                &self.package_graph,
+                &self.task_hasher.task_hash_tracker(),
+            ) {
+                Ok(report) => report.print(self.ui),
+                Err(e) => warn!("unable to audit task inputs: {e}"),
+            }
+        }
+
         let errors = Arc::into_inner(errors)
             .expect("only one strong reference to errors should remain")
             .into_inner()
@@ -520,6 +550,8 @@ enum TaskErrorCause {
     Spawn { msg: String },
     #[error("command {command} exited ({exit_code})")]
     Exit { command: String, exit_code: i32 },
+    #[error("command {command} timed out after {}s", timeout.as_secs())]
+    Timeout { command: String, timeout: Duration },
 }
 
 impl TaskError {
@@ -557,6 +589,10 @@ impl TaskErrorCause {
     fn from_execution(command: String, exit_code: i32) -> Self {
         TaskErrorCause::Exit { command, exit_code }
     }
+
+    fn from_timeout(command: String, timeout: Duration) -> Self {
+        TaskErrorCause::Timeout { command, timeout }
+    }
 }
 
 struct ExecContextFactory<'a> {
@@ -590,6 +626,9 @@ impl<'a> ExecContextFactory<'a> {
         workspace_directory: AbsoluteSystemPathBuf,
         execution_env: EnvironmentVariableMap,
         persistent: bool,
+        interactive: bool,
+        timeout: Option<Duration>,
+        retries: u32,
         task_access: TaskAccess,
     ) -> ExecContext {
         let task_id_for_display = self.visitor.display_task_id(&task_id);
@@ -615,6 +654,9 @@ impl<'a> ExecContextFactory<'a> {
             pass_through_args,
             errors: self.errors.clone(),
             persistent,
+            stdin: interactive.then(|| self.visitor.stdin.clone()),
+            timeout,
+            retries,
             task_access,
         }
     }
@@ -650,6 +692,10 @@ struct ExecContext {
     pass_through_args: Option<Vec<String>>,
     errors: Arc<Mutex<Vec<TaskError>>>,
     persistent: bool,
+    // Set for interactive tasks, which get turbo's stdin forwarded to them
+    stdin: Option<Arc<SharedStdin>>,
+    timeout: Option<Duration>,
+    retries: u32,
     task_access: TaskAccess,
 }
 
@@ -662,12 +708,18 @@ enum ExecOutcome {
     Task {
         exit_code: Option<i32>,
         message: String,
+        attempts: u32,
+        timed_out: bool,
+        resources: Option<ResourceUsage>,
     },
 }
 
 enum SuccessOutcome {
     CacheHit,
-    Run,
+    Run {
+        attempts: u32,
+        resources: Option<ResourceUsage>,
+    },
 }
 
 impl ExecContext {
@@ -689,13 +741,34 @@ impl ExecContext {
         telemetry: &PackageTaskEventBuilder,
     ) {
         let tracker = tracker.start().await;
-        let span = tracing::debug_span!("execute_task", task = %self.task_id.task());
+        let span = tracing::debug_span!(
+            "execute_task",
+            task = %self.task_id.task(),
+            user_cpu_ms = tracing::field::Empty,
+            system_cpu_ms = tracing::field::Empty,
+            peak_rss_bytes = tracing::field::Empty,
+        );
         span.follows_from(parent_span_id);
         let mut result = self
             .execute_inner(&output_client, telemetry)
-            .instrument(span)
+            .instrument(span.clone())
             .await;
 
+        // Record usage on the span so it shows up in --profile traces
+        if let ExecOutcome::Success(SuccessOutcome::Run {
+            resources: Some(resources),
+            ..
+        })
+        | ExecOutcome::Task {
+            resources: Some(resources),
+            ..
+        } = &result
+        {
+            span.record("user_cpu_ms", resources.user_cpu_ms);
+            span.record("system_cpu_ms", resources.system_cpu_ms);
+            span.record("peak_rss_bytes", resources.peak_rss_bytes);
+        }
+
         // If the task resulted in an error, do not group in order to better highlight
         // the error.
         let is_error = matches!(result, ExecOutcome::Task { .. });
@@ -713,7 +786,16 @@ impl ExecContext {
             ExecOutcome::Success(outcome) => {
                 let task_summary = match outcome {
                     SuccessOutcome::CacheHit => tracker.cached().await,
-                    SuccessOutcome::Run => tracker.build_succeeded(0).await,
+                    SuccessOutcome::Run {
+                        attempts,
+                        resources,
+                    } => {
+                        tracker
+                            .with_attempts(attempts, false)
+                            .with_resource_usage(resources)
+                            .build_succeeded(0)
+                            .await
+                    }
                 };
                 callback.send(Ok(())).ok();
                 if let Some(client) = spaces_client {
@@ -727,8 +809,18 @@ impl ExecContext {
                 callback.send(Err(StopExecution)).ok();
                 self.manager.stop().await;
             }
-            ExecOutcome::Task { exit_code, message } => {
-                let task_summary = tracker.build_failed(exit_code, message).await;
+            ExecOutcome::Task {
+                exit_code,
+                message,
+                attempts,
+                timed_out,
+                resources,
+            } => {
+                let task_summary = tracker
+                    .with_attempts(attempts, timed_out)
+                    .with_resource_usage(resources)
+                    .build_failed(exit_code, message)
+                    .await;
                 callback
                     .send(match self.continue_on_error {
                         true => Ok(()),
@@ -822,7 +914,7 @@ impl ExecContext {
 
         // set the trace file env var - frameworks that support this can use it to
         // write out a trace file that we will use to automatically cache the task
-        if self.task_access.is_enabled() {
+        if self.task_access.is_tracing() {
             let (task_access_trace_key, trace_file) = self.task_access.get_env_var(&self.task_hash);
             cmd.env(task_access_trace_key, trace_file.to_string());
         }
@@ -830,115 +922,206 @@ impl ExecContext {
         // Many persistent tasks if started hooked up to a pseudoterminal
         // will shut down if stdin is closed, so we open it even if we don't pass
         // anything to it.
-        if self.persistent {
+        if self.persistent || self.stdin.is_some() {
             cmd.open_stdin();
         }
 
-        let mut stdout_writer = match self
-            .task_cache
-            .output_writer(self.pretty_prefix.clone(), output_client.stdout())
-        {
-            Ok(w) => w,
-            Err(e) => {
-                telemetry.track_error(TrackedErrors::FailedToCaptureOutputs);
-                error!("failed to capture outputs for \"{}\": {e}", self.task_id);
-                return ExecOutcome::Internal;
-            }
+        // Held across every attempt so another interactive task can't take
+        // stdin between retries
+        let mut stdin = match &self.stdin {
+            Some(stdin) => Some(stdin.lock(self.manager.use_pty()).await),
+            None => None,
         };
 
-        let mut process = match self.manager.spawn(cmd, Duration::from_millis(500)) {
-            Some(Ok(child)) => child,
-            // Turbo was unable to spawn a process
-            Some(Err(e)) => {
-                // Note: we actually failed to spawn, but this matches the Go output
-                prefixed_ui.error(format!("command finished with error: {e}"));
-                let error_string = e.to_string();
-                self.errors
-                    .lock()
-                    .expect("lock poisoned")
-                    .push(TaskError::from_spawn(self.task_id_for_display.clone(), e));
-                return ExecOutcome::Task {
-                    exit_code: None,
-                    message: error_string,
-                };
-            }
-            // Turbo is shutting down
-            None => {
-                return ExecOutcome::Internal;
-            }
-        };
+        let max_attempts = self.retries + 1;
+        let mut attempt = 1;
+        // Usage is summed across every attempt
+        let mut resources: Option<ResourceUsage> = None;
+        loop {
+            // Each attempt starts a new log file, so only the output of the
+            // attempt that finishes the task gets cached
+            let mut stdout_writer = match self
+                .task_cache
+                .output_writer(self.pretty_prefix.clone(), output_client.stdout())
+            {
+                Ok(w) => w,
+                Err(e) => {
+                    telemetry.track_error(TrackedErrors::FailedToCaptureOutputs);
+                    error!("failed to capture outputs for \"{}\": {e}", self.task_id);
+                    return ExecOutcome::Internal;
+                }
+            };
 
-        let exit_status = match process.wait_with_piped_outputs(&mut stdout_writer).await {
-            Ok(Some(exit_status)) => exit_status,
-            Err(e) => {
-                telemetry.track_error(TrackedErrors::FailedToPipeOutputs);
-                error!("unable to pipe outputs from command: {e}");
-                return ExecOutcome::Internal;
-            }
-            Ok(None) => {
-                // TODO: how can this happen? we only update the
-                // exit status with Some and it is only initialized with
-                // None. Is it still running?
-                telemetry.track_error(TrackedErrors::UnknownChildExit);
-                error!("unable to determine why child exited");
-                return ExecOutcome::Internal;
-            }
-        };
-        let task_duration = task_start.elapsed();
+            let mut process = match self.manager.spawn(cmd.clone(), Duration::from_millis(500)) {
+                Some(Ok(child)) => child,
+                // Turbo was unable to spawn a process
+                Some(Err(e)) => {
+                    // Note: we actually failed to spawn, but this matches the Go output
+                    prefixed_ui.error(format!("command finished with error: {e}"));
+                    let error_string = e.to_string();
+                    self.errors
+                        .lock()
+                        .expect("lock poisoned")
+                        .push(TaskError::from_spawn(self.task_id_for_display.clone(), e));
+                    return ExecOutcome::Task {
+                        exit_code: None,
+                        message: error_string,
+                        attempts: attempt,
+                        timed_out: false,
+                        resources,
+                    };
+                }
+                // Turbo is shutting down
+                None => {
+                    return ExecOutcome::Internal;
+                }
+            };
 
-        match exit_status {
-            ChildExit::Finished(Some(0)) => {
-                // Attempt to flush stdout_writer and log any errors encountered
-                if let Err(e) = stdout_writer.flush() {
-                    error!("{e}");
-                } else if self
-                    .task_access
-                    .can_cache(&self.task_hash, &self.task_id_for_display)
-                    .unwrap_or(true)
-                {
-                    if let Err(e) = self.task_cache.save_outputs(task_duration, telemetry).await {
-                        error!("error caching output: {e}");
-                    } else {
-                        // If no errors, update hash tracker with expanded outputs
-                        self.hash_tracker.insert_expanded_outputs(
-                            self.task_id.clone(),
-                            self.task_cache.expanded_outputs().to_vec(),
-                        );
-                    }
+            let mut forward_process = process.clone();
+            let forward_stdin = async {
+                let Some(stdin) = stdin.as_mut() else {
+                    return;
+                };
+                if let Err(e) = forward_process.forward_stdin(stdin.input()).await {
+                    debug!("unable to forward stdin to {}: {e}", self.task_id);
                 }
+            };
+            let (result, ()) = tokio::join!(
+                self.wait_with_timeout(&mut process, &mut stdout_writer),
+                forward_stdin
+            );
+            let (exit_status, timed_out) = match result {
+                (Ok(Some(exit_status)), timed_out) => (exit_status, timed_out),
+                (Err(e), _) => {
+                    telemetry.track_error(TrackedErrors::FailedToPipeOutputs);
+                    error!("unable to pipe outputs from command: {e}");
+                    return ExecOutcome::Internal;
+                }
+                (Ok(None), _) => {
+                    // TODO: how can this happen? we only update the
+                    // exit status with Some and it is only initialized with
+                    // None. Is it still running?
+                    telemetry.track_error(TrackedErrors::UnknownChildExit);
+                    error!("unable to determine why child exited");
+                    return ExecOutcome::Internal;
+                }
+            };
+            let task_duration = task_start.elapsed();
+            resources = match (resources, process.resource_usage()) {
+                (Some(previous), Some(usage)) => Some(previous.combine(usage)),
+                (previous, usage) => previous.or(usage),
+            };
 
-                // Return success outcome
-                ExecOutcome::Success(SuccessOutcome::Run)
-            }
-            ChildExit::Finished(Some(code)) => {
-                // If there was an error, flush the buffered output
-                if let Err(e) = stdout_writer.flush() {
-                    error!("error flushing logs: {e}");
+            let error = match (exit_status, self.timeout) {
+                // A stopped process might still report an exit code, but it exited because we
+                // stopped it.
+                (_, Some(timeout)) if timed_out => {
+                    TaskErrorCause::from_timeout(process.label().to_string(), timeout)
                 }
-                if let Err(e) = self.task_cache.on_error(&mut prefixed_ui) {
-                    error!("error reading logs: {e}");
+                (ChildExit::Finished(Some(0)), _) => {
+                    // Attempt to flush stdout_writer and log any errors encountered
+                    if let Err(e) = stdout_writer.flush() {
+                        error!("{e}");
+                    } else if self
+                        .task_access
+                        .can_cache(&self.task_hash, &self.task_id_for_display)
+                        .unwrap_or(true)
+                    {
+                        if let Err(e) = self.task_cache.save_outputs(task_duration, telemetry).await
+                        {
+                            error!("error caching output: {e}");
+                        } else {
+                            // If no errors, update hash tracker with expanded outputs
+                            self.hash_tracker.insert_expanded_outputs(
+                                self.task_id.clone(),
+                                self.task_cache.expanded_outputs().to_vec(),
+                            );
+                        }
+                    }
+
+                    // Return success outcome
+                    return ExecOutcome::Success(SuccessOutcome::Run {
+                        attempts: attempt,
+                        resources,
+                    });
                 }
-                let error = TaskErrorCause::from_execution(process.label().to_string(), code);
-                let message = error.to_string();
-                if self.continue_on_error {
-                    prefixed_ui.warn("command finished with error, but continuing...");
-                } else {
-                    prefixed_ui.error(format!("command finished with error: {error}"));
+                (ChildExit::Finished(Some(code)), _) => {
+                    TaskErrorCause::from_execution(process.label().to_string(), code)
                 }
-                self.errors.lock().expect("lock poisoned").push(TaskError {
-                    task_id: self.task_id_for_display.clone(),
-                    cause: error,
-                });
-                ExecOutcome::Task {
-                    exit_code: Some(code),
-                    message,
+                // All of these indicate a failure where we don't know how to recover
+                (
+                    ChildExit::Finished(None)
+                    | ChildExit::Killed
+                    | ChildExit::KilledExternal
+                    | ChildExit::Failed,
+                    _,
+                ) => return ExecOutcome::Internal,
+            };
+
+            if attempt < max_attempts {
+                if let Err(e) = stdout_writer.flush() {
+                    error!("error flushing logs: {e}");
                 }
+                attempt += 1;
+                prefixed_ui.warn(format!(
+                    "{error}, retrying (attempt {attempt}/{max_attempts})..."
+                ));
+                continue;
+            }
+
+            // If there was an error, flush the buffered output
+            if let Err(e) = stdout_writer.flush() {
+                error!("error flushing logs: {e}");
+            }
+            if let Err(e) = self.task_cache.on_error(&mut prefixed_ui) {
+                error!("error reading logs: {e}");
+            }
+            let exit_code = match error {
+                TaskErrorCause::Exit { exit_code, .. } => Some(exit_code),
+                _ => None,
+            };
+            let message = error.to_string();
+            if self.continue_on_error {
+                prefixed_ui.warn("command finished with error, but continuing...");
+            } else {
+                prefixed_ui.error(format!("command finished with error: {error}"));
+            }
+            self.errors.lock().expect("lock poisoned").push(TaskError {
+                task_id: self.task_id_for_display.clone(),
+                cause: error,
+            });
+            return ExecOutcome::Task {
+                exit_code,
+                message,
+                attempts: attempt,
+                timed_out,
+                resources,
+            };
+        }
+    }
+
+    // Waits for the process to exit while piping its outputs. If the task has a
+    // timeout and the process runs longer than it, the process gets stopped.
+    // Also returns if the process was stopped due to the timeout.
+    async fn wait_with_timeout(
+        &self,
+        process: &mut Child,
+        stdout_writer: impl Write,
+    ) -> (Result<Option<ChildExit>, std::io::Error>, bool) {
+        let Some(timeout) = self.timeout else {
+            return (process.wait_with_piped_outputs(stdout_writer).await, false);
+        };
+
+        let mut stop_handle = process.clone();
+        let wait = process.wait_with_piped_outputs(stdout_writer);
+        tokio::pin!(wait);
+        tokio::select! {
+            result = &mut wait => (result, false),
+            _ = tokio::time::sleep(timeout) => {
+                // Keep piping outputs while the process shuts down
+                let (_, result) = tokio::join!(stop_handle.stop(), &mut wait);
+                (result, true)
             }
-            // All of these indicate a failure where we don't know how to recover
-            ChildExit::Finished(None)
-            | ChildExit::Killed
-            | ChildExit::KilledExternal
-            | ChildExit::Failed => ExecOutcome::Internal,
         }
     }
 
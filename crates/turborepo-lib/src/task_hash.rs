@@ -3,6 +3,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use futures::future::join_all;
 use rayon::prelude::*;
 use serde::Serialize;
 use thiserror::Error;
@@ -11,12 +12,13 @@ use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, AnchoredSystemPathBuf};
 use turborepo_cache::CacheHitMetadata;
 use turborepo_env::{BySource, DetailedMap, EnvironmentVariableMap, ResolvedEnvMode};
 use turborepo_repository::package_graph::{PackageInfo, PackageName};
-use turborepo_scm::SCM;
+use turborepo_scm::{package_deps::GitHashes, SCM};
 use turborepo_telemetry::events::{
     generic::GenericEventBuilder, task::PackageTaskEventBuilder, EventBuilder,
 };
 
 use crate::{
+    daemon::{DaemonClient, DaemonConnector},
     engine::TaskNode,
     framework::infer_framework,
     hash::{FileHashes, LockFilePackages, TaskHashable, TurboHash},
@@ -49,6 +51,16 @@ pub enum Error {
     Path(#[from] turbopath::PathError),
 }
 
+/// Input file hashes provided by the daemon, keyed by package path and the
+/// task's `inputs`
+pub type DaemonFileHashes = HashMap<(AnchoredSystemPathBuf, Vec<String>), GitHashes>;
+
+fn package_path(pkg: &PackageInfo) -> &AnchoredSystemPath {
+    pkg.package_json_path
+        .parent()
+        .unwrap_or_else(|| AnchoredSystemPath::new("").unwrap())
+}
+
 impl TaskHashable<'_> {
     fn calculate_task_hash(mut self) -> String {
         if matches!(self.env_mode, ResolvedEnvMode::Loose) {
@@ -66,12 +78,56 @@ pub struct PackageInputsHashes {
 }
 
 impl PackageInputsHashes {
-    #[tracing::instrument(skip(all_tasks, workspaces, task_definitions, repo_root, scm))]
+    /// Fetch the input file hashes for every task from the daemon. Hashes
+    /// the daemon can't provide in time are left out and get calculated
+    /// locally instead.
+    pub async fn fetch_daemon_file_hashes<'a>(
+        daemon: &DaemonClient<DaemonConnector>,
+        all_tasks: impl Iterator<Item = &'a TaskNode>,
+        workspaces: &HashMap<&PackageName, &PackageInfo>,
+        task_definitions: &HashMap<TaskId<'static>, TaskDefinition>,
+    ) -> DaemonFileHashes {
+        let specs = all_tasks
+            .filter_map(|task| {
+                let TaskNode::Task(task_id) = task else {
+                    return None;
+                };
+                let task_definition = task_definitions.get(task_id)?;
+                let pkg = workspaces.get(&task_id.to_workspace_name())?;
+                Some((package_path(pkg).to_owned(), task_definition.inputs.clone()))
+            })
+            .collect::<HashSet<_>>();
+
+        let fetches = specs.into_iter().map(|(package_path, inputs)| {
+            let mut daemon = daemon.clone();
+            async move {
+                match daemon.get_file_hashes(&package_path, &inputs).await {
+                    Ok(hashes) => Some(((package_path, inputs), hashes)),
+                    Err(e) => {
+                        debug!("daemon could not hash files for {}: {}", package_path, e);
+                        None
+                    }
+                }
+            }
+        });
+
+        join_all(fetches).await.into_iter().flatten().collect()
+    }
+
+    #[tracing::instrument(skip(
+        all_tasks,
+        workspaces,
+        task_definitions,
+        daemon_file_hashes,
+        repo_root,
+        scm
+    ))]
     pub fn calculate_file_hashes<'a>(
         scm: &SCM,
         all_tasks: impl ParallelIterator<Item = &'a TaskNode>,
         workspaces: HashMap<&PackageName, &PackageInfo>,
         task_definitions: &HashMap<TaskId<'static>, TaskDefinition>,
+        daemon_file_hashes: &DaemonFileHashes,
         repo_root: &AbsoluteSystemPath,
         telemetry: &GenericEventBuilder,
     ) -> Result<PackageInputsHashes, Error> {
@@ -109,20 +165,24 @@ impl PackageInputsHashes {
                     Err(err) => return Some(Err(err)),
                 };
 
-                let package_path = pkg
-                    .package_json_path
-                    .parent()
-                    .unwrap_or_else(|| AnchoredSystemPath::new("").unwrap());
-
-                let scm_telemetry = package_task_event.child();
-                let mut hash_object = match scm.get_package_file_hashes(
-                    repo_root,
-                    package_path,
-                    &task_definition.inputs,
-                    Some(scm_telemetry),
-                ) {
-                    Ok(hash_object) => hash_object,
-                    Err(err) => return Some(Err(err.into())),
+                let package_path = package_path(pkg);
+
+                let daemon_hashes = daemon_file_hashes
+                    .get(&(package_path.to_owned(), task_definition.inputs.clone()));
+                let mut hash_object = match daemon_hashes {
+                    Some(hash_object) => hash_object.clone(),
+                    None => {
+                        let scm_telemetry = package_task_event.child();
+                        match scm.get_package_file_hashes(
+                            repo_root,
+                            package_path,
+                            &task_definition.inputs,
+                            Some(scm_telemetry),
+                        ) {
+                            Ok(hash_object) => hash_object,
+                            Err(err) => return Some(Err(err.into())),
+                        }
+                    }
                 };
                 if let Some(dot_env) = &task_definition.dot_env {
                     if !dot_env.is_empty() {
@@ -167,6 +227,8 @@ pub struct TaskHashTracker {
 pub struct TaskHashTrackerState {
     #[serde(skip)]
     package_task_env_vars: HashMap<TaskId<'static>, DetailedMap>,
+    #[serde(skip)]
+    package_task_dot_env_vars: HashMap<TaskId<'static>, EnvironmentVariableMap>,
     package_task_hashes: HashMap<TaskId<'static>, String>,
     #[serde(skip)]
     package_task_framework: HashMap<TaskId<'static>, String>,
@@ -184,6 +246,7 @@ pub struct TaskHasher<'a> {
     run_opts: &'a RunOpts,
     env_at_execution_start: &'a EnvironmentVariableMap,
     global_hash: &'a str,
+    global_dot_env: EnvironmentVariableMap,
     task_hash_tracker: TaskHashTracker,
 }
 
@@ -193,6 +256,7 @@ impl<'a> TaskHasher<'a> {
         run_opts: &'a RunOpts,
         env_at_execution_start: &'a EnvironmentVariableMap,
         global_hash: &'a str,
+        global_dot_env: EnvironmentVariableMap,
     ) -> Self {
         let PackageInputsHashes {
             hashes,
@@ -203,6 +267,7 @@ impl<'a> TaskHasher<'a> {
             run_opts,
             env_at_execution_start,
             global_hash,
+            global_dot_env,
             task_hash_tracker: TaskHashTracker::new(expanded_hashes),
         }
     }
@@ -398,9 +463,10 @@ impl<'a> TaskHasher<'a> {
         task_id: &TaskId,
         task_env_mode: ResolvedEnvMode,
         task_definition: &TaskDefinition,
+        workspace_directory: &AbsoluteSystemPath,
         global_env: &EnvironmentVariableMap,
     ) -> Result<EnvironmentVariableMap, Error> {
-        match task_env_mode {
+        let process_env = match task_env_mode {
             ResolvedEnvMode::Strict => {
                 let mut pass_through_env = EnvironmentVariableMap::default();
                 let default_env_var_pass_through_map =
@@ -430,10 +496,38 @@ impl<'a> TaskHasher<'a> {
                 )?;
                 pass_through_env.union(&env_var_pass_through_map);
 
-                Ok(pass_through_env)
+                pass_through_env
             }
-            ResolvedEnvMode::Loose => Ok(self.env_at_execution_start.clone()),
-        }
+            ResolvedEnvMode::Loose => self.env_at_execution_start.clone(),
+        };
+
+        // Variables that make it through from the environment take precedence
+        // over the ones loaded from `.env` files
+        let mut dot_env = self.dot_env(task_definition, workspace_directory)?;
+        dot_env.difference(&process_env);
+        self.task_hash_tracker
+            .insert_dot_env_vars(task_id.clone().into_owned(), dot_env.clone());
+
+        let mut env = dot_env;
+        env.union(&process_env);
+        Ok(env)
+    }
+
+    // The task's `dotEnv` files take precedence over the `globalDotEnv` ones
+    fn dot_env(
+        &self,
+        task_definition: &TaskDefinition,
+        workspace_directory: &AbsoluteSystemPath,
+    ) -> Result<EnvironmentVariableMap, Error> {
+        let paths = task_definition
+            .dot_env
+            .iter()
+            .flatten()
+            .map(|path| workspace_directory.join_unix_path(path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut dot_env = self.global_dot_env.clone();
+        dot_env.union(&EnvironmentVariableMap::from_dot_env_files(&paths)?);
+        Ok(dot_env)
     }
 }
 
@@ -497,6 +591,18 @@ impl TaskHashTracker {
         state.package_task_env_vars.get(task_id).cloned()
     }
 
+    pub fn dot_env_vars(&self, task_id: &TaskId) -> Option<EnvironmentVariableMap> {
+        let state = self.state.lock().expect("hash tracker mutex poisoned");
+        state.package_task_dot_env_vars.get(task_id).cloned()
+    }
+
+    fn insert_dot_env_vars(&self, task_id: TaskId<'static>, dot_env_vars: EnvironmentVariableMap) {
+        let mut state = self.state.lock().expect("hash tracker mutex poisoned");
+        state
+            .package_task_dot_env_vars
+            .insert(task_id, dot_env_vars);
+    }
+
     pub fn framework(&self, task_id: &TaskId) -> Option<String> {
         let state = self.state.lock().expect("hash tracker mutex poisoned");
         state.package_task_framework.get(task_id).cloned()
@@ -537,7 +643,19 @@ impl TaskHashTracker {
 
 #[cfg(test)]
 mod test {
+    use tempfile::tempdir;
+    use test_case::test_case;
+    use turbopath::RelativeUnixPathBuf;
+
     use super::*;
+    use crate::cli::RunArgs;
+
+    fn env_map(vars: &[(&str, &str)]) -> EnvironmentVariableMap {
+        vars.iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>()
+            .into()
+    }
 
     #[test]
     fn test_hash_tracker_is_send_and_sync() {
@@ -548,4 +666,103 @@ mod test {
         assert_send::<TaskHashTracker>();
         assert_sync::<TaskHashTracker>();
     }
+
+    #[test_case(
+        ResolvedEnvMode::Loose,
+        &[
+            ("PATH", "/bin"),
+            ("TRACKED", "process"),
+            ("PASS", "process"),
+            ("SHARED", "process"),
+            ("SECRET", "process"),
+            ("OTHER", "process"),
+            ("TASK_ONLY", "task"),
+            ("GLOBAL_ONLY", "global"),
+        ],
+        &["GLOBAL_ONLY", "TASK_ONLY"] ;
+        "loose"
+    )]
+    #[test_case(
+        ResolvedEnvMode::Strict,
+        &[
+            ("PATH", "/bin"),
+            ("TRACKED", "process"),
+            ("PASS", "process"),
+            // filtered out of the process env, so the `.env` files supply them
+            ("SHARED", "task"),
+            ("SECRET", "task"),
+            ("TASK_ONLY", "task"),
+            ("GLOBAL_ONLY", "global"),
+        ],
+        &["GLOBAL_ONLY", "SECRET", "SHARED", "TASK_ONLY"] ;
+        "strict"
+    )]
+    fn test_env_precedence(
+        env_mode: ResolvedEnvMode,
+        expected: &[(&str, &str)],
+        expected_dot_env: &[&str],
+    ) {
+        let tmp = tempdir().unwrap();
+        let workspace_directory = AbsoluteSystemPath::from_std_path(tmp.path()).unwrap();
+        workspace_directory
+            .join_component(".env")
+            .create_with_contents("PASS=task\nSHARED=task\nSECRET=task\nTASK_ONLY=task\n")
+            .unwrap();
+
+        let run_opts = RunOpts::try_from(&RunArgs::default()).unwrap();
+        let env_at_execution_start = env_map(&[
+            ("PATH", "/bin"),
+            ("TRACKED", "process"),
+            ("PASS", "process"),
+            ("SHARED", "process"),
+            ("SECRET", "process"),
+            ("OTHER", "process"),
+        ]);
+        let global_dot_env = env_map(&[("SHARED", "global"), ("GLOBAL_ONLY", "global")]);
+        let task_hasher = TaskHasher::new(
+            PackageInputsHashes::default(),
+            &run_opts,
+            &env_at_execution_start,
+            "global-hash",
+            global_dot_env,
+        );
+
+        let task_id = TaskId::new("web", "build");
+        // Strict mode only lets through the variables the task hashed on
+        task_hasher.task_hash_tracker.insert_hash(
+            task_id.clone().into_owned(),
+            DetailedMap {
+                all: env_map(&[("TRACKED", "process")]),
+                ..Default::default()
+            },
+            "hash".to_string(),
+            None,
+        );
+        let task_definition = TaskDefinition {
+            pass_through_env: Some(vec!["PASS".to_string()]),
+            dot_env: Some(vec![RelativeUnixPathBuf::new(".env").unwrap()]),
+            ..Default::default()
+        };
+
+        let env = task_hasher
+            .env(
+                &task_id,
+                env_mode,
+                &task_definition,
+                workspace_directory,
+                &EnvironmentVariableMap::default(),
+            )
+            .unwrap();
+        assert_eq!(env.into_inner(), env_map(expected).into_inner());
+
+        let mut dot_env_keys = task_hasher
+            .task_hash_tracker
+            .dot_env_vars(&task_id)
+            .unwrap()
+            .into_inner()
+            .into_keys()
+            .collect::<Vec<_>>();
+        dot_env_keys.sort();
+        assert_eq!(dot_env_keys, expected_dot_env);
+    }
 }
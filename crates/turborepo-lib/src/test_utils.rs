@@ -0,0 +1,140 @@
+//! Package graphs for tests, built from package.json files in memory instead
+//! of discovering the packages of a repository on disk.
+
+use std::collections::HashMap;
+
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turborepo_lockfiles::Lockfile;
+use turborepo_repository::{
+    discovery::{DiscoveryResponse, PackageDiscovery},
+    package_graph::{PackageGraph, PackageGraphBuilder},
+    package_json::PackageJson,
+    package_manager::PackageManager,
+};
+
+pub(crate) struct MockDiscovery(pub PackageManager);
+impl PackageDiscovery for MockDiscovery {
+    async fn discover_packages(
+        &self,
+    ) -> Result<DiscoveryResponse, turborepo_repository::discovery::Error> {
+        Ok(DiscoveryResponse {
+            package_manager: self.0,
+            workspaces: vec![], // we don't care about this
+        })
+    }
+}
+
+// Only used to prevent package graph construction from attempting to read
+// lockfile from disk
+#[derive(Debug)]
+pub(crate) struct MockLockfile;
+impl Lockfile for MockLockfile {
+    fn resolve_package(
+        &self,
+        _workspace_path: &str,
+        _name: &str,
+        _version: &str,
+    ) -> Result<Option<turborepo_lockfiles::Package>, turborepo_lockfiles::Error> {
+        unreachable!()
+    }
+
+    fn all_dependencies(
+        &self,
+        _key: &str,
+    ) -> Result<Option<HashMap<String, String>>, turborepo_lockfiles::Error> {
+        unreachable!()
+    }
+
+    fn subgraph(
+        &self,
+        _workspace_packages: &[String],
+        _packages: &[String],
+    ) -> Result<Box<dyn Lockfile>, turborepo_lockfiles::Error> {
+        unreachable!()
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, turborepo_lockfiles::Error> {
+        unreachable!()
+    }
+
+    fn global_change(&self, _other: &dyn Lockfile) -> bool {
+        unreachable!()
+    }
+}
+
+/// Builds the package.json files of a monorepo rooted at `$root`. Packages are
+/// either given by the directory of their package.json and its contents:
+///
+/// `"apps/web" => json!({ "name": "web" })`
+///
+/// or by a name and the packages they depend on with `workspace:*`, which
+/// places them in `packages/<name>`:
+///
+/// `"web" => ["ui"]`
+macro_rules! package_jsons {
+    {$root:expr, $($dir:expr => json!($($json:tt)+)),+ $(,)?} => {
+        {
+            let mut _map = ::std::collections::HashMap::new();
+            $(
+                let path = $root.join_components(
+                    &$dir.split('/').chain(["package.json"]).collect::<Vec<_>>(),
+                );
+                let package_json =
+                    turborepo_repository::package_json::PackageJson::from_value(
+                        serde_json::json!($($json)+),
+                    )
+                    .unwrap();
+                _map.insert(path, package_json);
+            )+
+            _map
+        }
+    };
+    {$root:expr, $($name:expr => $deps:expr),+} => {
+        {
+            let mut _map = ::std::collections::HashMap::new();
+            $(
+                let path = $root.join_components(&["packages", $name, "package.json"]);
+                let dependencies = Some($deps.iter().map(|dep: &&str| (dep.to_string(), "workspace:*".to_string())).collect());
+                let package_json = turborepo_repository::package_json::PackageJson { name: Some($name.to_string()), dependencies, ..Default::default() };
+                _map.insert(path, package_json);
+            )+
+            _map
+        }
+    };
+}
+pub(crate) use package_jsons;
+
+/// A repository root for package graphs that are never read from disk
+pub(crate) fn mock_repo_root() -> AbsoluteSystemPathBuf {
+    AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap()
+}
+
+/// Starts building a package graph from `package_jsons!` that reports
+/// `package_manager` as the repository's package manager. Unless a lockfile
+/// is given, the builder reads it from disk if there is one.
+pub(crate) fn mock_package_graph_builder(
+    repo_root: &AbsoluteSystemPath,
+    jsons: HashMap<AbsoluteSystemPathBuf, PackageJson>,
+    package_manager: PackageManager,
+) -> PackageGraphBuilder<'_, MockDiscovery> {
+    PackageGraph::builder(repo_root, PackageJson::default())
+        .with_package_discovery(MockDiscovery(package_manager))
+        .with_package_jsons(Some(jsons))
+}
+
+pub(crate) fn mock_package_graph(
+    repo_root: &AbsoluteSystemPath,
+    jsons: HashMap<AbsoluteSystemPathBuf, PackageJson>,
+) -> PackageGraph {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    rt.block_on(
+        mock_package_graph_builder(repo_root, jsons, PackageManager::Npm)
+            .with_lockfile(Some(Box::new(MockLockfile)))
+            .build(),
+    )
+    .unwrap()
+}
@@ -2,6 +2,7 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     ops::{Deref, DerefMut},
     sync::Arc,
+    time::Duration,
 };
 
 use camino::Utf8Path;
@@ -53,6 +54,9 @@ pub struct TurboJson {
     pub(crate) global_env: Vec<String>,
     pub(crate) global_pass_through_env: Option<Vec<String>>,
     pub(crate) pipeline: Pipeline,
+    pub(crate) resources: BTreeMap<String, u32>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) boundaries: Option<RawBoundariesOptions>,
 }
 
 // Iterable is required to enumerate allowed keys
@@ -75,6 +79,8 @@ pub(crate) struct RawRemoteCacheOptions {
     timeout: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shared_directory: Option<String>,
 }
 
 impl From<&RawRemoteCacheOptions> for ConfigurationOptions {
@@ -88,11 +94,67 @@ impl From<&RawRemoteCacheOptions> for ConfigurationOptions {
             preflight: remote_cache_opts.preflight,
             timeout: remote_cache_opts.timeout,
             enabled: remote_cache_opts.enabled,
+            shared_directory: remote_cache_opts.shared_directory.clone(),
             ..Self::default()
         }
     }
 }
 
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawLocalCacheOptions {
+    // Human readable size, e.g. "10GB"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_size: Option<String>,
+    // Human readable age, e.g. "7d"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_age: Option<String>,
+}
+
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawOtelOptions {
+    // Base URL of an OTLP/HTTP collector, e.g. "http://localhost:4318"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) service_name: Option<String>,
+}
+
+// Where `turbo prune` copies an extra file to. Without `--docker` there is
+// only the full output, so `json` entries aren't copied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum PruneDestination {
+    Json,
+    Full,
+    Both,
+}
+
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawPruneOptions {
+    // Files, directories or globs relative to the repository root, mapped to
+    // the outputs they are copied to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) include: Option<BTreeMap<String, PruneDestination>>,
+}
+
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawBoundariesOptions {
+    // Tags that the packages this package imports must have one of
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) allow: Option<Vec<String>>,
+    // Tags that the packages this package imports must not have
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deny: Option<Vec<String>>,
+}
+
 #[derive(Serialize, Default, Debug, Clone, Iterable)]
 #[serde(rename_all = "camelCase")]
 // The raw deserialized turbo.json file.
@@ -127,6 +189,24 @@ pub struct RawTurboJson {
     // Configuration options when interfacing with the remote cache
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) remote_cache: Option<RawRemoteCacheOptions>,
+    // Limits applied to the local file system cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) local_cache: Option<RawLocalCacheOptions>,
+    // Where traces of each run are exported to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) otel: Option<RawOtelOptions>,
+    // Capacities of named resources that tasks can reserve while running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<BTreeMap<String, u32>>,
+    // Tags that `boundaries` rules of other packages refer to this package by
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<UnescapedString>>,
+    // Which tagged packages this package is allowed to import
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) boundaries: Option<RawBoundariesOptions>,
+    // Extra root files copied by `turbo prune`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) prune: Option<RawPruneOptions>,
 }
 
 #[derive(Serialize, Default, Debug, PartialEq, Clone)]
@@ -175,9 +255,17 @@ pub struct RawTaskDefinition {
     #[serde(skip_serializing_if = "Option::is_none")]
     persistent: Option<Spanned<bool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    interactive: Option<Spanned<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     outputs: Option<Vec<Spanned<UnescapedString>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     output_mode: Option<Spanned<OutputLogsMode>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<Spanned<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<Spanned<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<Spanned<BTreeMap<String, u32>>>,
 }
 
 macro_rules! set_field {
@@ -201,13 +289,17 @@ impl RawTaskDefinition {
         set_field!(self, other, inputs);
         set_field!(self, other, output_mode);
         set_field!(self, other, persistent);
+        set_field!(self, other, interactive);
         set_field!(self, other, env);
         set_field!(self, other, pass_through_env);
         set_field!(self, other, dot_env);
+        set_field!(self, other, timeout);
+        set_field!(self, other, retries);
+        set_field!(self, other, resources);
     }
 }
 
-const CONFIG_FILE: &str = "turbo.json";
+pub(crate) const CONFIG_FILE: &str = "turbo.json";
 const ENV_PIPELINE_DELIMITER: &str = "$";
 const TOPOLOGICAL_PIPELINE_DELIMITER: &str = "^";
 
@@ -355,6 +447,15 @@ impl TryFrom<RawTaskDefinition> for TaskDefinition {
             dot_env,
             output_mode: *raw_task.output_mode.unwrap_or_default(),
             persistent: *raw_task.persistent.unwrap_or_default(),
+            interactive: *raw_task.interactive.unwrap_or_default(),
+            timeout: raw_task
+                .timeout
+                .map(|timeout| Duration::from_secs(timeout.into_inner())),
+            retries: raw_task.retries.map(|retries| *retries).unwrap_or_default(),
+            resources: raw_task
+                .resources
+                .map(|resources| resources.into_inner())
+                .unwrap_or_default(),
         })
     }
 }
@@ -499,6 +600,14 @@ impl TryFrom<RawTurboJson> for TurboJson {
                 })
                 .transpose()?,
             pipeline: raw_turbo.pipeline.unwrap_or_default(),
+            resources: raw_turbo.resources.unwrap_or_default(),
+            tags: raw_turbo
+                .tags
+                .unwrap_or_default()
+                .into_iter()
+                .map(|tag| tag.into())
+                .collect(),
+            boundaries: raw_turbo.boundaries,
             // copy these over, we don't need any changes here.
             extends: raw_turbo
                 .extends
@@ -699,7 +808,7 @@ fn gather_env_vars(
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use std::{collections::BTreeMap, fs, time::Duration};
 
     use anyhow::Result;
     use biome_deserialize::json::deserialize_from_json_str;
@@ -711,7 +820,7 @@ mod tests {
     use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, RelativeUnixPathBuf};
     use turborepo_repository::package_json::PackageJson;
 
-    use super::{Pipeline, RawTurboJson, Spanned};
+    use super::{Pipeline, PruneDestination, RawBoundariesOptions, RawTurboJson, Spanned};
     use crate::{
         cli::OutputLogsMode,
         run::task_id::TaskName,
@@ -733,6 +842,22 @@ mod tests {
             ..TurboJson::default()
         }
     ; "global dot env (unsorted)")]
+    #[test_case(r#"{ "resources": { "memory": 8 } }"#,
+        TurboJson {
+            resources: [("memory".to_string(), 8)].into_iter().collect(),
+            ..TurboJson::default()
+        }
+    ; "resources")]
+    #[test_case(r#"{ "tags": ["ui"], "boundaries": { "allow": ["shared"], "deny": ["app"] } }"#,
+        TurboJson {
+            tags: vec!["ui".to_string()],
+            boundaries: Some(RawBoundariesOptions {
+                allow: Some(vec!["shared".to_string()]),
+                deny: Some(vec!["app".to_string()]),
+            }),
+            ..TurboJson::default()
+        }
+    ; "boundaries")]
     #[test_case(r#"{ "globalPassThroughEnv": ["GITHUB_TOKEN", "AWS_SECRET_KEY"] }"#,
         TurboJson {
             global_pass_through_env: Some(vec!["AWS_SECRET_KEY".to_string(), "GITHUB_TOKEN".to_string()]),
@@ -860,6 +985,53 @@ mod tests {
         TaskDefinition::default()
     ; "just persistent"
     )]
+    #[test_case(
+        r#"{ "interactive": true }"#,
+        RawTaskDefinition {
+            interactive: Some(Spanned::new(true).with_range(17..21)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            interactive: true,
+            ..Default::default()
+        }
+    ; "interactive"
+    )]
+    #[test_case(
+        r#"{ "timeout": 60, "retries": 2 }"#,
+        RawTaskDefinition {
+            timeout: Some(Spanned::new(60).with_range(13..15)),
+            retries: Some(Spanned::new(2).with_range(28..29)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            timeout: Some(Duration::from_secs(60)),
+            retries: 2,
+            ..Default::default()
+        }
+    ; "timeout and retries"
+    )]
+    #[test_case(
+        r#"{ "resources": { "memory": 4, "browser": 1 } }"#,
+        RawTaskDefinition {
+            resources: Some(
+                Spanned::new(
+                    [("memory".to_string(), 4), ("browser".to_string(), 1)]
+                        .into_iter()
+                        .collect::<BTreeMap<_, _>>()
+                )
+                .with_range(15..44)
+            ),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            resources: [("memory".to_string(), 4), ("browser".to_string(), 1)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }
+    ; "resources"
+    )]
     #[test_case(
         r#"{ "dotEnv": [] }"#,
         RawTaskDefinition {
@@ -899,6 +1071,10 @@ mod tests {
             inputs: Some(vec![Spanned::<UnescapedString>::new("package/a/src/**".into()).with_range(241..259)]),
             output_mode: Some(Spanned::new(OutputLogsMode::Full).with_range(286..292)),
             persistent: Some(Spanned::new(true).with_range(318..322)),
+            interactive: None,
+            timeout: None,
+            retries: None,
+            resources: None,
         },
         TaskDefinition {
           dot_env: Some(vec![RelativeUnixPathBuf::new("package/a/.env").unwrap()]),
@@ -914,6 +1090,10 @@ mod tests {
           task_dependencies: vec!["cli#build".into()],
           topological_dependencies: vec![],
           persistent: true,
+          interactive: false,
+          timeout: None,
+          retries: 0,
+          resources: BTreeMap::new(),
         }
       ; "full"
     )]
@@ -939,6 +1119,10 @@ mod tests {
             inputs: Some(vec![Spanned::<UnescapedString>::new("package\\a\\src\\**".into()).with_range(273..294)]),
             output_mode: Some(Spanned::new(OutputLogsMode::Full).with_range(325..331)),
             persistent: Some(Spanned::new(true).with_range(361..365)),
+            interactive: None,
+            timeout: None,
+            retries: None,
+            resources: None,
         },
         TaskDefinition {
             dot_env: Some(vec![RelativeUnixPathBuf::new("package\\a\\.env").unwrap()]),
@@ -954,6 +1138,10 @@ mod tests {
             task_dependencies: vec!["cli#build".into()],
             topological_dependencies: vec![],
             persistent: true,
+            interactive: false,
+            timeout: None,
+            retries: 0,
+            resources: BTreeMap::new(),
         }
       ; "full (windows)"
     )]
@@ -1069,4 +1257,50 @@ mod tests {
             .map(|mode| mode.into_inner());
         assert_eq!(actual, expected);
     }
+
+    #[test_case("json", Some(PruneDestination::Json) ; "json")]
+    #[test_case("full", Some(PruneDestination::Full) ; "full")]
+    #[test_case("both", Some(PruneDestination::Both) ; "both")]
+    #[test_case("docker", None ; "invalid value")]
+    fn test_parsing_prune_destination(destination: &str, expected: Option<PruneDestination>) {
+        let json: Result<RawTurboJson, _> = RawTurboJson::parse_from_serde(json!({
+            "prune": {
+                "include": {
+                    "tsconfig.base.json": destination,
+                }
+            }
+        }));
+
+        let actual = json
+            .ok()
+            .and_then(|j| j.prune)
+            .and_then(|prune| prune.include)
+            .and_then(|include| include.get("tsconfig.base.json").copied());
+        assert_eq!(actual, expected);
+    }
+
+    #[test_case("tsconfig.base.json", true ; "file")]
+    #[test_case("config/**/*.json", true ; "glob")]
+    #[test_case("./scripts", true ; "dot prefix")]
+    #[test_case("../secrets.json", false ; "parent directory")]
+    #[test_case("config/../../secrets.json", false ; "nested parent directory")]
+    #[test_case("/etc/passwd", false ; "absolute")]
+    #[test_case("C:/Users/secrets.json", false ; "windows absolute")]
+    #[test_case("config\\tsconfig.json", false ; "windows separator")]
+    #[test_case("", false ; "empty")]
+    fn test_parsing_prune_include(entry: &str, is_valid: bool) {
+        let json: Result<RawTurboJson, _> = RawTurboJson::parse_from_serde(json!({
+            "prune": {
+                "include": {
+                    entry: "both",
+                }
+            }
+        }));
+
+        let include = json.ok().and_then(|j| j.prune).and_then(|p| p.include);
+        assert_eq!(include.is_some(), is_valid);
+        if let Some(include) = include {
+            assert_eq!(include.get(entry), Some(&PruneDestination::Both));
+        }
+    }
 }
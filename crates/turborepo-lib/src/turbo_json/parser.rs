@@ -20,7 +20,10 @@ use thiserror::Error;
 use turbopath::AnchoredSystemPath;
 use turborepo_errors::WithMetadata;
 
-use super::RawRemoteCacheOptions;
+use super::{
+    PruneDestination, RawBoundariesOptions, RawLocalCacheOptions, RawOtelOptions, RawPruneOptions,
+    RawRemoteCacheOptions,
+};
 use crate::{
     cli::OutputLogsMode,
     config::ConfigurationOptions,
@@ -122,6 +125,29 @@ impl Deserializable for OutputLogsMode {
     }
 }
 
+impl Deserializable for PruneDestination {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        let destination = String::deserialize(value, name, diagnostics)?;
+        match destination.as_str() {
+            "json" => Some(PruneDestination::Json),
+            "full" => Some(PruneDestination::Full),
+            "both" => Some(PruneDestination::Both),
+            _ => {
+                diagnostics.push(DeserializationDiagnostic::new_unknown_value(
+                    &destination,
+                    value.range(),
+                    &["json", "full", "both"],
+                ));
+                None
+            }
+        }
+    }
+}
+
 impl Deserializable for TaskName<'static> {
     fn deserialize(
         value: &impl DeserializableValue,
@@ -244,6 +270,11 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.persistent = Some(Spanned::new(persistent).with_range(range));
                     }
                 }
+                "interactive" => {
+                    if let Some(interactive) = bool::deserialize(&value, &key_text, diagnostics) {
+                        result.interactive = Some(Spanned::new(interactive).with_range(range));
+                    }
+                }
                 "outputs" => {
                     if let Some(outputs) = Vec::deserialize(&value, &key_text, diagnostics) {
                         result.outputs = Some(outputs);
@@ -256,6 +287,21 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.output_mode = Some(Spanned::new(output_mode).with_range(range));
                     }
                 }
+                "timeout" => {
+                    if let Some(timeout) = u64::deserialize(&value, &key_text, diagnostics) {
+                        result.timeout = Some(Spanned::new(timeout).with_range(range));
+                    }
+                }
+                "retries" => {
+                    if let Some(retries) = u32::deserialize(&value, &key_text, diagnostics) {
+                        result.retries = Some(Spanned::new(retries).with_range(range));
+                    }
+                }
+                "resources" => {
+                    if let Some(resources) = BTreeMap::deserialize(&value, &key_text, diagnostics) {
+                        result.resources = Some(Spanned::new(resources).with_range(range));
+                    }
+                }
                 unknown_key => {
                     diagnostics.push(create_unknown_key_diagnostic_from_struct(
                         &result,
@@ -404,6 +450,70 @@ impl DeserializationVisitor for RawRemoteCacheOptionsVisitor {
                         result.enabled = Some(enabled);
                     }
                 }
+                "sharedDirectory" => {
+                    if let Some(shared_directory) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.shared_directory = Some(shared_directory.into());
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Deserializable for RawLocalCacheOptions {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawLocalCacheOptionsVisitor, name, diagnostics)
+    }
+}
+
+struct RawLocalCacheOptionsVisitor;
+
+impl DeserializationVisitor for RawLocalCacheOptionsVisitor {
+    type Output = RawLocalCacheOptions;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawLocalCacheOptions::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "maxSize" => {
+                    if let Some(max_size) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.max_size = Some(max_size.into());
+                    }
+                }
+                "maxAge" => {
+                    if let Some(max_age) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.max_age = Some(max_age.into());
+                    }
+                }
                 unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
                     &result,
                     unknown_key,
@@ -415,6 +525,216 @@ impl DeserializationVisitor for RawRemoteCacheOptionsVisitor {
     }
 }
 
+impl Deserializable for RawOtelOptions {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawOtelOptionsVisitor, name, diagnostics)
+    }
+}
+
+struct RawOtelOptionsVisitor;
+
+impl DeserializationVisitor for RawOtelOptionsVisitor {
+    type Output = RawOtelOptions;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawOtelOptions::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "endpoint" => {
+                    if let Some(endpoint) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.endpoint = Some(endpoint.into());
+                    }
+                }
+                "serviceName" => {
+                    if let Some(service_name) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.service_name = Some(service_name.into());
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Deserializable for RawBoundariesOptions {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawBoundariesOptionsVisitor, name, diagnostics)
+    }
+}
+
+struct RawBoundariesOptionsVisitor;
+
+impl DeserializationVisitor for RawBoundariesOptionsVisitor {
+    type Output = RawBoundariesOptions;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawBoundariesOptions::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "allow" => {
+                    if let Some(allow) =
+                        Vec::<UnescapedString>::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.allow = Some(allow.into_iter().map(|tag| tag.into()).collect());
+                    }
+                }
+                "deny" => {
+                    if let Some(deny) =
+                        Vec::<UnescapedString>::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.deny = Some(deny.into_iter().map(|tag| tag.into()).collect());
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Deserializable for RawPruneOptions {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawPruneOptionsVisitor, name, diagnostics)
+    }
+}
+
+struct RawPruneOptionsVisitor;
+
+impl DeserializationVisitor for RawPruneOptionsVisitor {
+    type Output = RawPruneOptions;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawPruneOptions::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "include" => {
+                    if let Some(include) =
+                        value.deserialize(PruneIncludeVisitor, &key_text, diagnostics)
+                    {
+                        result.include = Some(include);
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+        Some(result)
+    }
+}
+
+// Entries are copied from the repository root into the pruned output, so
+// they must stay inside the repository
+struct PruneIncludeVisitor;
+
+impl DeserializationVisitor for PruneIncludeVisitor {
+    type Output = BTreeMap<String, PruneDestination>;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = BTreeMap::new();
+        for (key, value) in members.flatten() {
+            let Some(entry) = String::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            if !is_valid_prune_include(&entry) {
+                diagnostics.push(
+                    DeserializationDiagnostic::new(format!(
+                        "`{entry}` must be a relative unix path inside the repository"
+                    ))
+                    .with_range(key.range()),
+                );
+                continue;
+            }
+            if let Some(destination) = PruneDestination::deserialize(&value, &entry, diagnostics) {
+                result.insert(entry, destination);
+            }
+        }
+        Some(result)
+    }
+}
+
+fn is_valid_prune_include(entry: &str) -> bool {
+    !entry.is_empty()
+        && !entry.starts_with('/')
+        && !entry.contains('\\')
+        && !entry.contains(':')
+        && !entry.split('/').any(|segment| segment == "..")
+}
+
 struct ConfigurationOptionsVisitor;
 
 impl DeserializationVisitor for ConfigurationOptionsVisitor {
@@ -495,6 +815,48 @@ impl DeserializationVisitor for ConfigurationOptionsVisitor {
                         result.enabled = Some(enabled);
                     }
                 }
+                "sharedDirectory" => {
+                    if let Some(shared_directory) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.shared_directory = Some(shared_directory.into());
+                    }
+                }
+                "cacheMaxSize" => {
+                    if let Some(cache_max_size) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.cache_max_size = Some(cache_max_size.into());
+                    }
+                }
+                "cacheMaxAge" => {
+                    if let Some(cache_max_age) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.cache_max_age = Some(cache_max_age.into());
+                    }
+                }
+                "otelEndpoint" => {
+                    if let Some(otel_endpoint) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.otel_endpoint = Some(otel_endpoint.into());
+                    }
+                }
+                "otelServiceName" => {
+                    if let Some(otel_service_name) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.otel_service_name = Some(otel_service_name.into());
+                    }
+                }
+                "otelHeaders" => {
+                    if let Some(otel_headers) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.otel_headers = Some(otel_headers.into());
+                    }
+                }
                 unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
                     &result,
                     unknown_key,
@@ -595,6 +957,43 @@ impl DeserializationVisitor for RawTurboJsonVisitor {
                         result.remote_cache = Some(remote_cache);
                     }
                 }
+                "localCache" => {
+                    if let Some(local_cache) =
+                        RawLocalCacheOptions::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.local_cache = Some(local_cache);
+                    }
+                }
+                "otel" => {
+                    if let Some(otel) = RawOtelOptions::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.otel = Some(otel);
+                    }
+                }
+                "resources" => {
+                    if let Some(resources) = BTreeMap::deserialize(&value, &key_text, diagnostics) {
+                        result.resources = Some(resources);
+                    }
+                }
+                "tags" => {
+                    if let Some(tags) = Vec::deserialize(&value, &key_text, diagnostics) {
+                        result.tags = Some(tags);
+                    }
+                }
+                "boundaries" => {
+                    if let Some(boundaries) =
+                        RawBoundariesOptions::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.boundaries = Some(boundaries);
+                    }
+                }
+                "prune" => {
+                    if let Some(prune) =
+                        RawPruneOptions::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.prune = Some(prune);
+                    }
+                }
                 unknown_key => {
                     diagnostics.push(create_unknown_key_diagnostic_from_struct(
                         &result,
@@ -652,8 +1051,12 @@ impl WithMetadata for RawTaskDefinition {
         self.inputs.add_text(text.clone());
         self.pass_through_env.add_text(text.clone());
         self.persistent.add_text(text.clone());
+        self.interactive.add_text(text.clone());
         self.outputs.add_text(text.clone());
-        self.output_mode.add_text(text);
+        self.output_mode.add_text(text.clone());
+        self.timeout.add_text(text.clone());
+        self.retries.add_text(text.clone());
+        self.resources.add_text(text);
     }
 
     fn add_path(&mut self, path: Arc<str>) {
@@ -663,8 +1066,12 @@ impl WithMetadata for RawTaskDefinition {
         self.inputs.add_path(path.clone());
         self.pass_through_env.add_path(path.clone());
         self.persistent.add_path(path.clone());
+        self.interactive.add_path(path.clone());
         self.outputs.add_path(path.clone());
-        self.output_mode.add_path(path);
+        self.output_mode.add_path(path.clone());
+        self.timeout.add_path(path.clone());
+        self.retries.add_path(path.clone());
+        self.resources.add_path(path);
     }
 }
 
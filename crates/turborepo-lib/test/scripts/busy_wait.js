@@ -0,0 +1,3 @@
+const start = Date.now();
+while (Date.now() - start < 500) {}
+console.log("done");
@@ -1,46 +1,19 @@
-use std::{any::Any, str::FromStr};
+use std::{any::Any, fmt, str::FromStr};
 
-use serde::Deserialize;
-
-use crate::Lockfile;
-
-mod de;
-
-type Map<K, V> = std::collections::BTreeMap<K, V>;
-
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error("unable to parse: {0}")]
-    SymlParse(String),
-    #[error("unable to convert to structured syml: {0}")]
-    SymlStructure(#[from] serde_json::Error),
-    #[error("unexpected non-utf8 yarn.lock")]
-    NonUTF8(#[from] std::str::Utf8Error),
-    #[error("Turborepo cannot serialize Bun lockfiles.")]
-    NotImplemented(),
-}
+use crate::{yarn1, Lockfile, Yarn1Lockfile};
 
+/// A Bun lockfile, read from the yarn v1 formatted text that `bun bun.lockb`
+/// prints. Bun's binary format isn't something we can write, so encoding
+/// produces the same yarn v1 text, which is what `bun install --yarn` writes
+/// alongside `bun.lockb`.
 #[derive(Debug)]
 pub struct BunLockfile {
-    inner: Map<String, Entry>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Entry {
-    name: Option<String>,
-    version: String,
-    uid: Option<String>,
-    resolved: Option<String>,
-    integrity: Option<String>,
-    registry: Option<String>,
-    dependencies: Option<Map<String, String>>,
-    optional_dependencies: Option<Map<String, String>>,
+    data: Yarn1Lockfile,
 }
 
 impl BunLockfile {
     pub fn from_bytes(input: &[u8]) -> Result<Self, super::Error> {
-        let input = std::str::from_utf8(input).map_err(Error::from)?;
+        let input = std::str::from_utf8(input).map_err(yarn1::Error::from)?;
         Self::from_str(input)
     }
 }
@@ -49,30 +22,20 @@ impl FromStr for BunLockfile {
     type Err = super::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = de::parse_syml(s)?;
-        let inner = serde_json::from_value(value)?;
-        Ok(Self { inner })
+        let data = Yarn1Lockfile::from_str(s)?;
+        Ok(Self { data })
     }
 }
 
 impl Lockfile for BunLockfile {
-    #[tracing::instrument(skip(self, _workspace_path))]
+    #[tracing::instrument(skip(self, workspace_path))]
     fn resolve_package(
         &self,
-        _workspace_path: &str,
+        workspace_path: &str,
         name: &str,
         version: &str,
     ) -> Result<Option<crate::Package>, crate::Error> {
-        for key in possible_keys(name, version) {
-            if let Some(entry) = self.inner.get(&key) {
-                return Ok(Some(crate::Package {
-                    key,
-                    version: entry.version.clone(),
-                }));
-            }
-        }
-
-        Ok(None)
+        self.data.resolve_package(workspace_path, name, version)
     }
 
     #[tracing::instrument(skip(self))]
@@ -80,15 +43,7 @@ impl Lockfile for BunLockfile {
         &self,
         key: &str,
     ) -> Result<Option<std::collections::HashMap<String, String>>, crate::Error> {
-        let Some(entry) = self.inner.get(key) else {
-            return Ok(None);
-        };
-
-        let all_deps: std::collections::HashMap<_, _> = entry.dependency_entries().collect();
-        Ok(match all_deps.is_empty() {
-            false => Some(all_deps),
-            true => None,
-        })
+        self.data.all_dependencies(key)
     }
 
     fn subgraph(
@@ -96,20 +51,13 @@ impl Lockfile for BunLockfile {
         _workspace_packages: &[String],
         packages: &[String],
     ) -> Result<Box<dyn Lockfile>, super::Error> {
-        let mut inner = Map::new();
-
-        for (key, entry) in packages.iter().filter_map(|key| {
-            let entry = self.inner.get(key)?;
-            Some((key, entry))
-        }) {
-            inner.insert(key.clone(), entry.clone());
-        }
-
-        Ok(Box::new(Self { inner }))
+        Ok(Box::new(Self {
+            data: self.data.subset(packages),
+        }))
     }
 
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
-        Err(crate::Error::Bun(Error::NotImplemented()))
+        Ok(self.to_string().into_bytes())
     }
 
     fn global_change(&self, other: &dyn Lockfile) -> bool {
@@ -120,43 +68,127 @@ impl Lockfile for BunLockfile {
     }
 }
 
-impl Entry {
-    fn dependency_entries(&self) -> impl Iterator<Item = (String, String)> + '_ {
-        self.dependencies
-            .iter()
-            .flatten()
-            .chain(self.optional_dependencies.iter().flatten())
-            .map(|(k, v)| (k.clone(), v.clone()))
+impl fmt::Display for BunLockfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(yarn1::HEADER)?;
+        f.write_str("\n")?;
+        self.data.write_entries(f)
     }
 }
 
-const PROTOCOLS: &[&str] = ["", "npm:", "file:", "workspace:", "yarn:"].as_slice();
-
-fn possible_keys<'a>(name: &'a str, version: &'a str) -> impl Iterator<Item = String> + 'a {
-    PROTOCOLS
-        .iter()
-        .copied()
-        .map(move |protocol| format!("{name}@{protocol}{version}"))
-}
-
 #[cfg(test)]
 mod test {
+    use pretty_assertions::assert_eq;
+
     use super::*;
+
+    const BUN: &str = include_str!("../../fixtures/bun-v1.lock");
     const FULL: &str = include_str!("../../fixtures/yarn1full.lock");
 
+    #[test]
+    fn test_roundtrip() {
+        let lockfile = BunLockfile::from_str(FULL).unwrap();
+        assert_eq!(FULL, lockfile.to_string());
+    }
+
+    #[test]
+    fn test_encode_drops_bun_hash() {
+        // The hash identifies the binary lockfile the text was printed from,
+        // so it isn't carried over into the encoded lockfile
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        let expected = BUN
+            .lines()
+            .filter(|line| !line.starts_with("# bun ./bun.lockb --hash: "))
+            .map(|line| format!("{line}\n"))
+            .collect::<String>();
+        assert_eq!(lockfile.to_string(), expected);
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        let closure = crate::transitive_closure(
+            &lockfile,
+            "apps/web",
+            [("react".to_string(), "^18.2.0".to_string())]
+                .into_iter()
+                .collect(),
+        )
+        .unwrap();
+        let mut keys = closure.into_iter().map(|pkg| pkg.key).collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "js-tokens@^3.0.0 || ^4.0.0",
+                "loose-envify@^1.1.0",
+                "react@^18.2.0"
+            ]
+        );
+
+        let pruned = lockfile.subgraph(&["apps/web".into()], &keys).unwrap();
+        let encoded = String::from_utf8(pruned.encode().unwrap()).unwrap();
+        assert_eq!(
+            encoded,
+            r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+"js-tokens@^3.0.0 || ^4.0.0":
+  version "4.0.0"
+  resolved "https://registry.npmjs.org/js-tokens/-/js-tokens-4.0.0.tgz"
+  integrity sha512-RdJUflcE3cUzKiMqQgsCu06FPu9UdIJO0beYbPhHN4k6apgJtifcoCtT9bcxOpYBtpD2kCM6Sbzg4CausW/PKQ==
+
+loose-envify@^1.1.0:
+  version "1.4.0"
+  resolved "https://registry.npmjs.org/loose-envify/-/loose-envify-1.4.0.tgz"
+  integrity sha512-lyuxPGr/Wfhrlem2CL/UcnUc1zcqKAImBDzukY7Y5F/yQiNdko6+fRLevlw1HgMySw7f611UIY408EtxRSoK3Q==
+  dependencies:
+    js-tokens "^3.0.0 || ^4.0.0"
+
+react@^18.2.0:
+  version "18.2.0"
+  resolved "https://registry.npmjs.org/react/-/react-18.2.0.tgz"
+  integrity sha512-/3IjMdb2L9QbBdWiW5e3P2/npwMBaU9mHCSCUzNln0ZCYbcfTsGbTJrU/kGemdH2IWmB2ioZ+zkxtmq6g09fGQ==
+  dependencies:
+    loose-envify "^1.1.0"
+"#
+        );
+    }
+
     #[test]
     fn test_key_splitting() {
         let lockfile = BunLockfile::from_str(FULL).unwrap();
-        for key in [
-            "@babel/types@^7.18.10",
-            "@babel/types@^7.18.6",
-            "@babel/types@^7.19.0",
-        ] {
+        for version in ["^7.18.10", "^7.18.6", "^7.19.0"] {
             assert!(
-                lockfile.inner.contains_key(key),
-                "missing {} in lockfile",
-                key
+                lockfile
+                    .resolve_package("", "@babel/types", version)
+                    .unwrap()
+                    .is_some(),
+                "missing @babel/types@{} in lockfile",
+                version
             );
         }
     }
+
+    #[test]
+    fn test_encoded_lockfile_is_yarn_lockfile() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        let pruned = lockfile
+            .subgraph(&["apps/web".into()], &["react@^18.2.0".into()])
+            .unwrap();
+        let encoded = pruned.encode().unwrap();
+        let yarn_lockfile = Yarn1Lockfile::from_bytes(&encoded).unwrap();
+        assert_eq!(
+            yarn_lockfile
+                .resolve_package("apps/web", "react", "^18.2.0")
+                .unwrap()
+                .map(|package| package.version),
+            Some("18.2.0".to_string())
+        );
+        assert_eq!(
+            String::from_utf8(encoded).unwrap(),
+            yarn_lockfile.to_string()
+        );
+    }
 }
@@ -19,8 +19,6 @@ pub enum Error {
     #[error(transparent)]
     Yarn1(#[from] crate::yarn1::Error),
     #[error(transparent)]
-    Bun(#[from] crate::bun::Error),
-    #[error(transparent)]
     Berry(#[from] crate::berry::Error),
     #[error("lockfile contains invalid path: {0}")]
     Path(#[from] turbopath::PathError),
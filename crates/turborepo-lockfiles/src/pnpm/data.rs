@@ -26,6 +26,11 @@ pub struct PnpmLockfile {
     importers: Map<String, ProjectSnapshot>,
     #[serde(skip_serializing_if = "Option::is_none")]
     packages: Option<Map<String, PackageSnapshot>>,
+    // Starting with v9 the dependencies of a package are stored separately
+    // from its resolution, with one entry for each set of peers it's installed
+    // with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshots: Option<Map<String, Snapshot>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     time: Option<Map<String, String>>,
 }
@@ -103,6 +108,21 @@ pub struct PackageSnapshot {
     other: Map<String, serde_yaml::Value>,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependencies: Option<Map<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    optional_dependencies: Option<Map<String, String>>,
+
+    #[serde(flatten)]
+    other: Map<String, serde_yaml::Value>,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct DependenciesMeta {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -155,6 +175,21 @@ impl PnpmLockfile {
             .and_then(|packages| packages.get(key))
     }
 
+    fn get_snapshot(&self, key: &str) -> Option<&Snapshot> {
+        self.snapshots
+            .as_ref()
+            .and_then(|snapshots| snapshots.get(key))
+    }
+
+    // Checks if there's an installable package for the given key. For v9 these
+    // are tracked in snapshots as packages are only the resolution information.
+    fn has_package(&self, key: &str) -> bool {
+        match self.is_v9() {
+            true => self.get_snapshot(key).is_some(),
+            false => self.get_packages(key).is_some(),
+        }
+    }
+
     fn get_workspace(&self, workspace_path: &str) -> Result<&ProjectSnapshot, crate::Error> {
         let key = match workspace_path {
             // For pnpm, the root is named "."
@@ -171,16 +206,35 @@ impl PnpmLockfile {
         matches!(self.lockfile_version.format, super::VersionFormat::String)
     }
 
+    fn is_v9(&self) -> bool {
+        self.is_v6()
+            && self
+                .lockfile_version
+                .version
+                .split('.')
+                .next()
+                .and_then(|major| major.parse::<u32>().ok())
+                .map_or(false, |major| major >= 9)
+    }
+
     fn format_key(&self, name: &str, version: &str) -> String {
-        match self.is_v6() {
-            true => format!("/{name}@{version}"),
-            false => format!("/{name}/{version}"),
+        match (self.is_v9(), self.is_v6()) {
+            (true, _) => format!("{name}@{version}"),
+            (false, true) => format!("/{name}@{version}"),
+            (false, false) => format!("/{name}/{version}"),
         }
     }
 
+    fn dep_path<'a>(&self, key: &'a str) -> Result<DepPath<'a>, Error> {
+        Ok(match self.is_v9() {
+            true => DepPath::try_from_v9(key)?,
+            false => DepPath::try_from(key)?,
+        })
+    }
+
     // Extracts the version from a dependency path
     fn extract_version<'a>(&self, key: &'a str) -> Result<Cow<'a, str>, Error> {
-        let dp = DepPath::try_from(key)?;
+        let dp = self.dep_path(key)?;
         // If there's a suffix, the suffix gets included as part of the version
         // so we can track patch file changes
         if let Some(suffix) = dp.peer_suffix {
@@ -217,30 +271,28 @@ impl PnpmLockfile {
         else {
             // Check if the specifier is already an exact version
             return Ok(self
-                .get_packages(&self.format_key(name, specifier))
-                .and(Some(specifier)));
+                .has_package(&self.format_key(name, specifier))
+                .then_some(specifier));
         };
 
         let override_specifier = self.apply_overrides(name, specifier);
         if resolved_specifier == override_specifier {
             Ok(Some(resolved_version))
-        } else if self
-            .get_packages(&self.format_key(name, override_specifier))
-            .is_some()
-        {
+        } else if self.has_package(&self.format_key(name, override_specifier)) {
             Ok(Some(override_specifier))
         } else {
             Ok(None)
         }
     }
 
-    fn prune_patches(
+    fn prune_patches<'a>(
+        &self,
         patches: &Map<String, PatchFile>,
-        pruned_packages: &Map<String, PackageSnapshot>,
+        pruned_keys: impl Iterator<Item = &'a String>,
     ) -> Result<Map<String, PatchFile>, Error> {
         let mut pruned_patches = Map::new();
-        for dependency in pruned_packages.keys() {
-            let dp = DepPath::try_from(dependency.as_str())?;
+        for dependency in pruned_keys {
+            let dp = self.dep_path(dependency)?;
             let patch_key = format!("{}@{}", dp.name, dp.version);
             if let Some(patch) = patches
                 .get(&patch_key)
@@ -252,6 +304,34 @@ impl PnpmLockfile {
         Ok(pruned_patches)
     }
 
+    // Adds the package with the given key to a pruned lockfile
+    fn add_to_subgraph(
+        &self,
+        key: &str,
+        pruned_packages: &mut Map<String, PackageSnapshot>,
+        pruned_snapshots: &mut Map<String, Snapshot>,
+    ) -> Result<(), crate::Error> {
+        if self.is_v9() {
+            let snapshot = self
+                .get_snapshot(key)
+                .ok_or_else(|| crate::Error::MissingPackage(key.into()))?;
+            pruned_snapshots.insert(key.to_string(), snapshot.clone());
+            // The package entry is shared by every snapshot of the same version
+            let dp = self.dep_path(key)?;
+            let package_key = format!("{}@{}", dp.name, dp.version);
+            let entry = self
+                .get_packages(&package_key)
+                .ok_or_else(|| crate::Error::MissingPackage(package_key.clone()))?;
+            pruned_packages.insert(package_key, entry.clone());
+        } else {
+            let entry = self
+                .get_packages(key)
+                .ok_or_else(|| crate::Error::MissingPackage(key.into()))?;
+            pruned_packages.insert(key.to_string(), entry.clone());
+        }
+        Ok(())
+    }
+
     // Create a projection of all fields in the lockfile that could affect all
     // workspaces
     fn global_fields(&self) -> GlobalFields {
@@ -283,7 +363,7 @@ impl crate::Lockfile for PnpmLockfile {
         version: &str,
     ) -> Result<Option<crate::Package>, crate::Error> {
         // Check if version is a key
-        if self.get_packages(version).is_some() {
+        if self.has_package(version) {
             let extracted_version = self.extract_version(version)?;
             return Ok(Some(crate::Package {
                 key: version.into(),
@@ -297,16 +377,19 @@ impl crate::Lockfile for PnpmLockfile {
 
         let key = self.format_key(name, resolved_version);
 
-        if let Some(pkg) = self.get_packages(&key) {
+        if self.has_package(&key) {
             Ok(Some(crate::Package {
-                key,
-                version: pkg
-                    .version
-                    .clone()
+                version: self
+                    .get_packages(&key)
+                    .and_then(|pkg| pkg.version.clone())
                     .unwrap_or_else(|| resolved_version.to_string()),
+                key,
             }))
-        } else if let Some(pkg) = self.get_packages(resolved_version) {
-            let version = pkg.version.clone().map_or_else(
+        } else if self.has_package(resolved_version) {
+            let pkg_version = self
+                .get_packages(resolved_version)
+                .and_then(|pkg| pkg.version.clone());
+            let version = pkg_version.map_or_else(
                 || {
                     self.extract_version(resolved_version)
                         .map(|s| s.to_string())
@@ -327,15 +410,22 @@ impl crate::Lockfile for PnpmLockfile {
         &self,
         key: &str,
     ) -> Result<Option<std::collections::HashMap<String, String>>, crate::Error> {
-        let Some(entry) = self.packages.as_ref().and_then(|pkgs| pkgs.get(key)) else {
-            return Ok(None);
+        let (dependencies, optional_dependencies) = if self.is_v9() {
+            let Some(entry) = self.get_snapshot(key) else {
+                return Ok(None);
+            };
+            (&entry.dependencies, &entry.optional_dependencies)
+        } else {
+            let Some(entry) = self.get_packages(key) else {
+                return Ok(None);
+            };
+            (&entry.dependencies, &entry.optional_dependencies)
         };
         Ok(Some(
-            entry
-                .dependencies
+            dependencies
                 .iter()
                 .flatten()
-                .chain(entry.optional_dependencies.iter().flatten())
+                .chain(optional_dependencies.iter().flatten())
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect(),
         ))
@@ -354,11 +444,9 @@ impl crate::Lockfile for PnpmLockfile {
             .collect::<Map<_, _>>();
 
         let mut pruned_packages = Map::new();
+        let mut pruned_snapshots = Map::new();
         for package in packages {
-            let entry = self
-                .get_packages(package.as_str())
-                .ok_or_else(|| crate::Error::MissingPackage(package.clone()))?;
-            pruned_packages.insert(package.clone(), entry.clone());
+            self.add_to_subgraph(package, &mut pruned_packages, &mut pruned_snapshots)?;
         }
         for importer in importers.values() {
             // Find all injected packages in each workspace and include it in
@@ -378,17 +466,24 @@ impl crate::Lockfile for PnpmLockfile {
                     .find_resolution(dependency)
                     .ok_or_else(|| Error::MissingInjectedPackage(dependency.clone()))?;
 
-                let entry = self
-                    .get_packages(version)
-                    .ok_or_else(|| crate::Error::MissingPackage(version.into()))?;
-                pruned_packages.insert(version.to_string(), entry.clone());
+                // Starting with v9 the version no longer includes the name
+                let key = match self.is_v9() {
+                    true => self.format_key(dependency, version),
+                    false => version.to_string(),
+                };
+                self.add_to_subgraph(&key, &mut pruned_packages, &mut pruned_snapshots)?;
             }
         }
 
+        // Patch hashes only appear in the snapshot keys for v9
+        let pruned_keys: Vec<&String> = match self.is_v9() {
+            true => pruned_snapshots.keys().collect(),
+            false => pruned_packages.keys().collect(),
+        };
         let patches = self
             .patched_dependencies
             .as_ref()
-            .map(|patches| Self::prune_patches(patches, &pruned_packages))
+            .map(|patches| self.prune_patches(patches, pruned_keys.into_iter()))
             .transpose()?;
 
         Ok(Box::new(Self {
@@ -397,6 +492,10 @@ impl crate::Lockfile for PnpmLockfile {
                 false => Some(pruned_packages),
                 true => None,
             },
+            snapshots: match pruned_snapshots.is_empty() {
+                false => Some(pruned_snapshots),
+                true => None,
+            },
             lockfile_version: self.lockfile_version.clone(),
             never_built_dependencies: self.never_built_dependencies.clone(),
             only_built_dependencies: self.only_built_dependencies.clone(),
@@ -504,13 +603,14 @@ mod tests {
     const PNPM_OVERRIDE: &[u8] = include_bytes!("../../fixtures/pnpm-override.yaml").as_slice();
     const PNPM_PATCH: &[u8] = include_bytes!("../../fixtures/pnpm-patch.yaml").as_slice();
     const PNPM_PATCH_V6: &[u8] = include_bytes!("../../fixtures/pnpm-patch-v6.yaml").as_slice();
+    const PNPM9: &[u8] = include_bytes!("../../fixtures/pnpm9.yaml").as_slice();
 
     use super::*;
     use crate::{Lockfile, Package};
 
     #[test]
     fn test_roundtrip() {
-        for fixture in &[PNPM6, PNPM7, PNPM8, PNPM8_6] {
+        for fixture in &[PNPM6, PNPM7, PNPM8, PNPM8_6, PNPM9] {
             let lockfile = PnpmLockfile::from_bytes(fixture).unwrap();
             let serialized_lockfile = serde_yaml::to_string(&lockfile).unwrap();
             let lockfile_from_serialized =
@@ -719,6 +819,39 @@ mod tests {
         }))
         ; "pnpm override"
     )]
+    #[test_case(
+        PNPM9,
+        "apps/web",
+        "react-dom",
+        "^18.2.0",
+        Ok(Some(crate::Package {
+            key: "react-dom@18.2.0(react@18.2.0)".into(),
+            version: "18.2.0(react@18.2.0)".into(),
+        }))
+        ; "v9 peer package"
+    )]
+    #[test_case(
+        PNPM9,
+        "packages/ui",
+        "string-width-cjs",
+        "npm:string-width@^4.2.0",
+        Ok(Some(crate::Package {
+            key: "string-width@4.2.3".into(),
+            version: "4.2.3".into(),
+        }))
+        ; "v9 alias"
+    )]
+    #[test_case(
+        PNPM9,
+        "packages/ui",
+        "is-number",
+        "6.0.0",
+        Ok(Some(crate::Package {
+            key: "is-number@6.0.0".into(),
+            version: "6.0.0".into(),
+        }))
+        ; "v9 transitive package"
+    )]
     fn test_resolve_package(
         lockfile: &[u8],
         workspace_path: &str,
@@ -799,6 +932,84 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_v9_transitive_closure() {
+        let lockfile = PnpmLockfile::from_bytes(PNPM9).unwrap();
+        let mut unresolved = std::collections::HashMap::new();
+        unresolved.insert(
+            "apps/web".to_string(),
+            [
+                ("react".to_string(), "^18.2.0".to_string()),
+                ("react-dom".to_string(), "^18.2.0".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let closures = crate::all_transitive_closures(&lockfile, unresolved).unwrap();
+        let mut closure = closures
+            .get("apps/web")
+            .unwrap()
+            .iter()
+            .map(|pkg| pkg.key.as_str())
+            .collect::<Vec<_>>();
+        closure.sort();
+        assert_eq!(
+            closure,
+            vec![
+                "js-tokens@4.0.0",
+                "loose-envify@1.4.0",
+                "react-dom@18.2.0(react@18.2.0)",
+                "react@18.2.0",
+                "scheduler@0.23.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_v9_subgraph() {
+        let lockfile = PnpmLockfile::from_bytes(PNPM9).unwrap();
+        let pruned = lockfile
+            .subgraph(
+                &["packages/ui".into()],
+                &[
+                    "is-odd@3.0.1(patch_hash=nrrwwz7lemethtlvvm75r5bmhq)".into(),
+                    "is-number@6.0.0".into(),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            pruned.patches().unwrap(),
+            vec![RelativeUnixPathBuf::new("patches/is-odd@3.0.1.patch").unwrap()]
+        );
+        let pruned = PnpmLockfile::from_bytes(&pruned.encode().unwrap()).unwrap();
+        assert_eq!(
+            pruned.packages.as_ref().unwrap().keys().collect::<Vec<_>>(),
+            vec!["is-number@6.0.0", "is-odd@3.0.1"]
+        );
+        assert_eq!(
+            pruned
+                .snapshots
+                .as_ref()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![
+                "is-number@6.0.0",
+                "is-odd@3.0.1(patch_hash=nrrwwz7lemethtlvvm75r5bmhq)"
+            ]
+        );
+        assert_eq!(
+            pruned.importers.keys().collect::<Vec<_>>(),
+            vec![".", "packages/ui"]
+        );
+    }
+
+    #[test]
+    fn test_v9_global_change() {
+        assert!(pnpm_global_change(PNPM8, PNPM9).unwrap());
+        assert!(!pnpm_global_change(PNPM9, PNPM9).unwrap());
+    }
+
     #[test]
     fn test_pnpm_alias_overlap() {
         let lockfile = PnpmLockfile::from_bytes(PNPM_ABSOLUTE).unwrap();
@@ -25,6 +25,15 @@ impl<'a> DepPath<'a> {
         }
     }
 
+    /// Parses a v9 dependency path. These are `name@version` followed by
+    /// any suffixes, without the leading `/` or host of earlier versions.
+    pub fn try_from_v9(value: &'a str) -> Result<Self, nom::error::Error<String>> {
+        let (_, dep_path) = parse_v9_dep_path(value)
+            .map_err(|e| e.to_owned())
+            .finish()?;
+        Ok(dep_path)
+    }
+
     pub fn with_host(mut self, host: Option<&'a str>) -> Self {
         self.host = host;
         self
@@ -82,6 +91,20 @@ fn parse_dep_path(i: &str) -> IResult<&str, DepPath> {
     ))
 }
 
+fn parse_v9_dep_path(i: &str) -> IResult<&str, DepPath> {
+    let (i, name) = parse_name(i)?;
+    let (i, _) = nom::character::complete::char('@')(i)?;
+    // Versions may contain '_' when they're URLs or file paths, with v9 all
+    // metadata is wrapped in parens
+    let (i, version) = is_not("(")(i)?;
+    let (i, peer_suffix) = opt(parse_new_peer_suffix)(i)?;
+    let (_, _) = nom::combinator::eof(i)?;
+    Ok((
+        "",
+        DepPath::new(name, version).with_peer_suffix(peer_suffix),
+    ))
+}
+
 fn parse_host(i: &str) -> IResult<&str, Option<&str>> {
     let (i, host) = opt(is_not("/"))(i)?;
     Ok((i, host))
@@ -116,9 +139,22 @@ fn parse_new_peer_suffix(i: &str) -> IResult<&str, &str> {
 
 fn parse_v6_suffix(i: &str) -> IResult<&str, &str> {
     let (i, _) = tag("(")(i)?;
-    let (i, entry) = is_not(")")(i)?;
-    let (i, _) = tag(")")(i)?;
-    Ok((i, entry))
+    // Suffixes can be nested if a peer dependency has peers of its own
+    // e.g. (react-dom@18.2.0(react@18.2.0))
+    let mut depth = 0usize;
+    for (idx, c) in i.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            ')' if idx > 0 => return Ok((&i[idx + 1..], &i[..idx])),
+            ')' => break,
+            _ => {}
+        }
+    }
+    Err(nom::Err::Error(nom::error::Error::new(
+        i,
+        nom::error::ErrorKind::IsNot,
+    )))
 }
 
 fn parse_v6_suffixes(i: &str) -> IResult<&str, Vec<&str>> {
@@ -143,6 +179,7 @@ mod tests {
     #[test_case("/is-even@1.0.0_foobar", DepPath::new("is-even", "1.0.0").with_peer_suffix(Some("foobar")); "v6 dep path with suffix")]
     #[test_case("/foo@1.0.0(bar@1.0.0)(baz@1.0.0)", DepPath::new("foo", "1.0.0").with_peer_suffix(Some("(bar@1.0.0)(baz@1.0.0)")); "v6 with multiple peers")]
     #[test_case("/@babel/helper-string-parser@7.19.4(patch_hash=wjhgmpzh47qmycrzgpeyoyh3ce)(@babel/core@7.21.0)", DepPath::new("@babel/helper-string-parser", "7.19.4").with_peer_suffix(Some("(patch_hash=wjhgmpzh47qmycrzgpeyoyh3ce)(@babel/core@7.21.0)")); "v6 with scope")]
+    #[test_case("/foo@1.0.0(bar@1.0.0(baz@1.0.0))", DepPath::new("foo", "1.0.0").with_peer_suffix(Some("(bar@1.0.0(baz@1.0.0))")); "v6 with nested peers")]
     fn dep_path_parse_tests(s: &str, expected: DepPath) {
         let (rest, actual) = parse_dep_path(s).unwrap();
         assert_eq!(rest, "");
@@ -153,8 +190,19 @@ mod tests {
     #[test_case("/foo/1.0.0_patchHash_peerHash", Some("patchHash"); "pre v6 patch")]
     #[test_case("/foo/1.0.0", None; "no suffix")]
     #[test_case("/foo/1.0.0(bar@1.0.0)", None; "no patch")]
+    #[test_case("/foo@1.0.0(bar@1.0.0(baz@1.0.0))(patch_hash=abc)", Some("abc"); "nested peers and patch")]
     fn dep_path_patch_hash(input: &str, expected: Option<&str>) {
         let dep_path = DepPath::try_from(input).unwrap();
         assert_eq!(dep_path.patch_hash(), expected);
     }
+
+    #[test_case("foo@1.0.0", DepPath::new("foo", "1.0.0"); "basic v9 dep path")]
+    #[test_case("@babel/core@7.21.0", DepPath::new("@babel/core", "7.21.0"); "v9 with scope")]
+    #[test_case("react-dom@18.2.0(react@18.2.0)", DepPath::new("react-dom", "18.2.0").with_peer_suffix(Some("(react@18.2.0)")); "v9 with peer")]
+    #[test_case("next@14.1.0(react-dom@18.2.0(react@18.2.0))(react@18.2.0)", DepPath::new("next", "14.1.0").with_peer_suffix(Some("(react-dom@18.2.0(react@18.2.0))(react@18.2.0)")); "v9 with nested peers")]
+    #[test_case("is-odd@3.0.1(patch_hash=nrrwwz7lemethtlvvm75r5bmhq)", DepPath::new("is-odd", "3.0.1").with_peer_suffix(Some("(patch_hash=nrrwwz7lemethtlvvm75r5bmhq)")); "v9 with patch")]
+    #[test_case("ui@file:packages/ui_lib", DepPath::new("ui", "file:packages/ui_lib"); "v9 file dependency")]
+    fn v9_dep_path_parse_tests(s: &str, expected: DepPath) {
+        assert_eq!(DepPath::try_from_v9(s).unwrap(), expected);
+    }
 }
@@ -7,6 +7,8 @@ use crate::Lockfile;
 mod de;
 mod ser;
 
+pub(crate) use ser::HEADER;
+
 type Map<K, V> = std::collections::BTreeMap<K, V>;
 
 #[derive(Debug, thiserror::Error)]
@@ -42,6 +44,20 @@ impl Yarn1Lockfile {
         let input = std::str::from_utf8(input).map_err(Error::from)?;
         Self::from_str(input)
     }
+
+    /// A lockfile containing only the given package keys
+    pub(crate) fn subset(&self, packages: &[String]) -> Self {
+        let mut inner = Map::new();
+
+        for (key, entry) in packages.iter().filter_map(|key| {
+            let entry = self.inner.get(key)?;
+            Some((key, entry))
+        }) {
+            inner.insert(key.clone(), entry.clone());
+        }
+
+        Self { inner }
+    }
 }
 
 impl FromStr for Yarn1Lockfile {
@@ -95,16 +111,7 @@ impl Lockfile for Yarn1Lockfile {
         _workspace_packages: &[String],
         packages: &[String],
     ) -> Result<Box<dyn Lockfile>, super::Error> {
-        let mut inner = Map::new();
-
-        for (key, entry) in packages.iter().filter_map(|key| {
-            let entry = self.inner.get(key)?;
-            Some((key, entry))
-        }) {
-            inner.insert(key.clone(), entry.clone());
-        }
-
-        Ok(Box::new(Self { inner }))
+        Ok(Box::new(self.subset(packages)))
     }
 
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
@@ -8,6 +8,10 @@ use super::{Entry, Yarn1Lockfile};
 
 const INDENT: &str = "  ";
 
+/// Comment at the top of every yarn v1 lockfile
+pub(crate) const HEADER: &str =
+    "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile v1\n";
+
 impl Yarn1Lockfile {
     fn reverse_lookup(&self) -> HashMap<&Entry, HashSet<&str>> {
         let mut reverse_lookup = HashMap::new();
@@ -17,14 +21,10 @@ impl Yarn1Lockfile {
         }
         reverse_lookup
     }
-}
 
-impl fmt::Display for Yarn1Lockfile {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(
-            "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile \
-             v1\n\n",
-        )?;
+    /// Writes every entry of the lockfile, without the header comment. This
+    /// is shared with Bun, which prints its lockfile in the same format.
+    pub(crate) fn write_entries(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let reverse_lookup = self.reverse_lookup();
         let mut added_keys: HashSet<&str> = HashSet::with_capacity(self.inner.len());
         for (key, entry) in self.inner.iter() {
@@ -49,6 +49,14 @@ impl fmt::Display for Yarn1Lockfile {
     }
 }
 
+impl fmt::Display for Yarn1Lockfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(HEADER)?;
+        f.write_str("\n")?;
+        self.write_entries(f)
+    }
+}
+
 impl fmt::Display for Entry {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut leading = LeadingNewline::new();
@@ -87,7 +95,7 @@ impl fmt::Display for Entry {
         }
         if let Some(registry) = &self.registry {
             f.write_fmt(format_args!(
-                "{}{INDENT}integrity {}",
+                "{}{INDENT}registry {}",
                 leading.leading(),
                 maybe_wrap(registry)
             ))?;
@@ -191,6 +199,7 @@ mod test {
             version: "12.2.5".into(),
             resolved: Some("https://registry.yarnpkg.com/next/-/next-12.2.5.tgz#14fb5975e8841fad09553b8ef41fe1393602b717".into()),
             integrity: Some("sha512-tBdjqX5XC/oFs/6gxrZhjmiq90YWizUYU6qOWAfat7zJwrwapJ+BYgX2PmiacunXMaRpeVT4vz5MSPSLgNkrpA==".into()),
+            registry: Some("https://registry.npmjs.org/".into()),
             dependencies: Some(vec![
                 ("@next/env".into(), "12.2.5".into()),
                 ("caniuse-lite".into(), "^1.0.30001332".into()),
@@ -204,6 +213,7 @@ mod test {
             r#"  version "12.2.5"
   resolved "https://registry.yarnpkg.com/next/-/next-12.2.5.tgz#14fb5975e8841fad09553b8ef41fe1393602b717"
   integrity sha512-tBdjqX5XC/oFs/6gxrZhjmiq90YWizUYU6qOWAfat7zJwrwapJ+BYgX2PmiacunXMaRpeVT4vz5MSPSLgNkrpA==
+  registry "https://registry.npmjs.org/"
   dependencies:
     "@next/env" "12.2.5"
     caniuse-lite "^1.0.30001332"
@@ -0,0 +1,301 @@
+//! Finds tasks and their references in turbo.json and package.json files.
+//!
+//! All ranges are byte ranges into the source text. Task ranges exclude the
+//! surrounding quotes so they can be replaced directly when renaming.
+
+use jsonc_parser::{
+    ast::{Object, ObjectPropName, StringLit},
+    common::Range,
+};
+
+/// Keys of a task definition whose values are lists of env var names
+const ENV_KEYS: &[&str] = &["env", "passThroughEnv"];
+/// Top level keys whose values are lists of env var names
+const GLOBAL_ENV_KEYS: &[&str] = &["globalEnv", "globalPassThroughEnv"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// A key in the pipeline, like `"build": {}`
+    Definition,
+    /// An entry in a task's `dependsOn`
+    DependsOn,
+}
+
+/// A task named in a turbo.json, such as `build`, `^build` or `web#build`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskReference {
+    pub kind: ReferenceKind,
+    pub package: Option<String>,
+    pub task: String,
+    /// Whether the task refers to the task in the package's dependencies
+    pub dependencies: bool,
+    /// The range of the task name, without a `^` or `package#` prefix
+    pub task_range: Range,
+}
+
+impl TaskReference {
+    fn new(kind: ReferenceKind, lit: &StringLit) -> Self {
+        // skip the opening quote
+        let mut start = lit.range.start + 1;
+        let mut value = lit.value.as_ref();
+
+        let dependencies = match value.strip_prefix('^') {
+            Some(rest) => {
+                start += 1;
+                value = rest;
+                true
+            }
+            None => false,
+        };
+        let package = match value.split_once('#') {
+            Some((package, task)) => {
+                start += package.len() + 1;
+                value = task;
+                Some(package.to_string())
+            }
+            None => None,
+        };
+
+        Self {
+            kind,
+            package,
+            task: value.to_string(),
+            dependencies,
+            task_range: Range {
+                start,
+                end: start + value.len(),
+            },
+        }
+    }
+}
+
+/// What the cursor is on in a turbo.json
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// A key in a task definition, like `outputs`
+    TaskDefinitionKey(String),
+    /// A pipeline key or `dependsOn` entry
+    Task(TaskReference),
+    /// An entry of `env`, `passThroughEnv` or their global equivalents
+    EnvVar,
+}
+
+fn contains(range: Range, offset: usize) -> bool {
+    range.start <= offset && offset <= range.end
+}
+
+fn parse_object<T>(text: &str, f: impl FnOnce(&Object) -> T) -> Option<T> {
+    let parse = jsonc_parser::parse_to_ast(text, &Default::default(), &Default::default()).ok()?;
+    let object = parse.value.as_ref()?.as_object()?;
+    Some(f(object))
+}
+
+/// Finds what is at the byte `offset` of a turbo.json
+pub fn target_at(text: &str, offset: usize) -> Option<Target> {
+    parse_object(text, |root| {
+        let in_global_env = GLOBAL_ENV_KEYS.iter().any(|key| {
+            root.get_array(key)
+                .is_some_and(|array| contains(array.range, offset))
+        });
+        if in_global_env {
+            return Some(Target::EnvVar);
+        }
+
+        for task in &root.get_object("pipeline")?.properties {
+            if let ObjectPropName::String(name) = &task.name {
+                if contains(name.range, offset) {
+                    return Some(Target::Task(TaskReference::new(
+                        ReferenceKind::Definition,
+                        name,
+                    )));
+                }
+            }
+            let Some(definition) = task.value.as_object() else {
+                continue;
+            };
+            if !contains(definition.range, offset) {
+                continue;
+            }
+
+            for property in &definition.properties {
+                if let ObjectPropName::String(key) = &property.name {
+                    if contains(key.range, offset) {
+                        return Some(Target::TaskDefinitionKey(key.value.to_string()));
+                    }
+                }
+                let Some(array) = property.value.as_array() else {
+                    continue;
+                };
+                if !contains(array.range, offset) {
+                    continue;
+                }
+                let key = property.name.as_str();
+                if ENV_KEYS.contains(&key) {
+                    return Some(Target::EnvVar);
+                }
+                if key == "dependsOn" {
+                    return array
+                        .elements
+                        .iter()
+                        .filter_map(|element| element.as_string_lit())
+                        .find(|lit| contains(lit.range, offset))
+                        .map(|lit| {
+                            Target::Task(TaskReference::new(ReferenceKind::DependsOn, lit))
+                        });
+                }
+            }
+        }
+
+        None
+    })
+    .flatten()
+}
+
+/// Every pipeline key and `dependsOn` entry in a turbo.json. Env var
+/// dependencies using the deprecated `$` syntax are skipped.
+pub fn task_references(text: &str) -> Vec<TaskReference> {
+    parse_object(text, |root| {
+        let mut references = vec![];
+        let pipeline = root.get_object("pipeline").map(|p| p.properties.iter());
+        for task in pipeline.into_iter().flatten() {
+            if let ObjectPropName::String(name) = &task.name {
+                references.push(TaskReference::new(ReferenceKind::Definition, name));
+            }
+            let depends_on = task
+                .value
+                .as_object()
+                .and_then(|definition| definition.get_array("dependsOn"))
+                .map(|array| array.elements.iter());
+            references.extend(
+                depends_on
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|element| element.as_string_lit())
+                    .filter(|lit| !lit.value.starts_with('$'))
+                    .map(|lit| TaskReference::new(ReferenceKind::DependsOn, lit)),
+            );
+        }
+        references
+    })
+    .unwrap_or_default()
+}
+
+/// The range of the key of `script` in a package.json's `scripts`
+pub fn script_range(text: &str, script: &str) -> Option<Range> {
+    parse_object(text, |root| {
+        root.get_object("scripts")?
+            .properties
+            .iter()
+            .find(|property| property.name.as_str() == script)
+            .map(|property| match &property.name {
+                ObjectPropName::String(lit) => lit.range,
+                ObjectPropName::Word(lit) => lit.range,
+            })
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    const TURBO_JSON: &str = r#"{
+  "globalEnv": ["CI"],
+  "pipeline": {
+    "build": {
+      "dependsOn": ["^build", "web#codegen", "$OLD_ENV"],
+      "env": ["API_URL"],
+      "outputs": ["dist/**"]
+    },
+    "web#lint": {}
+  }
+}"#;
+
+    fn offset_of(needle: &str) -> usize {
+        TURBO_JSON.find(needle).expect("needle is in turbo.json")
+    }
+
+    fn reference(
+        kind: ReferenceKind,
+        package: Option<&str>,
+        task: &str,
+        dependencies: bool,
+        start: usize,
+    ) -> TaskReference {
+        TaskReference {
+            kind,
+            package: package.map(|p| p.to_string()),
+            task: task.to_string(),
+            dependencies,
+            task_range: Range {
+                start,
+                end: start + task.len(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_task_references() {
+        assert_eq!(
+            task_references(TURBO_JSON),
+            vec![
+                reference(
+                    ReferenceKind::Definition,
+                    None,
+                    "build",
+                    false,
+                    offset_of("build\": {")
+                ),
+                reference(
+                    ReferenceKind::DependsOn,
+                    None,
+                    "build",
+                    true,
+                    offset_of("^build") + 1
+                ),
+                reference(
+                    ReferenceKind::DependsOn,
+                    Some("web"),
+                    "codegen",
+                    false,
+                    offset_of("codegen")
+                ),
+                reference(
+                    ReferenceKind::Definition,
+                    Some("web"),
+                    "lint",
+                    false,
+                    offset_of("lint")
+                ),
+            ]
+        );
+    }
+
+    #[test_case("outputs", Some(Target::TaskDefinitionKey("outputs".into())) ; "definition key")]
+    #[test_case("API_URL", Some(Target::EnvVar) ; "env")]
+    #[test_case("CI", Some(Target::EnvVar) ; "global env")]
+    #[test_case("dist", None ; "outputs entry")]
+    fn test_target_at(needle: &str, expected: Option<Target>) {
+        assert_eq!(target_at(TURBO_JSON, offset_of(needle) + 1), expected);
+    }
+
+    #[test]
+    fn test_target_at_task() {
+        let Some(Target::Task(reference)) = target_at(TURBO_JSON, offset_of("codegen") + 2) else {
+            panic!("expected a task");
+        };
+        assert_eq!(reference.package.as_deref(), Some("web"));
+        assert_eq!(reference.task, "codegen");
+        assert_eq!(reference.kind, ReferenceKind::DependsOn);
+    }
+
+    #[test]
+    fn test_script_range() {
+        let package_json = r#"{ "name": "web", "scripts": { "build": "tsc", "lint": "eslint" } }"#;
+        let range = script_range(package_json, "lint").expect("lint is a script");
+        assert_eq!(&package_json[range.start..range.end], "\"lint\"");
+        assert_eq!(script_range(package_json, "test"), None);
+    }
+}
@@ -0,0 +1,66 @@
+//! Hover documentation for the keys of a task definition in turbo.json
+
+/// Markdown documentation for a key of a task definition, if it is one turbo
+/// understands
+pub fn task_definition_key(key: &str) -> Option<&'static str> {
+    let docs = match key {
+        "cache" => {
+            "`cache`: `boolean`\n\nWhether to cache the outputs of this task. Defaults to `true`. \
+             Tasks that aren't cached always run."
+        }
+        "dependsOn" => {
+            "`dependsOn`: `string[]`\n\nThe tasks that must complete before this task runs.\n\n- \
+             `build` runs the `build` task of the same package first\n- `^build` runs the `build` \
+             task of the package's dependencies first\n- `web#build` runs the `build` task of the \
+             `web` package first"
+        }
+        "dotEnv" => {
+            "`dotEnv`: `string[]`\n\nAn ordered list of `.env` files, relative to the package, \
+             whose contents are included in this task's hash."
+        }
+        "env" => {
+            "`env`: `string[]`\n\nEnvironment variables whose values are included in this task's \
+             hash. Supports wildcards like `NEXT_PUBLIC_*` and exclusions like `!SECRET`."
+        }
+        "inputs" => {
+            "`inputs`: `string[]`\n\nGlobs of the files, relative to the package, that are \
+             included in this task's hash. Defaults to every file in the package that is checked \
+             into source control."
+        }
+        "passThroughEnv" => {
+            "`passThroughEnv`: `string[]`\n\nEnvironment variables that are made available to this \
+             task in strict env mode without being included in its hash."
+        }
+        "persistent" => {
+            "`persistent`: `boolean`\n\nMarks a long running task, like a dev server, that never \
+             exits. Other tasks can't depend on a persistent task."
+        }
+        "interactive" => {
+            "`interactive`: `boolean`\n\nMarks a task that reads input, like a prompt. turbo \
+             forwards its stdin to one interactive task at a time."
+        }
+        "outputs" => {
+            "`outputs`: `string[]`\n\nGlobs of the files, relative to the package, that this task \
+             produces. They are stored in the cache and restored on a cache hit."
+        }
+        "outputMode" => {
+            "`outputMode`: `\"full\" | \"hash-only\" | \"new-only\" | \"errors-only\" | \
+             \"none\"`\n\nWhich of this task's logs are shown. Defaults to `\"full\"`."
+        }
+        "timeout" => {
+            "`timeout`: `number`\n\nThe number of seconds this task can run before it is stopped \
+             and treated as a failure."
+        }
+        "retries" => {
+            "`retries`: `number`\n\nHow many more times this task is run if it fails or times out. \
+             Defaults to `0`."
+        }
+        "resources" => {
+            "`resources`: `Record<string, number>`\n\nThe amount of each named resource this task \
+             reserves while it runs. Tasks don't run concurrently if they would exceed the \
+             capacity of a resource set in the top level `resources`."
+        }
+        _ => return None,
+    };
+    Some(docs)
+}
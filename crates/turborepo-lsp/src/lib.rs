@@ -1,6 +1,9 @@
 #![deny(clippy::all)]
 #![warn(clippy::unwrap_used)]
 
+mod analysis;
+mod docs;
+
 use std::{
     borrow::Cow,
     collections::{HashMap, HashSet},
@@ -22,13 +25,15 @@ use tower_lsp::{
     lsp_types::*,
     Client, LanguageServer,
 };
-use turbopath::AbsoluteSystemPathBuf;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 use turborepo_lib::{DaemonClient, DaemonConnector, DaemonPackageDiscovery, DaemonPaths};
 use turborepo_repository::{
     discovery::{self, DiscoveryResponse, PackageDiscovery, WorkspaceData},
     package_json::PackageJson,
 };
 
+use crate::analysis::{ReferenceKind, Target, TaskReference};
+
 pub struct Backend {
     client: Client,
     repo_root: Arc<Mutex<Option<AbsoluteSystemPathBuf>>>,
@@ -125,7 +130,11 @@ impl LanguageServer for Backend {
                 )),
                 completion_provider: Some(CompletionOptions {
                     resolve_provider: Some(false),
-                    trigger_characters: Some(vec![".".to_string()]),
+                    trigger_characters: Some(vec![
+                        ".".to_string(),
+                        "\"".to_string(),
+                        "#".to_string(),
+                    ]),
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
                     ..Default::default()
@@ -164,6 +173,9 @@ impl LanguageServer for Backend {
                         work_done_progress: None,
                     },
                 })),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Left(true)),
                 ..ServerCapabilities::default()
             },
         })
@@ -333,6 +345,161 @@ impl LanguageServer for Backend {
         Ok(Some(locations))
     }
 
+    async fn hover(&self, params: HoverParams) -> LspResult<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let Some(rope) = self.document(&position.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let text = rope.chunks().join("");
+        let offset = offset_of(&rope, position.position);
+        let Some(Target::TaskDefinitionKey(key)) = analysis::target_at(&text, offset) else {
+            return Ok(None);
+        };
+
+        Ok(docs::task_definition_key(&key).map(|docs| Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: docs.to_string(),
+            }),
+            range: None,
+        }))
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> LspResult<Option<GotoDefinitionResponse>> {
+        let position = params.text_document_position_params;
+        let uri = position.text_document.uri;
+        let Some(rope) = self.document(&uri) else {
+            return Ok(None);
+        };
+
+        let text = rope.chunks().join("");
+        let offset = offset_of(&rope, position.position);
+        let Some(Target::Task(reference)) = analysis::target_at(&text, offset) else {
+            return Ok(None);
+        };
+
+        let Some(workspaces) = self.workspaces().await else {
+            return Ok(None);
+        };
+
+        // a task without a package in a workspace's turbo.json refers to that
+        // workspace, otherwise it could be any package that has the script
+        let current = uri.to_file_path().ok();
+        let current = workspaces.iter().find(|workspace| {
+            !workspace.is_root()
+                && workspace.turbo_json.as_ref().map(|p| p.as_std_path()) == current.as_deref()
+        });
+        let targets = workspaces
+            .iter()
+            .filter(|workspace| match (&reference.package, current) {
+                (Some(package), _) => workspace.name.as_deref() == Some(package.as_str()),
+                (None, Some(current)) if !reference.dependencies => {
+                    current.package_json == workspace.package_json
+                }
+                (None, _) => !workspace.is_root(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut locations = vec![];
+        for workspace in &targets {
+            if let Some((url, rope)) = self.read(&workspace.package_json) {
+                let text = rope.chunks().join("");
+                if let Some(range) = analysis::script_range(&text, &reference.task) {
+                    locations.push(Location::new(url, convert_ranges(&rope, range)));
+                }
+            }
+        }
+
+        for workspace in &workspaces {
+            let Some((url, rope)) = workspace.turbo_json.as_ref().and_then(|p| self.read(p)) else {
+                continue;
+            };
+            let text = rope.chunks().join("");
+            for definition in analysis::task_references(&text) {
+                let matches_package = match (&definition.package, workspace.is_root()) {
+                    // `pkg#task` in the root turbo.json
+                    (Some(package), true) => targets
+                        .iter()
+                        .any(|target| target.name.as_deref() == Some(package.as_str())),
+                    // `task` in the root turbo.json applies to every package
+                    (None, true) => true,
+                    // `task` in a workspace's turbo.json
+                    (None, false) => targets
+                        .iter()
+                        .any(|target| target.package_json == workspace.package_json),
+                    (Some(_), false) => false,
+                };
+                if definition.kind == ReferenceKind::Definition
+                    && definition.task == reference.task
+                    && matches_package
+                    && !(url == uri && definition.task_range == reference.task_range)
+                {
+                    locations.push(Location::new(
+                        url.clone(),
+                        convert_ranges(&rope, definition.task_range),
+                    ));
+                }
+            }
+        }
+
+        Ok(Some(GotoDefinitionResponse::Array(locations)))
+    }
+
+    async fn rename(&self, params: RenameParams) -> LspResult<Option<WorkspaceEdit>> {
+        let position = params.text_document_position;
+        let Some(rope) = self.document(&position.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let text = rope.chunks().join("");
+        let offset = offset_of(&rope, position.position);
+        let Some(Target::Task(TaskReference { task, .. })) = analysis::target_at(&text, offset)
+        else {
+            return Ok(None);
+        };
+
+        let new_name = params.new_name;
+        if new_name.is_empty() || new_name.contains(['#', '^', '"']) {
+            return Err(Error::invalid_params(
+                "a task name can't be empty or contain `#`, `^` or `\"`",
+            ));
+        }
+
+        let Some(workspaces) = self.workspaces().await else {
+            return Ok(None);
+        };
+
+        // tasks are renamed everywhere they are used, including in
+        // `package#task` and `^task` references
+        let mut changes = HashMap::new();
+        for turbo_json in workspaces.iter().filter_map(|w| w.turbo_json.as_ref()) {
+            let Some((url, rope)) = self.read(turbo_json) else {
+                continue;
+            };
+            let text = rope.chunks().join("");
+            let edits = analysis::task_references(&text)
+                .into_iter()
+                .filter(|reference| reference.task == task)
+                .map(|reference| TextEdit {
+                    range: convert_ranges(&rope, reference.task_range),
+                    new_text: new_name.clone(),
+                })
+                .collect::<Vec<_>>();
+            if !edits.is_empty() {
+                changes.insert(url, edits);
+            }
+        }
+
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
     async fn code_lens(&self, params: CodeLensParams) -> LspResult<Option<Vec<CodeLens>>> {
         self.client
             .log_message(MessageType::INFO, "code lens!")
@@ -534,7 +701,27 @@ impl LanguageServer for Backend {
             .await;
     }
 
-    async fn completion(&self, _: CompletionParams) -> LspResult<Option<CompletionResponse>> {
+    async fn completion(&self, params: CompletionParams) -> LspResult<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let target = self.document(&position.text_document.uri).and_then(|rope| {
+            let text = rope.chunks().join("");
+            analysis::target_at(&text, offset_of(&rope, position.position))
+        });
+
+        // env vars are completed from the environment the editor started us in
+        if target == Some(Target::EnvVar) {
+            let env_vars =
+                std::env::vars()
+                    .map(|(name, _)| name)
+                    .sorted()
+                    .map(|name| CompletionItem {
+                        label: name,
+                        kind: Some(CompletionItemKind::VARIABLE),
+                        ..Default::default()
+                    });
+            return Ok(Some(CompletionResponse::Array(env_vars.collect())));
+        }
+
         let packages = self
             .package_discovery()
             .await
@@ -565,7 +752,20 @@ impl LanguageServer for Backend {
                 ..Default::default()
             });
 
-        Ok(Some(CompletionResponse::Array(keys.chain(tasks).collect())))
+        let package_names = package_jsons
+            .iter()
+            .filter_map(|p| p.name.as_ref())
+            .unique()
+            .map(|name| CompletionItem {
+                label: name.to_owned(),
+                kind: Some(CompletionItemKind::MODULE),
+                insert_text: Some(format!("{}#", name)),
+                ..Default::default()
+            });
+
+        Ok(Some(CompletionResponse::Array(
+            keys.chain(tasks).chain(package_names).collect(),
+        )))
     }
 }
 
@@ -600,6 +800,75 @@ impl Backend {
             .await
     }
 
+    /// The contents of a document open in the editor
+    fn document(&self, uri: &Url) -> Option<crop::Rope> {
+        self.files
+            .lock()
+            .expect("only fails if poisoned")
+            .get(uri)
+            .cloned() // cloning is cheap
+    }
+
+    /// Read a file, preferring the editor's copy if it is open
+    fn read(&self, path: &AbsoluteSystemPath) -> Option<(Url, crop::Rope)> {
+        let url = Url::from_file_path(path.as_std_path()).ok()?;
+        match self.document(&url) {
+            Some(rope) => Some((url, rope)),
+            None => {
+                let data = std::fs::read_to_string(path).ok()?;
+                Some((url, crop::Rope::from(data)))
+            }
+        }
+    }
+
+    /// Every workspace in the repo, including the root
+    async fn workspaces(&self) -> Option<Vec<Workspace>> {
+        let repo_root = self
+            .repo_root
+            .lock()
+            .expect("only fails if poisoned")
+            .clone()?;
+
+        let packages = match self.package_discovery().await {
+            Ok(packages) => packages,
+            Err(e) => {
+                self.client
+                    .log_message(MessageType::WARNING, e.to_string())
+                    .await;
+                return None;
+            }
+        };
+
+        // package discovery does not yield the root, so we must add it
+        let root_turbo_json = repo_root.join_component("turbo.json");
+        let workspaces = chain(
+            packages.workspaces.into_iter(),
+            iter::once(WorkspaceData {
+                package_json: repo_root.join_component("package.json"),
+                turbo_json: root_turbo_json.exists().then_some(root_turbo_json),
+            }),
+        )
+        .map(|wd| {
+            let is_root = wd.package_json.parent() == Some(&*repo_root);
+            let name = if is_root {
+                Some("//".to_string())
+            } else {
+                // if we can't load a package.json, then we can't know its name
+                PackageJson::load(&wd.package_json)
+                    .ok()
+                    .and_then(|package_json| package_json.name)
+            };
+            Workspace {
+                name,
+                package_json: wd.package_json,
+                turbo_json: wd.turbo_json,
+            }
+        })
+        .collect();
+
+        Some(workspaces)
+    }
+
     /// Handle a file update to a rope, emitting diagnostics if necessary.
     async fn handle_file_update(&self, uri: Url, rope: Option<crop::Rope>, version: Option<i32>) {
         let rope = match rope {
@@ -840,6 +1109,28 @@ impl Backend {
     }
 }
 
+struct Workspace {
+    /// `//` for the root workspace
+    name: Option<String>,
+    package_json: AbsoluteSystemPathBuf,
+    turbo_json: Option<AbsoluteSystemPathBuf>,
+}
+
+impl Workspace {
+    fn is_root(&self) -> bool {
+        self.name.as_deref() == Some("//")
+    }
+}
+
+/// convert an lsp position to a byte offset, clamping it to the document
+fn offset_of(rope: &crop::Rope, position: Position) -> usize {
+    let line = position.line as usize;
+    if line >= rope.line_len() {
+        return rope.byte_len();
+    }
+    (rope.byte_of_line(line) + position.character as usize).min(rope.byte_len())
+}
+
 fn convert_ranges(rope: &crop::Rope, range: jsonc_parser::common::Range) -> Range {
     let start_line = rope.line_of_byte(range.start);
     let end_line = rope.line_of_byte(range.end);
@@ -21,6 +21,15 @@ impl SCM {
         }
     }
 
+    /// Finds the branch that changes are usually merged into, preferring the
+    /// remote's default branch
+    pub fn default_branch(&self, path: &AbsoluteSystemPath) -> Result<String, Error> {
+        match self {
+            Self::Git(git) => git.default_branch(),
+            Self::Manual => Err(Error::GitRequired(path.to_owned())),
+        }
+    }
+
     pub fn changed_files(
         &self,
         turbo_root: &AbsoluteSystemPath,
@@ -92,6 +101,25 @@ impl Git {
         Ok(output.trim().to_owned())
     }
 
+    fn default_branch(&self) -> Result<String, Error> {
+        // Set when the repository was cloned, but not for every CI checkout
+        if let Ok(output) =
+            self.execute_git_command(&["symbolic-ref", "--short", "refs/remotes/origin/HEAD"], "")
+        {
+            let output = String::from_utf8(output)?;
+            return Ok(output.trim().to_owned());
+        }
+
+        ["origin/main", "origin/master", "main", "master"]
+            .into_iter()
+            .find(|branch| {
+                self.execute_git_command(&["rev-parse", "--verify", "--quiet", branch], "")
+                    .is_ok()
+            })
+            .map(|branch| branch.to_owned())
+            .ok_or_else(|| Error::git_error("unable to find a default branch"))
+    }
+
     fn changed_files(
         &self,
         turbo_root: &AbsoluteSystemPath,
@@ -236,7 +264,7 @@ mod tests {
     use which::which;
 
     use super::previous_content;
-    use crate::{git::changed_files, Error};
+    use crate::{git::changed_files, Error, SCM};
 
     fn setup_repository() -> Result<(TempDir, Repository), Error> {
         let repo_root = tempfile::tempdir()?;
@@ -634,6 +662,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_default_branch() -> Result<(), Error> {
+        let (repo_root, repo) = setup_repository()?;
+        let root = AbsoluteSystemPathBuf::try_from(repo_root.path()).unwrap();
+        let scm = SCM::new(&root);
+
+        assert_matches!(scm.default_branch(&root), Err(Error::Git(_, _)));
+
+        root.join_component("foo.js")
+            .create_with_contents("let z = 0;")?;
+        let first_commit_oid = commit_file(&repo, Path::new("foo.js"), None);
+        let first_commit = repo.find_commit(first_commit_oid).unwrap();
+        repo.branch("main", &first_commit, true).unwrap();
+        assert_eq!(scm.default_branch(&root)?, "main");
+
+        repo.reference("refs/remotes/origin/trunk", first_commit_oid, false, "")
+            .unwrap();
+        repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/trunk",
+            false,
+            "",
+        )
+        .unwrap();
+        assert_eq!(scm.default_branch(&root)?, "origin/trunk");
+
+        Ok(())
+    }
+
     #[test]
     fn test_error_cases() -> Result<(), Error> {
         let repo_dir = tempfile::tempdir()?;
@@ -1,6 +1,12 @@
 #![deny(clippy::all)]
 
-use std::{collections::HashMap, fs::OpenOptions, io::Write, net::SocketAddr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::OpenOptions,
+    io::Write,
+    net::SocketAddr,
+    sync::Arc,
+};
 
 use anyhow::Result;
 use axum::{
@@ -35,11 +41,16 @@ pub const EXPECTED_SPACE_RUN_URL: &str = "https://example.com";
 pub const EXPECTED_SSO_TEAM_ID: &str = "expected_sso_team_id";
 pub const EXPECTED_SSO_TEAM_SLUG: &str = "expected_sso_team_slug";
 
+/// The first upload of an artifact whose hash starts with this prefix fails
+/// with a 503, so clients can test that uploads are retried
+pub const FLAKY_ARTIFACT_PREFIX: &str = "flaky-";
+
 pub async fn start_test_server(port: u16) -> Result<()> {
     let get_durations_ref = Arc::new(Mutex::new(HashMap::new()));
     let head_durations_ref = get_durations_ref.clone();
     let put_durations_ref = get_durations_ref.clone();
     let put_tempdir_ref = Arc::new(tempfile::tempdir()?);
+    let failed_puts_ref = Arc::new(Mutex::new(HashSet::new()));
     let get_tempdir_ref = put_tempdir_ref.clone();
 
     let get_analytics_events_ref = Arc::new(Mutex::new(Vec::new()));
@@ -147,6 +158,13 @@ pub async fn start_test_server(port: u16) -> Result<()> {
             "/v8/artifacts/:hash",
             put(
                 |Path(hash): Path<String>, headers: HeaderMap, mut body: BodyStream| async move {
+                    if hash.starts_with(FLAKY_ARTIFACT_PREFIX)
+                        && failed_puts_ref.lock().await.insert(hash.clone())
+                    {
+                        while body.next().await.is_some() {}
+                        return (StatusCode::SERVICE_UNAVAILABLE, Json(hash));
+                    }
+
                     let root_path = put_tempdir_ref.path();
                     let file_path = root_path.join(&hash);
                     let mut file = OpenOptions::new()
@@ -161,8 +179,13 @@ pub async fn start_test_server(port: u16) -> Result<()> {
                         .and_then(|duration| duration.parse::<u32>().ok())
                         .expect("x-artifact-duration header is missing");
 
+                    let tag = headers
+                        .get("x-artifact-tag")
+                        .and_then(|header_value| header_value.to_str().ok())
+                        .map(|tag| tag.to_string());
+
                     let mut durations_map = put_durations_ref.lock().await;
-                    durations_map.insert(hash.clone(), duration);
+                    durations_map.insert(hash.clone(), (duration, tag));
 
                     while let Some(item) = body.next().await {
                         let chunk = item.unwrap();
@@ -181,18 +204,21 @@ pub async fn start_test_server(port: u16) -> Result<()> {
                 let Ok(buffer) = std::fs::read(file_path) else {
                     return (StatusCode::NOT_FOUND, HeaderMap::new(), Vec::new());
                 };
-                let duration = get_durations_ref
+                let (duration, tag) = get_durations_ref
                     .lock()
                     .await
                     .get(&hash)
                     .cloned()
-                    .unwrap_or(0);
+                    .unwrap_or_default();
                 let mut headers = HeaderMap::new();
 
                 headers.insert(
                     "x-artifact-duration",
                     HeaderValue::from_str(&duration.to_string()).unwrap(),
                 );
+                if let Some(tag) = tag {
+                    headers.insert("x-artifact-tag", HeaderValue::from_str(&tag).unwrap());
+                }
 
                 (StatusCode::FOUND, headers, buffer)
             }),
@@ -202,7 +228,8 @@ pub async fn start_test_server(port: u16) -> Result<()> {
             head(|Path(hash): Path<String>| async move {
                 let mut headers = HeaderMap::new();
 
-                let Some(duration) = head_durations_ref.lock().await.get(&hash).cloned() else {
+                let Some((duration, _)) = head_durations_ref.lock().await.get(&hash).cloned()
+                else {
                     return (StatusCode::NOT_FOUND, headers);
                 };
 
@@ -38,6 +38,38 @@ export interface WorkspaceSchema extends BaseSchema {
    * @defaultValue ["//"]
    */
   extends: Array<string>;
+
+  /**
+   * This key is only available in Workspace Configs
+   * and cannot be used in your root turbo.json.
+   *
+   * Tags for this workspace, matched by the `boundaries` rules
+   * of other workspaces when running `turbo boundaries`.
+   *
+   * @defaultValue []
+   */
+  tags?: Array<string>;
+
+  /**
+   * This key is only available in Workspace Configs
+   * and cannot be used in your root turbo.json.
+   *
+   * Restricts which workspaces this workspace can import,
+   * based on their tags. Checked by `turbo boundaries`.
+   */
+  boundaries?: Boundaries;
+}
+
+export interface Boundaries {
+  /**
+   * Only workspaces with at least one of these tags can be imported.
+   */
+  allow?: Array<string>;
+
+  /**
+   * Workspaces with any of these tags can't be imported.
+   */
+  deny?: Array<string>;
 }
 
 export interface RootSchema extends BaseSchema {
@@ -95,7 +127,8 @@ export interface RootSchema extends BaseSchema {
 
   /**
    * A priority-ordered (most-significant to least-significant) array of project-anchored
-   * Unix-style paths to `.env` files to include in the global hash.
+   * Unix-style paths to `.env` files to include in the global hash. The variables in
+   * these files are loaded into the environment of every task.
    *
    * Documentation: https://turbo.build/repo/docs/reference/configuration#globalDotEnv
    *
@@ -111,6 +144,41 @@ export interface RootSchema extends BaseSchema {
    * @defaultValue `{}`
    */
   remoteCache?: RemoteCache;
+
+  /**
+   * Limits applied to the local filesystem cache. When a limit is exceeded,
+   * the least recently used artifacts are removed before a run starts.
+   *
+   * @defaultValue `{}`
+   */
+  localCache?: LocalCache;
+
+  /**
+   * An OpenTelemetry collector that a trace of every run is exported to. The run
+   * is the root span and each task that ran is one of its child spans.
+   *
+   * @defaultValue `{}`
+   */
+  otel?: Otel;
+
+  /**
+   * The capacity of named resources that tasks can reserve with their `resources`
+   * key. Tasks that would exceed the capacity of a resource wait until enough of it
+   * is released by other tasks. Capacities can be overridden with `--resource`.
+   *
+   * @defaultValue `{}`
+   */
+  resources?: Record<string, number>;
+
+  /**
+   * Extra files from the root of the repository that `turbo prune` copies
+   * into its output.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#prune
+   *
+   * @defaultValue `{}`
+   */
+  prune?: Prune;
 }
 
 export interface Pipeline {
@@ -171,7 +239,8 @@ export interface Pipeline {
 
   /**
    * A priority-ordered (most-significant to least-significant) array of workspace-anchored
-   * Unix-style paths to `.env` files to include in the task hash.
+   * Unix-style paths to `.env` files to include in the task hash. The variables in
+   * these files are loaded into the task's environment.
    *
    * Documentation: https://turbo.build/repo/docs/reference/configuration#dotEnv
    *
@@ -249,6 +318,40 @@ export interface Pipeline {
    * @defaultValue false
    */
   persistent?: boolean;
+
+  /**
+   * Indicates whether the task reads input from the terminal, like a prompt.
+   * turbo forwards its stdin to one interactive task at a time. Outside of a
+   * terminal, interactive tasks can't run in parallel with other tasks.
+   *
+   * @defaultValue false
+   */
+  interactive?: boolean;
+
+  /**
+   * The maximum number of seconds the task is allowed to run. A task that runs
+   * longer is stopped and treated as a failure. Must be at least 1.
+   *
+   * When not set, the task can run indefinitely.
+   */
+  timeout?: number;
+
+  /**
+   * The number of times to re-run the task if it fails or times out before
+   * reporting it as a failure. Persistent tasks can't be retried.
+   *
+   * @defaultValue 0
+   */
+  retries?: number;
+
+  /**
+   * The amount of each named resource this task reserves while it runs, e.g.
+   * `{ "memory": 4, "browser": 1 }`. Resources without a configured capacity
+   * are unlimited.
+   *
+   * @defaultValue `{}`
+   */
+  resources?: Record<string, number>;
 }
 
 export interface RemoteCache {
@@ -258,6 +361,11 @@ export interface RemoteCache {
    * variable `TURBO_REMOTE_CACHE_SIGNATURE_KEY`. Turborepo will reject any downloaded artifacts
    * that have an invalid signature or are missing a signature.
    *
+   * To sign with an Ed25519 private key instead, set `TURBO_REMOTE_CACHE_SIGNATURE_PRIVATE_KEY`
+   * and `TURBO_REMOTE_CACHE_SIGNATURE_KEY_ID`. Machines that only verify artifacts need just
+   * `TURBO_REMOTE_CACHE_SIGNATURE_PUBLIC_KEYS`, a comma separated list of
+   * `<key id>=<base64 public key>` entries.
+   *
    * @defaultValue false
    */
   signature?: boolean;
@@ -271,6 +379,65 @@ export interface RemoteCache {
    * @defaultValue true
    */
   enabled?: boolean;
+
+  /**
+   * A directory shared between machines, such as an NFS or SMB mount, to use as
+   * a remote cache. Relative paths are resolved from the repository root.
+   * Does not require logging in or linking to a remote cache.
+   *
+   * @defaultValue undefined
+   */
+  sharedDirectory?: string;
+}
+
+export interface LocalCache {
+  /**
+   * The maximum size of the local cache, e.g. `"10GB"` or `"500MB"`.
+   * Can be overridden with the `TURBO_CACHE_MAX_SIZE` environment variable.
+   *
+   * @defaultValue undefined
+   */
+  maxSize?: string;
+
+  /**
+   * The maximum time an artifact can go unused before it is removed,
+   * e.g. `"7d"` or `"12h"`.
+   * Can be overridden with the `TURBO_CACHE_MAX_AGE` environment variable.
+   *
+   * @defaultValue undefined
+   */
+  maxAge?: string;
+}
+
+export interface Otel {
+  /**
+   * The base URL of a collector that accepts OTLP over HTTP with protobuf
+   * encoding, e.g. `"http://localhost:4318"`. Traces are sent to `/v1/traces`.
+   * Can be overridden with the `TURBO_OTEL_ENDPOINT` environment variable.
+   *
+   * @defaultValue undefined
+   */
+  endpoint?: string;
+
+  /**
+   * The `service.name` that traces are reported under.
+   * Can be overridden with the `TURBO_OTEL_SERVICE_NAME` environment variable.
+   *
+   * @defaultValue "turbo"
+   */
+  serviceName?: string;
+}
+
+export interface Prune {
+  /**
+   * Files, directories or globs relative to the root of the repository, mapped
+   * to where `turbo prune --docker` copies them: `"json"` for files needed to
+   * install dependencies, `"full"` for files needed to build, or `"both"`.
+   * Without `--docker`, `"full"` and `"both"` entries are copied to the output.
+   *
+   * @defaultValue `{}`
+   */
+  include?: Record<string, "json" | "full" | "both">;
 }
 
 export type OutputMode =
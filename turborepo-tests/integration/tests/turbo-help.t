@@ -9,16 +9,21 @@ Test help flag
   
   Commands:
     bin         Get the path to the Turbo binary
+    boundaries  Check that packages only import what they declare
     completion  Generate the autocompletion script for the specified shell
+    cache       Inspect and clean up the local filesystem cache
     daemon      Runs the Turborepo background daemon
+    deps        Check the external and internal dependencies of your monorepo
     generate    Generate a new app / package
     telemetry   Enable or disable anonymous telemetry
     link        Link your local directory to a Vercel organization and enable remote caching
     login       Login to your Vercel account
     logout      Logout to your Vercel account
     prune       Prepare a subset of your monorepo
+    query       Query the package and task graph of your monorepo as JSON
     run         Run tasks across projects in your monorepo
     unlink      Unlink the current directory from your Vercel organization and disable Remote Caching
+    watch       Re-run tasks in affected packages when files change
   
   Options:
         --version                         
@@ -46,6 +51,8 @@ Test help flag
             Set the number of concurrent cache operations (default 10) [default: 10]
         --concurrency <CONCURRENCY>
             Limit the concurrency of task execution. Use 1 for serial (i.e. one-at-a-time) execution
+        --resource <NAME=CAPACITY>
+            Set the capacity of a named resource that tasks can reserve, e.g. `--resource memory=8`. Overrides the capacity set in turbo.json
         --continue
             Continue execution even if a task exits with an error or non-zero exit code. The default behavior is to bail
         --dry-run [<DRY_RUN>]
@@ -64,6 +71,8 @@ Test help flag
             Environment variable mode. Use "loose" to pass the entire existing environment. Use "strict" to use an allowlist specified in turbo.json. Use "infer" to defer to existence of "passThroughEnv" or "globalPassThroughEnv" in turbo.json. (default infer) [default: infer] [possible values: infer, loose, strict]
     -F, --filter <FILTER>
             Use the given selector to specify package(s) to act as entry points. The syntax mirrors pnpm's syntax, and additional documentation and examples can be found in turbo's documentation https://turbo.build/repo/docs/reference/command-line-reference/run#--filter
+        --affected
+            Run only in packages that changed since the base of this CI run, and in the packages that depend on them. The base is read from the CI provider, like the target branch of a pull request, and falls back to the default branch of the repository
         --scope <SCOPE>
             DEPRECATED: Specify package(s) to act as entry points for task execution. Supports globs
         --ignore <IGNORE>
@@ -95,7 +104,11 @@ Test help flag
         --remote-cache-read-only [<BOOL>]
             Treat remote cache as read only [env: TURBO_REMOTE_CACHE_READ_ONLY=] [default: false] [possible values: true, false]
         --summarize [<SUMMARIZE>]
-            Generate a summary of the turbo run [env: TURBO_RUN_SUMMARY=] [possible values: true, false]
+            Generate a summary of the turbo run. Use `junit:<path>` or `markdown:<path>` to also write a JUnit XML or Markdown report of the executed tasks. Can be passed multiple times [env: TURBO_RUN_SUMMARY=]
+        --explain-miss
+            Explain why tasks missed the cache by comparing their hash inputs against the most recent run summary that contains each task. Implies --summarize
+        --audit-inputs
+            Ask tasks to trace the files and env vars they access, and report accesses that aren't declared as task inputs along with declared inputs that went unused. Only tools that write a task access trace can be audited
         --log-prefix <LOG_PREFIX>
             Use "none" to remove prefixes from task logs. Use "task" to get task id prefixing. Use "auto" to let turbo decide how to prefix the logs based on the execution environment. In most cases this will be the same as "task". Note that tasks running in parallel interleave their logs, so removing prefixes can make it difficult to associate logs with tasks. Use --log-order=grouped to prevent interleaving. (default auto) [default: auto] [possible values: auto, none, task]
 
@@ -111,16 +124,21 @@ Test help flag
   
   Commands:
     bin         Get the path to the Turbo binary
+    boundaries  Check that packages only import what they declare
     completion  Generate the autocompletion script for the specified shell
+    cache       Inspect and clean up the local filesystem cache
     daemon      Runs the Turborepo background daemon
+    deps        Check the external and internal dependencies of your monorepo
     generate    Generate a new app / package
     telemetry   Enable or disable anonymous telemetry
     link        Link your local directory to a Vercel organization and enable remote caching
     login       Login to your Vercel account
     logout      Logout to your Vercel account
     prune       Prepare a subset of your monorepo
+    query       Query the package and task graph of your monorepo as JSON
     run         Run tasks across projects in your monorepo
     unlink      Unlink the current directory from your Vercel organization and disable Remote Caching
+    watch       Re-run tasks in affected packages when files change
   
   Options:
         --version                         
@@ -148,6 +166,8 @@ Test help flag
             Set the number of concurrent cache operations (default 10) [default: 10]
         --concurrency <CONCURRENCY>
             Limit the concurrency of task execution. Use 1 for serial (i.e. one-at-a-time) execution
+        --resource <NAME=CAPACITY>
+            Set the capacity of a named resource that tasks can reserve, e.g. `--resource memory=8`. Overrides the capacity set in turbo.json
         --continue
             Continue execution even if a task exits with an error or non-zero exit code. The default behavior is to bail
         --dry-run [<DRY_RUN>]
@@ -166,6 +186,8 @@ Test help flag
             Environment variable mode. Use "loose" to pass the entire existing environment. Use "strict" to use an allowlist specified in turbo.json. Use "infer" to defer to existence of "passThroughEnv" or "globalPassThroughEnv" in turbo.json. (default infer) [default: infer] [possible values: infer, loose, strict]
     -F, --filter <FILTER>
             Use the given selector to specify package(s) to act as entry points. The syntax mirrors pnpm's syntax, and additional documentation and examples can be found in turbo's documentation https://turbo.build/repo/docs/reference/command-line-reference/run#--filter
+        --affected
+            Run only in packages that changed since the base of this CI run, and in the packages that depend on them. The base is read from the CI provider, like the target branch of a pull request, and falls back to the default branch of the repository
         --scope <SCOPE>
             DEPRECATED: Specify package(s) to act as entry points for task execution. Supports globs
         --ignore <IGNORE>
@@ -197,7 +219,11 @@ Test help flag
         --remote-cache-read-only [<BOOL>]
             Treat remote cache as read only [env: TURBO_REMOTE_CACHE_READ_ONLY=] [default: false] [possible values: true, false]
         --summarize [<SUMMARIZE>]
-            Generate a summary of the turbo run [env: TURBO_RUN_SUMMARY=] [possible values: true, false]
+            Generate a summary of the turbo run. Use `junit:<path>` or `markdown:<path>` to also write a JUnit XML or Markdown report of the executed tasks. Can be passed multiple times [env: TURBO_RUN_SUMMARY=]
+        --explain-miss
+            Explain why tasks missed the cache by comparing their hash inputs against the most recent run summary that contains each task. Implies --summarize
+        --audit-inputs
+            Ask tasks to trace the files and env vars they access, and report accesses that aren't declared as task inputs along with declared inputs that went unused. Only tools that write a task access trace can be audited
         --log-prefix <LOG_PREFIX>
             Use "none" to remove prefixes from task logs. Use "task" to get task id prefixing. Use "auto" to let turbo decide how to prefix the logs based on the execution environment. In most cases this will be the same as "task". Note that tasks running in parallel interleave their logs, so removing prefixes can make it difficult to associate logs with tasks. Use --log-order=grouped to prevent interleaving. (default auto) [default: auto] [possible values: auto, none, task]
 